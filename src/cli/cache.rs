@@ -0,0 +1,843 @@
+//! The `cache` commands and the caches of `--use` and `--save-as`.
+
+use std::{
+    fs::{self, OpenOptions},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config_file::{
+        ArgCache, CACHE_FILE_NAME, CacheLock, CachedArg, ConfigReader, DATA_DIR_ENV,
+        cache_args_text, cache_chain, cache_entries, cache_file_path, cache_layer, cache_summaries,
+        empty_cache_file, export_caches, filetemp_data_dir, format_caches, glob_match,
+        import_caches, is_glob, migrate_cache_file, parse_cache_args, parse_caches, prune_caches,
+        read_cache_file, record_cache_use, remove_cache, remove_matching_caches, rename_cache,
+        save_cache, write_cache_file,
+    },
+    error::{ArgError, CacheError, FiletempError, IoError},
+    file_types::{FileType, process_args, verify_existed_args},
+    logging,
+    output_sink::Preview,
+    program_args::{ArgProcessErr, CommandArg, prompt, providers::format_date, resolve::Layer},
+    scratch,
+    text_edit::{display_width, pad, unified_diff},
+};
+
+use super::generate::preview;
+use super::sources::apply_layers;
+use super::{define_args, print_arg_err, usage_err};
+
+pub(super) fn define_roundtrip_args(cmd: &mut CommandArg) {
+    define_args(cmd);
+    cmd.usage_name("filetemp cache roundtrip-test");
+}
+
+pub(super) fn cache_roundtrip_test(cmd: &mut CommandArg) -> Result<(), FiletempError> {
+    add_cache_layers(cmd)?;
+    apply_layers(cmd)?;
+    if let Err(ArgProcessErr::MissingArgs { missing, required }) = cmd.assert_required_args_exist()
+    {
+        return Err(ArgError::missing(cmd, &missing, required).into());
+    }
+    verify_existed_args(cmd)?;
+
+    let output = process_args(cmd)?;
+    verify_cache_roundtrip(cmd, &output)?;
+
+    logging::success(format_args!(
+        "Round trip OK, {} cached argument(s) reproduce identical output",
+        cmd.extract_given_args().len()
+    ));
+    Ok(())
+}
+
+/// Saving the arguments of `cmd` to a cache and loading them into a fresh
+/// command line must reproduce `output` byte for byte.
+pub(super) fn verify_cache_roundtrip(cmd: &CommandArg, output: &str) -> Result<(), FiletempError> {
+    let file_type = cmd.get_file_type();
+    let mut saved = ArgCache::new("roundtrip", file_type);
+    saved
+        .args
+        .extend(cmd.extract_given_args().into_iter().map(CachedArg::from));
+    let text = format_caches(&[saved]);
+
+    let mut loaded = CommandArg::new();
+    define_args(&mut loaded);
+    if loaded.process_program_args(&[file_type.to_str()]).is_err() {
+        return Err(ArgError::UnknownFileType(file_type.to_str().to_string()).into());
+    }
+
+    let valid_args: Vec<String> = loaded
+        .query_valid_args()
+        .map(|a| a.name.to_string())
+        .collect();
+    for cache in parse_caches(&text, file_type, valid_args.iter().map(String::as_str))? {
+        loaded.add_layer(cache_layer(&cache));
+    }
+    if let Err((e, _)) = loaded.resolve() {
+        print_arg_err(e);
+        return Err(CacheError::Roundtrip(String::from("a saved argument was refused")).into());
+    }
+
+    if let Err(ArgProcessErr::MissingArgs { missing, .. }) = loaded.assert_required_args_exist() {
+        return Err(CacheError::Roundtrip(format!(
+            "loading lost arguments: --{}",
+            missing.join(", --")
+        ))
+        .into());
+    }
+    verify_existed_args(&loaded)?;
+
+    let reproduced = process_args(&loaded)?;
+    if reproduced != output {
+        return Err(CacheError::Roundtrip(format!(
+            "loading the saved arguments changes the output:\n{}",
+            unified_diff(output, &reproduced, "saved", "loaded")
+        ))
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Create the cache directory and an empty cache file, in the resolved data
+/// directory or in the given one.
+pub(super) fn init_cache_store(args: &[&str]) -> Result<(), FiletempError> {
+    let resolved = filetemp_data_dir().ok().map(|(dir, _)| dir);
+    let dir = match (args, &resolved) {
+        ([], Some(d)) => d.clone(),
+        ([], None) => return Err(CacheError::NoDataDir.into()),
+        ([dir], _) => PathBuf::from(dir),
+        _ => return Err(usage_err("Usage: filetemp cache init [DIR]")),
+    };
+
+    if let Err(e) = fs::create_dir_all(&dir) {
+        return Err(CacheError::Io(IoError::new("create", dir, e)).into());
+    }
+    migrate_cache_file(&dir)?;
+
+    let path = dir.join(CACHE_FILE_NAME);
+    let _lock = CacheLock::acquire(&path, true)?;
+    if path.exists() {
+        println!("Cache file \"{}\" already exists", path.display());
+    } else {
+        write_cache_file(&path, &empty_cache_file())?;
+        logging::success(format_args!(
+            "Created an empty cache file \"{}\"",
+            path.display()
+        ));
+    }
+
+    let in_use = resolved.is_some_and(|r| {
+        fs::canonicalize(&r)
+            .ok()
+            .is_some_and(|r| fs::canonicalize(&dir).ok() == Some(r))
+    });
+    if !in_use {
+        println!(
+            "Set {}=\"{}\" to keep caches there",
+            DATA_DIR_ENV,
+            dir.display()
+        );
+    }
+    Ok(())
+}
+
+/// Print the saved caches as a table, the cache file is only read.
+/// A pattern keeps the caches whose name matches it.
+pub(super) fn list_caches(args: &[&str]) -> Result<(), FiletempError> {
+    // Names alone, one per line, for scripts and the completion of --use.
+    let names_only = args.contains(&"--names");
+    let pattern = match args.iter().filter(|a| **a != "--names").collect::<Vec<_>>()[..] {
+        [] => None,
+        [pattern] if !pattern.starts_with('-') => Some(*pattern),
+        _ => return Err(usage_err("Usage: filetemp cache list [--names] [PATTERN]")),
+    };
+
+    let path = cache_file_path()?;
+    let _lock = CacheLock::acquire(&path, false)?;
+    let content = read_cache_file(&path)?;
+    let mut summaries = cache_summaries(&content)?;
+    if let Some(p) = pattern {
+        summaries.retain(|s| glob_match(p, &s.cache_name));
+    }
+    if names_only {
+        let mut names: Vec<&str> = summaries.iter().map(|s| s.cache_name.as_str()).collect();
+        names.sort_unstable();
+        names.dedup();
+        for name in names {
+            println!("{}", name);
+        }
+        return Ok(());
+    }
+    if let Some(p) = pattern
+        && summaries.is_empty()
+    {
+        eprintln!("No saved caches match \"{}\"", p);
+        return Ok(());
+    }
+    if summaries.is_empty() {
+        eprintln!("No saved caches, create one with --save-as <NAME>");
+        return Ok(());
+    }
+
+    let name_width = summaries
+        .iter()
+        .map(|s| display_width(&s.cache_name))
+        .chain(std::iter::once("NAME".len()))
+        .max()
+        .unwrap_or(0);
+    let type_width = summaries
+        .iter()
+        .map(|s| s.file_type.len())
+        .chain(std::iter::once("FILE TYPE".len()))
+        .max()
+        .unwrap_or(0);
+
+    println!(
+        "{:<nw$}  {:<tw$}  ARGS  USES  CREATED     LAST USED",
+        "NAME",
+        "FILE TYPE",
+        nw = name_width,
+        tw = type_width
+    );
+    for s in summaries.iter() {
+        // Names are padded by the columns they take, wide characters take two.
+        println!(
+            "{}  {:<tw$}  {:<4}  {:<4}  {:<10}  {}",
+            pad(&s.cache_name, name_width),
+            s.file_type,
+            s.arg_count,
+            s.uses,
+            s.created
+                .map(format_date)
+                .unwrap_or_else(|| String::from("-")),
+            s.last_used
+                .map(format_date)
+                .unwrap_or_else(|| String::from("never")),
+            tw = type_width
+        );
+    }
+    Ok(())
+}
+
+/// Remove caches not used for longer than the given age.
+pub(super) fn prune_saved_caches(args: &[&str]) -> Result<(), FiletempError> {
+    const USAGE: &str = "Usage: filetemp cache prune --older-than <AGE>, e.g. 90d, 4w or 12h";
+    let max_age = match args {
+        ["--older-than", age] => match parse_age(age) {
+            Some(a) => a,
+            None => {
+                return Err(
+                    ArgError::Invalid(format!("Invalid age \"{}\"\n{}", age, USAGE)).into(),
+                );
+            }
+        },
+        _ => return Err(usage_err(USAGE)),
+    };
+
+    let path = cache_file_path()?;
+    let _lock = CacheLock::acquire(&path, true)?;
+    let content = read_cache_file(&path)?;
+    let (result, removed) = prune_caches(&content, max_age)?;
+    if removed.is_empty() {
+        println!("No caches unused for longer than {}", args[1]);
+        return Ok(());
+    }
+
+    write_cache_file(&path, &result)?;
+    let names: Vec<String> = removed
+        .iter()
+        .map(|(t, n)| format!("{} ({})", n, t))
+        .collect();
+    logging::success(format_args!(
+        "Pruned {} cache(s): {}",
+        names.len(),
+        names.join(", ")
+    ));
+    Ok(())
+}
+
+/// Seconds of an age given as a number with an `h`, `d` or `w` suffix.
+fn parse_age(age: &str) -> Option<i64> {
+    let unit = match age.chars().last()? {
+        'h' => 3600,
+        'd' => 86400,
+        'w' => 7 * 86400,
+        _ => return None,
+    };
+    let count: i64 = age[..age.len() - 1].parse().ok()?;
+    count.checked_mul(unit)
+}
+
+/// Print the stored arguments of a cache and the command line they stand for.
+pub(super) fn show_cache(args: &[&str]) -> Result<(), FiletempError> {
+    let (file_type, name) = match cache_command_args(args) {
+        (t, [name]) => (t, *name),
+        _ => {
+            return Err(usage_err("Usage: filetemp cache show [FILE_TYPE] <NAME>"));
+        }
+    };
+
+    let path = cache_file_path()?;
+    let _lock = CacheLock::acquire(&path, false)?;
+    let content = read_cache_file(&path)?;
+    let (file_type, extends, entries) = match cache_entries(&content, name, file_type)? {
+        Some(e) => e,
+        None => return Err(no_cache_err(name, file_type)),
+    };
+
+    println!("[{}]", name);
+    println!("file_type: {}", file_type);
+    let mut command = vec![String::from("filetemp"), file_type.to_string()];
+    if let Some(parent) = &extends {
+        println!("extends: {}", display_value(parent));
+        command.push(String::from("--use"));
+        command.push(shell_word(parent));
+    }
+    for (arg, value) in entries.iter() {
+        println!("{}: {}", arg, display_value(value));
+        command.push(format!("--{}", arg));
+        command.push(shell_word(value));
+    }
+    println!("\nCommand line:\n    {}", command.join(" "));
+    Ok(())
+}
+
+/// A stored value on one line, line breaks and other control characters escaped.
+fn display_value(value: &str) -> String {
+    if value.contains(char::is_control) {
+        format!(
+            "\"{}\"",
+            value.escape_debug().to_string().replace("\\'", "'")
+        )
+    } else {
+        value.to_string()
+    }
+}
+
+/// A value as one word of a POSIX shell command line, quoted when needed.
+pub(super) fn shell_word(value: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_.,/:=+@%".contains(c);
+    if !value.is_empty() && value.chars().all(plain) {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// Open the stored arguments of a cache in the user's editor, and save them back
+/// once they are valid arguments of the cache's file type.
+pub(super) fn edit_cache(args: &[&str]) -> Result<(), FiletempError> {
+    let (file_type, name) = match cache_command_args(args) {
+        (t, [name]) => (t, *name),
+        _ => {
+            return Err(usage_err("Usage: filetemp cache edit [FILE_TYPE] <NAME>"));
+        }
+    };
+
+    let path = cache_file_path()?;
+    let content = {
+        let _lock = CacheLock::acquire(&path, false)?;
+        read_cache_file(&path)?
+    };
+    let (file_type, text) = match cache_args_text(&content, name, file_type)? {
+        Some(t) => t,
+        None => return Err(no_cache_err(name, file_type)),
+    };
+    let file_type = FileType::match_type(&file_type);
+
+    let edit_dir = scratch::new_dir("cache-edit").map_err(|e| CacheError::Unchanged {
+        name: name.to_string(),
+        reason: Some(e),
+    })?;
+    let edit_path = edit_dir.join(format!("{}.toml", file_type.to_str()));
+    if let Err(e) = fs::write(&edit_path, &text) {
+        return Err(IoError::new("write", edit_path, e).into());
+    }
+
+    let edited = loop {
+        run_editor(&edit_path, name)?;
+        let edited = match fs::read_to_string(&edit_path) {
+            Ok(e) => e,
+            Err(e) => return Err(IoError::new("read", edit_path, e).into()),
+        };
+        if edited == text {
+            eprintln!("Cache \"{}\" is unchanged", name);
+            return Ok(());
+        }
+
+        match validate_edited_cache(&edited, name, file_type) {
+            Ok(cache) => break cache,
+            Err(e) => {
+                logging::error(e);
+                if !prompt::can_prompt() || !prompt::confirm("Edit again?", true) {
+                    return Err(CacheError::Unchanged {
+                        name: name.to_string(),
+                        reason: None,
+                    }
+                    .into());
+                }
+            }
+        }
+    };
+
+    // Read again under the lock, the cache file may have changed while editing.
+    let _lock = CacheLock::acquire(&path, true)?;
+    let content = read_cache_file(&path)?;
+    let result = save_cache(&content, &edited)?;
+    verify_cache_chain(&result, file_type, name)?;
+    write_cache_file(&path, &result)?;
+    logging::verbose(format_args!("Saved cache \"{}\"", name));
+    Ok(())
+}
+
+/// `$VISUAL` or `$EDITOR`, which may carry arguments, e.g. "code --wait".
+fn run_editor(path: &Path, cache_name: &str) -> Result<(), CacheError> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|e| !e.trim().is_empty())
+        .unwrap_or_else(|| String::from(if cfg!(windows) { "notepad" } else { "vi" }));
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or_default();
+
+    match std::process::Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+    {
+        Ok(s) if s.success() => Ok(()),
+        Ok(_) => Err(CacheError::Unchanged {
+            name: cache_name.to_string(),
+            reason: Some(format!("Editor \"{}\" failed", editor)),
+        }),
+        Err(e) => Err(CacheError::Unchanged {
+            name: cache_name.to_string(),
+            reason: Some(format!(
+                "Failed to run editor \"{}\" ({}), set $VISUAL or $EDITOR",
+                editor, e
+            )),
+        }),
+    }
+}
+
+/// The edited arguments, checked as if loaded with `--use`.
+fn validate_edited_cache(
+    text: &str,
+    name: &str,
+    file_type: FileType,
+) -> Result<ArgCache, FiletempError> {
+    let mut loaded = CommandArg::new();
+    define_args(&mut loaded);
+    if loaded.process_program_args(&[file_type.to_str()]).is_err() {
+        return Err(ArgError::UnknownFileType(file_type.to_str().to_string()).into());
+    }
+
+    let valid_args = file_type_arg_names(file_type)?;
+    let cache = parse_cache_args(text, name, file_type, valid_args.iter().map(String::as_str))?;
+    loaded.add_layer(cache_layer(&cache));
+    apply_layers(&mut loaded)?;
+    Ok(cache)
+}
+
+pub(super) fn delete_cache(args: &[&str]) -> Result<(), FiletempError> {
+    let (file_type, name) = match cache_command_args(args) {
+        (t, [name]) => (t, *name),
+        _ => {
+            return Err(usage_err(
+                "Usage: filetemp cache delete [FILE_TYPE] <NAME|PATTERN>",
+            ));
+        }
+    };
+
+    let path = cache_file_path()?;
+    let _lock = CacheLock::acquire(&path, true)?;
+    let content = read_cache_file(&path)?;
+    if is_glob(name) {
+        let (result, removed) = remove_matching_caches(&content, name, file_type)?;
+        if removed.is_empty() {
+            return Err(CacheError::NoMatch(name.to_string()).into());
+        }
+        write_cache_file(&path, &result)?;
+        let names: Vec<String> = removed
+            .iter()
+            .map(|(t, n)| format!("{} ({})", n, t))
+            .collect();
+        logging::success(format_args!(
+            "Deleted {} cache(s): {}",
+            names.len(),
+            names.join(", ")
+        ));
+        return Ok(());
+    }
+
+    let result = match remove_cache(&content, name, file_type)? {
+        Some(r) => r,
+        None => return Err(no_cache_err(name, file_type)),
+    };
+    write_cache_file(&path, &result)?;
+    logging::verbose(format_args!("Deleted cache \"{}\"", name));
+    Ok(())
+}
+
+pub(super) fn rename_saved_cache(args: &[&str]) -> Result<(), FiletempError> {
+    let (file_type, old, new) = match cache_command_args(args) {
+        (t, [old, new]) => (t, *old, *new),
+        _ => {
+            return Err(usage_err(
+                "Usage: filetemp cache rename [FILE_TYPE] <OLD> <NEW>",
+            ));
+        }
+    };
+    verify_cache_name(new)?;
+
+    let path = cache_file_path()?;
+    let _lock = CacheLock::acquire(&path, true)?;
+    let content = read_cache_file(&path)?;
+    let result = match rename_cache(&content, old, new, file_type)? {
+        Some(r) => r,
+        None => return Err(no_cache_err(old, file_type)),
+    };
+    write_cache_file(&path, &result)?;
+    logging::verbose(format_args!("Renamed cache \"{}\" to \"{}\"", old, new));
+    Ok(())
+}
+
+pub(super) fn export_saved_caches(args: &[&str]) -> Result<(), FiletempError> {
+    const USAGE: &str = "Usage: filetemp cache export [[FILE_TYPE] NAME] [--path <FILE>]";
+    let mut names = Vec::new();
+    let mut out_path = None;
+    let mut rest = args.iter();
+    while let Some(&arg) = rest.next() {
+        match arg {
+            "--path" => match rest.next() {
+                Some(&p) => out_path = Some(p),
+                None => return Err(usage_err(USAGE)),
+            },
+            _ if arg.starts_with('-') => return Err(usage_err(USAGE)),
+            _ => names.push(arg),
+        }
+    }
+    let (file_type, name) = match cache_command_args(&names) {
+        (None, []) => (None, None),
+        (t, [name]) => (t, Some(*name)),
+        _ => return Err(usage_err(USAGE)),
+    };
+
+    let path = cache_file_path()?;
+    let _lock = CacheLock::acquire(&path, false)?;
+    let content = read_cache_file(&path)?;
+    let json = match export_caches(&content, name, file_type)? {
+        Some(j) => j,
+        None => return Err(no_cache_err(name.unwrap_or_default(), file_type)),
+    };
+
+    match out_path {
+        Some(p) => {
+            if let Err(e) = fs::write(p, json) {
+                return Err(IoError::new("write", p, e).into());
+            }
+            logging::verbose(format_args!("Exported caches to \"{}\"", p));
+        }
+        None => print!("{}", json),
+    }
+    Ok(())
+}
+
+pub(super) fn import_saved_caches(args: &[&str]) -> Result<(), FiletempError> {
+    let (file, replace) = match args {
+        [file] => (*file, false),
+        [file, "--replace"] | ["--replace", file] => (*file, true),
+        _ => {
+            return Err(usage_err("Usage: filetemp cache import <FILE> [--replace]"));
+        }
+    };
+
+    let json = match fs::read_to_string(file) {
+        Ok(j) => j,
+        Err(e) => return Err(IoError::new("read", file, e).into()),
+    };
+    let path = cache_file_path()?;
+    let _lock = CacheLock::acquire(&path, true)?;
+    let content = read_cache_file(&path)?;
+    let (result, names) = import_caches(&content, &json, replace)?;
+    for name in names.iter() {
+        verify_cache_name(name)?;
+    }
+    write_cache_file(&path, &result)?;
+    logging::success(format_args!(
+        "Imported {} cache(s): {}",
+        names.len(),
+        names.join(", ")
+    ));
+    Ok(())
+}
+
+/// Cache commands take the file type before the names to tell apart caches of
+/// the same name, a leading argument naming a file type is taken as one.
+fn cache_command_args<'a, 'b>(args: &'a [&'b str]) -> (Option<&'static str>, &'a [&'b str]) {
+    match args {
+        [first, rest @ ..] if !rest.is_empty() => match FileType::match_type(first) {
+            FileType::Unknown => (None, args),
+            t => (Some(t.to_str()), rest),
+        },
+        _ => (None, args),
+    }
+}
+
+fn no_cache_err(name: &str, file_type: Option<&str>) -> FiletempError {
+    CacheError::NotFound {
+        name: name.to_string(),
+        file_type: file_type.map(String::from),
+    }
+    .into()
+}
+
+/// Cache names are TOML table keys, kept to a single printable line.
+/// Commas separate the caches given to `--use`.
+fn verify_cache_name(name: &str) -> Result<(), ArgError> {
+    if name.trim().is_empty() || name.contains(char::is_control) || name.contains(',') {
+        Err(ArgError::Invalid(format!(
+            "Invalid cache name \"{}\", it must not be empty or contain commas or control characters",
+            name.escape_debug()
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// Names given to `--use`, a comma separated list of caches to layer.
+pub(super) fn used_cache_names(cmd: &CommandArg) -> Vec<String> {
+    match cmd.get_arg("use") {
+        Some(names) => names
+            .split(',')
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .map(String::from)
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Add a layer for each cache given to `--use`, later caches take precedence
+/// over earlier ones and a cache over the caches it extends.
+pub(super) fn add_cache_layers(cmd: &mut CommandArg) -> Result<(), FiletempError> {
+    let cache_names = used_cache_names(cmd);
+    if cache_names.is_empty() {
+        return Ok(());
+    }
+    if cmd.get_flag("no-cache") {
+        logging::verbose(format_args!(
+            "Not loading cache \"{}\", --no-cache is given",
+            cache_names.join(",")
+        ));
+        return Ok(());
+    }
+
+    let config_file_path = cache_file_path()?;
+    let _lock = CacheLock::acquire(&config_file_path, false)?;
+
+    // No cache file yet means no saved caches, not an error.
+    let file_type = cmd.get_file_type();
+    let caches = match OpenOptions::new().read(true).open(&config_file_path) {
+        Ok(config_file) => {
+            let mut reader: ConfigReader = ConfigReader::new(config_file, &config_file_path);
+            let valid_args = cmd
+                .query_valid_args()
+                .map(|arg_group| arg_group.name.as_ref());
+            let names: Vec<&str> = cache_names.iter().map(|n| n.as_str()).collect();
+            reader.read_caches(file_type, &names, valid_args)?
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(CacheError::Io(IoError::new("open", config_file_path, e)).into()),
+    };
+
+    for cache_name in cache_names.iter() {
+        let chain = match cache_chain(&caches, cache_name)? {
+            Some(c) => c,
+            None => return Err(missing_cache_err(&config_file_path, cache_name, file_type)),
+        };
+        for cache_item in chain {
+            cmd.add_layer(cache_layer(cache_item));
+            logging::verbose(format_args!(
+                "Loaded cache \"{}\" with {} argument(s)",
+                cache_item.cache_name,
+                cache_item.args.len()
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Names of the arguments a cache of `file_type` may store.
+fn file_type_arg_names(file_type: FileType) -> Result<Vec<String>, ArgError> {
+    let mut cmd = CommandArg::new();
+    define_args(&mut cmd);
+    if cmd.process_program_args(&[file_type.to_str()]).is_err() {
+        return Err(ArgError::UnknownFileType(file_type.to_str().to_string()));
+    }
+    Ok(cmd.query_valid_args().map(|a| a.name.to_string()).collect())
+}
+
+/// Saved content must not leave a cache extending a missing cache or itself.
+fn verify_cache_chain(content: &str, file_type: FileType, name: &str) -> Result<(), FiletempError> {
+    let valid_args = file_type_arg_names(file_type)?;
+    let caches = parse_caches(content, file_type, valid_args.iter().map(String::as_str))?;
+    for cache in caches
+        .iter()
+        .filter(|c| c.extends.is_some() || c.cache_name == name)
+    {
+        cache_chain(&caches, &cache.cache_name)?;
+    }
+    Ok(())
+}
+
+/// A cache of another file type would inject arguments meant for that type,
+/// so it is named in the error instead.
+fn missing_cache_err(path: &Path, cache_name: &str, file_type: FileType) -> FiletempError {
+    let content = read_cache_file(path).unwrap_or_default();
+    let other_types: Vec<String> = cache_summaries(&content)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|s| s.cache_name == cache_name)
+        .map(|s| s.file_type)
+        .collect();
+    if other_types.is_empty() {
+        no_cache_err(cache_name, Some(file_type.to_str()))
+    } else {
+        CacheError::OtherFileType {
+            name: cache_name.to_string(),
+            saved_for: other_types,
+            file_type: file_type.to_str().to_string(),
+        }
+        .into()
+    }
+}
+
+/// Count a `--use` of each cache and note when it happened, for `cache list` and `cache prune`.
+pub(super) fn record_arg_cache_use(cmd: &CommandArg) -> Result<(), FiletempError> {
+    let cache_names = used_cache_names(cmd);
+    if cache_names.is_empty() || cmd.get_flag("no-cache") || preview(cmd) != Preview::Off {
+        return Ok(());
+    }
+
+    let path = cache_file_path()?;
+    let _lock = CacheLock::acquire(&path, true)?;
+    let mut content = read_cache_file(&path)?;
+    for cache_name in cache_names {
+        if let Some(result) = record_cache_use(&content, &cache_name, cmd.get_file_type())? {
+            content = result;
+        }
+    }
+    Ok(write_cache_file(&path, &content)?)
+}
+
+pub(super) fn write_arg_cache(cmd: &CommandArg) -> Result<(), FiletempError> {
+    let cache_name = if let Some(n) = cmd.get_arg("save-as") {
+        n
+    } else {
+        return Ok(());
+    };
+    if cmd.get_flag("no-cache") {
+        logging::verbose(format_args!(
+            "Not saving cache \"{}\", --no-cache is given",
+            cache_name
+        ));
+        return Ok(());
+    }
+    if preview(cmd) != Preview::Off {
+        println!("Would save the arguments as cache \"{}\"", cache_name);
+        return Ok(());
+    }
+
+    verify_cache_name(cache_name)?;
+
+    let file_type = cmd.get_file_type();
+    let mut new_cache = ArgCache::new(cache_name, file_type);
+    new_cache.extends = cmd.get_arg("extends").map(String::from);
+    new_cache
+        .args
+        .extend(cmd.extract_given_args().into_iter().map(CachedArg::from));
+
+    // Read again under the lock, other processes may have saved caches meanwhile.
+    let config_file_path = cache_file_path()?;
+    let _lock = CacheLock::acquire(&config_file_path, true)?;
+    let content = read_cache_file(&config_file_path)?;
+
+    // Only what differs from the parent is stored, the rest is inherited.
+    if let Some(parent) = &new_cache.extends {
+        let valid_args = file_type_arg_names(file_type)?;
+        let caches = parse_caches(&content, file_type, valid_args.iter().map(String::as_str))?;
+        let chain = match cache_chain(&caches, parent)? {
+            Some(c) => c,
+            None => {
+                return Err(CacheError::Extends(format!(
+                    "Cannot extend \"{}\", it is not a saved {} cache",
+                    parent,
+                    file_type.to_str()
+                ))
+                .into());
+            }
+        };
+        let inherited: Vec<Layer> = chain.into_iter().map(cache_layer).collect();
+
+        let own = new_cache.args;
+        new_cache.args = own
+            .iter()
+            .filter(|arg| {
+                let values: Vec<String> = own
+                    .iter()
+                    .filter(|a| a.arg == arg.arg)
+                    .map(|a| a.content.clone())
+                    .collect();
+                // The nearest cache giving the argument is the one it is inherited from.
+                inherited.iter().rev().find_map(|l| l.values_of(&arg.arg))
+                    != Some(values.as_slice())
+            })
+            .map(|arg| CachedArg {
+                arg: arg.arg.clone(),
+                content: arg.content.clone(),
+            })
+            .collect();
+    }
+
+    let result = save_cache(&content, &new_cache)?;
+    verify_cache_chain(&result, file_type, cache_name)?;
+
+    write_cache_file(&config_file_path, &result)?;
+    logging::verbose(format_args!(
+        "Saved cache \"{}\" to \"{}\"",
+        cache_name,
+        config_file_path.display()
+    ));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_value_escapes_control_characters_only() {
+        assert_eq!(display_value("a: b \"c\" 'd'"), "a: b \"c\" 'd'");
+        assert_eq!(display_value("one\ntwo"), "\"one\\ntwo\"");
+        assert_eq!(display_value("tab\there"), "\"tab\\there\"");
+        assert_eq!(
+            display_value("say \"hi\"\n'bye'"),
+            "\"say \\\"hi\\\"\\n'bye'\""
+        );
+    }
+
+    #[test]
+    fn shell_word_quotes_when_needed() {
+        assert_eq!(shell_word("demo"), "demo");
+        assert_eq!(shell_word("https://x.org:80/a"), "https://x.org:80/a");
+        assert_eq!(shell_word(""), "''");
+        assert_eq!(shell_word("a b"), "'a b'");
+        assert_eq!(shell_word("$HOME"), "'$HOME'");
+        assert_eq!(shell_word("it's"), "'it'\\''s'");
+        assert_eq!(shell_word("one\ntwo"), "'one\ntwo'");
+    }
+}
@@ -0,0 +1,202 @@
+//! The `config` commands editing the user and project configs.
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    config_file::filetemp_data_dir,
+    error::{ArgError, FiletempError},
+    file_types::FileType,
+    logging,
+    program_args::{ArgProcessErr, CommandArg},
+    project_config::{
+        PROJECT_CONFIG_NAMES, USER_CONFIG_FILE_NAME, edit as config_edit, file_type_defaults,
+        find_project_config, read_config,
+    },
+};
+
+use super::{define_args, usage_err};
+
+/// The config file `filetemp config` reads or changes, the nearest project
+/// config with `--project`, otherwise config.toml in the data directory, and
+/// the other words. Setting a value creates .filetemp.toml in the current
+/// directory when there is no project config yet.
+fn config_scope<'a>(
+    args: &[&'a str],
+    create: bool,
+) -> Result<(PathBuf, Vec<&'a str>), FiletempError> {
+    let words: Vec<&str> = args.iter().copied().filter(|a| *a != "--project").collect();
+    if words.len() == args.len() {
+        let (dir, _) = filetemp_data_dir()?;
+        return Ok((dir.join(USER_CONFIG_FILE_NAME), words));
+    }
+
+    let path = match find_project_config(Path::new(".")) {
+        Some(p) => p,
+        None if create => PathBuf::from(PROJECT_CONFIG_NAMES[0]),
+        None => {
+            return Err(ArgError::Config(format!(
+                "No project config in the current directory or above, looked for {}",
+                PROJECT_CONFIG_NAMES.join(", ")
+            ))
+            .into());
+        }
+    };
+    Ok((path, words))
+}
+
+/// The file type and argument of a `<FILE_TYPE>.<ARG>` key.
+fn config_key<'a>(key: &'a str, usage: &str) -> Result<(FileType, &'a str), FiletempError> {
+    let (file_type, arg) = match key.split_once('.') {
+        Some((t, a)) if !t.is_empty() && !a.is_empty() => (t, a),
+        _ => return Err(usage_err(usage)),
+    };
+    match FileType::match_type(file_type) {
+        FileType::Unknown => Err(ArgError::UnknownFileType(file_type.to_string()).into()),
+        ty => Ok((ty, arg)),
+    }
+}
+
+/// An argument a config file cannot hold, with the closest name if it looks like a typo.
+fn config_arg_err(e: ArgProcessErr, file_type: FileType) -> FiletempError {
+    match e {
+        ArgProcessErr::InvalidArg(arg, suggestion) => {
+            let mut message = format!("Unknown argument \"{}\" of {}", arg, file_type.to_str());
+            if let Some(s) = suggestion {
+                message.push_str(&format!(", did you mean \"{}\"?", s));
+            }
+            ArgError::Config(message).into()
+        }
+        e => ArgError::Parse(e).into(),
+    }
+}
+
+pub(super) fn config_get(args: &[&str]) -> Result<(), FiletempError> {
+    const USAGE: &str = "Usage: filetemp config get <FILE_TYPE>.<ARG> [--project]";
+    let (path, words) = config_scope(args, false)?;
+    let key = match words.as_slice() {
+        [key] if !key.starts_with('-') => *key,
+        _ => return Err(usage_err(USAGE)),
+    };
+    let (file_type, arg) = config_key(key, USAGE)?;
+    let mut cmd = CommandArg::new();
+    define_args(&mut cmd);
+    let arg = cmd
+        .config_arg_name(file_type, arg)
+        .map_err(|e| config_arg_err(e, file_type))?;
+
+    let table = if path.is_file() {
+        read_config(&path).map_err(ArgError::Config)?
+    } else {
+        toml::Table::new()
+    };
+    let defaults =
+        file_type_defaults(&path, &table, file_type.to_str()).map_err(ArgError::Config)?;
+    match defaults.into_iter().find(|(a, _)| *a == arg) {
+        Some((_, values)) => {
+            for value in values.iter() {
+                println!("{}", value);
+            }
+            Ok(())
+        }
+        None => Err(ArgError::Config(format!(
+            "{}.{} is not set in \"{}\"",
+            file_type.to_str(),
+            arg,
+            path.display()
+        ))
+        .into()),
+    }
+}
+
+/// Set a default in the config, several values for an argument taking more than one.
+pub(super) fn config_set(args: &[&str]) -> Result<(), FiletempError> {
+    const USAGE: &str = "Usage: filetemp config set <FILE_TYPE>.<ARG> <VALUE>... [--project]";
+    let (path, words) = config_scope(args, true)?;
+    let (key, values) = match words.split_first() {
+        Some((key, values)) if !values.is_empty() => (*key, values),
+        _ => return Err(usage_err(USAGE)),
+    };
+    let (file_type, arg) = config_key(key, USAGE)?;
+    let mut cmd = CommandArg::new();
+    define_args(&mut cmd);
+    let (arg, value) = cmd
+        .config_value(file_type, arg, values)
+        .map_err(|e| config_arg_err(e, file_type))?;
+
+    let mut doc = config_edit::load(&path).map_err(ArgError::Config)?;
+    config_edit::set(&mut doc, &path, file_type.to_str(), &arg, &value)
+        .map_err(ArgError::Config)?;
+    config_edit::save(&path, &doc)?;
+    logging::success(format_args!(
+        "Set {}.{} in \"{}\"",
+        file_type.to_str(),
+        arg,
+        path.display()
+    ));
+    Ok(())
+}
+
+/// Remove a default from the config. An argument the file type no longer has
+/// is removed by the name written in the config.
+pub(super) fn config_unset(args: &[&str]) -> Result<(), FiletempError> {
+    const USAGE: &str = "Usage: filetemp config unset <FILE_TYPE>.<ARG> [--project]";
+    let (path, words) = config_scope(args, false)?;
+    let key = match words.as_slice() {
+        [key] if !key.starts_with('-') => *key,
+        _ => return Err(usage_err(USAGE)),
+    };
+    let (file_type, arg) = config_key(key, USAGE)?;
+    let mut cmd = CommandArg::new();
+    define_args(&mut cmd);
+    let name = cmd
+        .config_arg_name(file_type, arg)
+        .unwrap_or_else(|_| arg.to_string());
+
+    let mut doc = config_edit::load(&path).map_err(ArgError::Config)?;
+    if !config_edit::unset(&mut doc, file_type.to_str(), &name)
+        && !config_edit::unset(&mut doc, file_type.to_str(), arg)
+    {
+        println!(
+            "{}.{} is not set in \"{}\"",
+            file_type.to_str(),
+            name,
+            path.display()
+        );
+        return Ok(());
+    }
+    config_edit::save(&path, &doc)?;
+    logging::success(format_args!(
+        "Removed {}.{} from \"{}\"",
+        file_type.to_str(),
+        name,
+        path.display()
+    ));
+    Ok(())
+}
+
+/// Print each value of the config as `<TABLE>.<KEY> = <VALUE>`.
+pub(super) fn config_list(args: &[&str]) -> Result<(), FiletempError> {
+    let (path, words) = config_scope(args, false)?;
+    if !words.is_empty() {
+        return Err(usage_err("Usage: filetemp config list [--project]"));
+    }
+    if !path.is_file() {
+        logging::verbose(format_args!("No config file \"{}\"", path.display()));
+        return Ok(());
+    }
+
+    logging::verbose(format_args!("Values of \"{}\"", path.display()));
+    let table = read_config(&path).map_err(ArgError::Config)?;
+    print_config_values("", &table);
+    Ok(())
+}
+
+fn print_config_values(prefix: &str, table: &toml::Table) {
+    for (key, value) in table.iter() {
+        let key = format!("{}{}", prefix, key);
+        match value {
+            toml::Value::Table(t) => print_config_values(&format!("{}.", key), t),
+            v => println!("{} = {}", key, v),
+        }
+    }
+}
@@ -0,0 +1,818 @@
+//! Generating files: `gen`, `apply`, `watch`, `new` and plugins, and writing their output.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+use crate::{
+    config_file::filetemp_data_dir,
+    error::{ArgError, CacheError, FiletempError, GenerateError, IoError},
+    events::{self, Event, EventBus},
+    exit_status::{self, Failure},
+    file_types::{
+        self, FileType, cmake_merge, companions, custom, generate_example, get_result_filename,
+        process_args, stamp, verify_existed_args,
+    },
+    hooks,
+    i18n::{tr, trf},
+    logging,
+    logging::color::{self, Stream},
+    output_sink::{FILE_MODE, OutputSink, Overwrite, Preview, open_sink},
+    plugins,
+    program_args::{ArgProcessErr, CommandArg, prompt},
+    project_config::{SPEC_FILE_NAME, find_spec, load_spec},
+    watch,
+};
+
+use super::cache::{
+    record_arg_cache_use, shell_word, used_cache_names, verify_cache_roundtrip, write_arg_cache,
+};
+use super::sources::resolve_arg_sources;
+use super::{define_args, fail, process_arg_parse_err, report, usage_err};
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum OutputMode {
+    NoOutput,
+    OutputFile,
+    OutputShow,
+    OutputShowAndFile,
+    SetConfig,
+}
+
+impl OutputMode {
+    fn from_cmd(cmd: &CommandArg) -> Self {
+        let mut ret = Self::NoOutput;
+        if cmd.get_arg("save-as").is_some() || cmd.get_arg("use").is_some() {
+            ret = Self::SetConfig;
+        }
+        if cmd.get_arg("path").is_some() || cmd.get_arg("archive").is_some() {
+            ret = Self::OutputFile;
+        }
+        if cmd.get_flag("show") {
+            ret = if ret == Self::OutputFile {
+                Self::OutputShowAndFile
+            } else {
+                Self::OutputShow
+            };
+        }
+
+        ret
+    }
+
+    fn show(self) -> bool {
+        self == Self::OutputShow || self == Self::OutputShowAndFile
+    }
+
+    fn file(self) -> bool {
+        self == Self::OutputFile || self == Self::OutputShowAndFile
+    }
+
+    fn has_output(self) -> bool {
+        self.show() || self.file()
+    }
+}
+
+pub(super) fn generate(raw_args: &[&str], usage_name: &str) {
+    if let Some((&names, args)) = raw_args.split_first()
+        && names.contains('+')
+    {
+        generate_group(names, args, usage_name);
+        return;
+    }
+    if let Some(&name) = raw_args.first()
+        && FileType::match_type(name) == FileType::Unknown
+        && let Some(plugin) = plugins::find_plugin(name)
+    {
+        generate_with_plugin(&plugin, name, &raw_args[1..], usage_name);
+        return;
+    }
+
+    // Define usable arguments.
+    let mut cmd = CommandArg::new();
+    define_args(&mut cmd);
+    cmd.usage_name(usage_name.to_string());
+
+    // Process actual arguments, check their validity.
+    if let Err(e) = cmd.process_program_args(raw_args) {
+        process_arg_parse_err(&cmd, e);
+        return;
+    }
+    logging::set_level(logging::level_from_flags(
+        cmd.get_flag("quiet"),
+        cmd.get_flag("verbose"),
+    ));
+
+    let file_type = cmd.get_file_type();
+
+    let output_mode = OutputMode::from_cmd(&cmd);
+    let explain = cmd.get_arg("explain").map(String::from);
+
+    // Do nothing if no output is required or no possibility for cache IO.
+    if output_mode == OutputMode::NoOutput && explain.is_none() {
+        return;
+    }
+
+    if let Err(e) = resolve_arg_sources(&mut cmd) {
+        let missing = match &e {
+            FiletempError::Cache(CacheError::NotFound { name, .. }) => Some(name.clone()),
+            _ => None,
+        };
+        report(e);
+        if let Some(name) = missing {
+            eprintln!("{}", trf("Create it with --save-as {}", &[&name]));
+        }
+        return;
+    }
+
+    if let Some(arg) = explain {
+        // Built-in defaults apply last, a missing required argument is not given by any source.
+        let _ = cmd.assert_required_args_exist();
+        match cmd.explain(&arg) {
+            Ok(text) => print!("{}", text),
+            Err(e) => fail(Failure::Args, e),
+        }
+        return;
+    }
+    if let Err(e) = record_arg_cache_use(&cmd) {
+        logging::warn(e);
+    }
+
+    if output_mode.has_output() {
+        let mut result = cmd.assert_required_args_exist();
+        if let Err(ArgProcessErr::MissingArgs { missing, .. }) = &result
+            && !cmd.get_flag("no-interactive")
+            && prompt::can_prompt()
+        {
+            result = cmd.prompt_for_args(missing);
+        }
+
+        match result {
+            Ok(()) => {}
+            Err(ArgProcessErr::MissingArgs { missing, required }) => {
+                exit_status::fail(Failure::Args);
+                eprint!("{}", cmd.missing_args_message(&missing, required));
+                return;
+            }
+            Err(e) => {
+                process_arg_parse_err(&cmd, e);
+                return;
+            }
+        }
+    }
+
+    if let Err(e) = verify_existed_args(&cmd) {
+        fail(Failure::Args, e);
+        return;
+    }
+
+    if let Some(p) = cmd.get_path("path")
+        && !cmd.get_flag("i-know-what-im-doing")
+        && let Err(e) = check_output_dir(p)
+    {
+        fail(Failure::Io, e);
+        return;
+    }
+
+    let mut events = EventBus::new();
+    events.subscribe(events::print_warnings);
+    if preview(&cmd) == Preview::Off {
+        events.subscribe(events::print_progress);
+    }
+    events.emit(Event::GenerationStarted { file_type });
+
+    let mut result_str = String::new();
+    if output_mode.has_output() {
+        let process_result: Result<String, FiletempError> = process_args(&cmd);
+
+        result_str = match process_result {
+            Ok(r) => r,
+            Err(e) => {
+                report(e);
+                return;
+            }
+        };
+    }
+
+    // The stamp is left out of `result_str`, which is compared with the output of the cache.
+    let output = match output_mode.has_output() && cmd.get_flag("stamp") {
+        true => match stamp_output(&cmd, &result_str) {
+            Ok(s) => s,
+            Err(e) => {
+                fail(Failure::Args, e);
+                return;
+            }
+        },
+        false => result_str.clone(),
+    };
+
+    if output_mode.show() {
+        print_preview(get_result_filename(file_type), &output);
+    }
+
+    let mut merged = None;
+    if output_mode.file() && cmd.get_flag("merge") {
+        let result = merge_existing(&cmd, &output).and_then(|m| match m {
+            // The stamp of the existing file is replaced.
+            Some(m) if cmd.get_flag("stamp") => stamp_output(&cmd, &m)
+                .map(Some)
+                .map_err(FiletempError::from),
+            m => Ok(m),
+        });
+        match result {
+            Ok(m) => merged = m,
+            Err(e) => {
+                report(e);
+                return;
+            }
+        }
+    }
+
+    if output_mode.file() {
+        let overwrite = Overwrite::from_flags(
+            cmd.get_flag("force"),
+            cmd.get_flag("backup"),
+            !cmd.get_flag("no-interactive") && prompt::can_prompt(),
+        );
+        if merged.is_none() {
+            warn_hand_edits(&cmd, overwrite, &mut events);
+        }
+        let sink = open_sink(
+            cmd.get_path("path"),
+            cmd.get_path("archive"),
+            overwrite,
+            preview(&cmd),
+        );
+
+        let written = sink.and_then(|mut sink| {
+            let written = match &merged {
+                Some(m) => write_output(&cmd, sink.as_mut(), m, true, &mut events),
+                None => write_output(&cmd, sink.as_mut(), &output, false, &mut events),
+            };
+            // Files written before a failure still go into the manifest.
+            written.and(sink.finish())
+        });
+        if let Err(e) = written {
+            fail(Failure::Io, e);
+            return;
+        }
+        run_type_hooks(&cmd, &mut events);
+        run_git_init(&cmd, &mut events);
+    }
+
+    if cmd.get_arg("save-as").is_some()
+        && !cmd.get_flag("no-cache")
+        && output_mode.has_output()
+        && let Err(e) = logging::without_verbose(|| verify_cache_roundtrip(&cmd, &result_str))
+    {
+        events.warn(&e.to_string());
+    }
+
+    if let Err(e) = write_arg_cache(&cmd) {
+        exit_status::fail(e.failure());
+        events.warn(&e.to_string());
+    }
+
+    events.finish();
+}
+
+/// The spec given first in `args` or the nearest one, and the options after it.
+/// None after reporting that there is none.
+fn spec_and_options<'a, 'b>(args: &'a [&'b str]) -> Option<(PathBuf, &'a [&'b str])> {
+    let (spec, options) = match args.split_first() {
+        Some((first, rest)) if !first.starts_with('-') => (Some(PathBuf::from(first)), rest),
+        _ => (None, args),
+    };
+    match spec.or_else(|| find_spec(Path::new("."))) {
+        Some(s) => Some((s, options)),
+        None => {
+            report(usage_err(&format!(
+                "No {} in the current directory or above it, give the spec to apply",
+                SPEC_FILE_NAME
+            )));
+            None
+        }
+    }
+}
+
+/// Generate every file type of the project spec, the one given or the nearest
+/// above the current directory, with the options after it added to each.
+pub(super) fn apply_spec(args: &[&str]) {
+    let (spec, options) = match spec_and_options(args) {
+        Some(s) => s,
+        None => return,
+    };
+    let entries = match load_spec(&spec) {
+        Ok(e) => e,
+        Err(e) => {
+            report(ArgError::Config(e).into());
+            return;
+        }
+    };
+    if entries.is_empty() {
+        logging::warn(format_args!("\"{}\" lists no file types", spec.display()));
+        return;
+    }
+
+    for entry in entries {
+        logging::verbose(format_args!(
+            "Applying [{}] of \"{}\"",
+            entry.file_type,
+            spec.display()
+        ));
+        let words: Vec<&str> = std::iter::once(entry.file_type.as_str())
+            .chain(entry.args.iter().map(String::as_str))
+            .chain(options.iter().copied())
+            .collect();
+        generate(&words, "filetemp apply");
+    }
+}
+
+/// Apply a spec in a child process whenever it or its templates change, so
+/// each run loads the templates anew. Generated files are replaced.
+pub(super) fn watch_spec(args: &[&str]) {
+    let mut args = args.to_vec();
+    let debounce = match take_debounce_arg(&mut args) {
+        Ok(ms) => Duration::from_millis(ms.unwrap_or(watch::DEFAULT_DEBOUNCE_MS)),
+        Err(e) => {
+            report(usage_err(&e));
+            return;
+        }
+    };
+    let (spec, options) = match spec_and_options(&args) {
+        Some(s) => s,
+        None => return,
+    };
+    let exe = match std::env::current_exe() {
+        Ok(e) => e,
+        Err(e) => {
+            fail(
+                Failure::Io,
+                format!("Failed to find the filetemp executable: {}", e),
+            );
+            return;
+        }
+    };
+
+    let data_dir = filetemp_data_dir().ok().map(|(dir, _)| dir);
+    watch::run(&spec, options, data_dir.as_deref(), debounce, || {
+        let mut child = Command::new(&exe);
+        child.arg("apply").arg(&spec).args(options);
+        if !options.iter().any(|o| o.eq_ignore_ascii_case("--force")) {
+            child.arg("--force");
+        }
+        match child.stdin(Stdio::null()).status() {
+            Ok(status) => status.success(),
+            Err(e) => {
+                logging::warn(format_args!("Failed to run \"{}\": {}", exe.display(), e));
+                false
+            }
+        }
+    });
+}
+
+/// Remove `--debounce <MS>` from `args`, giving its value.
+fn take_debounce_arg(args: &mut Vec<&str>) -> Result<Option<u64>, String> {
+    let idx = match args
+        .iter()
+        .position(|a| *a == "--debounce" || a.starts_with("--debounce="))
+    {
+        Some(i) => i,
+        None => return Ok(None),
+    };
+    let arg = args.remove(idx);
+    let value = match arg.split_once('=') {
+        Some((_, v)) => v,
+        None if idx < args.len() => args.remove(idx),
+        None => return Err(String::from("Missing value for --debounce <MS>")),
+    };
+    match value.parse() {
+        Ok(ms) => Ok(Some(ms)),
+        Err(_) => Err(format!(
+            "Invalid value \"{}\" for --debounce, expected milliseconds",
+            value
+        )),
+    }
+}
+
+/// Ask what `gen` would be given, show the file it generates and write it on
+/// confirmation, saving the answers as a cache if a name is given.
+pub(super) fn new_wizard(args: &[&str]) {
+    if args.len() > 1 {
+        report(usage_err("Usage: filetemp new [FILE_TYPE]"));
+        return;
+    }
+    if !prompt::can_prompt() {
+        fail(
+            Failure::Args,
+            tr("filetemp new asks its questions in a terminal, use filetemp gen in scripts"),
+        );
+        return;
+    }
+
+    let mut cmd = CommandArg::new();
+    define_args(&mut cmd);
+    let file_type = match args.first() {
+        Some(name) => match FileType::match_type(name) {
+            FileType::Unknown => {
+                report(ArgError::UnknownFileType(name.to_string()).into());
+                return;
+            }
+            ty => ty,
+        },
+        None => match cmd.ask_file_type() {
+            Some(ty) => ty,
+            None => return,
+        },
+    };
+    let mut words = vec![file_type.to_str().to_string()];
+    match cmd.ask_type_args(file_type) {
+        Some(answers) => words.extend(answers),
+        None => return,
+    }
+    let path = match prompt::ask("Directory to generate into", Some(".")) {
+        Some(p) => p,
+        None => return,
+    };
+
+    let preview: Vec<&str> = words.iter().map(String::as_str).chain(["--show"]).collect();
+    generate(&preview, "filetemp new");
+    // Ends the preview line, stdout is flushed only at newlines.
+    println!();
+    if exit_status::failed() || !prompt::confirm(&trf("Write it into \"{}\"?", &[&path]), true) {
+        return;
+    }
+    words.extend([String::from("--path"), path]);
+    match prompt::ask("Save the answers as a cache named, empty to skip", None) {
+        Some(name) if !name.is_empty() => words.extend([String::from("--save-as"), name]),
+        Some(_) => {}
+        None => return,
+    }
+
+    generate(
+        &words.iter().map(String::as_str).collect::<Vec<_>>(),
+        "filetemp new",
+    );
+    if !exit_status::failed() {
+        let command: Vec<String> = words.iter().map(|w| shell_word(w)).collect();
+        eprintln!(
+            "{}",
+            trf(
+                "The same from the command line: filetemp {}",
+                &[&command.join(" ")]
+            )
+        );
+    }
+}
+
+/// Generate each file type of `names`, e.g. `cmake+gitignore`, with the
+/// arguments it defines of `args`. Companions among the names are written
+/// along with the first file type, as `--with` would. The types run one after
+/// another, they share `--path` and the manifest kept there.
+fn generate_group(names: &str, args: &[&str], usage_name: &str) {
+    let mut types = Vec::new();
+    let mut with = Vec::new();
+    for name in names.split('+') {
+        if companions::COMPANIONS.contains(&name) {
+            with.push(name);
+            continue;
+        }
+        match FileType::match_type(name) {
+            FileType::Unknown => {
+                report(ArgError::UnknownFileType(name.to_string()).into());
+                return;
+            }
+            ty if !types.contains(&ty) => types.push(ty),
+            _ => {}
+        }
+    }
+    if types.is_empty() {
+        report(usage_err(&format!(
+            "\"{}\" names no file type, companions are written along with one",
+            names
+        )));
+        return;
+    }
+
+    let mut cmd = CommandArg::new();
+    define_args(&mut cmd);
+    let with = format!("--with={}", with.join(","));
+    for (idx, &ty) in types.iter().enumerate() {
+        let mut words = vec![ty.to_str()];
+        let mut pos = 0;
+        while pos < args.len() {
+            let arg = args[pos];
+            if arg == "--" {
+                if cmd.takes_raw_values(ty) {
+                    words.extend_from_slice(&args[pos..]);
+                }
+                break;
+            }
+            // Arguments of another type in the group, with their values, are left out.
+            let known = cmd.known_option(ty, arg);
+            let other = types
+                .iter()
+                .find_map(|&t| cmd.known_option(t, arg).filter(|_| known.is_none()));
+            match (known, other) {
+                (Some(takes_value), _) => {
+                    words.push(arg);
+                    if takes_value && let Some(&value) = args.get(pos + 1) {
+                        words.push(value);
+                        pos += 1;
+                    }
+                }
+                (None, Some(takes_value)) => pos += usize::from(takes_value),
+                // Reported by the parser of this type.
+                (None, None) => words.push(arg),
+            }
+            pos += 1;
+        }
+        if idx == 0 && with.len() > "--with=".len() {
+            words.push(&with);
+        }
+
+        logging::verbose(format_args!("Generating {} of \"{}\"", ty.to_str(), names));
+        generate(&words, usage_name);
+    }
+}
+
+/// Generate a file type no manifest declares with its `filetemp-<type>` plugin,
+/// the arguments filetemp does not define are sent to the plugin.
+fn generate_with_plugin(plugin: &Path, file_type: &str, args: &[&str], usage_name: &str) {
+    let mut cmd = CommandArg::new();
+    plugins::define_args(&mut cmd, format!("{} {}", usage_name, file_type));
+    let after_help = format!(
+        "\"{}\" is generated by the plugin \"{}\". Other arguments are sent to it as JSON \
+         on stdin, its stdout is the generated file. Caches and configs do not apply to plugins.",
+        file_type,
+        plugin.display()
+    );
+    cmd.after_help(after_help);
+
+    let (own, plugin_args) = plugins::split_args(&cmd, args);
+    // A plugin never takes a word starting with "-" as a value, own options are
+    // checked for help when parsed.
+    let plugin_help = plugin_args
+        .iter()
+        .take_while(|a| **a != "--")
+        .any(|a| *a == "--help" || *a == "-h");
+    let own = match plugin_help {
+        true => vec!["--help"],
+        false => own,
+    };
+    if let Err(e) = cmd.process_program_args(&own) {
+        process_arg_parse_err(&cmd, e);
+        return;
+    }
+    logging::set_level(logging::level_from_flags(
+        cmd.get_flag("quiet"),
+        cmd.get_flag("verbose"),
+    ));
+
+    let output_mode = OutputMode::from_cmd(&cmd);
+    if !output_mode.has_output() {
+        return;
+    }
+    if let Some(p) = cmd.get_path("path")
+        && !cmd.get_flag("i-know-what-im-doing")
+        && let Err(e) = check_output_dir(p)
+    {
+        fail(Failure::Io, e);
+        return;
+    }
+
+    let mut events = EventBus::new();
+    events.subscribe(events::print_warnings);
+    if preview(&cmd) == Preview::Off {
+        events.subscribe(events::print_progress);
+    }
+    logging::verbose(format_args!(
+        "Generating {} file with \"{}\"",
+        file_type,
+        plugin.display()
+    ));
+
+    let content = match plugins::run(plugin, &plugins::request(file_type, &plugin_args)) {
+        Ok(c) => c,
+        Err(e) => {
+            fail(Failure::Generation, e);
+            return;
+        }
+    };
+    if output_mode.show() {
+        print_preview(cmd.get_arg("output-name").unwrap_or(file_type), &content);
+    }
+
+    if output_mode.file() {
+        let sink = open_sink(
+            cmd.get_path("path"),
+            cmd.get_path("archive"),
+            Overwrite::from_flags(
+                cmd.get_flag("force"),
+                cmd.get_flag("backup"),
+                prompt::can_prompt(),
+            ),
+            preview(&cmd),
+        );
+        let file_name = Path::new(cmd.get_arg("output-name").unwrap_or(file_type));
+        let written = sink.and_then(|mut sink| {
+            let written = sink
+                .write_file(file_name, content.as_bytes(), FILE_MODE)
+                .map(|path| events.emit(Event::FileWritten { path: &path }));
+            written.and(sink.finish())
+        });
+        if let Err(e) = written {
+            fail(Failure::Io, e);
+            return;
+        }
+    }
+
+    events.finish();
+}
+
+/// Name of the generated file below `--path`.
+fn output_file_name(cmd: &CommandArg) -> &Path {
+    Path::new(
+        cmd.get_arg("output-name")
+            .unwrap_or(get_result_filename(cmd.get_file_type())),
+    )
+}
+
+/// `content` with the provenance comment of `--stamp`.
+fn stamp_output(cmd: &CommandArg, content: &str) -> Result<String, ArgError> {
+    let file_name = output_file_name(cmd).file_name().unwrap_or_default();
+    stamp::add(
+        content,
+        &file_name.to_string_lossy(),
+        &used_cache_names(cmd),
+    )
+    .map_err(ArgError::Invalid)
+}
+
+/// Warn if the file at `--path` about to be replaced was edited since it was stamped.
+fn warn_hand_edits(cmd: &CommandArg, overwrite: Overwrite, events: &mut EventBus) {
+    // A refused file is kept, a backed up one keeps its edits in the backup.
+    if matches!(overwrite, Overwrite::Refuse | Overwrite::Backup) {
+        return;
+    }
+    let path = match cmd.get_path("path") {
+        Some(p) => p.join(output_file_name(cmd)),
+        None => return,
+    };
+    let stamp = match fs::read_to_string(&path)
+        .ok()
+        .as_deref()
+        .and_then(stamp::find)
+    {
+        Some(s) => s,
+        None => return,
+    };
+    if stamp.edited {
+        events.warn(&format!(
+            "\"{}\" was edited by hand since filetemp generated it on {}, replacing it \
+             loses the edits",
+            path.display(),
+            stamp.date
+        ));
+    }
+}
+
+/// The generated file merged into the one already at `--path`, or None if there is none.
+fn merge_existing(cmd: &CommandArg, generated: &str) -> Result<Option<String>, FiletempError> {
+    if cmd.get_file_type() != FileType::CMake {
+        return Err(
+            ArgError::Invalid(String::from("--merge only works for the cmake file type")).into(),
+        );
+    }
+    let path = match cmd.get_path("path") {
+        Some(p) => p.join(output_file_name(cmd)),
+        None => return Ok(None),
+    };
+    let existing = match fs::read_to_string(&path) {
+        Ok(e) => e,
+        Err(_) if !path.exists() => return Ok(None),
+        Err(e) => return Err(IoError::new("read", path, e).into()),
+    };
+    match cmake_merge::merge(&existing, generated) {
+        Ok(merged) => Ok(Some(merged)),
+        Err(message) => Err(GenerateError::Merge { path, message }.into()),
+    }
+}
+
+/// Write the generated file and the example sources if requested. A merged
+/// file replaces the existing one it was merged with. Stops at the first
+/// file that cannot be written.
+fn write_output(
+    cmd: &CommandArg,
+    sink: &mut dyn OutputSink,
+    content: &str,
+    merged: bool,
+    events: &mut EventBus,
+) -> Result<(), String> {
+    let file_name = output_file_name(cmd);
+    if merged {
+        sink.allow_merge(file_name);
+    }
+    let path = sink.write_file(file_name, content.as_bytes(), FILE_MODE)?;
+    events.emit(Event::FileWritten { path: &path });
+
+    if cmd.get_flag("gen-example") {
+        let path = generate_example(cmd, sink).map_err(|e| e.to_string())?;
+        events.emit(Event::FileWritten { path: &path });
+    }
+
+    let mut names = companions::requested(cmd);
+    // A repository started with --git-init gets a .gitignore unless it has one.
+    if cmd.get_flag("git-init")
+        && !names.contains(&"gitignore")
+        && cmd
+            .get_path("path")
+            .is_some_and(|p| !p.join(".gitignore").exists())
+    {
+        names.push("gitignore");
+    }
+    for name in names {
+        let (file_name, content) = companions::generate(name, cmd);
+        let path = sink.write_file(Path::new(file_name), content.as_bytes(), FILE_MODE)?;
+        events.emit(Event::FileWritten { path: &path });
+    }
+    Ok(())
+}
+
+/// Print the `--show` output, highlighted if stdout is a colored terminal.
+fn print_preview(file_name: &str, content: &str) {
+    match color::enabled(Stream::Stdout) {
+        true => print!("{}", file_types::highlight(file_name, content)),
+        false => print!("{}", content),
+    }
+}
+
+/// `--dry-run` and `--diff` only show what generating would write.
+pub(super) fn preview(cmd: &CommandArg) -> Preview {
+    Preview::from_flags(cmd.get_flag("dry-run"), cmd.get_flag("diff"))
+}
+
+/// Run the hooks a manifest file type declares in the `--path` directory.
+fn run_type_hooks(cmd: &CommandArg, events: &mut EventBus) {
+    let hooks = match cmd.get_file_type() {
+        FileType::Custom(idx) => &custom::types()[idx].hooks,
+        _ => return,
+    };
+    match cmd.get_path("path") {
+        _ if hooks.is_empty() => {}
+        _ if cmd.get_flag("no-hooks") => logging::verbose("Skipped hooks, --no-hooks was given"),
+        Some(_) if preview(cmd) != Preview::Off => hooks::print_dry_run(hooks),
+        Some(dir) => hooks::run_hooks(hooks, dir, events),
+        None => logging::verbose("Skipped hooks, they only run for --path"),
+    }
+}
+
+/// `--git-init`, after the hooks so the initial commit holds what they write.
+fn run_git_init(cmd: &CommandArg, events: &mut EventBus) {
+    let dir = match cmd.get_path("path") {
+        Some(d) if cmd.get_flag("git-init") => d,
+        _ => return,
+    };
+    match preview(cmd) {
+        Preview::Off => hooks::git_init(dir, cmd.get_arg("git-commit"), events),
+        _ => hooks::print_git_dry_run(cmd.get_arg("git-commit")),
+    }
+}
+
+/// Directories holding more entries than this are unlikely to be a project root.
+const CROWDED_DIR_ENTRIES: usize = 1000;
+
+/// Refuse to generate into the filesystem root, the home directory or a crowded
+/// directory, which usually means a mistyped `--path`.
+fn check_output_dir(path: &Path) -> Result<(), String> {
+    let dir = match fs::canonicalize(path) {
+        Ok(d) => d,
+        Err(_) => return Ok(()),
+    };
+
+    let reason = if dir.parent().is_none() {
+        Some(tr("it is the filesystem root").to_string())
+    } else if std::env::home_dir().and_then(|h| fs::canonicalize(h).ok()) == Some(dir.clone()) {
+        Some(tr("it is the home directory").to_string())
+    } else {
+        let entries = fs::read_dir(&dir).map_or(0, |e| e.take(CROWDED_DIR_ENTRIES + 1).count());
+        if entries > CROWDED_DIR_ENTRIES {
+            Some(trf(
+                "it contains more than {} entries",
+                &[&CROWDED_DIR_ENTRIES],
+            ))
+        } else {
+            None
+        }
+    };
+
+    match reason {
+        Some(r) => Err(trf(
+            "Refusing to generate into \"{}\", {}. Pass --i-know-what-im-doing to proceed anyway.",
+            &[&dir.display(), &r],
+        )),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,786 @@
+//! The `filetemp` commands, `run` dispatches a command line to the one it names.
+
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config_file::{filetemp_data_dir, upgrade_data_dir},
+    error::{ArgError, CacheError, FiletempError},
+    exit_status::{self, Failure},
+    file_types::{self, companions, custom, license},
+    i18n::{tr, trf},
+    lint, logging, matrix,
+    output_sink::{
+        self,
+        manifest::{self, MANIFEST_FILE_NAME},
+    },
+    program_args::{
+        Arg, ArgProcessErr, CommandArg, ValueType,
+        completions::Shell,
+        normalize, providers,
+        subcommand::{self, Dispatch, Subcommand},
+        suggest,
+    },
+    templates,
+};
+
+mod cache;
+mod config;
+mod generate;
+mod sources;
+
+use cache::{
+    cache_roundtrip_test, define_roundtrip_args, delete_cache, edit_cache, export_saved_caches,
+    import_saved_caches, init_cache_store, list_caches, prune_saved_caches, rename_saved_cache,
+    show_cache,
+};
+use config::{config_get, config_list, config_set, config_unset};
+use generate::{apply_spec, generate, new_wizard, watch_spec};
+
+/// The subcommands `run` dispatches to, in the order `--help` lists them.
+pub const SUBCOMMANDS: &[Subcommand] = &[
+    Subcommand {
+        usage: "gen <FILE_TYPE> [OPTIONS]",
+        about: "Generate a file, also the default when no subcommand is given",
+        run: |args| generate(args, "filetemp gen"),
+    },
+    Subcommand {
+        usage: "apply [SPEC] [OPTIONS]",
+        about: "Generate each file type of a filetemp-spec.toml, the OPTIONS are given to all",
+        run: apply_spec,
+    },
+    Subcommand {
+        usage: "watch [SPEC] [--debounce <MS>] [OPTIONS]",
+        about: "Apply a filetemp-spec.toml again whenever it or the user templates it uses change",
+        run: watch_spec,
+    },
+    Subcommand {
+        usage: "new [FILE_TYPE]",
+        about: "Ask for a file type and its arguments one by one, preview and write the file",
+        run: new_wizard,
+    },
+    Subcommand {
+        usage: "lint <FILE_TYPE> [OPTIONS]",
+        about: "Check an existing file against best-practice rules",
+        run: lint_main,
+    },
+    Subcommand {
+        usage: "matrix <FILE_TYPE> [OPTIONS]",
+        about: "List generator features by the version introducing them",
+        run: |args| subcommand_main(args, matrix::define_args, matrix::run, Failure::Args),
+    },
+    Subcommand {
+        usage: "template [NAME] [OPTIONS]",
+        about: "Render a user template, or a bundle given with --template-archive",
+        run: |args| {
+            subcommand_main(
+                args,
+                templates::define_args,
+                run_template,
+                Failure::Generation,
+            )
+        },
+    },
+    Subcommand {
+        usage: "template add <URL> [OPTIONS]",
+        about: "Download a template or bundle from git or https into the templates directory",
+        run: |args| {
+            subcommand_main(
+                args,
+                templates::remote::define_add_args,
+                run_template_add,
+                Failure::Io,
+            )
+        },
+    },
+    Subcommand {
+        usage: "template check <NAME> [OPTIONS]",
+        about: "Report syntax errors and undeclared variables of a user template",
+        run: |args| {
+            subcommand_main(
+                args,
+                templates::check::define_args,
+                run_template_check,
+                Failure::Io,
+            )
+        },
+    },
+    Subcommand {
+        usage: "bundle <NAME> [OPTIONS]",
+        about: "Lay out a project from a bundle of templates in the templates directory",
+        run: bundle_main,
+    },
+    Subcommand {
+        usage: "clean [--path <DIR>] [--force]",
+        about: "Remove the files and directories generating created in DIR, by its manifest",
+        run: |args| subcommand_main(args, define_clean_args, run_clean, Failure::Io),
+    },
+    Subcommand {
+        usage: "list",
+        about: "List file types with their output files and arguments",
+        run: |_| {
+            let mut cmd = CommandArg::new();
+            define_args(&mut cmd);
+            print!("{}", cmd.file_type_list());
+            for s in custom::skipped() {
+                logging::warn(s);
+            }
+        },
+    },
+    Subcommand {
+        usage: "upgrade [--dry-run]",
+        about: "Move the data directory and convert the cache file to the current format",
+        run: upgrade_main,
+    },
+    Subcommand {
+        usage: "config get <FILE_TYPE>.<ARG> [--project]",
+        about: "Print a default of the user config, or of the project config with --project",
+        run: |args| {
+            if let Err(e) = config_get(args) {
+                report(e);
+            }
+        },
+    },
+    Subcommand {
+        usage: "config set <FILE_TYPE>.<ARG> <VALUE>... [--project]",
+        about: "Set a default in the user config, e.g. cmake.version 3.20",
+        run: |args| {
+            if let Err(e) = config_set(args) {
+                report(e);
+            }
+        },
+    },
+    Subcommand {
+        usage: "config unset <FILE_TYPE>.<ARG> [--project]",
+        about: "Remove a default from the user config",
+        run: |args| {
+            if let Err(e) = config_unset(args) {
+                report(e);
+            }
+        },
+    },
+    Subcommand {
+        usage: "config list [--project]",
+        about: "Print the values of the user config",
+        run: |args| {
+            if let Err(e) = config_list(args) {
+                report(e);
+            }
+        },
+    },
+    Subcommand {
+        usage: "cache init [DIR]",
+        about: "Create the cache file, in the data directory or DIR",
+        run: |args| {
+            if let Err(e) = init_cache_store(args) {
+                report(e);
+            }
+        },
+    },
+    Subcommand {
+        usage: "cache list [--names] [PATTERN]",
+        about: "List saved argument caches, or those matching e.g. 'proj-*', --names prints only their names",
+        run: |args| {
+            if let Err(e) = list_caches(args) {
+                report(e);
+            }
+        },
+    },
+    Subcommand {
+        usage: "cache show [FILE_TYPE] <NAME>",
+        about: "Print the arguments of a saved cache",
+        run: |args| {
+            if let Err(e) = show_cache(args) {
+                report(e);
+            }
+        },
+    },
+    Subcommand {
+        usage: "cache edit [FILE_TYPE] <NAME>",
+        about: "Edit the arguments of a saved cache in $VISUAL or $EDITOR",
+        run: |args| {
+            if let Err(e) = edit_cache(args) {
+                report(e);
+            }
+        },
+    },
+    Subcommand {
+        usage: "cache delete [FILE_TYPE] <NAME|PATTERN>",
+        about: "Delete a saved argument cache, or all matching e.g. 'tmp-*'",
+        run: |args| {
+            if let Err(e) = delete_cache(args) {
+                report(e);
+            }
+        },
+    },
+    Subcommand {
+        usage: "cache rename [FILE_TYPE] <OLD> <NEW>",
+        about: "Rename a saved argument cache",
+        run: |args| {
+            if let Err(e) = rename_saved_cache(args) {
+                report(e);
+            }
+        },
+    },
+    Subcommand {
+        usage: "cache export [[FILE_TYPE] NAME] [--path <FILE>]",
+        about: "Write saved caches as JSON, to share them or check them in",
+        run: |args| {
+            if let Err(e) = export_saved_caches(args) {
+                report(e);
+            }
+        },
+    },
+    Subcommand {
+        usage: "cache import <FILE> [--replace]",
+        about: "Add the caches of an exported JSON file",
+        run: |args| {
+            if let Err(e) = import_saved_caches(args) {
+                report(e);
+            }
+        },
+    },
+    Subcommand {
+        usage: "cache prune --older-than <AGE>",
+        about: "Delete caches not used for longer than AGE, e.g. 90d",
+        run: |args| {
+            if let Err(e) = prune_saved_caches(args) {
+                report(e);
+            }
+        },
+    },
+    Subcommand {
+        usage: "cache roundtrip-test <FILE_TYPE> [OPTIONS]",
+        about: "Check that saving and loading the arguments reproduces the output",
+        run: |args| {
+            subcommand_main(
+                args,
+                define_roundtrip_args,
+                cache_roundtrip_test,
+                Failure::Cache,
+            )
+        },
+    },
+    Subcommand {
+        usage: "completions <bash|zsh|fish|powershell>",
+        about: "Print a shell completion script",
+        run: print_completions,
+    },
+    Subcommand {
+        usage: "providers",
+        about: "List providers of computed default values",
+        run: |_| print!("{}", providers::list_providers()),
+    },
+];
+
+/// Run the command `raw_args` names, the arguments after the program name.
+pub fn run(raw_args: &[&str]) {
+    match subcommand::dispatch(SUBCOMMANDS, raw_args) {
+        Dispatch::Run(s, args) => (s.run)(args),
+        Dispatch::UnknownInGroup {
+            group,
+            given: Some(given),
+            commands,
+        } => {
+            fail(
+                Failure::Args,
+                trf("Unknown {} command: \"{}\"", &[&group, &given]),
+            );
+            if let Some(s) = suggest::closest(given, commands.into_iter()) {
+                eprintln!(
+                    "{}",
+                    trf("Did you mean \"{}\"?", &[&format!("{} {}", group, s)])
+                );
+            }
+        }
+        Dispatch::UnknownInGroup {
+            group,
+            given: None,
+            commands,
+        } => fail(
+            Failure::Args,
+            trf(
+                "Missing {} command, expected one of: {}",
+                &[&group, &commands.join(", ")],
+            ),
+        ),
+        // Without a subcommand the arguments are for `gen`.
+        Dispatch::NotFound => generate(raw_args, "filetemp"),
+    }
+}
+
+fn print_completions(args: &[&str]) {
+    match args.first().map(|s| s.parse::<Shell>()) {
+        Some(Ok(shell)) => {
+            let mut cmd = CommandArg::new();
+            define_args(&mut cmd);
+            print!("{}", cmd.completion_script(shell));
+        }
+        Some(Err(_)) => fail(
+            Failure::Args,
+            format_args!(
+                "Invalid shell: \"{}\", possible values: bash, zsh, fish, powershell",
+                args[0]
+            ),
+        ),
+        None => fail(
+            Failure::Args,
+            "Missing shell, possible values: bash, zsh, fish, powershell",
+        ),
+    }
+}
+
+fn subcommand_main<E: Display>(
+    args: &[&str],
+    define: fn(&mut CommandArg),
+    run: fn(&mut CommandArg) -> Result<(), E>,
+    failure: Failure,
+) {
+    let mut cmd = CommandArg::new();
+    define(&mut cmd);
+
+    if let Err(e) = cmd.process_program_args(args) {
+        process_arg_parse_err(&cmd, e);
+        return;
+    }
+
+    if let Err(e) = run(&mut cmd) {
+        fail(failure, e);
+    }
+}
+
+/// `filetemp lint`, exiting with its own status when errors are found.
+fn lint_main(args: &[&str]) {
+    let mut cmd = CommandArg::new();
+    lint::define_args(&mut cmd);
+
+    if let Err(e) = cmd.process_program_args(args) {
+        process_arg_parse_err(&cmd, e);
+        return;
+    }
+
+    match lint::run(&mut cmd) {
+        Ok(findings) if findings.iter().any(|f| f.severity == lint::Severity::Error) => {
+            exit_status::fail(Failure::LintErrors)
+        }
+        Ok(_) => {}
+        Err(e) => report(e),
+    }
+}
+
+/// Report an error and exit with the status of `failure`.
+fn fail(failure: Failure, message: impl Display) {
+    exit_status::fail(failure);
+    logging::error(message);
+}
+
+/// Report an error with the exit status of its kind.
+fn report(e: FiletempError) {
+    fail(e.failure(), e);
+}
+
+/// User templates are kept in the data directory, next to the cache file.
+pub fn user_template_dir() -> Option<PathBuf> {
+    filetemp_data_dir()
+        .ok()
+        .map(|(dir, _)| dir.join(templates::TEMPLATE_DIR_NAME))
+}
+
+fn run_template(cmd: &mut CommandArg) -> Result<(), String> {
+    templates::run(cmd, user_template_dir().as_deref())
+}
+
+fn run_template_add(cmd: &mut CommandArg) -> Result<(), String> {
+    let (data_dir, _) = filetemp_data_dir().map_err(|e| e.to_string())?;
+    templates::remote::add(cmd, &data_dir)
+}
+
+fn run_template_check(cmd: &mut CommandArg) -> Result<(), String> {
+    let (data_dir, _) = filetemp_data_dir().map_err(|e| e.to_string())?;
+    templates::check::run(cmd, &data_dir)
+}
+
+/// Like `subcommand_main`, but the variables of the bundle named first are
+/// options, so the bundle is loaded before the arguments are parsed.
+fn bundle_main(args: &[&str]) {
+    let bundle = match args.first().filter(|a| !a.starts_with('-')) {
+        Some(name) => match templates::find_bundle(user_template_dir().as_deref(), name) {
+            Ok(b) => Some(b),
+            Err(e) => {
+                fail(Failure::Args, e);
+                return;
+            }
+        },
+        None => None,
+    };
+
+    let mut cmd = CommandArg::new();
+    templates::define_bundle_args(&mut cmd, bundle.as_ref());
+    if let Err(e) = cmd.process_program_args(args) {
+        process_arg_parse_err(&cmd, e);
+        return;
+    }
+
+    if let Err(e) = templates::run_bundle(&mut cmd, bundle.as_ref()) {
+        fail(Failure::Generation, e);
+    }
+}
+
+fn define_clean_args(cmd: &mut CommandArg) {
+    cmd.usage_name("filetemp clean")
+        .no_file_type()
+        .add_general_arg_def(
+            Arg::new("path")
+                .value_name("DIR")
+                .normalize(normalize::path)
+                .value_type(ValueType::Path { must_exist: true })
+                .help("Directory generated into, by default the current one"),
+        )
+        .add_general_arg_def(
+            Arg::new("force")
+                .flag(true)
+                .help("Also remove files changed since they were generated"),
+        )
+        .add_general_arg_def(
+            Arg::new("verbose")
+                .short('v')
+                .flag(true)
+                .help("Report each file and directory removed"),
+        )
+        .after_help(format!(
+            "Generating into --path records the files and directories it creates in \
+             {} there, clean removes exactly those and then the manifest. Files \
+             replaced rather than created are not recorded, nor what hooks write.",
+            MANIFEST_FILE_NAME
+        ));
+}
+
+fn define_upgrade_args(cmd: &mut CommandArg) {
+    cmd.usage_name("filetemp upgrade")
+        .no_file_type()
+        .add_general_arg_def(
+            Arg::new("dry-run")
+                .flag(true)
+                .help("Report what would change without changing anything"),
+        )
+        .after_help(
+            "Caches and config.toml of older versions are still read, and the cache file \
+             is converted the next time filetemp uses it. Upgrade does it at once: a .filetemp \
+             data directory moves to filetemp on Linux, a cache.txt becomes cache.toml and \
+             an older cache.toml is rewritten in the current format version. The replaced \
+             files are kept with a .bak suffix.",
+        );
+}
+
+fn upgrade_main(args: &[&str]) {
+    // The parser shows the help without arguments, upgrading is what is asked for.
+    if args.is_empty() {
+        if let Err(e) = upgrade(false) {
+            report(e.into());
+        }
+        return;
+    }
+    subcommand_main(
+        args,
+        define_upgrade_args,
+        |cmd| upgrade(cmd.get_flag("dry-run")),
+        Failure::Cache,
+    );
+}
+
+/// Bring the data directory up to date and report each change.
+fn upgrade(dry_run: bool) -> Result<(), CacheError> {
+    let changes = upgrade_data_dir(dry_run)?;
+    if changes.is_empty() {
+        println!("{}", tr("The data directory is up to date"));
+    } else if dry_run {
+        println!("{}", tr("Upgrading would make these changes:"));
+        for change in changes {
+            println!("    {}", change);
+        }
+    } else {
+        for change in changes {
+            logging::success(change);
+        }
+    }
+    Ok(())
+}
+
+/// Undo generating into a directory, by its manifest.
+fn run_clean(cmd: &mut CommandArg) -> Result<(), String> {
+    if cmd.get_flag("verbose") {
+        logging::set_level(logging::Level::Verbose);
+    }
+    let dir = cmd.get_path("path").unwrap_or(Path::new("."));
+    let cleaned = manifest::clean(dir, cmd.get_flag("force"))?;
+    for path in cleaned.changed.iter() {
+        logging::warn(format_args!(
+            "Kept \"{}\", it changed since it was generated, give --force to remove it",
+            path.display()
+        ));
+    }
+    logging::success(format_args!(
+        "Removed {} file(s) and {} directory(ies) from \"{}\"",
+        cleaned.files,
+        cleaned.dirs,
+        dir.display()
+    ));
+    Ok(())
+}
+
+fn define_args(cmd: &mut CommandArg) {
+    file_types::register_types(cmd);
+    cmd.add_general_arg_def(
+            Arg::new("path")
+                .value_name("PATH")
+                .normalize(normalize::path)
+                .value_type(ValueType::Path { must_exist: false })
+                .validator(output_sink::validate_output_dir)
+                .help("Path where the file is generated to, created if missing"),
+        )
+        .add_general_arg_def(
+            Arg::new("archive")
+                .value_name("FILE")
+                .value_type(ValueType::Path { must_exist: false })
+                .conflicts_with("path")
+                .help("Write the generated files into a .tar.gz, .tgz or .zip archive instead"),
+        )
+        .add_general_arg_def(
+            Arg::new("show")
+                .short('s')
+                .flag(true)
+                .last_wins(true)
+                .help("Show output content to stdout"),
+        )
+        .add_general_arg_def(
+            Arg::new("save-as")
+                .value_name("CACHE_NAME")
+                .help("Save current argument set to cache"),
+        )
+        .add_general_arg_def(
+            Arg::new("use").value_name("CACHE_NAME").help(
+                "Use existed cache, later ones of a comma separated list override earlier ones",
+            ),
+        )
+        .add_general_arg_def(
+            Arg::new("extends")
+                .value_name("CACHE_NAME")
+                .requires("save-as")
+                .help("Save the cache as an extension of another one, storing only what differs"),
+        )
+        .add_general_arg_def(
+            Arg::new("builtin")
+                .flag(true)
+                .help("Use the built-in generator even if a user template overrides it"),
+        )
+        .add_general_arg_def(
+            Arg::new("no-hooks")
+                .flag(true)
+                .help("Do not run the commands a file type declares for after generating"),
+        )
+        .add_general_arg_def(Arg::new("dry-run").flag(true).help(
+            "List the files that would be written, with their sizes, instead of writing them",
+        ))
+        .add_general_arg_def(
+            Arg::new("diff")
+                .flag(true)
+                .requires("path")
+                .conflicts_with("dry-run")
+                .help("Print a unified diff against the files in --path instead of writing them"),
+        )
+        .add_general_arg_def(
+            Arg::new("force")
+                .flag(true)
+                .help("Replace existing files without asking"),
+        )
+        .add_general_arg_def(
+            Arg::new("backup")
+                .flag(true)
+                .help("Move existing files to <name>.bak before replacing them"),
+        )
+        .add_general_arg_def(
+            Arg::new("no-cache")
+                .flag(true)
+                .help("Neither read nor write the argument cache, --use and --save-as are ignored"),
+        )
+        .add_general_arg_def(
+            Arg::new("explain")
+                .value_name("ARG")
+                .help("Print which source gives the value of an argument instead of generating"),
+        )
+        .add_general_arg_def(
+            Arg::new("gen-example")
+                .flag(true)
+                .requires("path|archive")
+                .help("Generate example project"),
+        )
+        .add_general_arg_def(
+            Arg::new("output-name")
+                .value_name("FILE")
+                .requires("path|archive")
+                .validator(output_sink::validate_relative_path)
+                .help("Write the generated file under this name, e.g. CMakeLists.txt.new or sub/CMakeLists.txt"),
+        )
+        .add_general_arg_def(
+            Arg::new("merge")
+                .flag(true)
+                .requires("path")
+                .help("Add only the commands an existing CMakeLists.txt at --path lacks, keeping the rest"),
+        )
+        .add_general_arg_def(
+            Arg::new("stamp")
+                .flag(true)
+                .help("Start the file with a comment naming the version, caches and date of its generation"),
+        )
+        .add_general_arg_def(
+            Arg::new("with")
+                .value_name("FILES")
+                .requires("path|archive")
+                .validator(companions::validate_list)
+                .help("Also write companion files, a comma separated list of gitignore, clang-format and editorconfig"),
+        )
+        .add_general_arg_def(
+            Arg::new("license-header")
+                .value_name("LICENSE")
+                .requires("gen-example")
+                .validator(license::validate)
+                .help("Start the example sources with a copyright comment, mit, apache-2.0 or custom:<FILE> with {year} and {author}"),
+        )
+        .add_general_arg_def(
+            Arg::new("license-author")
+                .value_name("NAME")
+                .requires("license-header")
+                .help("Author named in the license header, user.name of git by default"),
+        )
+        .add_general_arg_def(
+            Arg::new("from-json")
+                .value_name("FILE")
+                .help("Read file type arguments from a JSON object of names to values, - for stdin"),
+        )
+        .add_general_arg_def(
+            Arg::new("stdin-args")
+                .flag(true)
+                .conflicts_with("from-json=-")
+                .help("Read name:value lines of file type arguments from stdin, as in a cache, below those given"),
+        )
+        .add_general_arg_def(
+            Arg::new("git-init")
+                .flag(true)
+                .requires("path")
+                .conflicts_with("archive")
+                .help("Start a git repository in --path after generating, with a .gitignore unless it has one"),
+        )
+        .add_general_arg_def(
+            Arg::new("git-commit")
+                .value_name("MESSAGE")
+                .requires("git-init")
+                .help("Commit the files of the new repository with this message"),
+        )
+        .add_general_arg_def(
+            Arg::new("verbose")
+                .short('v')
+                .flag(true)
+                .last_wins(true)
+                .help("Report each generation step to stderr"),
+        )
+        .add_general_arg_def(
+            Arg::new("quiet")
+                .short('q')
+                .flag(true)
+                .last_wins(true)
+                .conflicts_with("verbose")
+                .help("Only report errors"),
+        )
+        .add_general_arg_def(
+            Arg::new("no-interactive")
+                .flag(true)
+                .help("Never ask for missing required arguments, even in a terminal"),
+        )
+        .add_general_arg_def(
+            Arg::new("i-know-what-im-doing")
+                .flag(true)
+                .requires("path")
+                .help("Allow generating into the filesystem root, home or crowded directories"),
+        )
+        .subcommands(SUBCOMMANDS)
+        .after_help(
+            "Values can be given as \"--name value\" or \"--name=value\", \
+             flags also accept \"--name=true\" and \"--name=false\".\n\
+             \"--no-<flag>\" turns a flag off even if a loaded cache would turn it on.\n\
+             Arguments of the file type can be set in the environment, e.g. \
+             FILETEMP_PROJ for --proj or FILETEMP_TARGET_NAME for --target-name.\n\
+             Project defaults are read from a table named after the file type, e.g. [cmake], \
+             in the nearest .filetemp.toml, .filetemp or filetemp.toml above the current \
+             directory.\n\
+             User defaults are read the same way from config.toml next to the cache file.\n\
+             \"filetemp config set cmake.version 3.20\" sets a user default without editing \
+             config.toml, --project sets it in the nearest project config instead, creating \
+             .filetemp.toml in the current directory if there is none.\n\
+             Values are taken from the command line, then --from-json, --stdin-args, the environment, the project \
+             defaults, the user defaults, the caches given to --use and finally the built-in \
+             defaults; --explain <ARG> shows which one applied.\n\
+             Caches and config.toml are kept in $FILETEMP_DATA_DIR if set, otherwise in the \
+             user data directory, e.g. $XDG_DATA_HOME/filetemp.\n\
+             More file types are declared by a <NAME>.toml in its types directory, with the \
+             output file name, the template to render and the [args] it takes, whose \
+             defaults may come from a provider like git-user-name, and hooks, commands run \
+             in the --path directory after generating unless --no-hooks is given.\n\
+             A user template named like a built-in type, e.g. templates/cmake.txt, is \
+             rendered in its place with the arguments of the type as variables, unless \
+             --builtin is given.\n\
+             Replacing a file generated with --stamp warns if it was edited by hand since.\n\
+             Generating into --path records the files and directories it creates in \
+             .filetemp-manifest, \"filetemp clean --path <DIR>\" removes them.\n\
+             \"filetemp apply\" generates each table of the nearest filetemp-spec.toml, e.g. \
+             [cmake] or [[cmake]] for several, with the arguments it holds, into the \
+             directory of the spec unless a path is given there.\n\
+             \"filetemp watch\" applies the spec again, replacing the files it generated, \
+             whenever the spec, the manifest of a declared type or a user template it uses \
+             changes and stays unchanged for --debounce milliseconds, 300 by default.\n\
+             File types joined by \"+\", e.g. cmake+gitignore+editorconfig, are generated \
+             one after another into the same --path, each taking the arguments it knows.\n\
+             Any other FILE_TYPE is generated by a filetemp-<FILE_TYPE> executable on PATH, \
+             see \"filetemp <FILE_TYPE> --help\" once it is installed.\n\
+             \"filetemp --version\" or \"filetemp -V\" prints the version and build.\n\
+             Every command accepts --keep-temp to keep its temporary files for debugging, \
+             --ignore-case to match long option names regardless of case, like file types, \
+             --debug-bundle <FILE> to write a bug report there if it crashes, \
+             --log-file <FILE> to append a timestamped trace of cache resolution and file \
+             writes there, --color auto|always|never, auto colors terminals unless \
+             NO_COLOR is set, and --lang en|zh for the language of messages, taken from \
+             LC_ALL, LC_MESSAGES or LANG without it.\n\
+             Exit status: 0 on success, 1 if \"filetemp lint\" found errors, 2 for invalid \
+             arguments, 3 for cache errors, 4 for failed reads or writes, 5 if the file \
+             could not be generated, 101 on a crash.",
+        );
+}
+
+fn usage_err(usage: &str) -> FiletempError {
+    ArgError::Invalid(usage.to_string()).into()
+}
+
+/// Report an error in the given command line together with its usage.
+fn process_arg_parse_err(cmd: &CommandArg, e: ArgProcessErr) {
+    if let ArgProcessErr::PrintedHelp | ArgProcessErr::PrintedVersion = e {
+        return;
+    }
+
+    exit_status::fail(Failure::Args);
+    print_arg_err(e);
+    eprintln!("{}", cmd.usage_hint());
+}
+
+fn print_arg_err(e: ArgProcessErr) {
+    let suggestion = match &e {
+        ArgProcessErr::PrintedHelp | ArgProcessErr::PrintedVersion => return,
+        ArgProcessErr::InvalidArg(_, s) => s.clone(),
+        ArgProcessErr::InvalidFileType(_, s) => s.map(String::from),
+        _ => None,
+    };
+    logging::error(&e);
+    if let Some(s) = suggestion {
+        eprintln!("{}", trf("Did you mean \"{}\"?", &[&s]));
+    }
+}
@@ -0,0 +1,238 @@
+//! Sources of argument values other than the command line, applied before generating.
+
+use std::{fs, path::Path};
+
+use crate::{
+    config_file::{filetemp_data_dir, parse_arg_lines},
+    error::{ArgError, FiletempError, IoError},
+    logging,
+    program_args::{
+        CommandArg,
+        resolve::{Layer, Source},
+        suggest,
+    },
+    project_config::{USER_CONFIG_FILE_NAME, file_type_defaults, load_project_config, read_config},
+};
+
+use super::cache::add_cache_layers;
+
+/// Apply the values of every source to `cmd` by precedence: the command line,
+/// the environment, the project config, the user config, the caches given to
+/// `--use` and, once required arguments are checked, the built-in defaults.
+pub(super) fn resolve_arg_sources(cmd: &mut CommandArg) -> Result<(), FiletempError> {
+    add_json_layer(cmd)?;
+    add_stdin_layer(cmd)?;
+    cmd.add_environment_layers();
+    add_project_layer(cmd)?;
+    add_user_layer(cmd)?;
+    add_cache_layers(cmd)?;
+    apply_layers(cmd)
+}
+
+pub(super) fn apply_layers(cmd: &mut CommandArg) -> Result<(), FiletempError> {
+    match cmd.resolve() {
+        Ok(()) => Ok(()),
+        Err((e, layer)) => Err(FiletempError::refused(e, layer)),
+    }
+}
+
+/// Add the arguments of `--from-json`, an object of argument names of the file
+/// type to values, read from a file or from stdin for `-`.
+fn add_json_layer(cmd: &mut CommandArg) -> Result<(), FiletempError> {
+    let from = match cmd.get_arg("from-json") {
+        Some(f) => f.to_string(),
+        None => return Ok(()),
+    };
+    let mut layer = Layer::new(Source::Json, from.as_str());
+    let text = match from.as_str() {
+        "-" => std::io::read_to_string(std::io::stdin())
+            .map_err(|e| IoError::new("read", "stdin", e))?,
+        file => fs::read_to_string(file).map_err(|e| IoError::new("read", file, e))?,
+    };
+    let document: serde_json::Value = serde_json::from_str(&text)
+        .map_err(|e| ArgError::Config(format!("Invalid {}: {}", layer, e)))?;
+    let object = match document.as_object() {
+        Some(o) => o,
+        None => {
+            return Err(ArgError::Config(format!(
+                "Expected an object of argument names to values in {}",
+                layer
+            ))
+            .into());
+        }
+    };
+
+    for (arg, value) in object.iter() {
+        check_arg_name(cmd, arg, &layer)?;
+        let items: Vec<&serde_json::Value> = match value {
+            serde_json::Value::Array(a) => a.iter().collect(),
+            v => vec![v],
+        };
+        for item in items {
+            let value = match item {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Number(n) => n.to_string(),
+                serde_json::Value::Bool(b) => b.to_string(),
+                _ => {
+                    return Err(ArgError::Config(format!(
+                        "Unsupported value for \"{}\" in {}, expected a string, number, \
+                         boolean or an array of them",
+                        arg, layer
+                    ))
+                    .into());
+                }
+            };
+            layer.push(arg, &value);
+        }
+    }
+
+    logging::verbose(format_args!(
+        "Read {} argument(s) from {}",
+        object.len(),
+        layer
+    ));
+    cmd.add_layer(layer);
+    Ok(())
+}
+
+/// Add the `name:value` lines of `--stdin-args`, arguments of the file type.
+fn add_stdin_layer(cmd: &mut CommandArg) -> Result<(), FiletempError> {
+    if !cmd.get_flag("stdin-args") {
+        return Ok(());
+    }
+    let text =
+        std::io::read_to_string(std::io::stdin()).map_err(|e| IoError::new("read", "stdin", e))?;
+    let args = parse_arg_lines(&text).map_err(ArgError::Config)?;
+
+    let mut layer = Layer::new(Source::Stdin, "");
+    for arg in args.iter() {
+        check_arg_name(cmd, &arg.arg, &layer)?;
+        layer.push(&arg.arg, &arg.content);
+    }
+
+    logging::verbose(format_args!(
+        "Read {} argument value(s) from stdin",
+        args.len()
+    ));
+    cmd.add_layer(layer);
+    Ok(())
+}
+
+/// Refuse an argument `layer` gives that is not one of the file type. General
+/// arguments, such as `path` or `use`, are read before any layer is applied,
+/// so they are only taken from the command line.
+fn check_arg_name(cmd: &CommandArg, arg: &str, layer: &Layer) -> Result<(), ArgError> {
+    if cmd.type_arg_names().any(|a| a == arg) {
+        return Ok(());
+    }
+    if cmd.general_option(&format!("--{}", arg)).is_some() {
+        return Err(ArgError::Config(format!(
+            "\"{}\" in {} is a general argument, give it on the command line",
+            arg, layer
+        )));
+    }
+    let mut message = format!("Unknown argument \"{}\" in {}", arg, layer);
+    if let Some(s) = suggest::closest(arg, cmd.type_arg_names()) {
+        message.push_str(&format!(", did you mean \"{}\"?", s));
+    }
+    Err(ArgError::Config(message))
+}
+
+/// Defaults of the nearest project config above the current directory.
+fn add_project_layer(cmd: &mut CommandArg) -> Result<(), ArgError> {
+    match load_project_config(Path::new(".")).map_err(ArgError::Config)? {
+        Some((path, table)) => add_config_layer(cmd, Source::ProjectConfig, &path, &table),
+        None => Ok(()),
+    }
+}
+
+/// Defaults of the user config next to the cache file.
+fn add_user_layer(cmd: &mut CommandArg) -> Result<(), ArgError> {
+    // Without a data directory there is no user config to read.
+    let path = match filetemp_data_dir() {
+        Ok((dir, _)) => dir.join(USER_CONFIG_FILE_NAME),
+        Err(_) => return Ok(()),
+    };
+    if !path.is_file() {
+        return Ok(());
+    }
+    let table = read_config(&path).map_err(ArgError::Config)?;
+    add_config_layer(cmd, Source::UserConfig, &path, &table)
+}
+
+fn add_config_layer(
+    cmd: &mut CommandArg,
+    source: Source,
+    path: &Path,
+    table: &toml::Table,
+) -> Result<(), ArgError> {
+    let file_type = cmd.get_file_type().to_str();
+    let defaults = file_type_defaults(path, table, file_type).map_err(ArgError::Config)?;
+
+    let valid_args: Vec<String> = cmd.query_valid_args().map(|a| a.name.to_string()).collect();
+    let mut layer = Layer::new(source, path.display().to_string());
+    for (arg, values) in defaults.iter() {
+        if !valid_args.contains(arg) {
+            return Err(ArgError::Config(format!(
+                "Unknown argument \"{}\" in [{}] of \"{}\"",
+                arg,
+                file_type,
+                path.display()
+            )));
+        }
+        for v in values.iter() {
+            layer.push(arg, v);
+        }
+    }
+
+    if !defaults.is_empty() {
+        logging::verbose(format_args!(
+            "Read defaults for {} argument(s) from \"{}\"",
+            defaults.len(),
+            path.display()
+        ));
+    }
+    cmd.add_layer(layer);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::define_args;
+
+    fn cmake_cmd() -> CommandArg {
+        let mut cmd = CommandArg::new();
+        define_args(&mut cmd);
+        cmd.process_program_args(&["cmake"]).unwrap();
+        cmd
+    }
+
+    #[test]
+    fn json_takes_only_file_type_args() {
+        let cmd = cmake_cmd();
+        let layer = Layer::new(Source::Json, "args.json");
+        assert!(check_arg_name(&cmd, "proj", &layer).is_ok());
+        for general in ["path", "use", "save-as", "show"] {
+            let err = check_arg_name(&cmd, general, &layer).unwrap_err();
+            assert!(err.to_string().contains("general argument"), "{}", err);
+        }
+        let err = check_arg_name(&cmd, "prj", &layer).unwrap_err();
+        assert!(err.to_string().contains("did you mean \"proj\""), "{}", err);
+    }
+
+    #[test]
+    fn stdin_takes_only_file_type_args() {
+        let cmd = cmake_cmd();
+        let layer = Layer::new(Source::Stdin, "");
+        let args = parse_arg_lines("proj:demo\nversion:3.20\n").unwrap();
+        for arg in args.iter() {
+            assert!(check_arg_name(&cmd, &arg.arg, &layer).is_ok());
+        }
+        for line in ["path:out", "use:base", "from-json:args.json"] {
+            let args = parse_arg_lines(line).unwrap();
+            let err = check_arg_name(&cmd, &args[0].arg, &layer).unwrap_err();
+            assert!(err.to_string().contains("general argument"), "{}", err);
+        }
+    }
+}
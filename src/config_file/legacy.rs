@@ -0,0 +1,295 @@
+//! The line based cache format used before caches were stored as TOML, read to
+//! migrate it. Its `name:value` lines are also how `--stdin-args` are given.
+
+use super::{ArgCache, CachedArg, UNCACHED_ARGS, line_excerpt};
+use crate::file_types::FileType;
+
+enum LineResult<'a> {
+    CacheName(&'a str),
+    FileTy(FileType),
+    ArgItem(CachedArg),
+    /// Message and byte column of the error in the line.
+    ParseError(String, usize),
+    Discard,
+}
+
+/// Parse `[name]` headers followed by `name:value` lines, caches end at a blank line.
+/// Argument names are not checked, the migrated file is validated when it is used.
+pub fn parse(content: &str) -> Result<Vec<ArgCache>, String> {
+    let mut caches: Vec<ArgCache> = Vec::new();
+
+    let mut current_cache = ArgCache::new("", FileType::Unknown);
+    let mut parsing_cache = false;
+
+    for (idx, line) in content.lines().enumerate() {
+        if line.is_empty() && parsing_cache {
+            if let FileType::Unknown = current_cache.file_type {
+                return Err(format!(
+                    "Argument cache parse error: File type not specified for cache \"{}\"",
+                    current_cache.cache_name
+                ));
+            }
+            caches.push(current_cache);
+            current_cache = ArgCache::new("", FileType::Unknown);
+            parsing_cache = false;
+            continue;
+        }
+
+        let line_num = idx + 1;
+        let cache = parsing_cache.then_some(current_cache.cache_name.as_str());
+        match parse_line(line) {
+            LineResult::ParseError(err, col) => {
+                return Err(line_error(&err, line_num, line, col, cache));
+            }
+            LineResult::CacheName(cache_name) => {
+                current_cache.cache_name = cache_name.to_string();
+                parsing_cache = true;
+            }
+            LineResult::ArgItem(arg) => {
+                if !parsing_cache {
+                    return Err(line_error(
+                        "Argument outside of a cache",
+                        line_num,
+                        line,
+                        0,
+                        None,
+                    ));
+                }
+                if !UNCACHED_ARGS.contains(&arg.arg.as_str()) {
+                    current_cache.args.push(arg);
+                }
+            }
+            LineResult::FileTy(FileType::Unknown) => {
+                let col = line.find(':').map_or(0, |i| i + 1);
+                return Err(line_error("Invalid file type", line_num, line, col, cache));
+            }
+            LineResult::FileTy(ty) => current_cache.file_type = ty,
+            LineResult::Discard => {}
+        }
+    }
+
+    if parsing_cache {
+        if let FileType::Unknown = current_cache.file_type {
+            return Err(format!(
+                "Argument cache parse error: File type not specified for cache \"{}\"",
+                current_cache.cache_name
+            ));
+        }
+        caches.push(current_cache);
+    }
+
+    Ok(caches)
+}
+
+/// Arguments of `name:value` lines without cache headers, a `file_type` line is ignored.
+/// Names are kept as given, even of arguments a cache leaves out, for the caller to check.
+pub fn parse_arg_lines(content: &str) -> Result<Vec<CachedArg>, String> {
+    let mut args = Vec::new();
+    for (idx, line) in content.lines().enumerate() {
+        let error = |message: &str, col: usize| {
+            format!(
+                "Argument parse error: {} at line {}{}",
+                message,
+                idx + 1,
+                line_excerpt(idx + 1, line, col)
+            )
+        };
+        match parse_line(line) {
+            LineResult::ParseError(err, col) => return Err(error(&err, col)),
+            LineResult::CacheName(_) => {
+                return Err(error("Expected \"name:value\", not a cache header", 0));
+            }
+            LineResult::ArgItem(arg) => args.push(arg),
+            LineResult::FileTy(_) | LineResult::Discard => {}
+        }
+    }
+    Ok(args)
+}
+
+/// `message` with the one-based line number and the cache being parsed,
+/// followed by the line with a caret under the byte column `col`.
+fn line_error(
+    message: &str,
+    line_num: usize,
+    line: &str,
+    col: usize,
+    cache: Option<&str>,
+) -> String {
+    let mut result = format!(
+        "Argument cache parse error: {} at line {}",
+        message, line_num
+    );
+    if let Some(name) = cache {
+        result.push_str(&format!(" in cache \"{}\"", name));
+    }
+    result.push_str(&line_excerpt(line_num, line, col));
+    result
+}
+
+fn parse_line(line: &str) -> LineResult<'_> {
+    let line = strip_comment(line);
+    if line.is_empty() {
+        return LineResult::Discard;
+    }
+
+    if let Some(header) = line.strip_prefix('[') {
+        return match header.strip_suffix(']') {
+            None => LineResult::ParseError(String::from("Missing ]"), line.len()),
+            Some("") => LineResult::ParseError(String::from("Having empty cache name"), 1),
+            Some(name) => LineResult::CacheName(name),
+        };
+    }
+
+    // Empty content is kept, an explicitly empty value is still a value.
+    match line.split_once(':') {
+        Some(("", _)) => LineResult::ParseError(String::from("Having empty argument name"), 0),
+        Some(("file_type", content)) => LineResult::FileTy(FileType::match_type(content)),
+        Some((arg, content)) => LineResult::ArgItem(CachedArg {
+            arg: arg.to_string(),
+            content: unescape_value(content),
+        }),
+        None => LineResult::ParseError(
+            String::from("Expected \"name:value\", the \":\" is missing"),
+            line.len(),
+        ),
+    }
+}
+
+/// Hand edited files may carry comments: a line starting with `#`, or a `#`
+/// after whitespace that is not escaped as `\#`. A `#` right after other text,
+/// as in `repo#tag`, belongs to the value.
+fn strip_comment(line: &str) -> &str {
+    if line.trim_start().starts_with('#') {
+        return "";
+    }
+
+    let bytes = line.as_bytes();
+    for (idx, &b) in bytes.iter().enumerate() {
+        if b == b'#' && idx > 0 && bytes[idx - 1].is_ascii_whitespace() {
+            return line[..idx].trim_end();
+        }
+    }
+    line
+}
+
+/// Line breaks and backslashes were escaped to keep values on one line,
+/// unknown escapes are kept as written. `\#` keeps a `#` that would start a comment.
+fn unescape_value(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('\\') => result.push('\\'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('#') => result.push('#'),
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_file::tests::{MULTIBYTE, Rng};
+
+    fn arg_of(line: &str) -> (String, String) {
+        match parse_line(line) {
+            LineResult::ArgItem(a) => (a.arg, a.content),
+            _ => panic!("\"{}\" is not an argument line", line),
+        }
+    }
+
+    #[test]
+    fn unescape_known_escapes() {
+        assert_eq!(unescape_value(r"a\nb"), "a\nb");
+        assert_eq!(unescape_value(r"a\r\nb"), "a\r\nb");
+        assert_eq!(unescape_value(r"a\\nb"), "a\\nb");
+        assert_eq!(unescape_value(r"repo\#tag"), "repo#tag");
+    }
+
+    #[test]
+    fn unescape_keeps_unknown_escapes() {
+        assert_eq!(unescape_value(r"C:\path\to"), r"C:\path\to");
+        assert_eq!(unescape_value(r#"\"quoted\""#), r#"\"quoted\""#);
+        assert_eq!(unescape_value("ends with \\"), "ends with \\");
+    }
+
+    #[test]
+    fn value_keeps_colons_after_the_first() {
+        assert_eq!(
+            arg_of("description:see https://example.com:8080"),
+            (
+                String::from("description"),
+                String::from("see https://example.com:8080")
+            )
+        );
+        assert_eq!(
+            arg_of("link:a::b"),
+            (String::from("link"), String::from("a::b"))
+        );
+    }
+
+    #[test]
+    fn value_with_escaped_line_break_and_quotes() {
+        assert_eq!(
+            arg_of(r#"description:say "hi"\nand 'bye' \# kept"#),
+            (
+                String::from("description"),
+                String::from("say \"hi\"\nand 'bye' # kept")
+            )
+        );
+    }
+
+    #[test]
+    fn comment_after_whitespace_is_dropped() {
+        assert_eq!(
+            arg_of("proj:demo # the name"),
+            (String::from("proj"), String::from("demo"))
+        );
+    }
+
+    #[test]
+    fn arbitrary_multibyte_lines_parse() {
+        for seed in 0..500 {
+            let mut rng = Rng::new(seed);
+            let arg = rng.text(MULTIBYTE, 8, true);
+            let content = rng.text(MULTIBYTE, 16, false);
+            if arg == "file_type" {
+                continue;
+            }
+            assert_eq!(
+                arg_of(&format!("{}:{}", arg, content)),
+                (arg, content),
+                "seed {}",
+                seed
+            );
+        }
+
+        match parse_line("[项目") {
+            LineResult::ParseError(_, col) => assert_eq!(col, "[项目".len()),
+            _ => panic!("\"[项目\" has no closing ]"),
+        }
+        assert!(matches!(
+            parse_line("[项目]"),
+            LineResult::CacheName("项目")
+        ));
+    }
+
+    #[test]
+    fn caret_is_under_the_character_after_multibyte_text() {
+        let excerpt = line_excerpt(3, "项目:😀", "项目".len());
+        assert_eq!(excerpt, "\n  |\n3 | 项目:😀\n  |   ^");
+    }
+}
@@ -1,280 +1,1662 @@
 use std::{
-    fmt::Write,
-    io::{Read, Write as _},
-    ops::{Deref, DerefMut},
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Seek, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
-use line_ending::LineEnding;
+use cache_dir::get_data_dir;
+use serde_json::json;
+use toml::{Table, Value};
 
-use crate::{file_types::FileType, program_args::ArgPair};
+use crate::{
+    error::{CacheError, IoError},
+    file_types::FileType,
+    logging,
+    program_args::{
+        ArgPair, normalize,
+        resolve::{Layer, Source},
+    },
+};
 
-static mut CACHE_STR: Option<&'static str> = None;
+mod legacy;
+mod store;
 
-/// Return the whole cache string slice.
-/// UNSAFE, always ensure CACHE_STR is already initialized.
-fn get_cache_str() -> &'static str {
-    unsafe { CACHE_STR.unwrap() }
-}
+pub use legacy::parse_arg_lines;
+pub use store::CacheStore;
 
-pub struct ArgCache<'a> {
-    pub file_type: FileType,
-    pub cache_name: &'a str,
-    pub args: Vec<ArgPair<'a>>,
+pub const CACHE_FILE_NAME: &str = "cache.toml";
+const LEGACY_CACHE_FILE_NAME: &str = "cache.txt";
+
+/// Format version written to the cache file, files without one are version 1.
+const CACHE_FORMAT_VERSION: i64 = 5;
+
+/// Upgrades of the cache file, the one at index `i` turns version `i + 1` into `i + 2`.
+const MIGRATIONS: [fn(Table) -> Result<Table, CacheError>; 4] =
+    [nest_caches, key_by_file_type, add_metadata, allow_extends];
+
+/// Key of the table of a cache holding its timestamps and use count, not an argument.
+const META_KEY: &str = "_meta";
+
+/// Key of a cache naming the cache of the same file type it builds on, not an argument.
+const EXTENDS_KEY: &str = "extends";
+
+/// Arguments that only describe a single run, they are never stored in a cache.
+const UNCACHED_ARGS: [&str; 6] = ["show", "path", "save-as", "use", "no-cache", "extends"];
+
+/// A stored value of an argument, repeatable arguments have one per value.
+#[derive(Clone)]
+pub struct CachedArg {
+    pub arg: String,
+    pub content: String,
 }
 
-impl ArgCache<'_> {
-    fn new() -> Self {
+impl From<ArgPair<'_>> for CachedArg {
+    fn from(pair: ArgPair<'_>) -> Self {
         Self {
-            file_type: FileType::Unknown,
-            cache_name: "",
-            args: Vec::new(),
+            arg: pair.arg.to_string(),
+            content: pair.content.to_string(),
         }
     }
 }
 
-pub struct ArgCacheCollection<'a> {
-    caches: Vec<ArgCache<'a>>,
+#[derive(Clone)]
+pub struct ArgCache {
+    pub file_type: FileType,
+    pub cache_name: String,
+    /// Parent cache whose arguments this one overrides when loaded.
+    pub extends: Option<String>,
+    pub args: Vec<CachedArg>,
 }
 
-impl<'a> ArgCacheCollection<'a> {
-    pub fn new(caches: Vec<ArgCache<'a>>) -> Self {
-        Self { caches }
-    }
-
-    pub fn new_empty() -> Self {
-        Self { caches: Vec::new() }
+impl ArgCache {
+    pub fn new(cache_name: &str, file_type: FileType) -> Self {
+        Self {
+            file_type,
+            cache_name: cache_name.to_string(),
+            extends: None,
+            args: Vec::new(),
+        }
     }
 }
 
-impl<'a> Deref for ArgCacheCollection<'a> {
-    type Target = Vec<ArgCache<'a>>;
-
-    fn deref(&self) -> &Self::Target {
-        &self.caches
-    }
+/// Advisory lock on the cache file, released when dropped. It is taken on a
+/// separate lock file, so it stays valid while the cache file is replaced.
+pub struct CacheLock {
+    _file: File,
 }
 
-impl<'a> DerefMut for ArgCacheCollection<'a> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.caches
+impl CacheLock {
+    /// Shared for reading, exclusive for a read-modify-write of the cache file.
+    /// Blocks until other processes release a conflicting lock.
+    pub fn acquire(cache_path: &Path, exclusive: bool) -> Result<Self, CacheError> {
+        let lock_path = cache_path.with_extension("lock");
+        let file = match OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&lock_path)
+        {
+            Ok(f) => f,
+            Err(e) => return Err(IoError::new("open", lock_path, e).into()),
+        };
+
+        let locked = if exclusive {
+            file.lock()
+        } else {
+            file.lock_shared()
+        };
+        if let Err(e) = locked {
+            return Err(IoError::new("lock", lock_path, e).into());
+        }
+        logging::trace(format_args!(
+            "Locked \"{}\" {}",
+            lock_path.display(),
+            if exclusive { "exclusively" } else { "shared" }
+        ));
+        Ok(Self { _file: file })
     }
 }
 
+/// Reads the caches asked for from the cache file a line at a time, keeping
+/// only their tables, so a file of many caches is never held whole.
 pub struct ConfigReader {
-    file_handle: std::fs::File,
-}
-
-enum LineResult<'a> {
-    CacheName(&'a str),
-    FileTy(FileType),
-    ArgItem(ArgPair<'a>),
-    ParseError(String),
-    Discard,
+    reader: BufReader<File>,
+    /// Where the file was opened, for errors.
+    path: PathBuf,
 }
 
 impl ConfigReader {
-    pub fn new(config_file: std::fs::File) -> Self {
+    pub fn new(config_file: File, path: &Path) -> Self {
         Self {
-            file_handle: config_file,
+            reader: BufReader::new(config_file),
+            path: path.to_path_buf(),
         }
     }
 
-    pub fn read_from_config<'b, I>(&mut self, valid_args: I) -> Result<Vec<ArgCache<'b>>, String>
+    /// The saved caches of `file_type` named in `names` and the caches they
+    /// extend, other caches are skipped without being parsed.
+    pub fn read_caches<'a, I>(
+        &mut self,
+        file_type: FileType,
+        names: &[&str],
+        valid_args: I,
+    ) -> Result<Vec<ArgCache>, CacheError>
     where
-        I: Iterator<Item = &'static str> + Clone,
+        I: Iterator<Item = &'a str> + Clone,
     {
-        let mut caches: Vec<ArgCache> = Vec::new();
-
-        let mut temp_str = String::new();
-        if let Err(_) = self.file_handle.read_to_string(&mut temp_str) {
-            return Err(String::from("Failed to read from config cache file."));
-        }
-        unsafe {
-            CACHE_STR = Some(Box::leak(temp_str.into_boxed_str()));
-        }
-
-        let mut current_cache = ArgCache::new();
-        let mut parsing_cache = false;
-
-        for (idx, line) in get_cache_str().lines().enumerate() {
-            if line.is_empty() && parsing_cache {
-                if let FileType::Unknown = current_cache.file_type {
-                    return Err(format!(
-                        "Argument cache parse error: File type not specified for cache \"{}\"",
-                        current_cache.cache_name
-                    ));
-                } else {
-                    caches.push(current_cache);
-                    current_cache = ArgCache::new();
-                    parsing_cache = false;
+        let mut wanted: Vec<String> = names.iter().map(|n| n.to_string()).collect();
+        let caches = loop {
+            let content = self.select(file_type.to_str(), &wanted)?;
+            let caches = parse_caches(&content, file_type, valid_args.clone())?;
+
+            // A parent is only known once the cache extending it is read, the
+            // file is read again for parents not read yet.
+            let mut missing = false;
+            for parent in caches.iter().filter_map(|c| c.extends.as_ref()) {
+                if !wanted.contains(parent) {
+                    wanted.push(parent.clone());
+                    missing = true;
                 }
-            } else {
-                match parse_line(valid_args.clone(), idx, line) {
-                    LineResult::ParseError(err) => {
-                        return Err(err);
+            }
+            if !missing {
+                break caches;
+            }
+        };
+
+        logging::verbose(format_args!(
+            "Read {} saved {} cache(s) from the cache file",
+            caches.len(),
+            file_type.to_str()
+        ));
+        for cache in caches.iter() {
+            logging::trace(format_args!(
+                "Cache \"{}\" has {} argument(s){}",
+                cache.cache_name,
+                cache.args.len(),
+                match &cache.extends {
+                    Some(parent) => format!(", extends \"{}\"", parent),
+                    None => String::new(),
+                }
+            ));
+        }
+        Ok(caches)
+    }
+
+    /// The text of the tables of the `wanted` caches of `file_type` and of every
+    /// other table except those of other caches, e.g. the version before the
+    /// first table or a `[caches]` of inline tables.
+    fn select(&mut self, file_type: &str, wanted: &[String]) -> Result<String, CacheError> {
+        if let Err(e) = self.reader.rewind() {
+            return Err(IoError::new("read", &self.path, e).into());
+        }
+
+        let mut selected = String::new();
+        let mut line = String::new();
+        let mut line_num = 0;
+        let mut lines = TomlLines::default();
+        let mut keep = true;
+        while read_utf8_line(&mut self.reader, &mut line, &mut line_num, &self.path)? {
+            if let Some(keys) = lines.table_header(&line) {
+                keep = match keys.as_slice() {
+                    [caches, ty, name, ..] if caches == "caches" => {
+                        ty == file_type && wanted.contains(name)
                     }
-                    LineResult::CacheName(cache_name) => {
-                        current_cache.cache_name = cache_name;
-                        parsing_cache = true;
+                    _ => true,
+                };
+            }
+            if keep {
+                selected.push_str(&line);
+            }
+        }
+        Ok(selected)
+    }
+}
+
+/// Tells table headers from lines continuing a multi-line string or array.
+#[derive(Default)]
+struct TomlLines {
+    /// Delimiter of the multi-line string the previous lines left open.
+    in_string: Option<&'static str>,
+    /// Brackets of arrays the previous lines left open.
+    depth: usize,
+}
+
+impl TomlLines {
+    /// The keys of the table `line` starts, e.g. `[caches.cmake.base]`, if it is a
+    /// table header. Each line of the file is given in order.
+    fn table_header(&mut self, line: &str) -> Option<Vec<String>> {
+        let at_top = self.in_string.is_none() && self.depth == 0;
+        self.scan(line);
+        match line.trim_start().starts_with('[') && at_top {
+            true => header_keys(line),
+            false => None,
+        }
+    }
+
+    /// Follow the strings, comments and brackets of `line`.
+    fn scan(&mut self, line: &str) {
+        let mut rest = line;
+        loop {
+            if let Some(delimiter) = self.in_string {
+                match rest.find(delimiter) {
+                    Some(end) => {
+                        rest = &rest[end + delimiter.len()..];
+                        self.in_string = None;
                     }
-                    LineResult::ArgItem(arg) => {
-                        if parsing_cache {
-                            current_cache.args.push(ArgPair {
-                                arg: arg.arg,
-                                content: arg.content,
-                            });
-                        } else {
-                            return Err(format!(
-                                "Invalid content in config cache file: \"{}\"",
-                                line
-                            ));
-                        }
+                    None => return,
+                }
+            }
+            let c = match rest.chars().next() {
+                Some(c) => c,
+                None => return,
+            };
+            match c {
+                '#' => return,
+                '[' => self.depth += 1,
+                ']' => self.depth = self.depth.saturating_sub(1),
+                '"' | '\'' => {
+                    let delimiter = if c == '"' { "\"\"\"" } else { "'''" };
+                    if rest.starts_with(delimiter) {
+                        self.in_string = Some(delimiter);
+                        rest = &rest[delimiter.len()..];
+                        continue;
                     }
-                    LineResult::FileTy(ty) => match ty {
-                        FileType::Unknown => {
-                            return Err(format!(
-                                "Argument cache parse error: Invalid file type for cache \"{}\"",
-                                current_cache.cache_name
-                            ));
-                        }
-                        _ => current_cache.file_type = ty,
-                    },
-                    LineResult::Discard => {}
+                    rest = match closing_quote(&rest[1..], c) {
+                        Some(end) => &rest[end + 2..],
+                        None => "",
+                    };
+                    continue;
                 }
+                _ => {}
             }
+            rest = &rest[c.len_utf8()..];
         }
+    }
+}
 
-        if parsing_cache {
-            if let FileType::Unknown = current_cache.file_type {
-                return Err(format!(
-                    "Argument cache parse error: File type not specified for cache \"{}\"",
-                    current_cache.cache_name
-                ));
-            } else {
-                caches.push(current_cache);
-            }
+/// Byte index of the quote ending a single line string, escapes only count in
+/// basic strings.
+fn closing_quote(s: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (idx, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quote == '"' => escaped = true,
+            c if c == quote => return Some(idx),
+            _ => {}
         }
-
-        Ok(caches)
     }
+    None
 }
 
-fn parse_line<I>(valid_args: I, line_num: usize, line: &str) -> LineResult<'_>
-where
-    I: Iterator<Item = &'static str>,
-{
-    macro_rules! line_err {
-        ($msg: literal) => {
-            format!(
-                concat!("Argument cache parse error: ", $msg, "at line {}"),
-                line_num
-            )
+/// Keys of a table header line, read by the TOML parser so quoted keys work.
+fn header_keys(line: &str) -> Option<Vec<String>> {
+    let table = line.parse::<Table>().ok()?;
+    let mut keys = Vec::new();
+    let mut current = &table;
+    while let Some((key, value)) = current.iter().next() {
+        keys.push(key.clone());
+        current = match value {
+            Value::Table(t) => t,
+            Value::Array(a) => match a.last() {
+                Some(Value::Table(t)) => t,
+                _ => break,
+            },
+            _ => break,
         };
     }
+    Some(keys)
+}
 
-    let mut is_arg_item: bool = true;
-    let mut cache_name_start_size: usize = 0;
+/// Bring the cache file in `dir` to the current format: a cache file of the old
+/// line based format is rewritten as TOML, an older TOML file is upgraded in place.
+pub fn migrate_cache_file(dir: &Path) -> Result<(), CacheError> {
+    migrate_legacy_cache(dir)?;
+    upgrade_cache_file(&dir.join(CACHE_FILE_NAME))
+}
 
-    let mut arg_end_size: usize = 0;
-    let mut ct_start_size: usize = 0;
+/// Rewrite a cache file of the old line based format as TOML, once.
+/// The old file is kept next to the new one with a ".bak" suffix.
+fn migrate_legacy_cache(dir: &Path) -> Result<(), CacheError> {
+    let legacy_path = dir.join(LEGACY_CACHE_FILE_NAME);
+    let path = dir.join(CACHE_FILE_NAME);
+    if path.exists() || !legacy_path.is_file() {
+        return Ok(());
+    }
+
+    // Another process may have migrated while this one waited for the lock.
+    let _lock = CacheLock::acquire(&path, true)?;
+    if path.exists() {
+        return Ok(());
+    }
 
-    for (idx, (bidx, c)) in line.char_indices().enumerate() {
-        if idx == 0 && c == '[' {
-            is_arg_item = false;
-            cache_name_start_size = '['.len_utf8();
+    let content = read_cache_file(&legacy_path)?;
+    let caches = match legacy::parse(&content) {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(CacheError::Parse(format!(
+                "Failed to migrate \"{}\": {}",
+                legacy_path.display(),
+                e
+            )));
+        }
+    };
+
+    write_cache_file(&path, &format_caches(&caches))?;
+    if std::fs::rename(&legacy_path, legacy_path.with_extension("txt.bak")).is_err() {
+        logging::warn(format_args!(
+            "Migrated caches to \"{}\", but failed to rename \"{}\"",
+            path.display(),
+            legacy_path.display()
+        ));
+    }
+
+    logging::verbose(format_args!(
+        "Migrated {} cache(s) from \"{}\" to \"{}\"",
+        caches.len(),
+        legacy_path.display(),
+        path.display()
+    ));
+    Ok(())
+}
+
+/// Content of the cache file at `path`, empty if there is none yet. A file that
+/// is not UTF-8 is refused, saying where, rather than taken as empty and
+/// overwritten by the next save.
+pub fn read_cache_file(path: &Path) -> Result<String, CacheError> {
+    let bytes = match std::fs::read(path) {
+        Ok(b) => b,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(String::new()),
+        Err(e) => return Err(IoError::new("read", path, e).into()),
+    };
+    String::from_utf8(bytes).map_err(|e| {
+        let valid = &e.as_bytes()[..e.utf8_error().valid_up_to()];
+        let line_start = valid.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+        let line = valid.iter().filter(|&&b| b == b'\n').count() + 1;
+        not_utf8(path, line, &valid[line_start..])
+    })
+}
+
+/// Read the next line of the cache file at `path` into `line`, false at its
+/// end. `line_num` counts the lines read, to tell which one is not UTF-8.
+fn read_utf8_line(
+    reader: &mut impl BufRead,
+    line: &mut String,
+    line_num: &mut usize,
+    path: &Path,
+) -> Result<bool, CacheError> {
+    let mut bytes = std::mem::take(line).into_bytes();
+    bytes.clear();
+    match reader.read_until(b'\n', &mut bytes) {
+        Ok(0) => return Ok(false),
+        Ok(_) => *line_num += 1,
+        Err(e) => return Err(IoError::new("read", path, e).into()),
+    }
+    match String::from_utf8(bytes) {
+        Ok(l) => {
+            *line = l;
+            Ok(true)
+        }
+        Err(e) => {
+            let valid = &e.as_bytes()[..e.utf8_error().valid_up_to()];
+            Err(not_utf8(path, *line_num, valid))
+        }
+    }
+}
+
+/// A cache file that stops being UTF-8 on line `line` after the bytes `before`.
+fn not_utf8(path: &Path, line: usize, before: &[u8]) -> CacheError {
+    let column = std::str::from_utf8(before).map_or(0, |s| s.chars().count()) + 1;
+    CacheError::Parse(format!(
+        "\"{}\" is not UTF-8 at line {}, column {}, it has to be saved as UTF-8",
+        path.display(),
+        line,
+        column
+    ))
+}
+
+/// The format version of the cache file at `path`, read from the lines before
+/// its first table. None if it cannot be opened.
+fn stored_version(path: &Path) -> Result<Option<i64>, CacheError> {
+    let file = match File::open(path) {
+        Ok(f) => f,
+        Err(_) => return Ok(None),
+    };
+    let mut reader = BufReader::new(file);
+    let mut preamble = String::new();
+    let mut line = String::new();
+    let mut line_num = 0;
+    let mut lines = TomlLines::default();
+    while read_utf8_line(&mut reader, &mut line, &mut line_num, path)? {
+        if lines.table_header(&line).is_some() {
             break;
         }
+        preamble.push_str(&line);
+    }
+    Ok(Some(file_version(&parse_document(&preamble)?)?))
+}
 
-        if c == ':' {
-            if idx == 0 {
-                return LineResult::ParseError(line_err!("Having empty argument name"));
+/// Rewrite the cache file if it was written in an older format version.
+fn upgrade_cache_file(path: &Path) -> Result<(), CacheError> {
+    let outdated = |path: &Path| -> Result<Option<String>, CacheError> {
+        match stored_version(path)? {
+            Some(v) if v < CACHE_FORMAT_VERSION => {}
+            _ => return Ok(None),
+        }
+        read_cache_file(path).map(Some)
+    };
+
+    if outdated(path)?.is_none() {
+        return Ok(());
+    }
+    // Another process may have upgraded while this one waited for the lock.
+    let _lock = CacheLock::acquire(path, true)?;
+    let content = match outdated(path)? {
+        Some(c) => c,
+        None => return Ok(()),
+    };
+
+    let caches = parse_table(&content)?;
+    write_cache_file(path, &format_table(caches))?;
+    logging::verbose(format_args!(
+        "Upgraded \"{}\" to cache format version {}",
+        path.display(),
+        CACHE_FORMAT_VERSION
+    ));
+    Ok(())
+}
+
+/// Move a data directory of the old location to the current one and bring its
+/// cache file to the current format, as `filetemp upgrade` does. Each replaced
+/// file is kept as a ".bak" file. Gives a line for each change, with `dry_run`
+/// for each change that would be made without making it.
+pub fn upgrade_data_dir(dry_run: bool) -> Result<Vec<String>, CacheError> {
+    let mut changes = Vec::new();
+    let (mut dir, source) = filetemp_data_dir()?;
+
+    // The config file moves along with the caches.
+    if source != DATA_DIR_ENV && cfg!(target_os = "linux") && dir.ends_with(".filetemp") {
+        let new_dir = dir.with_file_name("filetemp");
+        changes.push(format!(
+            "Moved \"{}\" to \"{}\"",
+            dir.display(),
+            new_dir.display()
+        ));
+        if !dry_run {
+            if let Err(e) = std::fs::rename(&dir, &new_dir) {
+                return Err(IoError::new("move", dir, e).into());
             }
+            dir = new_dir;
+        }
+    }
 
-            arg_end_size = bidx;
-            ct_start_size = bidx + ':'.len_utf8();
+    let legacy_path = dir.join(LEGACY_CACHE_FILE_NAME);
+    let path = dir.join(CACHE_FILE_NAME);
+    if legacy_path.is_file() && !path.exists() {
+        changes.push(format!(
+            "Converted \"{}\" to \"{}\", the original is kept as \"{}\"",
+            legacy_path.display(),
+            path.display(),
+            legacy_path.with_extension("txt.bak").display()
+        ));
+        if !dry_run {
+            migrate_legacy_cache(&dir)?;
+        }
+        return Ok(changes);
+    }
 
-            if ct_start_size == line.len() {
-                return LineResult::ParseError(line_err!("Having empty argument content"));
+    let content = read_cache_file(&path)?;
+    if content.is_empty() {
+        return Ok(changes);
+    }
+    let version = file_version(&parse_document(&content)?)?;
+    if version > CACHE_FORMAT_VERSION {
+        return Err(CacheError::Parse(format!(
+            "Format version {} of \"{}\" is newer than this filetemp supports ({})",
+            version,
+            path.display(),
+            CACHE_FORMAT_VERSION
+        )));
+    }
+    if version < CACHE_FORMAT_VERSION {
+        let backup = path.with_extension(format!("toml.v{}.bak", version));
+        changes.push(format!(
+            "Upgraded \"{}\" from format version {} to {}, the original is kept as \"{}\"",
+            path.display(),
+            version,
+            CACHE_FORMAT_VERSION,
+            backup.display()
+        ));
+        if !dry_run {
+            if let Err(e) = std::fs::copy(&path, &backup) {
+                return Err(IoError::new("write", backup, e).into());
             }
-            break;
+            upgrade_cache_file(&path)?;
         }
     }
+    Ok(changes)
+}
+
+/// Overrides the directory of the argument cache and the user config.
+pub const DATA_DIR_ENV: &str = "FILETEMP_DATA_DIR";
+
+/// Directory holding the argument cache and the user config, and where its
+/// location comes from. `FILETEMP_DATA_DIR` is used as given, otherwise it is
+/// below the platform data directory, which follows `XDG_DATA_HOME` on Linux.
+pub fn filetemp_data_dir() -> Result<(PathBuf, &'static str), CacheError> {
+    if let Some(dir) = std::env::var_os(DATA_DIR_ENV).filter(|d| !d.is_empty()) {
+        let dir = PathBuf::from(normalize::path(&dir.to_string_lossy()));
+        if cfg!(windows)
+            && let Some(reason) = normalize::windows_name_problem(&dir)
+        {
+            return Err(CacheError::DataDir { dir, reason });
+        }
+        return Ok((dir, DATA_DIR_ENV));
+    }
+
+    let data_dir = match get_data_dir() {
+        Ok(d) => d,
+        Err(_) => return Err(CacheError::NoDataDir),
+    };
+    let source = if std::env::var_os("XDG_DATA_HOME").is_some() && cfg!(target_os = "linux") {
+        "XDG_DATA_HOME"
+    } else {
+        "the platform data directory"
+    };
+
+    // XDG data is kept in a directory named after the program, a ".filetemp"
+    // directory of older versions is still used while it exists.
+    let legacy = data_dir.join(".filetemp");
+    if cfg!(target_os = "linux") {
+        let dir = data_dir.join("filetemp");
+        if !dir.exists() && legacy.is_dir() {
+            return Ok((legacy, source));
+        }
+        return Ok((dir, source));
+    }
+    Ok((legacy, source))
+}
 
-    if is_arg_item {
-        let arg = &line[0..arg_end_size];
-        let content = &line[ct_start_size..];
+/// Location of the argument cache, its directory is created if missing
+/// and a cache file of an older format is migrated.
+pub fn cache_file_path() -> Result<PathBuf, CacheError> {
+    static REPORTED: std::sync::Once = std::sync::Once::new();
 
-        for valid_arg in valid_args {
-            if arg == valid_arg {
-                if arg == "save-as" || arg == "use" || arg == "show" || arg == "path" {
-                    return LineResult::Discard;
-                } else {
-                    return LineResult::ArgItem(ArgPair {
-                        arg: valid_arg,
-                        content,
-                    });
+    let (config_file_dir, source) = filetemp_data_dir()?;
+    REPORTED.call_once(|| {
+        logging::verbose(format_args!(
+            "Using cache directory \"{}\" from {}",
+            config_file_dir.display(),
+            source
+        ))
+    });
+
+    if let Err(e) = std::fs::create_dir_all(&config_file_dir) {
+        return Err(IoError::new("create", config_file_dir, e).into());
+    }
+
+    migrate_cache_file(&config_file_dir)?;
+    Ok(config_file_dir.join(CACHE_FILE_NAME))
+}
+
+/// Replace the cache file atomically: the content is written to a temporary
+/// file in the same directory, then renamed over the old one. A crash midway
+/// leaves the old file intact.
+pub fn write_cache_file(path: &Path, content: &str) -> Result<(), CacheError> {
+    let tmp_path = path.with_extension(format!("toml.tmp-{}", std::process::id()));
+    let written = File::create(&tmp_path).and_then(|mut f| {
+        f.write_all(content.as_bytes())?;
+        f.sync_all()
+    });
+
+    if let Err(e) = written.and_then(|_| std::fs::rename(&tmp_path, path)) {
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(IoError::new("write", path, e).into());
+    }
+    logging::trace(format_args!(
+        "Wrote cache file \"{}\", {} byte(s)",
+        path.display(),
+        content.len()
+    ));
+    Ok(())
+}
+
+/// Name, file type and argument count of a cache, read without validating its arguments.
+pub struct CacheSummary {
+    pub cache_name: String,
+    pub file_type: String,
+    pub arg_count: usize,
+    /// Unix times of the first save and the last `--use`.
+    pub created: Option<i64>,
+    pub last_used: Option<i64>,
+    pub uses: i64,
+}
+
+fn parse_document(content: &str) -> Result<Table, CacheError> {
+    match content.parse::<Table>() {
+        Ok(t) => Ok(t),
+        Err(e) => Err(CacheError::Parse(toml_error_message(content, &e))),
+    }
+}
+
+/// A TOML parse error with its line and column, the table it is in, and the
+/// offending line with a caret under the position.
+pub fn toml_error(prefix: &str, content: &str, err: &toml::de::Error) -> String {
+    format!("{}: {}", prefix, toml_error_message(content, err))
+}
+
+fn toml_error_message(content: &str, err: &toml::de::Error) -> String {
+    let message = err.message().trim_end();
+    let start = match err.span() {
+        Some(s) => s.start.min(content.len()),
+        None => return message.to_string(),
+    };
+
+    let before = &content[..start];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let line_num = before.matches('\n').count() + 1;
+    let line = content[line_start..].lines().next().unwrap_or_default();
+    let col = start - line_start;
+
+    let mut result = format!(
+        "{} at line {}, column {}",
+        message,
+        line_num,
+        line[..col.min(line.len())].chars().count() + 1
+    );
+    if let Some(table) = content[..line_start]
+        .lines()
+        .rev()
+        .map(str::trim)
+        .find(|l| l.starts_with('['))
+    {
+        result.push_str(&format!(" in {}", table));
+    }
+    result.push_str(&line_excerpt(line_num, line, col));
+    result
+}
+
+/// The line with its number and a caret under the byte column `col`.
+fn line_excerpt(line_num: usize, line: &str, col: usize) -> String {
+    let gutter = " ".repeat(line_num.to_string().len());
+    let pad: String = line
+        .char_indices()
+        .take_while(|(i, _)| *i < col)
+        .map(|(_, c)| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    format!(
+        "\n{} |\n{} | {}\n{} | {}^",
+        gutter, line_num, line, gutter, pad
+    )
+}
+
+fn file_version(doc: &Table) -> Result<i64, CacheError> {
+    match doc.get("version") {
+        None => Ok(1),
+        Some(Value::Integer(v)) if *v >= 1 => Ok(*v),
+        Some(_) => Err(CacheError::Parse(String::from("Invalid format version"))),
+    }
+}
+
+/// The table of caches in a cache file, upgraded from older format versions.
+fn parse_table(content: &str) -> Result<Table, CacheError> {
+    let mut doc = parse_document(content)?;
+    let version = file_version(&doc)?;
+    if version > CACHE_FORMAT_VERSION {
+        return Err(CacheError::Parse(format!(
+            "Format version {} is newer than this filetemp supports ({})",
+            version, CACHE_FORMAT_VERSION
+        )));
+    }
+
+    for migrate in MIGRATIONS.iter().skip(version as usize - 1) {
+        doc = migrate(doc)?;
+    }
+
+    match doc.remove("caches") {
+        None => Ok(Table::new()),
+        Some(Value::Table(t)) => Ok(t),
+        Some(_) => Err(CacheError::Parse(String::from("\"caches\" is not a table"))),
+    }
+}
+
+/// Content of a cache file without caches.
+pub fn empty_cache_file() -> String {
+    format_table(Table::new())
+}
+
+/// Content of a cache file holding `caches`, in the current format version.
+fn format_table(caches: Table) -> String {
+    let mut doc = Table::new();
+    doc.insert(
+        String::from("version"),
+        Value::Integer(CACHE_FORMAT_VERSION),
+    );
+    doc.insert(String::from("caches"), Value::Table(caches));
+    doc.to_string()
+}
+
+/// Version 1 kept the caches at the top level, version 2 moves them under
+/// `caches` to make room for the version and other file wide fields.
+fn nest_caches(doc: Table) -> Result<Table, CacheError> {
+    let mut result = Table::new();
+    result.insert(String::from("version"), Value::Integer(2));
+    result.insert(String::from("caches"), Value::Table(doc));
+    Ok(result)
+}
+
+/// Version 3 keys caches by file type and name, `[caches.<file_type>.<name>]`,
+/// so caches of different file types may share a name.
+fn key_by_file_type(mut doc: Table) -> Result<Table, CacheError> {
+    let caches = match doc.remove("caches") {
+        None => Table::new(),
+        Some(Value::Table(t)) => t,
+        Some(_) => {
+            return Err(CacheError::Parse(String::from("\"caches\" is not a table")));
+        }
+    };
+
+    let mut by_type = Table::new();
+    for (name, item) in caches {
+        let mut fields = match item {
+            Value::Table(t) => t,
+            _ => {
+                return Err(CacheError::Parse(format!(
+                    "\"{}\" is not a cache table",
+                    name
+                )));
+            }
+        };
+        let file_type = match fields.remove("file_type").as_ref().and_then(Value::as_str) {
+            Some(t) => FileType::match_type(t),
+            None => FileType::Unknown,
+        };
+        if let FileType::Unknown = file_type {
+            return Err(CacheError::Parse(format!(
+                "Invalid file type for cache \"{}\"",
+                name
+            )));
+        }
+
+        file_type_table(&mut by_type, file_type.to_str()).insert(name, Value::Table(fields));
+    }
+
+    doc.insert(String::from("version"), Value::Integer(3));
+    doc.insert(String::from("caches"), Value::Table(by_type));
+    Ok(doc)
+}
+
+/// Version 4 records when a cache was created and last used, and how often it
+/// was used. Existing caches count as created when they are upgraded.
+fn add_metadata(mut doc: Table) -> Result<Table, CacheError> {
+    if let Some(Value::Table(caches)) = doc.get_mut("caches") {
+        for (_, named) in caches.iter_mut() {
+            let named = match named.as_table_mut() {
+                Some(t) => t,
+                None => continue,
+            };
+            for (_, fields) in named.iter_mut() {
+                let fields = match fields.as_table_mut() {
+                    Some(t) => t,
+                    None => continue,
+                };
+                fields.insert(String::from(META_KEY), Value::Table(new_metadata()));
+            }
+        }
+    }
+
+    doc.insert(String::from("version"), Value::Integer(4));
+    Ok(doc)
+}
+
+/// Version 5 lets a cache extend another one. Older caches extend none, so
+/// only the version changes.
+fn allow_extends(mut doc: Table) -> Result<Table, CacheError> {
+    doc.insert(String::from("version"), Value::Integer(5));
+    Ok(doc)
+}
+
+fn unix_time() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn new_metadata() -> Table {
+    let mut meta = Table::new();
+    meta.insert(String::from("created"), Value::Integer(unix_time()));
+    meta.insert(String::from("uses"), Value::Integer(0));
+    meta
+}
+
+/// Arguments stored in the fields of a cache, without its metadata.
+fn arg_fields(fields: &Table) -> impl Iterator<Item = (&String, &Value)> {
+    fields
+        .iter()
+        .filter(|(k, _)| *k != META_KEY && *k != EXTENDS_KEY)
+}
+
+fn extends_field(fields: &Table) -> Option<&str> {
+    fields.get(EXTENDS_KEY)?.as_str()
+}
+
+/// The caches the named one is built from, its farthest ancestor first and
+/// itself last. `None` if there is no such cache among `caches`, which are of
+/// one file type.
+pub fn cache_chain<'a>(
+    caches: &'a [ArgCache],
+    name: &str,
+) -> Result<Option<Vec<&'a ArgCache>>, CacheError> {
+    let find = |n: &str| caches.iter().find(|c| c.cache_name == n);
+    let mut current = match find(name) {
+        Some(c) => c,
+        None => return Ok(None),
+    };
+
+    let mut chain = vec![current];
+    while let Some(parent) = &current.extends {
+        if chain.iter().any(|c| c.cache_name == *parent) {
+            let mut names: Vec<&str> = chain.iter().map(|c| c.cache_name.as_str()).collect();
+            names.push(parent);
+            return Err(CacheError::Extends(format!(
+                "Cache \"{}\" extends itself: {}",
+                parent,
+                names.join(" -> ")
+            )));
+        }
+        current = match find(parent) {
+            Some(c) => c,
+            None => {
+                return Err(CacheError::Extends(format!(
+                    "Cache \"{}\" extends \"{}\", which is not a saved {} cache",
+                    current.cache_name,
+                    parent,
+                    current.file_type.to_str()
+                )));
+            }
+        };
+        chain.push(current);
+    }
+
+    chain.reverse();
+    let names: Vec<&str> = chain.iter().map(|c| c.cache_name.as_str()).collect();
+    logging::trace(format_args!(
+        "Cache \"{}\" resolves to {}",
+        name,
+        names.join(" -> ")
+    ));
+    Ok(Some(chain))
+}
+
+fn meta_time(fields: &Table, key: &str) -> Option<i64> {
+    fields.get(META_KEY)?.get(key)?.as_integer()
+}
+
+/// Stored values of a cache entry, a repeated argument is an array.
+fn entry_values(cache_name: &str, arg: &str, value: &Value) -> Result<Vec<String>, CacheError> {
+    let items: Vec<&Value> = match value {
+        Value::Array(a) => a.iter().collect(),
+        v => vec![v],
+    };
+
+    let mut values = Vec::with_capacity(items.len());
+    for item in items {
+        match item {
+            Value::String(s) => values.push(s.clone()),
+            Value::Integer(n) => values.push(n.to_string()),
+            Value::Boolean(b) => values.push(b.to_string()),
+            _ => {
+                return Err(CacheError::Parse(format!(
+                    "Unsupported value for \"{}\" in cache \"{}\"",
+                    arg, cache_name
+                )));
+            }
+        }
+    }
+    Ok(values)
+}
+
+/// A cache in the table of caches: file type, name and stored fields.
+type CacheTable<'a> = (&'a str, &'a str, &'a Table);
+
+/// The caches of every file type, in file order.
+fn cache_tables(table: &Table) -> Result<Vec<CacheTable<'_>>, CacheError> {
+    let mut caches = Vec::new();
+    for (file_type, named) in table.iter() {
+        let named = match named.as_table() {
+            Some(t) => t,
+            None => {
+                return Err(CacheError::Parse(format!(
+                    "[caches.{}] is not a table of caches",
+                    file_type
+                )));
+            }
+        };
+        for (name, item) in named.iter() {
+            match item.as_table() {
+                Some(t) => caches.push((file_type.as_str(), name.as_str(), t)),
+                None => {
+                    return Err(CacheError::Parse(format!(
+                        "\"{}\" is not a cache table",
+                        name
+                    )));
                 }
             }
         }
+    }
+    Ok(caches)
+}
+
+/// The cache named `name`, of `file_type` if given. Several caches of that name
+/// for different file types are an error naming them.
+fn find_cache<'a>(
+    table: &'a Table,
+    name: &str,
+    file_type: Option<&str>,
+) -> Result<Option<CacheTable<'a>>, CacheError> {
+    let mut found: Vec<CacheTable<'a>> = cache_tables(table)?
+        .into_iter()
+        .filter(|(t, n, _)| *n == name && file_type.is_none_or(|f| f.eq_ignore_ascii_case(t)))
+        .collect();
 
-        if arg == "file_type" {
-            return LineResult::FileTy(FileType::match_type(content));
+    if found.len() > 1 {
+        return Err(CacheError::Ambiguous {
+            name: name.to_string(),
+            file_types: found.iter().map(|(t, _, _)| t.to_string()).collect(),
+        });
+    }
+    Ok(found.pop())
+}
+
+/// Whether a cache name given to a command is a pattern for `glob_match`.
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+/// Match a cache name against a pattern where `*` stands for any run of
+/// characters and `?` for a single one.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // Position after the last `*` and the name position it was tried at.
+    let mut star: Option<(usize, usize)> = None;
+    let (mut p, mut n) = (0, 0);
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((sp, sn)) => {
+                    star = Some((sp, sn + 1));
+                    p = sp;
+                    n = sn + 1;
+                }
+                None => return false,
+            },
         }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
 
-        LineResult::ParseError(format!(
-            "Argument parse error: Having invalid argument name \"{}\" at line {}",
-            arg, line_num
-        ))
-    } else {
-        let cache_name_end_size: usize = line.len() - ']'.len_utf8();
+/// The caches of one file type, created if missing.
+fn file_type_table<'a>(table: &'a mut Table, file_type: &str) -> &'a mut Table {
+    let entry = table
+        .entry(file_type)
+        .or_insert_with(|| Value::Table(Table::new()));
+    if !entry.is_table() {
+        *entry = Value::Table(Table::new());
+    }
+    entry.as_table_mut().unwrap()
+}
 
-        if line.chars().last().unwrap() != ']' {
-            LineResult::ParseError(line_err!("Missing ]"))
-        } else if cache_name_start_size >= cache_name_end_size {
-            LineResult::ParseError(line_err!("Having empty cache name"))
-        } else {
-            LineResult::CacheName(&line[cache_name_start_size..cache_name_end_size])
+/// Summaries of the caches in a cache file, in file order.
+pub fn cache_summaries(content: &str) -> Result<Vec<CacheSummary>, CacheError> {
+    let table = parse_table(content)?;
+    let mut summaries = Vec::new();
+    for (file_type, name, fields) in cache_tables(&table)? {
+        let mut summary = CacheSummary {
+            cache_name: name.to_string(),
+            file_type: file_type.to_string(),
+            arg_count: 0,
+            created: meta_time(fields, "created"),
+            last_used: meta_time(fields, "last_used"),
+            uses: meta_time(fields, "uses").unwrap_or(0),
+        };
+        for (arg, value) in arg_fields(fields) {
+            summary.arg_count += entry_values(name, arg, value)?.len();
         }
+        summaries.push(summary);
     }
+
+    Ok(summaries)
 }
 
-pub struct ConfigWriter {
-    file_handle: std::fs::File,
+/// File type, parent and stored `name`/`value` pairs of a cache.
+pub type CacheEntries = (String, Option<String>, Vec<(String, String)>);
+
+/// Entries of the named cache, `None` if there is no such cache.
+pub fn cache_entries(
+    content: &str,
+    name: &str,
+    file_type: Option<&str>,
+) -> Result<Option<CacheEntries>, CacheError> {
+    let table = parse_table(content)?;
+    let (file_type, _, fields) = match find_cache(&table, name, file_type)? {
+        Some(c) => c,
+        None => return Ok(None),
+    };
+
+    let mut entries = Vec::new();
+    for (arg, value) in arg_fields(fields) {
+        for v in entry_values(name, arg, value)? {
+            entries.push((arg.clone(), v));
+        }
+    }
+    Ok(Some((
+        file_type.to_string(),
+        extends_field(fields).map(String::from),
+        entries,
+    )))
 }
 
-impl ConfigWriter {
-    pub fn new(file: std::fs::File) -> Self {
-        Self { file_handle: file }
+/// Content without the named cache, `None` if there is no such cache.
+pub fn remove_cache(
+    content: &str,
+    name: &str,
+    file_type: Option<&str>,
+) -> Result<Option<String>, CacheError> {
+    let mut table = parse_table(content)?;
+    let file_type = match find_cache(&table, name, file_type)? {
+        Some((t, _, _)) => t.to_string(),
+        None => return Ok(None),
+    };
+
+    let named = file_type_table(&mut table, &file_type);
+    named.remove(name);
+    if named.is_empty() {
+        table.remove(&file_type);
     }
+    Ok(Some(format_table(table)))
+}
 
-    pub fn write_to_config(
-        &mut self,
-        cache: ArgCacheCollection,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let le = match LineEnding::from_current_platform() {
-            LineEnding::CR => "\r",
-            LineEnding::LF => "\n",
-            LineEnding::CRLF => "\r\n",
+/// Content with the named cache under a new name, `None` if there is no such cache.
+/// The new name must not be taken by another cache of the same file type.
+pub fn rename_cache(
+    content: &str,
+    old: &str,
+    new: &str,
+    file_type: Option<&str>,
+) -> Result<Option<String>, CacheError> {
+    let mut table = parse_table(content)?;
+    let file_type = match find_cache(&table, old, file_type)? {
+        Some((t, _, _)) => t.to_string(),
+        None => return Ok(None),
+    };
+
+    let named = file_type_table(&mut table, &file_type);
+    if named.contains_key(new) {
+        return Err(CacheError::Exists {
+            name: new.to_string(),
+            file_type,
+        });
+    }
+    let renamed: Table = std::mem::take(named)
+        .into_iter()
+        .map(|(n, v)| {
+            if n == old {
+                (new.to_string(), v)
+            } else {
+                (n, v)
+            }
+        })
+        .collect();
+    *named = renamed;
+    Ok(Some(format_table(table)))
+}
+
+/// Version of the JSON document written by `export_caches`.
+const EXPORT_VERSION: i64 = 1;
+
+/// Caches as a JSON document for sharing, all of them or only those of the given
+/// name and file type. `None` if no cache matches.
+pub fn export_caches(
+    content: &str,
+    name: Option<&str>,
+    file_type: Option<&str>,
+) -> Result<Option<String>, CacheError> {
+    let table = parse_table(content)?;
+
+    let mut caches = Vec::new();
+    for (cache_type, cache_name, fields) in cache_tables(&table)? {
+        if name.is_some_and(|n| n != cache_name)
+            || file_type.is_some_and(|t| !t.eq_ignore_ascii_case(cache_type))
+        {
+            continue;
+        }
+
+        let mut args = serde_json::Map::new();
+        for (arg, value) in arg_fields(fields) {
+            let values = entry_values(cache_name, arg, value)?;
+            let v = match value {
+                Value::Array(_) => json!(values),
+                _ => json!(values[0]),
+            };
+            args.insert(arg.clone(), v);
+        }
+        let mut cache = json!({
+            "name": cache_name,
+            "file_type": cache_type,
+            "args": args,
+        });
+        if let Some(parent) = extends_field(fields) {
+            cache["extends"] = json!(parent);
+        }
+        caches.push(cache);
+    }
+
+    if (name.is_some() || file_type.is_some()) && caches.is_empty() {
+        return Ok(None);
+    }
+    let doc = json!({ "version": EXPORT_VERSION, "caches": caches });
+    Ok(Some(format!(
+        "{}\n",
+        serde_json::to_string_pretty(&doc).unwrap()
+    )))
+}
+
+/// Content with the caches of an exported JSON document added, and their names.
+/// Existing caches of the same name are only overwritten with `replace`.
+pub fn import_caches(
+    content: &str,
+    json: &str,
+    replace: bool,
+) -> Result<(String, Vec<String>), CacheError> {
+    let doc: serde_json::Value = match serde_json::from_str(json) {
+        Ok(d) => d,
+        Err(e) => return Err(CacheError::Import(e.to_string())),
+    };
+    if doc["version"].as_i64() != Some(EXPORT_VERSION) {
+        return Err(CacheError::Import(format!(
+            "Unsupported document version, expected {}",
+            EXPORT_VERSION
+        )));
+    }
+    let caches = match doc["caches"].as_array() {
+        Some(c) => c,
+        None => return Err(CacheError::Import(String::from("Missing \"caches\" array"))),
+    };
+
+    let mut table = parse_table(content)?;
+    let mut names = Vec::new();
+    for cache in caches {
+        let name = match cache["name"].as_str() {
+            Some(n) if !n.is_empty() => n,
+            _ => return Err(CacheError::Import(String::from("A cache has no name"))),
         };
+        let file_type = FileType::match_type(cache["file_type"].as_str().unwrap_or_default());
+        if let FileType::Unknown = file_type {
+            return Err(CacheError::Import(format!(
+                "Invalid file type for cache \"{}\"",
+                name
+            )));
+        }
+        if find_cache(&table, name, Some(file_type.to_str()))?.is_some() && !replace {
+            return Err(CacheError::Exists {
+                name: name.to_string(),
+                file_type: file_type.to_str().to_string(),
+            });
+        }
 
-        let mut result = String::new();
-        for item in cache.iter() {
-            write!(&mut result, "[{}]{}", item.cache_name, le)?;
-            write!(&mut result, "file_type:{}{}", item.file_type.to_str(), le)?;
-            for arg_item in item.args.iter() {
-                if arg_item.arg != "show"
-                    && arg_item.arg != "path"
-                    && arg_item.arg != "save-as"
-                    && arg_item.arg != "use"
-                {
-                    write!(&mut result, "{}:{}{}", arg_item.arg, arg_item.content, le)?;
+        let mut fields = Table::new();
+        if let Some(parent) = cache["extends"].as_str() {
+            fields.insert(String::from(EXTENDS_KEY), Value::String(parent.to_string()));
+        }
+        for (arg, value) in cache["args"].as_object().into_iter().flatten() {
+            let v = match value {
+                serde_json::Value::Array(items) => {
+                    let items: Option<Vec<Value>> = items.iter().map(json_scalar_to_toml).collect();
+                    items.map(Value::Array)
                 }
+                v => json_scalar_to_toml(v),
+            };
+            match v {
+                Some(v) => fields.insert(arg.clone(), v),
+                None => {
+                    return Err(CacheError::Import(format!(
+                        "Unsupported value for \"{}\" in cache \"{}\"",
+                        arg, name
+                    )));
+                }
+            };
+        }
+
+        fields.insert(String::from(META_KEY), Value::Table(new_metadata()));
+        file_type_table(&mut table, file_type.to_str())
+            .insert(name.to_string(), Value::Table(fields));
+        names.push(name.to_string());
+    }
+
+    Ok((format_table(table), names))
+}
+
+fn json_scalar_to_toml(value: &serde_json::Value) -> Option<Value> {
+    match value {
+        serde_json::Value::String(s) => Some(Value::String(s.clone())),
+        serde_json::Value::Bool(b) => Some(Value::String(b.to_string())),
+        serde_json::Value::Number(n) => Some(Value::String(n.to_string())),
+        _ => None,
+    }
+}
+
+/// Parse the caches of `file_type` in the content of a cache file, a table of
+/// arguments per cache. Caches of other file types are not read.
+pub fn parse_caches<'a, I>(
+    content: &str,
+    file_type: FileType,
+    valid_args: I,
+) -> Result<Vec<ArgCache>, CacheError>
+where
+    I: Iterator<Item = &'a str> + Clone,
+{
+    let table = parse_table(content)?;
+
+    let mut caches: Vec<ArgCache> = Vec::new();
+    for (_, name, fields) in cache_tables(&table)?
+        .into_iter()
+        .filter(|(t, _, _)| *t == file_type.to_str())
+    {
+        caches.push(read_cache(name, file_type, fields, valid_args.clone())?);
+    }
+
+    Ok(caches)
+}
+
+fn read_cache<'a, I>(
+    name: &str,
+    file_type: FileType,
+    fields: &Table,
+    valid_args: I,
+) -> Result<ArgCache, CacheError>
+where
+    I: Iterator<Item = &'a str> + Clone,
+{
+    let mut cache = ArgCache::new(name, file_type);
+    cache.extends = match fields.get(EXTENDS_KEY) {
+        None => None,
+        Some(Value::String(parent)) => Some(parent.clone()),
+        Some(_) => {
+            return Err(CacheError::Parse(format!(
+                "\"{}\" of cache \"{}\" must be a cache name",
+                EXTENDS_KEY, name
+            )));
+        }
+    };
+    for (key, value) in arg_fields(fields) {
+        let arg = match valid_args.clone().find(|a| a == key) {
+            Some(a) => a,
+            None => {
+                return Err(CacheError::Parse(format!(
+                    "Having invalid argument name \"{}\" in cache \"{}\"",
+                    key, name
+                )));
             }
-            result.push_str(le);
+        };
+        if UNCACHED_ARGS.contains(&arg) {
+            continue;
+        }
+
+        for content in entry_values(name, key, value)? {
+            cache.args.push(CachedArg {
+                arg: arg.to_string(),
+                content,
+            });
         }
+    }
+    Ok(cache)
+}
 
-        self.file_handle.write(result.as_bytes())?;
+/// File type and stored arguments of the named cache as a TOML document to edit,
+/// `None` if there is no such cache. The metadata is left out.
+pub fn cache_args_text(
+    content: &str,
+    name: &str,
+    file_type: Option<&str>,
+) -> Result<Option<(String, String)>, CacheError> {
+    let table = parse_table(content)?;
+    let (file_type, _, fields) = match find_cache(&table, name, file_type)? {
+        Some(c) => c,
+        None => return Ok(None),
+    };
 
-        Ok(())
+    let args: Table = fields
+        .iter()
+        .filter(|(k, _)| *k != META_KEY)
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    let text = format!(
+        "# Arguments of the {} cache \"{}\", one per line, repeated values as an array.\n\
+         # extends = \"<NAME>\" builds on another cache of the same file type.\n\
+         # Save and close the editor to apply the changes.\n\n{}",
+        file_type, name, args
+    );
+    Ok(Some((file_type.to_string(), text)))
+}
+
+/// Parse a cache edited as the document of `cache_args_text`.
+pub fn parse_cache_args<'a, I>(
+    text: &str,
+    name: &str,
+    file_type: FileType,
+    valid_args: I,
+) -> Result<ArgCache, CacheError>
+where
+    I: Iterator<Item = &'a str> + Clone,
+{
+    let fields = parse_document(text)?;
+    read_cache(name, file_type, &fields, valid_args)
+}
+
+/// Content with `cache` added, or replacing the cache of the same file type and
+/// name in place. Other caches are kept as they are.
+/// A replaced cache keeps its metadata.
+pub fn save_cache(content: &str, cache: &ArgCache) -> Result<String, CacheError> {
+    let mut table = parse_table(content)?;
+    let named = file_type_table(&mut table, cache.file_type.to_str());
+    let mut fields = cache_fields(cache);
+    if let Some(meta) = named
+        .get(&cache.cache_name)
+        .and_then(|old| old.get(META_KEY))
+    {
+        fields.insert(String::from(META_KEY), meta.clone());
+    }
+    named.insert(cache.cache_name.to_string(), Value::Table(fields));
+    Ok(format_table(table))
+}
+
+/// Content with a use of the cache recorded, `None` if there is no such cache.
+pub fn record_cache_use(
+    content: &str,
+    name: &str,
+    file_type: FileType,
+) -> Result<Option<String>, CacheError> {
+    let mut table = parse_table(content)?;
+    let fields = match file_type_table(&mut table, file_type.to_str())
+        .get_mut(name)
+        .and_then(Value::as_table_mut)
+    {
+        Some(f) => f,
+        None => return Ok(None),
+    };
+
+    let meta = fields
+        .entry(META_KEY)
+        .or_insert_with(|| Value::Table(new_metadata()));
+    if let Value::Table(meta) = meta {
+        let uses = meta.get("uses").and_then(Value::as_integer).unwrap_or(0);
+        meta.insert(String::from("uses"), Value::Integer(uses + 1));
+        meta.insert(String::from("last_used"), Value::Integer(unix_time()));
+    }
+    Ok(Some(format_table(table)))
+}
+
+/// Content without the caches not used, or created if never used, within the
+/// last `max_age` seconds, and the file type and name of each removed cache.
+pub fn prune_caches(
+    content: &str,
+    max_age: i64,
+) -> Result<(String, Vec<(String, String)>), CacheError> {
+    let table = parse_table(content)?;
+    let cutoff = unix_time() - max_age;
+
+    let stale: Vec<(String, String)> = cache_tables(&table)?
+        .into_iter()
+        .filter(|(_, _, fields)| {
+            meta_time(fields, "last_used")
+                .or_else(|| meta_time(fields, "created"))
+                .is_some_and(|t| t < cutoff)
+        })
+        .map(|(t, n, _)| (t.to_string(), n.to_string()))
+        .collect();
+
+    let mut table = table;
+    drop_caches(&mut table, &stale);
+    Ok((format_table(table), stale))
+}
+
+/// Content without the caches whose name matches `pattern`, of `file_type` if
+/// given, and the file type and name of each removed cache.
+pub fn remove_matching_caches(
+    content: &str,
+    pattern: &str,
+    file_type: Option<&str>,
+) -> Result<(String, Vec<(String, String)>), CacheError> {
+    let table = parse_table(content)?;
+    let matched: Vec<(String, String)> = cache_tables(&table)?
+        .into_iter()
+        .filter(|(t, n, _)| {
+            glob_match(pattern, n) && file_type.is_none_or(|f| f.eq_ignore_ascii_case(t))
+        })
+        .map(|(t, n, _)| (t.to_string(), n.to_string()))
+        .collect();
+
+    let mut table = table;
+    drop_caches(&mut table, &matched);
+    Ok((format_table(table), matched))
+}
+
+/// Remove the given file type and name pairs, and file types left without caches.
+fn drop_caches(table: &mut Table, caches: &[(String, String)]) {
+    for (file_type, name) in caches.iter() {
+        let named = file_type_table(table, file_type);
+        named.remove(name);
+        if named.is_empty() {
+            table.remove(file_type);
+        }
+    }
+}
+
+/// Serialize caches in the format read by `parse_caches`.
+pub fn format_caches(cache: &[ArgCache]) -> String {
+    let mut result = Table::new();
+    for item in cache.iter() {
+        file_type_table(&mut result, item.file_type.to_str()).insert(
+            item.cache_name.to_string(),
+            Value::Table(cache_fields(item)),
+        );
+    }
+
+    format_table(result)
+}
+
+fn cache_fields(item: &ArgCache) -> Table {
+    let mut fields = Table::new();
+    if let Some(parent) = &item.extends {
+        fields.insert(String::from(EXTENDS_KEY), Value::String(parent.clone()));
+    }
+    for arg_item in item.args.iter() {
+        if UNCACHED_ARGS.contains(&arg_item.arg.as_str()) {
+            continue;
+        }
+
+        let value = Value::String(arg_item.content.clone());
+        match fields.get_mut(&arg_item.arg) {
+            Some(Value::Array(values)) => values.push(value),
+            Some(first) => *first = Value::Array(vec![first.clone(), value]),
+            None => {
+                fields.insert(arg_item.arg.clone(), value);
+            }
+        }
+    }
+
+    fields.insert(String::from(META_KEY), Value::Table(new_metadata()));
+    fields
+}
+
+/// The arguments of a cache, all values of a repeatable argument come from one cache.
+pub fn cache_layer(cache: &ArgCache) -> Layer {
+    let mut layer = Layer::new(Source::Cache, cache.cache_name.as_str());
+    for arg in cache.args.iter() {
+        layer.push(&arg.arg, &arg.content);
+    }
+    layer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Save `values` as a cache and load it back.
+    fn save_and_load(values: &[(&str, &str)]) -> Vec<(String, String)> {
+        let mut cache = ArgCache::new("demo", FileType::CMake);
+        for &(arg, content) in values {
+            cache.args.push(CachedArg {
+                arg: arg.to_string(),
+                content: content.to_string(),
+            });
+        }
+        let content = save_cache(&empty_cache_file(), &cache).unwrap();
+        let valid_args = ["proj", "description", "link"];
+        let loaded = parse_caches(&content, FileType::CMake, valid_args.into_iter()).unwrap();
+        assert_eq!(loaded.len(), 1);
+        loaded[0]
+            .args
+            .iter()
+            .map(|a| (a.arg.clone(), a.content.clone()))
+            .collect()
+    }
+
+    /// Xorshift generator, the same seed gives the same cases on every run.
+    pub(super) struct Rng(u64);
+
+    impl Rng {
+        pub(super) fn new(seed: u64) -> Self {
+            Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+        }
+
+        pub(super) fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+
+        /// Up to `max_len` characters picked from `pool`, at least one if `non_empty`.
+        pub(super) fn text(&mut self, pool: &[char], max_len: usize, non_empty: bool) -> String {
+            let len = self.below(max_len + 1).max(non_empty as usize);
+            (0..len).map(|_| pool[self.below(pool.len())]).collect()
+        }
+    }
+
+    /// One to four byte characters, wide ones, a combining mark and a zero width joiner.
+    pub(super) const MULTIBYTE: &[char] = &[
+        'a', 'Z', '7', '-', '_', 'é', 'ß', 'ж', 'ع', '项', '目', '测', '试', 'ア', '한', '\u{301}',
+        '\u{200D}', '€', '😀', '🦀', '𝄞',
+    ];
+
+    /// `MULTIBYTE` and the characters the cache formats escape or treat specially.
+    const MULTIBYTE_AND_SPECIAL: &[char] = &[
+        'a', 'é', '项', '目', '😀', '\u{301}', ':', '"', '\'', '\\', '#', '[', ']', '=', ' ', '\t',
+        '\n', '\r',
+    ];
+
+    fn owned(values: &[(&str, &str)]) -> Vec<(String, String)> {
+        values
+            .iter()
+            .map(|&(a, c)| (a.to_string(), c.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn values_with_colons_load_unchanged() {
+        let values = [
+            ("proj", "demo"),
+            (
+                "description",
+                "key: value, url https://example.com:8080/a:b",
+            ),
+            ("link", "a::b"),
+            ("link", ":"),
+        ];
+        assert_eq!(save_and_load(&values), owned(&values));
+    }
+
+    #[test]
+    fn values_with_line_breaks_load_unchanged() {
+        let values = [
+            ("description", "first line\nsecond line\n"),
+            ("link", "\r\n"),
+            ("proj", "trailing\\n is not a line break"),
+        ];
+        assert_eq!(save_and_load(&values), owned(&values));
+    }
+
+    #[test]
+    fn values_with_quotes_load_unchanged() {
+        let values = [
+            ("description", "say \"hi\" and 'bye'"),
+            ("link", "\"\"\""),
+            ("link", "'''\n\"\"\""),
+            ("proj", "back\\slash # not a comment"),
+        ];
+        assert_eq!(save_and_load(&values), owned(&values));
+    }
+
+    #[test]
+    fn empty_values_load_unchanged() {
+        let values = [("description", ""), ("link", "")];
+        assert_eq!(save_and_load(&values), owned(&values));
+    }
+
+    #[test]
+    fn multibyte_cache_name_round_trips() {
+        let mut cache = ArgCache::new("项目", FileType::CMake);
+        cache.args.push(CachedArg {
+            arg: String::from("proj"),
+            content: String::from("项目"),
+        });
+        let content = save_cache(&empty_cache_file(), &cache).unwrap();
+        let loaded = parse_caches(&content, FileType::CMake, ["proj"].into_iter()).unwrap();
+        assert_eq!(loaded[0].cache_name, "项目");
+        assert_eq!(loaded[0].args[0].content, "项目");
+    }
+
+    #[test]
+    fn arbitrary_multibyte_caches_round_trip() {
+        let valid_args = ["proj", "description", "link"];
+        for seed in 0..500 {
+            let mut rng = Rng::new(seed);
+            let name = rng.text(MULTIBYTE, 12, true);
+            let mut cache = ArgCache::new(&name, FileType::CMake);
+            for _ in 0..rng.below(4) {
+                cache.args.push(CachedArg {
+                    arg: valid_args[rng.below(valid_args.len())].to_string(),
+                    content: rng.text(MULTIBYTE_AND_SPECIAL, 16, false),
+                });
+            }
+
+            let content = save_cache(&empty_cache_file(), &cache).unwrap();
+            let loaded = parse_caches(&content, FileType::CMake, valid_args.into_iter())
+                .unwrap_or_else(|e| panic!("seed {}: {}", seed, e));
+            assert_eq!(loaded.len(), 1, "seed {}", seed);
+            assert_eq!(loaded[0].cache_name, name, "seed {}", seed);
+            // Values are grouped by argument, each keeps the order it was given in.
+            let pairs = |args: &[CachedArg]| -> Vec<(String, String)> {
+                let mut pairs: Vec<_> = args
+                    .iter()
+                    .map(|a| (a.arg.clone(), a.content.clone()))
+                    .collect();
+                pairs.sort_by(|a, b| a.0.cmp(&b.0));
+                pairs
+            };
+            assert_eq!(pairs(&loaded[0].args), pairs(&cache.args), "seed {}", seed);
+        }
     }
 }
@@ -0,0 +1,83 @@
+//! The cache file for tools embedding filetemp, reading and saving caches
+//! the way `--use` and `--save-as` do.
+
+use std::path::{Path, PathBuf};
+
+use super::{
+    ArgCache, CacheLock, UNCACHED_ARGS, cache_chain, cache_file_path, parse_caches,
+    read_cache_file, save_cache, write_cache_file,
+};
+use crate::{
+    error::{ArgError, CacheError, FiletempError},
+    file_types::FileType,
+    program_args::CommandArg,
+};
+
+pub struct CacheStore {
+    path: PathBuf,
+    content: String,
+}
+
+impl CacheStore {
+    /// Read the cache file at `path`, or the one in the data directory without
+    /// it. A file that does not exist yet holds no caches.
+    pub fn load(path: Option<&Path>) -> Result<Self, CacheError> {
+        let path = match path {
+            Some(p) => p.to_path_buf(),
+            None => cache_file_path()?,
+        };
+        let _lock = CacheLock::acquire(&path, false)?;
+        let content = read_cache_file(&path)?;
+        Ok(Self { path, content })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The cache `name` of `file_type` and the caches it extends, its farthest
+    /// ancestor first, to give to `ResolvedArgs::parse_with_caches`.
+    pub fn get(&self, file_type: &str, name: &str) -> Result<Vec<ArgCache>, FiletempError> {
+        let ty = FileType::match_type(file_type);
+        let names = arg_names(file_type)?;
+        let caches = parse_caches(&self.content, ty, names.iter().map(String::as_str))?;
+        match cache_chain(&caches, name)? {
+            Some(chain) => Ok(chain.into_iter().cloned().collect()),
+            None => Err(CacheError::NotFound {
+                name: name.to_string(),
+                file_type: Some(ty.to_str().to_string()),
+            }
+            .into()),
+        }
+    }
+
+    /// Add `cache`, or replace the cache of the same file type and name, and
+    /// write the cache file. Caches saved meanwhile by others are kept.
+    pub fn save(&mut self, cache: &ArgCache) -> Result<(), CacheError> {
+        let _lock = CacheLock::acquire(&self.path, true)?;
+        let content = read_cache_file(&self.path)?;
+        let content = save_cache(&content, cache)?;
+        write_cache_file(&self.path, &content)?;
+        self.content = content;
+        Ok(())
+    }
+}
+
+/// Names of the arguments a cache of `file_type` may store, and those older
+/// versions stored that are skipped.
+fn arg_names(file_type: &str) -> Result<Vec<String>, ArgError> {
+    let mut cmd = CommandArg::new();
+    crate::file_types::register_types(&mut cmd);
+    if FileType::match_type(file_type) == FileType::Unknown
+        || cmd
+            .process_program_args(&[FileType::match_type(file_type).to_str()])
+            .is_err()
+    {
+        return Err(ArgError::UnknownFileType(file_type.to_string()));
+    }
+    Ok(cmd
+        .type_arg_names()
+        .chain(UNCACHED_ARGS)
+        .map(String::from)
+        .collect())
+}
@@ -0,0 +1,145 @@
+use std::{
+    backtrace::Backtrace,
+    fmt::Write,
+    panic::PanicHookInfo,
+    path::{Path, PathBuf},
+};
+
+use crate::program_args::version_message;
+
+/// Lines of the argument cache included in a debug bundle.
+const CACHE_EXCERPT_LINES: usize = 40;
+
+/// Argument names whose values never end up in a report.
+const SENSITIVE_NAMES: [&str; 4] = ["token", "secret", "password", "key"];
+
+const REDACTED: &str = "<redacted>";
+
+/// Replace the default panic output with a short bug notice, and write a
+/// report to `bundle` if one was requested.
+pub fn install_panic_hook(args: Vec<String>, bundle: Option<PathBuf>, cache: Option<PathBuf>) {
+    std::panic::set_hook(Box::new(move |info| {
+        eprintln!("filetemp crashed unexpectedly: {}", panic_message(info));
+        eprintln!(
+            "This is a bug, please report it at {}",
+            env!("CARGO_PKG_REPOSITORY")
+        );
+
+        match &bundle {
+            Some(path) => match write_report(path, info, &args, cache.as_deref()) {
+                Ok(_) => eprintln!("A report was written to \"{}\"", path.display()),
+                Err(_) => eprintln!("Failed to write the report to \"{}\"", path.display()),
+            },
+            None => eprintln!("Run again with --debug-bundle <FILE> to write a report to attach."),
+        }
+    }));
+}
+
+fn panic_message(info: &PanicHookInfo) -> String {
+    let payload = info.payload();
+    let msg = if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        String::from("unknown panic")
+    };
+
+    match info.location() {
+        Some(l) => format!("{} ({}:{})", msg, l.file(), l.line()),
+        None => msg,
+    }
+}
+
+fn write_report(
+    path: &Path,
+    info: &PanicHookInfo,
+    args: &[String],
+    cache: Option<&Path>,
+) -> std::io::Result<()> {
+    let mut report = String::new();
+    writeln!(report, "{}", version_message()).unwrap();
+    writeln!(
+        report,
+        "os: {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    )
+    .unwrap();
+    writeln!(report, "panic: {}", redact_text(&panic_message(info))).unwrap();
+    writeln!(report, "args: {}", redact_args(args).join(" ")).unwrap();
+
+    report.push_str("\ncache excerpt:\n");
+    match cache.and_then(|c| std::fs::read_to_string(c).ok()) {
+        Some(content) => {
+            for line in content.lines().take(CACHE_EXCERPT_LINES) {
+                writeln!(report, "    {}", redact_cache_line(line)).unwrap();
+            }
+        }
+        None => report.push_str("    (none)\n"),
+    }
+
+    writeln!(report, "\nbacktrace:\n{}", Backtrace::force_capture()).unwrap();
+    std::fs::write(path, report)
+}
+
+fn is_sensitive(name: &str) -> bool {
+    let name = name.to_lowercase();
+    SENSITIVE_NAMES.iter().any(|s| name.contains(s))
+}
+
+fn redact_args(args: &[String]) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut mask_next = false;
+    for arg in args.iter() {
+        if mask_next {
+            out.push(String::from(REDACTED));
+            mask_next = false;
+            continue;
+        }
+
+        match arg.strip_prefix("--") {
+            Some(long) => match long.split_once('=') {
+                Some((name, _)) if is_sensitive(name) => {
+                    out.push(format!("--{}={}", name, REDACTED))
+                }
+                Some(_) => out.push(redact_text(arg)),
+                None => {
+                    mask_next = is_sensitive(long);
+                    out.push(arg.to_string());
+                }
+            },
+            None => out.push(redact_text(arg)),
+        }
+    }
+    out
+}
+
+fn redact_cache_line(line: &str) -> String {
+    match line.split_once('=') {
+        Some((name, _)) if is_sensitive(name.trim()) => format!("{}= {}", name, REDACTED),
+        _ => redact_text(line),
+    }
+}
+
+/// Mask credentials in URLs and the user's home directory in paths.
+fn redact_text(text: &str) -> String {
+    let mut out = text.to_string();
+
+    if let Some(scheme_end) = out.find("://") {
+        let rest = &out[scheme_end + 3..];
+        let host_end = rest.find('/').unwrap_or(rest.len());
+        if let Some(at) = rest[..host_end].rfind('@') {
+            out.replace_range(scheme_end + 3..scheme_end + 3 + at, REDACTED);
+        }
+    }
+
+    if let Some(home) = std::env::home_dir()
+        && let Some(home) = home.to_str()
+        && home.len() > 1
+    {
+        out = out.replace(home, "~");
+    }
+
+    out
+}
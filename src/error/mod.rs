@@ -0,0 +1,354 @@
+//! Errors by kind, so callers can tell a bad argument from a broken cache file
+//! or a failed write. IO errors keep the path they concern and their cause.
+
+use std::{
+    error::Error,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    exit_status::Failure,
+    i18n::{tr, trf},
+    program_args::{
+        ArgProcessErr, CommandArg,
+        resolve::{Layer, Source},
+    },
+};
+
+/// Any error of filetemp, by the kind of failure.
+#[derive(Debug)]
+pub enum FiletempError {
+    Arg(ArgError),
+    Cache(CacheError),
+    Generate(GenerateError),
+    Io(IoError),
+}
+
+impl FiletempError {
+    /// Exit status of a run stopped by this error.
+    pub fn failure(&self) -> Failure {
+        match self {
+            FiletempError::Arg(_) => Failure::Args,
+            FiletempError::Cache(_) => Failure::Cache,
+            FiletempError::Generate(_) => Failure::Generation,
+            FiletempError::Io(_) => Failure::Io,
+        }
+    }
+
+    /// A value of `layer` refused by its argument. The command line is not to
+    /// blame for a value saved in a cache, so that is a cache error.
+    pub fn refused(e: ArgProcessErr, layer: &Layer) -> Self {
+        let message = format!("{} in {}", e, layer);
+        match layer.source {
+            Source::Cache => CacheError::Refused(message).into(),
+            Source::ProjectConfig | Source::UserConfig => ArgError::Config(message).into(),
+            _ => ArgError::Invalid(message).into(),
+        }
+    }
+}
+
+impl fmt::Display for FiletempError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FiletempError::Arg(e) => e.fmt(f),
+            FiletempError::Cache(e) => e.fmt(f),
+            FiletempError::Generate(e) => e.fmt(f),
+            FiletempError::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for FiletempError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FiletempError::Arg(e) => e.source(),
+            FiletempError::Cache(e) => e.source(),
+            FiletempError::Generate(e) => e.source(),
+            FiletempError::Io(e) => e.source(),
+        }
+    }
+}
+
+impl From<ArgError> for FiletempError {
+    fn from(e: ArgError) -> Self {
+        FiletempError::Arg(e)
+    }
+}
+
+impl From<CacheError> for FiletempError {
+    fn from(e: CacheError) -> Self {
+        FiletempError::Cache(e)
+    }
+}
+
+impl From<GenerateError> for FiletempError {
+    fn from(e: GenerateError) -> Self {
+        FiletempError::Generate(e)
+    }
+}
+
+impl From<IoError> for FiletempError {
+    fn from(e: IoError) -> Self {
+        FiletempError::Io(e)
+    }
+}
+
+/// Invalid, missing or conflicting arguments.
+#[derive(Debug)]
+pub enum ArgError {
+    UnknownFileType(String),
+    /// Refused while parsing the command line.
+    Parse(ArgProcessErr),
+    /// Required arguments no source gave, and the message listing them with their help.
+    Missing {
+        args: Vec<String>,
+        message: String,
+    },
+    /// Refused by the checks of a file type, or given wrongly to a command.
+    Invalid(String),
+    /// A project or user config giving defaults is malformed or names unknown arguments.
+    Config(String),
+}
+
+impl ArgError {
+    /// The `missing` required arguments of `cmd`, `required` of them in total.
+    pub fn missing(cmd: &CommandArg, missing: &[String], required: usize) -> Self {
+        ArgError::Missing {
+            args: missing.to_vec(),
+            message: cmd.missing_args_message(missing, required),
+        }
+    }
+}
+
+impl fmt::Display for ArgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgError::UnknownFileType(name) => {
+                f.write_str(&trf("Invalid file type: \"{}\"", &[name]))
+            }
+            ArgError::Parse(e) => e.fmt(f),
+            ArgError::Missing { message, .. } => write!(f, "{}", message.trim_end()),
+            ArgError::Invalid(message) | ArgError::Config(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for ArgError {}
+
+/// Reading, saving or resolving argument caches.
+#[derive(Debug)]
+pub enum CacheError {
+    /// No data directory to keep the cache file in.
+    NoDataDir,
+    /// The data directory given in the environment cannot be used, and why.
+    DataDir {
+        dir: PathBuf,
+        reason: String,
+    },
+    /// The cache file, or a cache edited by hand, is malformed.
+    Parse(String),
+    /// A document given to `cache import` is malformed.
+    Import(String),
+    NotFound {
+        name: String,
+        file_type: Option<String>,
+    },
+    /// No saved cache matches a pattern of names.
+    NoMatch(String),
+    /// The named cache exists only for other file types.
+    OtherFileType {
+        name: String,
+        saved_for: Vec<String>,
+        file_type: String,
+    },
+    /// Caches of the same name exist for several file types.
+    Ambiguous {
+        name: String,
+        file_types: Vec<String>,
+    },
+    Exists {
+        name: String,
+        file_type: String,
+    },
+    /// A cache extends a missing cache or itself.
+    Extends(String),
+    /// A saved value is refused by its argument, e.g. one edited by hand.
+    Refused(String),
+    /// Saving the arguments and loading them again changes the output.
+    Roundtrip(String),
+    /// Editing a cache ended without saving it, and why if not refused by the user.
+    Unchanged {
+        name: String,
+        reason: Option<String>,
+    },
+    Io(IoError),
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheError::NoDataDir => f.write_str(&trf(
+                "Cannot find the user data directory, set {} to choose where caches are kept",
+                &[&crate::config_file::DATA_DIR_ENV],
+            )),
+            CacheError::DataDir { dir, reason } => f.write_str(&trf(
+                "Cannot keep caches in \"{}\" given by {}: {}",
+                &[&dir.display(), &crate::config_file::DATA_DIR_ENV, reason],
+            )),
+            CacheError::Parse(message) => {
+                f.write_str(&trf("Argument cache parse error: {}", &[message]))
+            }
+            CacheError::Import(message) => f.write_str(&trf("Cache import error: {}", &[message])),
+            CacheError::NotFound {
+                name,
+                file_type: Some(t),
+            } => f.write_str(&trf("No saved {} cache named \"{}\"", &[t, name])),
+            CacheError::NotFound {
+                name,
+                file_type: None,
+            } => f.write_str(&trf("No saved cache named \"{}\"", &[name])),
+            CacheError::NoMatch(pattern) => {
+                f.write_str(&trf("No saved caches match \"{}\"", &[pattern]))
+            }
+            CacheError::OtherFileType {
+                name,
+                saved_for,
+                file_type,
+            } => f.write_str(&trf(
+                "Cache \"{}\" was saved for {}, not for {}",
+                &[name, &saved_for.join(", "), file_type],
+            )),
+            CacheError::Ambiguous { name, file_types } => f.write_str(&trf(
+                "Caches named \"{}\" exist for {}, give the file type before the name",
+                &[name, &file_types.join(", ")],
+            )),
+            CacheError::Exists { name, file_type } => f.write_str(&trf(
+                "A {} cache named \"{}\" already exists",
+                &[file_type, name],
+            )),
+            CacheError::Extends(message) | CacheError::Refused(message) => {
+                write!(f, "{}", message)
+            }
+            CacheError::Roundtrip(message) => {
+                f.write_str(&trf("Cache round trip failed, {}", &[message]))
+            }
+            CacheError::Unchanged { name, reason } => match reason {
+                Some(r) => f.write_str(&trf("{}, cache \"{}\" was not changed", &[r, name])),
+                None => f.write_str(&trf("Cache \"{}\" was not changed", &[name])),
+            },
+            CacheError::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for CacheError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CacheError::Io(e) => e.source(),
+            _ => None,
+        }
+    }
+}
+
+impl From<IoError> for CacheError {
+    fn from(e: IoError) -> Self {
+        CacheError::Io(e)
+    }
+}
+
+/// The file could not be generated.
+#[derive(Debug)]
+pub enum GenerateError {
+    /// The template at `path` failed to render.
+    Template {
+        path: PathBuf,
+        message: String,
+    },
+    /// Example sources were asked of a file type without them.
+    NoExample(String),
+    /// The generated file cannot be merged into the existing one at `path`.
+    Merge {
+        path: PathBuf,
+        message: String,
+    },
+    /// The output sink refused a generated file.
+    Write(String),
+    Io(IoError),
+}
+
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenerateError::Template { path, message } => {
+                f.write_str(&trf("{} in \"{}\"", &[message, &path.display()]))
+            }
+            GenerateError::NoExample(name) => {
+                f.write_str(&trf("File type \"{}\" has no example project", &[name]))
+            }
+            GenerateError::Merge { path, message } => f.write_str(&trf(
+                "Cannot merge into \"{}\": {}",
+                &[&path.display(), message],
+            )),
+            GenerateError::Write(message) => write!(f, "{}", message),
+            GenerateError::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for GenerateError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GenerateError::Io(e) => e.source(),
+            _ => None,
+        }
+    }
+}
+
+impl From<IoError> for GenerateError {
+    fn from(e: IoError) -> Self {
+        GenerateError::Io(e)
+    }
+}
+
+/// A file operation that failed, with the path it was done on.
+#[derive(Debug)]
+pub struct IoError {
+    /// What was done, e.g. "read" or "create".
+    action: &'static str,
+    path: PathBuf,
+    source: io::Error,
+}
+
+impl IoError {
+    pub fn new(action: &'static str, path: impl Into<PathBuf>, source: io::Error) -> Self {
+        Self {
+            action,
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn kind(&self) -> io::ErrorKind {
+        self.source.kind()
+    }
+}
+
+impl fmt::Display for IoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&trf(
+            "Failed to {} \"{}\": {}",
+            &[&tr(self.action), &self.path.display(), &self.source],
+        ))
+    }
+}
+
+impl Error for IoError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
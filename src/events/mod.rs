@@ -0,0 +1,102 @@
+use std::path::Path;
+
+use crate::{file_types::FileType, logging};
+
+/// Progress of a generation run, for tools wrapping filetemp.
+pub enum Event<'a> {
+    GenerationStarted {
+        file_type: FileType,
+    },
+    FileWritten {
+        path: &'a Path,
+    },
+    /// A problem that does not stop the run.
+    WarningEmitted {
+        message: &'a str,
+    },
+    Finished {
+        files_written: usize,
+        warnings: usize,
+    },
+}
+
+type Listener = Box<dyn FnMut(&Event)>;
+
+/// Dispatches events to every subscribed listener, in subscription order.
+pub struct EventBus {
+    listeners: Vec<Listener>,
+    files_written: usize,
+    warnings: usize,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self {
+            listeners: Vec::new(),
+            files_written: 0,
+            warnings: 0,
+        }
+    }
+
+    pub fn subscribe(&mut self, listener: impl FnMut(&Event) + 'static) -> &mut Self {
+        self.listeners.push(Box::new(listener));
+        self
+    }
+
+    pub fn emit(&mut self, event: Event) {
+        match event {
+            Event::FileWritten { .. } => self.files_written += 1,
+            Event::WarningEmitted { .. } => self.warnings += 1,
+            _ => {}
+        }
+
+        for listener in self.listeners.iter_mut() {
+            listener(&event);
+        }
+    }
+
+    pub fn warn(&mut self, message: &str) {
+        self.emit(Event::WarningEmitted { message });
+    }
+
+    /// Emit `Finished` with the counts collected so far.
+    pub fn finish(&mut self) {
+        self.emit(Event::Finished {
+            files_written: self.files_written,
+            warnings: self.warnings,
+        });
+    }
+}
+
+/// Listener used by the command line, reports warnings to stderr unless quiet.
+pub fn print_warnings(event: &Event) {
+    if let Event::WarningEmitted { message } = event {
+        logging::warn(message);
+    }
+}
+
+/// Listener used by the command line, reports each step to stderr when verbose.
+pub fn print_progress(event: &Event) {
+    match event {
+        Event::GenerationStarted { file_type } => {
+            logging::verbose(format_args!("Generating {} file", file_type.to_str()))
+        }
+        Event::FileWritten { path } => {
+            logging::verbose(format_args!("Wrote \"{}\"", path.display()))
+        }
+        Event::WarningEmitted { .. } => {}
+        Event::Finished {
+            files_written,
+            warnings,
+        } => logging::verbose(format_args!(
+            "Finished, {} file(s) written, {} warning(s)",
+            files_written, warnings
+        )),
+    }
+}
@@ -0,0 +1,43 @@
+//! Exit status of a run, so scripts can tell what kind of failure stopped it.
+//! The first failure reported decides the status, a run without one exits 0.
+
+use std::{
+    process::ExitCode,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+/// Kinds of failure, each with its own exit status.
+#[derive(Clone, Copy)]
+pub enum Failure {
+    /// `filetemp lint` reported findings of error severity.
+    LintErrors = 1,
+    /// Invalid, missing or conflicting arguments, or an unknown command.
+    Args = 2,
+    /// Reading, writing or locking the cache file.
+    Cache = 3,
+    /// Writing the generated files, or reading the files a command is given.
+    Io = 4,
+    /// The file could not be generated, e.g. a template or plugin failed.
+    Generation = 5,
+}
+
+static STATUS: AtomicU8 = AtomicU8::new(0);
+
+/// Record `failure` as the exit status, unless an earlier one was recorded.
+pub fn fail(failure: Failure) {
+    let _ = STATUS.compare_exchange(0, failure as u8, Ordering::Relaxed, Ordering::Relaxed);
+}
+
+/// Whether a failure was recorded, for commands running others in turn.
+pub fn failed() -> bool {
+    STATUS.load(Ordering::Relaxed) != 0
+}
+
+pub fn status() -> ExitCode {
+    ExitCode::from(STATUS.load(Ordering::Relaxed))
+}
+
+/// Exit right away with `failure`, for errors before a command runs.
+pub fn exit(failure: Failure) -> ! {
+    std::process::exit(failure as i32)
+}
@@ -1,8 +1,21 @@
-use std::{fmt::Write, str::FromStr};
-
-use crate::program_args::CommandArg;
-
-const C_EXAMPLE: &'static str = "\
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    error::{ArgError, GenerateError},
+    file_types::{
+        FileTypeGenerator,
+        cmake_version::{CMakeVersionRange, feature, standard_feature},
+    },
+    logging,
+    output_sink::{FILE_MODE, OutputSink},
+    program_args::{Arg, ArgFileTypeView, ArgSet, CommandArg, ValueType, normalize},
+};
+
+const C_EXAMPLE: &str = "\
 #include <stdio.h>
 
 int main()
@@ -11,7 +24,7 @@ int main()
     return 0;
 }";
 
-const CXX_OLD_EXAMPLE: &'static str = "\
+const CXX_OLD_EXAMPLE: &str = "\
 #include <iostream>
 
 int main()
@@ -19,7 +32,7 @@ int main()
     std::cout << \"Hello World\" << std::endl;
 }";
 
-const CXX_23_EXAMPLE: &'static str = "\
+const CXX_23_EXAMPLE: &str = "\
 #include <print>
 
 int main()
@@ -27,7 +40,7 @@ int main()
     std::println(\"Hello World\");
 }";
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum TargetType {
     Executable,
     StaticLib,
@@ -50,10 +63,10 @@ impl FromStr for TargetType {
     }
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum LanguageType {
     C,
-    CXX,
+    Cxx,
 }
 
 impl FromStr for LanguageType {
@@ -63,7 +76,7 @@ impl FromStr for LanguageType {
         if s.eq_ignore_ascii_case("C") {
             Ok(Self::C)
         } else if s.eq_ignore_ascii_case("CXX") {
-            Ok(Self::CXX)
+            Ok(Self::Cxx)
         } else {
             Err(())
         }
@@ -74,10 +87,46 @@ pub struct CMakeListsFile<'a> {
     cmake_version: &'a str,
     project_name: &'a str,
     main_language: LanguageType,
-    c_standard: Option<i32>,
-    cxx_standard: Option<i32>,
+    c_standard: Option<i64>,
+    cxx_standard: Option<i64>,
     target_type: TargetType,
     target_name: &'a str,
+    project_version: Option<&'a str>,
+    description: Option<&'a str>,
+    homepage: Option<&'a str>,
+    links: Vec<&'a str>,
+    packages: Vec<&'a str>,
+    fetches: Vec<FetchDependency<'a>>,
+    compile_options: Vec<&'a str>,
+}
+
+/// A `NAME=REPO[#TAG]` dependency downloaded at configure time.
+pub struct FetchDependency<'a> {
+    name: &'a str,
+    repository: &'a str,
+    tag: Option<&'a str>,
+}
+
+impl<'a> FetchDependency<'a> {
+    /// Split a value accepted by `validate_fetch`.
+    fn parse(value: &'a str) -> Option<Self> {
+        let (name, rest) = value.split_once('=')?;
+        let (repository, tag) = match rest.rsplit_once('#') {
+            Some((r, t)) => (r, Some(t)),
+            None => (rest, None),
+        };
+        Some(Self {
+            name,
+            repository,
+            tag,
+        })
+    }
+}
+
+impl Default for CMakeListsFile<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'a> CMakeListsFile<'a> {
@@ -85,11 +134,18 @@ impl<'a> CMakeListsFile<'a> {
         Self {
             cmake_version: "",
             project_name: "",
-            main_language: LanguageType::CXX,
+            main_language: LanguageType::Cxx,
             c_standard: None,
             cxx_standard: None,
             target_type: TargetType::Executable,
             target_name: "",
+            project_version: None,
+            description: None,
+            homepage: None,
+            links: Vec::new(),
+            packages: Vec::new(),
+            fetches: Vec::new(),
+            compile_options: Vec::new(),
         }
     }
 
@@ -108,12 +164,12 @@ impl<'a> CMakeListsFile<'a> {
         self
     }
 
-    pub fn require_c_standard(&mut self, standard: i32) -> &mut Self {
+    pub fn require_c_standard(&mut self, standard: i64) -> &mut Self {
         self.c_standard = Some(standard);
         self
     }
 
-    pub fn require_cxx_standard(&mut self, standard: i32) -> &mut Self {
+    pub fn require_cxx_standard(&mut self, standard: i64) -> &mut Self {
         self.cxx_standard = Some(standard);
         self
     }
@@ -128,6 +184,121 @@ impl<'a> CMakeListsFile<'a> {
         self
     }
 
+    pub fn set_project_version(&mut self, ver: &'a str) -> &mut Self {
+        self.project_version = Some(ver);
+        self
+    }
+
+    pub fn set_description(&mut self, desc: &'a str) -> &mut Self {
+        self.description = Some(desc);
+        self
+    }
+
+    pub fn set_homepage(&mut self, url: &'a str) -> &mut Self {
+        self.homepage = Some(url);
+        self
+    }
+
+    pub fn link_library(&mut self, lib: &'a str) -> &mut Self {
+        self.links.push(lib);
+        self
+    }
+
+    pub fn find_package(&mut self, pkg: &'a str) -> &mut Self {
+        self.packages.push(pkg);
+        self
+    }
+
+    pub fn fetch_dependency(&mut self, dep: FetchDependency<'a>) -> &mut Self {
+        self.fetches.push(dep);
+        self
+    }
+
+    pub fn add_compile_option(&mut self, opt: &'a str) -> &mut Self {
+        self.compile_options.push(opt);
+        self
+    }
+
+    /// Locate dependencies before the target is declared.
+    fn write_dependencies(&self, out: &mut String) {
+        for p in self.packages.iter() {
+            writeln!(out, "find_package({} REQUIRED)", p).unwrap();
+        }
+        if !self.packages.is_empty() {
+            out.push('\n');
+        }
+
+        if self.fetches.is_empty() {
+            return;
+        }
+        out.push_str("include(FetchContent)\n");
+        for d in self.fetches.iter() {
+            write!(
+                out,
+                "FetchContent_Declare(\n    {}\n    GIT_REPOSITORY {}",
+                d.name,
+                quote_argument(d.repository)
+            )
+            .unwrap();
+            if let Some(t) = d.tag {
+                write!(out, "\n    GIT_TAG {}", quote_argument(t)).unwrap();
+            }
+            out.push_str("\n)\n");
+        }
+        let names: Vec<&str> = self.fetches.iter().map(|d| d.name).collect();
+        write!(out, "FetchContent_MakeAvailable({})\n\n", names.join(" ")).unwrap();
+    }
+
+    /// Languages enabled by `project()`, the main language plus any with a standard set.
+    fn languages(&self) -> &'static str {
+        let c = self.main_language == LanguageType::C || self.c_standard.is_some();
+        let cxx = self.main_language == LanguageType::Cxx || self.cxx_standard.is_some();
+        match (c, cxx) {
+            (true, true) => "C CXX",
+            (true, false) => "C",
+            _ => "CXX",
+        }
+    }
+
+    fn write_project(&self, out: &mut String) {
+        let mut clauses = Vec::new();
+        if let Some(v) = self.project_version {
+            clauses.push(format!("VERSION {}", v));
+        }
+        if let Some(d) = self.description {
+            clauses.push(format!("DESCRIPTION {}", quote_argument(d)));
+        }
+        if let Some(h) = self.homepage {
+            clauses.push(format!("HOMEPAGE_URL {}", quote_argument(h)));
+        }
+
+        if clauses.is_empty() {
+            write!(
+                out,
+                "project({} LANGUAGES {})\n\n",
+                self.project_name,
+                self.languages()
+            )
+            .unwrap();
+            return;
+        }
+
+        clauses.push(format!("LANGUAGES {}", self.languages()));
+        write!(out, "project({}", self.project_name).unwrap();
+        for c in clauses.iter() {
+            write!(out, "\n    {}", c).unwrap();
+        }
+        out.push_str("\n)\n\n");
+    }
+
+    /// Whether the required minimum version has a feature, assumes yes if no version is given.
+    fn supports(&self, feature_name: &str) -> bool {
+        match self.cmake_version.parse::<CMakeVersionRange>() {
+            Ok(range) => feature(feature_name).supported_by(range.min),
+            Err(_) => true,
+        }
+    }
+
     pub fn output_string(&self) -> String {
         let mut out = String::new();
         write!(
@@ -155,28 +326,134 @@ impl<'a> CMakeListsFile<'a> {
             .unwrap();
         }
 
-        write!(&mut out, "project({})\n\n", self.project_name).unwrap();
+        self.write_project(&mut out);
+        self.write_dependencies(&mut out);
+
+        let main_src = format!(
+            "src/main.{}",
+            if let LanguageType::Cxx = self.main_language {
+                "cpp"
+            } else {
+                "c"
+            }
+        );
+
+        // Older versions need the sources listed directly in the target command.
+        let sourceless = self.supports("sourceless-targets");
+        let inline_src = if sourceless {
+            String::new()
+        } else {
+            format!(" {}", main_src)
+        };
 
         match self.target_type {
             TargetType::Executable => {
-                write!(&mut out, "add_executable({})\n\n", self.target_name).unwrap();
+                write!(
+                    &mut out,
+                    "add_executable({}{})\n\n",
+                    self.target_name, inline_src
+                )
+                .unwrap();
             }
             TargetType::StaticLib => {
-                write!(&mut out, "add_library({} STATIC)\n\n", self.target_name).unwrap();
+                write!(
+                    &mut out,
+                    "add_library({} STATIC{})\n\n",
+                    self.target_name, inline_src
+                )
+                .unwrap();
             }
             TargetType::SharedLib => {
-                write!(&mut out, "add_library({} SHARED)\n\n", self.target_name).unwrap();
+                write!(
+                    &mut out,
+                    "add_library({} SHARED{})\n\n",
+                    self.target_name, inline_src
+                )
+                .unwrap();
             }
         }
 
-        write!(&mut out, "target_include_directories({pn} PRIVATE src)\ntarget_sources({pn} PRIVATE src/main.{ext})",
-            pn = self.target_name, ext = if let LanguageType::CXX = self.main_language {"cpp"} else {"c"}).unwrap();
+        write!(
+            &mut out,
+            "target_include_directories({} PRIVATE src)",
+            self.target_name
+        )
+        .unwrap();
+
+        if sourceless {
+            write!(
+                &mut out,
+                "\ntarget_sources({} PRIVATE {})",
+                self.target_name, main_src
+            )
+            .unwrap();
+        }
+
+        if !self.compile_options.is_empty() {
+            let opts: Vec<String> = self
+                .compile_options
+                .iter()
+                .map(|o| {
+                    if o.contains(|c: char| c.is_whitespace() || "()#\"\\$;".contains(c)) {
+                        quote_argument(o)
+                    } else {
+                        o.to_string()
+                    }
+                })
+                .collect();
+            write!(
+                &mut out,
+                "\ntarget_compile_options({} PRIVATE {})",
+                self.target_name,
+                opts.join(" ")
+            )
+            .unwrap();
+        }
+
+        let libs: Vec<String> = self
+            .links
+            .iter()
+            .map(|l| l.to_string())
+            .chain(self.packages.iter().map(|p| format!("{}::{}", p, p)))
+            .chain(self.fetches.iter().map(|d| d.name.to_string()))
+            .collect();
+        if !libs.is_empty() {
+            write!(
+                &mut out,
+                "\ntarget_link_libraries({} PRIVATE {})",
+                self.target_name,
+                libs.join(" ")
+            )
+            .unwrap();
+        }
 
         out
     }
 }
 
-pub(super) fn process_args(cmd: &CommandArg) -> String {
+/// Quote a value as a CMake quoted argument.
+fn quote_argument(value: &str) -> String {
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        if c == '\\' || c == '"' || c == '$' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+    out
+}
+
+/// Project versions are `major[.minor[.patch[.tweak]]]` of non-negative integers.
+fn is_valid_project_version(v: &str) -> bool {
+    let parts: Vec<&str> = v.split('.').collect();
+    parts.len() <= 4
+        && parts
+            .iter()
+            .all(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit()))
+}
+
+fn process_args(cmd: &CommandArg) -> String {
     let mut f: CMakeListsFile = CMakeListsFile::new();
 
     macro_rules! use_argument {
@@ -185,79 +462,445 @@ pub(super) fn process_args(cmd: &CommandArg) -> String {
                 f.$func(a);
             }
         };
+        (int $str_name:literal, $func:ident) => {
+            if let Some(n) = cmd.get_int($str_name) {
+                f.$func(n);
+            }
+        };
         ($type:ty, $str_name:literal, $func:ident) => {
-            if let Some(a) = cmd.get_arg($str_name) {
-                f.$func(a.parse::<$type>().unwrap());
+            if let Some(&v) = cmd.get_parsed::<$type>($str_name) {
+                f.$func(v);
             }
         };
     }
 
     use_argument!("version", require_version);
     use_argument!("proj", set_project_name);
-    use_argument!(i32, "cstd", require_c_standard);
-    use_argument!(i32, "cxxstd", require_cxx_standard);
+    use_argument!("proj-version", set_project_version);
+    use_argument!("description", set_description);
+    use_argument!("homepage", set_homepage);
+    use_argument!(int "cstd", require_c_standard);
+    use_argument!(int "cxxstd", require_cxx_standard);
     use_argument!(LanguageType, "main-lang", set_main_language);
     use_argument!(TargetType, "target-type", set_target_type);
 
+    for l in cmd.get_args("link") {
+        f.link_library(l);
+    }
+    for o in cmd.get_raw_values() {
+        f.add_compile_option(o);
+    }
+    for p in cmd.get_args("find-package") {
+        f.find_package(p);
+    }
+    for d in cmd.get_args("fetch") {
+        f.fetch_dependency(FetchDependency::parse(d).unwrap());
+    }
+
     if let Some(tn) = cmd.get_arg("target-name") {
         f.set_target_name(tn);
     } else {
-        f.set_target_name(cmd.get_arg("proj").unwrap());
+        let proj = cmd.get_arg("proj").unwrap();
+        logging::verbose(format_args!(
+            "No --target-name given, naming the target \"{}\" after the project",
+            proj
+        ));
+        f.set_target_name(proj);
     }
 
     f.output_string()
 }
 
-pub(super) fn verify_existed_args(cmd: &CommandArg) -> Result<(), String> {
-    macro_rules! assert_parse_ok {
-        ($type: ty, $arg: literal, $errfmt: literal) => {
-            if let Some(r) = cmd.get_arg($arg)
-                && r.parse::<$type>().is_err()
-            {
-                return Err(format!($errfmt, r));
-            }
-        };
+pub struct CMakeGenerator;
+
+impl FileTypeGenerator for CMakeGenerator {
+    fn name(&self) -> &str {
+        "cmake"
     }
 
-    assert_parse_ok!(i32, "cstd", "Invalid C standard: {}");
-    assert_parse_ok!(i32, "cxxstd", "Invalid C++ standard: {}");
-    assert_parse_ok!(LanguageType, "main-lang", "Invalid main language: {}");
-    assert_parse_ok!(TargetType, "target-type", "Invalid target type: {}");
+    fn output_name(&self) -> &str {
+        "CMakeLists.txt"
+    }
 
-    Ok(())
+    fn arg_defs(&self, view: &mut ArgFileTypeView) {
+        define_args(view);
+    }
+
+    fn verify(&self, cmd: &CommandArg) -> Result<(), ArgError> {
+        verify_existed_args(cmd).map_err(ArgError::Invalid)
+    }
+
+    fn render(&self, cmd: &CommandArg) -> Result<String, GenerateError> {
+        Ok(process_args(cmd))
+    }
+
+    fn example_files(
+        &self,
+        cmd: &CommandArg,
+        sink: &mut dyn OutputSink,
+    ) -> Result<PathBuf, GenerateError> {
+        generate_example(cmd, sink).map_err(GenerateError::Write)
+    }
+}
+
+fn define_args(view: &mut ArgFileTypeView) {
+    view.add_arg_def(
+        Arg::new("version")
+            .value_name("VER")
+            .normalize(normalize::trim)
+            .validator(validate_version)
+            .required(true)
+            .help("Used in \"cmake_minimum_required\", a version or a <min>...<max> range"),
+    )
+    .add_arg_def(
+        Arg::new("proj")
+            .short('p')
+            .alias("project")
+            .value_name("NAME")
+            .normalize(normalize::trim)
+            .validator(validate_project_name)
+            .required(true)
+            .help("Project name"),
+    )
+    .add_arg_def(
+        Arg::new("proj-version")
+            .value_name("VER")
+            .normalize(normalize::trim)
+            .validator(validate_project_version)
+            .help("Project version, emitted as VERSION in \"project\""),
+    )
+    .add_arg_def(
+        Arg::new("description")
+            .value_name("TEXT")
+            .help("Project description, emitted as DESCRIPTION in \"project\""),
+    )
+    .add_arg_def(
+        Arg::new("homepage")
+            .value_name("URL")
+            .help("Project homepage, emitted as HOMEPAGE_URL in \"project\""),
+    )
+    .add_arg_def(
+        Arg::new("main-lang")
+            .short('l')
+            .value_name("LANG")
+            .normalize(normalize::lowercase)
+            .default_val("cxx")
+            .value_type(ValueType::Enum(&["c", "cxx"]))
+            .value_parser::<LanguageType>()
+            .help("Main language, decides whether \"main.c\" or \"main.cpp\" is generated"),
+    )
+    .add_arg_def(
+        Arg::new("cstd")
+            .value_name("STD")
+            .normalize(normalize::language_standard)
+            .value_type(ValueType::Int {
+                min: None,
+                max: None,
+            })
+            .possible_values(["90", "99", "11", "17", "23"])
+            .conflicts_with("main-lang=cxx")
+            .help("C standard, e.g. 11 or c11"),
+    )
+    .add_arg_def(
+        Arg::new("cxxstd")
+            .value_name("STD")
+            .normalize(normalize::language_standard)
+            .value_type(ValueType::Int {
+                min: None,
+                max: None,
+            })
+            .possible_values(["98", "11", "14", "17", "20", "23", "26"])
+            .help("C++ standard, e.g. 17 or c++17"),
+    )
+    .add_arg_def(
+        Arg::new("target-type")
+            .short('t')
+            .value_name("TYPE")
+            .normalize(normalize::lowercase)
+            .value_type(ValueType::Enum(&["executable", "staticlib", "sharedlib"]))
+            .value_parser::<TargetType>()
+            .help("Target type, executable if not specified"),
+    )
+    .add_arg_def(
+        Arg::new("target-name")
+            .short('n')
+            .value_name("NAME")
+            .normalize(normalize::trim)
+            .validator(validate_target_name)
+            .help("Target name, use project name if not specified"),
+    )
+    .add_arg_def(
+        Arg::new("link")
+            .value_name("LIB")
+            .multiple(true)
+            .normalize(normalize::forward_slashes)
+            .validator(validate_dependency_name)
+            .help("Link a library already known to the toolchain"),
+    )
+    .add_arg_def(
+        Arg::new("find-package")
+            .value_name("PKG")
+            .multiple(true)
+            .normalize(normalize::trim)
+            .validator(validate_dependency_name)
+            .help("Find an installed package and link its PKG::PKG target"),
+    )
+    .add_arg_def(
+        Arg::new("fetch")
+            .value_name("NAME=REPO[#TAG]")
+            .multiple(true)
+            .normalize(normalize::forward_slashes)
+            .validator(validate_fetch)
+            .help("Download a git repository with FetchContent and link its NAME target"),
+    )
+    .add_arg_set(ArgSet::new("linking", &["link", "find-package", "fetch"]).at_most_one())
+    .add_arg_set(
+        ArgSet::new("std", &["cstd", "cxxstd"])
+            .at_least_one()
+            .when("main-lang"),
+    )
+    .raw_values("Compile options of the target, e.g. -- -Wall -Wextra");
+}
+
+pub fn validate_version(v: &str) -> Result<(), String> {
+    match v.parse::<CMakeVersionRange>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(String::from(
+            "expected a version like 3.20 or a range like 3.16...3.28",
+        )),
+    }
+}
+
+/// Names are written unquoted, so they must be a single CMake argument.
+fn validate_unquoted(v: &str, what: &str) -> Result<(), String> {
+    let unquotable = |c: char| c.is_whitespace() || "()#\"\\;$".contains(c);
+    if v.is_empty() {
+        Err(format!("the {} cannot be empty", what))
+    } else if v.chars().any(unquotable) {
+        Err(format!(
+            "the {} cannot contain whitespace or any of ( ) # \" \\ ; ${}",
+            what,
+            try_instead(&sanitized(v, |c| !unquotable(c)))
+        ))
+    } else {
+        Ok(())
+    }
 }
 
-pub(super) fn generate_example(cmd: &CommandArg, path: &std::path::Path) -> Result<(), String> {
-    let src_path = path.join("src");
-    if let Err(_) = std::fs::create_dir_all(&src_path) {
-        return Err(String::from("Failed to create source directory"));
+/// Names CMake keeps for targets of its own, refused by policy CMP0037.
+const RESERVED_TARGET_NAMES: &[&str] = &[
+    "all",
+    "ALL_BUILD",
+    "clean",
+    "depend",
+    "edit_cache",
+    "help",
+    "install",
+    "INSTALL",
+    "list_install_components",
+    "package",
+    "PACKAGE",
+    "package_source",
+    "preinstall",
+    "rebuild_cache",
+    "RUN_TESTS",
+    "test",
+    "ZERO_CHECK",
+];
+
+fn is_target_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_.+-".contains(c)
+}
+
+/// Why CMake would refuse `name` as the name of a target, if it would, and a
+/// name it accepts instead.
+fn target_name_problem(name: &str) -> Option<(String, String)> {
+    if name.is_empty() {
+        Some((String::from("cannot be empty"), String::new()))
+    } else if !name.chars().all(is_target_char) {
+        Some((
+            String::from("may only contain letters, digits and _ . + -"),
+            sanitized(name, is_target_char),
+        ))
+    } else if RESERVED_TARGET_NAMES.contains(&name) {
+        Some((
+            format!("cannot be \"{}\", CMake reserves it", name),
+            format!("{}_target", name),
+        ))
+    } else {
+        None
     }
+}
+
+/// `name` with each run of characters `keep` refuses replaced by one underscore,
+/// underscores left at either end are dropped.
+fn sanitized(name: &str, keep: impl Fn(char) -> bool) -> String {
+    let mut out = String::new();
+    for c in name.chars() {
+        match keep(c) {
+            true => out.push(c),
+            false if !out.ends_with('_') => out.push('_'),
+            false => {}
+        }
+    }
+    out.trim_matches('_').to_string()
+}
+
+/// Suggestion appended to a rejection, nothing if sanitizing left no name.
+fn try_instead(name: &str) -> String {
+    match name {
+        "" => String::new(),
+        n => format!(", try \"{}\"", n),
+    }
+}
 
+pub fn validate_project_name(v: &str) -> Result<(), String> {
+    validate_unquoted(v, "project name")
+}
+
+pub fn validate_target_name(v: &str) -> Result<(), String> {
+    match target_name_problem(v) {
+        Some((problem, suggestion)) => Err(format!(
+            "the target name {}{}",
+            problem,
+            try_instead(&suggestion)
+        )),
+        None => Ok(()),
+    }
+}
+
+pub fn validate_dependency_name(v: &str) -> Result<(), String> {
+    validate_unquoted(v, "dependency name")
+}
+
+pub fn validate_fetch(v: &str) -> Result<(), String> {
+    let dep = match FetchDependency::parse(v) {
+        Some(d) => d,
+        None => return Err(String::from("expected NAME=REPO or NAME=REPO#TAG")),
+    };
+    validate_unquoted(dep.name, "dependency name")?;
+    if dep.repository.is_empty() || dep.tag.is_some_and(str::is_empty) {
+        Err(String::from("expected NAME=REPO or NAME=REPO#TAG"))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn validate_project_version(v: &str) -> Result<(), String> {
+    if is_valid_project_version(v) {
+        Ok(())
+    } else {
+        Err(String::from("expected up to four numbers like 1.2.3"))
+    }
+}
+
+/// Checks across arguments, single values are validated while parsing.
+fn verify_existed_args(cmd: &CommandArg) -> Result<(), String> {
+    // Without --target-name the target is named after the project.
+    if cmd.get_arg("target-name").is_none()
+        && let Some(proj) = cmd.get_arg("proj")
+        && let Some((problem, suggestion)) = target_name_problem(proj)
+    {
+        let give = match suggestion.as_str() {
+            "" => String::from("give --target-name"),
+            s => format!("give --target-name, e.g. --target-name {}", s),
+        };
+        return Err(format!(
+            "the target is named after --proj \"{}\", but a target name {}; {}",
+            proj, problem, give
+        ));
+    }
+
+    // Refuse standards the required minimum version cannot express.
+    let min_version = match cmd.get_arg("version") {
+        Some(v) => v.parse::<CMakeVersionRange>().unwrap().min,
+        None => return Ok(()),
+    };
+
+    for (arg, lang) in [("cstd", "c"), ("cxxstd", "cxx")] {
+        let standard = match cmd.get_int(arg) {
+            Some(s) => s,
+            None => continue,
+        };
+
+        if let Some(f) = standard_feature(lang, standard)
+            && !f.supported_by(min_version)
+        {
+            return Err(format!(
+                "{} requires CMake {} or newer, but --version is {}",
+                f.description, f.since, min_version
+            ));
+        }
+    }
+
+    for (arg, feature_name) in [
+        ("fetch", "fetch-content-make-available"),
+        ("proj-version", "project-version"),
+        ("description", "project-description"),
+        ("homepage", "project-homepage"),
+    ] {
+        let f = feature(feature_name);
+        if cmd.get_arg(arg).is_some() && !f.supported_by(min_version) {
+            return Err(format!(
+                "{} requires CMake {} or newer, but --version is {}",
+                f.description, f.since, min_version
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn generate_example(cmd: &CommandArg, sink: &mut dyn OutputSink) -> Result<PathBuf, String> {
     let main_path;
     let main_content;
-    if let LanguageType::C = cmd.get_arg_parsed_unsafe("main-lang") {
-        main_path = src_path.join("main.c");
+    if cmd.get_parsed::<LanguageType>("main-lang") == Some(&LanguageType::C) {
+        main_path = "src/main.c";
         main_content = C_EXAMPLE;
     } else {
-        main_path = src_path.join("main.cpp");
-        main_content = if cmd
-            .get_arg("cxxstd")
-            .map(|s| s.parse::<i32>().unwrap() >= 23)
-            .unwrap_or(false)
-        {
+        main_path = "src/main.cpp";
+        main_content = if cmd.get_int("cxxstd").is_some_and(|s| s >= 23) {
             CXX_23_EXAMPLE
         } else {
             CXX_OLD_EXAMPLE
         };
     }
 
-    if let Err(_) = std::fs::write(&main_path, main_content.as_bytes()) {
-        Err(String::from("Failed to create example main file"))
-    } else {
-        Ok(())
-    }
+    sink.write_file(Path::new(main_path), main_content.as_bytes(), FILE_MODE)
 }
 
-pub(super) fn get_filename() -> &'static str {
-    "CMakeLists.txt"
+#[cfg(test)]
+mod tests {
+    use crate::{FiletempError, Generator, ResolvedArgs};
+
+    fn render(args: &[&str]) -> Result<String, FiletempError> {
+        let args = ResolvedArgs::parse("cmake", args)?;
+        Ok(Generator::new("cmake")?.render(&args)?.content)
+    }
+
+    #[test]
+    fn c_and_cxx_standards_give_a_mixed_project() {
+        let content = render(&[
+            "--proj",
+            "x",
+            "--version",
+            "3.20",
+            "--cstd",
+            "11",
+            "--cxxstd",
+            "17",
+        ])
+        .unwrap();
+        assert!(content.contains("set(CMAKE_C_STANDARD 11)"));
+        assert!(content.contains("set(CMAKE_CXX_STANDARD 17)"));
+        assert!(content.contains("project(x LANGUAGES C CXX)"));
+    }
+
+    #[test]
+    fn cstd_conflicts_only_with_a_given_cxx_main_language() {
+        let with_c = ["--proj", "x", "--version", "3.20", "--cstd", "11"];
+        assert!(render(&with_c).unwrap().contains("LANGUAGES C CXX"));
+        assert!(render(&[&with_c[..], &["--main-lang", "c"]].concat()).is_ok());
+        assert!(render(&[&with_c[..], &["--main-lang", "cxx"]].concat()).is_err());
+    }
 }
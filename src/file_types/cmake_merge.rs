@@ -0,0 +1,187 @@
+//! Merge a generated CMakeLists.txt into an existing one for `--merge`, adding
+//! only the commands it lacks and keeping everything it has.
+
+use crate::{
+    file_types::cmake_parser::{self, CMakeCommand, CMakeDocument},
+    logging,
+    text_edit::{TextEdit, apply_edits},
+};
+
+/// Commands a listfile has once, an existing one is kept whatever its arguments.
+const SINGLETONS: &[&str] = &["cmake_minimum_required", "project"];
+
+/// Commands defining what their first argument names, e.g. a target or a package.
+const DEFINITIONS: &[&str] = &[
+    "add_executable",
+    "add_library",
+    "find_package",
+    "fetchcontent_declare",
+    "option",
+    "set",
+];
+
+/// What makes two commands the same for merging.
+fn merge_key(cmd: &CMakeCommand) -> Vec<String> {
+    let name = cmd.name.to_ascii_lowercase();
+    let args: Vec<String> = match name.as_str() {
+        n if SINGLETONS.contains(&n) => Vec::new(),
+        n if DEFINITIONS.contains(&n) => cmd.args.iter().take(1).map(|a| a.value.clone()).collect(),
+        _ => cmd.args.iter().map(|a| a.value.clone()).collect(),
+    };
+    std::iter::once(name).chain(args).collect()
+}
+
+/// `existing` with the commands of `generated` it lacks. Each is inserted after
+/// the existing command that precedes it in `generated`, so a new target comes
+/// with its target_* commands and find_package stays above its use.
+pub fn merge(existing: &str, generated: &str) -> Result<String, String> {
+    let old = cmake_parser::parse(existing)?;
+    let new = cmake_parser::parse(generated)?;
+    let eol = old.line_ending();
+
+    let old_keys: Vec<Vec<String>> = old.commands.iter().map(merge_key).collect();
+    let mut edits = Vec::new();
+    // Where missing commands go, after the line of the last present one.
+    let mut anchor: Option<usize> = None;
+    let mut missing: Vec<&CMakeCommand> = Vec::new();
+
+    for cmd in new.commands.iter() {
+        let key = merge_key(cmd);
+        match old_keys.iter().position(|k| *k == key) {
+            Some(idx) => {
+                if !missing.is_empty() {
+                    edits.push(insertion(&old, &new, anchor, &missing, eol));
+                    missing.clear();
+                }
+                let span = &old.commands[idx].span;
+                anchor = Some(line_end(existing, span.end));
+            }
+            None => {
+                logging::verbose(format_args!(
+                    "Adding {}({})",
+                    cmd.name,
+                    cmd.args.first().map_or("", |a| a.value.as_str())
+                ));
+                missing.push(cmd);
+            }
+        }
+    }
+    if !missing.is_empty() {
+        edits.push(insertion(&old, &new, anchor, &missing, eol));
+    }
+
+    if edits.is_empty() {
+        logging::verbose("The existing file has every generated command");
+    }
+    Ok(apply_edits(existing, &edits).0)
+}
+
+/// Insert a run of generated commands, with what lies between them in the
+/// generated file, after `anchor` or at the start of the file.
+fn insertion(
+    old: &CMakeDocument,
+    new: &CMakeDocument,
+    anchor: Option<usize>,
+    run: &[&CMakeCommand],
+    eol: &str,
+) -> TextEdit {
+    let first = run[0];
+    let last = run[run.len() - 1];
+    let start = line_start(&new.source, first.span.start);
+    let text = new.source[start..last.span.end].replace("\r\n", "\n");
+
+    // Keep a blank line before the run if the generated file has one.
+    let before = &new.source[..start];
+    let separator = match before.trim_end_matches([' ', '\t']).ends_with("\n\n")
+        || before.ends_with("\r\n\r\n")
+    {
+        true => "\n\n",
+        false => "\n",
+    };
+
+    let text = match anchor {
+        Some(_) => format!("{}{}", separator, text),
+        None if old.source.is_empty() => format!("{}\n", text),
+        None => format!("{}{}", text, separator),
+    };
+    TextEdit::insert(anchor.unwrap_or(0), text.replace('\n', eol))
+}
+
+fn line_start(source: &str, pos: usize) -> usize {
+    source[..pos].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// End of the line `pos` is on, before its line ending.
+fn line_end(source: &str, pos: usize) -> usize {
+    let end = source[pos..].find('\n').map_or(source.len(), |i| pos + i);
+    match source[..end].ends_with('\r') {
+        true => end - 1,
+        false => end,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENERATED: &str = "cmake_minimum_required(VERSION 3.20)\n\
+        \n\
+        project(demo LANGUAGES CXX)\n\
+        \n\
+        add_executable(demo)\n\
+        target_sources(demo PRIVATE src/main.cpp)\n\
+        target_link_libraries(demo PRIVATE m)\n";
+
+    #[test]
+    fn adds_missing_commands_after_the_ones_before_them() {
+        let existing = "cmake_minimum_required(VERSION 3.20)\n\
+            project(demo LANGUAGES CXX)\n\
+            # kept as written\n";
+        assert_eq!(
+            merge(existing, GENERATED).unwrap(),
+            "cmake_minimum_required(VERSION 3.20)\n\
+             project(demo LANGUAGES CXX)\n\
+             \n\
+             add_executable(demo)\n\
+             target_sources(demo PRIVATE src/main.cpp)\n\
+             target_link_libraries(demo PRIVATE m)\n\
+             # kept as written\n"
+        );
+    }
+
+    #[test]
+    fn keeps_conflicting_commands_as_they_are() {
+        let existing = "cmake_minimum_required(VERSION 3.10)\n\
+            project(legacy VERSION 1.0)\n\
+            add_executable(demo app.cpp)\n\
+            target_link_libraries(demo PRIVATE pthread)\n";
+        assert_eq!(
+            merge(existing, GENERATED).unwrap(),
+            "cmake_minimum_required(VERSION 3.10)\n\
+             project(legacy VERSION 1.0)\n\
+             add_executable(demo app.cpp)\n\
+             target_sources(demo PRIVATE src/main.cpp)\n\
+             target_link_libraries(demo PRIVATE m)\n\
+             target_link_libraries(demo PRIVATE pthread)\n"
+        );
+    }
+
+    #[test]
+    fn file_with_every_command_is_unchanged() {
+        let existing = GENERATED.replace("add_executable(demo)", "add_executable(demo) # app");
+        assert_eq!(merge(&existing, GENERATED).unwrap(), existing);
+    }
+
+    #[test]
+    fn empty_file_takes_the_generated_one() {
+        assert_eq!(merge("", GENERATED).unwrap(), GENERATED);
+    }
+
+    #[test]
+    fn inserted_lines_use_the_existing_line_endings() {
+        let existing = "cmake_minimum_required(VERSION 3.20)\r\nproject(demo LANGUAGES CXX)\r\n";
+        let merged = merge(existing, GENERATED).unwrap();
+        assert!(merged.starts_with(existing));
+        assert_eq!(merged.matches('\n').count(), merged.matches("\r\n").count());
+    }
+}
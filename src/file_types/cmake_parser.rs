@@ -0,0 +1,390 @@
+use std::ops::Range;
+
+use crate::{
+    file_types::cmake_version::{CMakeVersion, CMakeVersionRange},
+    logging::color,
+};
+
+pub struct CMakeArg {
+    /// Argument text without quotes or brackets, escapes are kept as written.
+    pub value: String,
+    /// Byte range of the argument in the source, including quotes or brackets.
+    pub span: Range<usize>,
+}
+
+pub struct CMakeCommand {
+    pub name: String,
+    pub args: Vec<CMakeArg>,
+    /// 1-based line number of the command name.
+    pub line: usize,
+    /// Byte range from the command name to the closing parenthesis, inclusive.
+    pub span: Range<usize>,
+}
+
+impl CMakeCommand {
+    /// Case-insensitive command name comparison, as CMake itself does.
+    pub fn is(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+    }
+
+    pub fn has_arg(&self, value: &str) -> bool {
+        self.args.iter().any(|a| a.value == value)
+    }
+
+    /// The argument following a keyword, e.g. the version after `VERSION`.
+    pub fn arg_after(&self, keyword: &str) -> Option<&CMakeArg> {
+        let idx = self.args.iter().position(|a| a.value == keyword)?;
+        self.args.get(idx + 1)
+    }
+}
+
+pub struct CMakeComment {
+    pub text: String,
+    /// 1-based line number where the comment starts.
+    pub line: usize,
+}
+
+/// A parsed CMake listfile.
+pub struct CMakeDocument {
+    pub source: String,
+    pub commands: Vec<CMakeCommand>,
+    pub comments: Vec<CMakeComment>,
+}
+
+impl CMakeDocument {
+    pub fn find_command(&self, name: &str) -> Option<&CMakeCommand> {
+        self.commands.iter().find(|c| c.is(name))
+    }
+
+    /// Whitespace preceding a command on its line.
+    pub fn indent_of(&self, cmd: &CMakeCommand) -> &str {
+        let line_start = self.source[..cmd.span.start]
+            .rfind('\n')
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let prefix = &self.source[line_start..cmd.span.start];
+        &prefix[..prefix.len() - prefix.trim_start().len()]
+    }
+
+    /// Line ending used by the file, for inserting new lines consistently.
+    pub fn line_ending(&self) -> &'static str {
+        if self.source.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        }
+    }
+
+    /// Lower bound of the `cmake_minimum_required(VERSION)` range, if there is a valid one.
+    pub fn minimum_version(&self) -> Option<CMakeVersion> {
+        let cmd = self.find_command("cmake_minimum_required")?;
+        let ver = cmd.arg_after("VERSION")?;
+        ver.value.parse::<CMakeVersionRange>().ok().map(|r| r.min)
+    }
+
+    pub fn commands_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a CMakeCommand> {
+        self.commands.iter().filter(move |c| c.is(name))
+    }
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+/// Parse a CMake listfile into its command invocations and comments.
+pub fn parse(source: &str) -> Result<CMakeDocument, String> {
+    let mut p = Parser {
+        src: source,
+        pos: 0,
+        line: 1,
+    };
+
+    let mut commands = Vec::new();
+    let mut comments = Vec::new();
+
+    loop {
+        p.skip_spaces_and_newlines();
+        let c = match p.peek() {
+            Some(c) => c,
+            None => break,
+        };
+
+        if c == '#' {
+            comments.push(p.comment()?);
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            commands.push(p.command(&mut comments)?);
+        } else {
+            return Err(p.error(&format!("Unexpected character '{}'", c)));
+        }
+    }
+
+    Ok(CMakeDocument {
+        source: source.to_string(),
+        commands,
+        comments,
+    })
+}
+
+/// The listfile with ANSI colors for command names, keywords, quoted and
+/// bracket arguments, variable references and comments.
+pub fn highlight(source: &str) -> Result<String, String> {
+    let doc = parse(source)?;
+    let mut spans: Vec<(Range<usize>, Option<&str>)> = Vec::new();
+    for cmd in doc.commands.iter() {
+        let name_end = cmd.span.start + cmd.name.len();
+        spans.push((cmd.span.start..name_end, Some(color::COMMAND)));
+        for arg in cmd.args.iter() {
+            let text = &source[arg.span.clone()];
+            let keyword = text.len() > 1
+                && text
+                    .chars()
+                    .all(|c| c.is_ascii_uppercase() || c == '_' || c.is_ascii_digit());
+            let style = match text {
+                t if t.starts_with('"') || t.starts_with('[') => Some(color::STRING),
+                t if t.contains("${") => Some(color::VARIABLE),
+                _ if keyword => Some(color::KEYWORD),
+                _ => None,
+            };
+            spans.push((arg.span.clone(), style));
+        }
+    }
+
+    let mut out = String::new();
+    let mut pos = 0;
+    for (range, style) in spans {
+        if range.start < pos {
+            continue;
+        }
+        push_comments(&mut out, &source[pos..range.start]);
+        match style {
+            Some(s) => out.push_str(&color::ansi(s, &source[range.clone()])),
+            None => out.push_str(&source[range.clone()]),
+        }
+        pos = range.end;
+    }
+    push_comments(&mut out, &source[pos..]);
+    Ok(out)
+}
+
+/// Text between commands and arguments, a `#` starts a comment to the end of the line.
+fn push_comments(out: &mut String, mut gap: &str) {
+    while let Some(idx) = gap.find('#') {
+        out.push_str(&gap[..idx]);
+        let end = gap[idx..].find('\n').map_or(gap.len(), |e| idx + e);
+        out.push_str(&color::ansi(color::COMMENT, &gap[idx..end]));
+        gap = &gap[end..];
+    }
+    out.push_str(gap);
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn error(&self, msg: &str) -> String {
+        format!("CMake parse error: {} at line {}", msg, self.line)
+    }
+
+    fn skip_spaces_and_newlines(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Count the `=` of a bracket opening like `[==[` at the current position.
+    fn bracket_level(&self) -> Option<usize> {
+        let rest = &self.src[self.pos..];
+        if !rest.starts_with('[') {
+            return None;
+        }
+        let level = rest[1..].chars().take_while(|&c| c == '=').count();
+        if rest[1 + level..].starts_with('[') {
+            Some(level)
+        } else {
+            None
+        }
+    }
+
+    /// Consume a bracket construct and return its inner text.
+    fn bracket_content(&mut self, level: usize) -> Result<String, String> {
+        let start_line = self.line;
+        for _ in 0..level + 2 {
+            self.bump();
+        }
+
+        let close = format!("]{}]", "=".repeat(level));
+        match self.src[self.pos..].find(&close) {
+            Some(off) => {
+                let content = self.src[self.pos..self.pos + off].to_string();
+                let end = self.pos + off + close.len();
+                while self.pos < end {
+                    self.bump();
+                }
+                Ok(content)
+            }
+            None => Err(format!(
+                "CMake parse error: Unterminated bracket starting at line {}",
+                start_line
+            )),
+        }
+    }
+
+    fn comment(&mut self) -> Result<CMakeComment, String> {
+        let line = self.line;
+        self.bump();
+
+        if let Some(level) = self.bracket_level() {
+            let text = self.bracket_content(level)?;
+            return Ok(CMakeComment { text, line });
+        }
+
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c == '\n' {
+                break;
+            }
+            self.bump();
+        }
+
+        Ok(CMakeComment {
+            text: self.src[start..self.pos].trim_end_matches('\r').to_string(),
+            line,
+        })
+    }
+
+    fn command(&mut self, comments: &mut Vec<CMakeComment>) -> Result<CMakeCommand, String> {
+        let start = self.pos;
+        let line = self.line;
+
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        let name = self.src[start..self.pos].to_string();
+
+        while let Some(c) = self.peek() {
+            if c == ' ' || c == '\t' {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+
+        if self.peek() != Some('(') {
+            return Err(self.error(&format!("Expected '(' after \"{}\"", name)));
+        }
+        self.bump();
+
+        let mut args = Vec::new();
+        let mut depth: usize = 0;
+
+        loop {
+            self.skip_spaces_and_newlines();
+            let c = match self.peek() {
+                Some(c) => c,
+                None => {
+                    return Err(format!(
+                        "CMake parse error: Unterminated \"{}\" starting at line {}",
+                        name, line
+                    ));
+                }
+            };
+
+            let arg_start = self.pos;
+            let value = match c {
+                '#' => {
+                    comments.push(self.comment()?);
+                    continue;
+                }
+                '(' => {
+                    self.bump();
+                    depth += 1;
+                    String::from("(")
+                }
+                ')' => {
+                    self.bump();
+                    if depth == 0 {
+                        break;
+                    }
+                    depth -= 1;
+                    String::from(")")
+                }
+                '"' => self.quoted()?,
+                _ => match self.bracket_level() {
+                    Some(level) => self.bracket_content(level)?,
+                    None => self.unquoted(),
+                },
+            };
+
+            args.push(CMakeArg {
+                value,
+                span: arg_start..self.pos,
+            });
+        }
+
+        Ok(CMakeCommand {
+            name,
+            args,
+            line,
+            span: start..self.pos,
+        })
+    }
+
+    fn quoted(&mut self) -> Result<String, String> {
+        let start_line = self.line;
+        self.bump();
+        let start = self.pos;
+
+        loop {
+            match self.bump() {
+                Some('\\') => {
+                    self.bump();
+                }
+                Some('"') => break,
+                Some(_) => {}
+                None => {
+                    return Err(format!(
+                        "CMake parse error: Unterminated quoted argument starting at line {}",
+                        start_line
+                    ));
+                }
+            }
+        }
+
+        Ok(self.src[start..self.pos - 1].to_string())
+    }
+
+    fn unquoted(&mut self) -> String {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || c == '(' || c == ')' || c == '#' || c == '"' {
+                break;
+            }
+            self.bump();
+            if c == '\\' {
+                self.bump();
+            }
+        }
+
+        self.src[start..self.pos].to_string()
+    }
+}
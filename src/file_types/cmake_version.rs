@@ -0,0 +1,208 @@
+use std::{fmt::Display, str::FromStr};
+
+/// A CMake version number as used by `cmake_minimum_required`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct CMakeVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl CMakeVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl FromStr for CMakeVersion {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('.');
+        let mut nums = [0u32; 3];
+        let mut count = 0;
+
+        for part in parts.by_ref() {
+            if count == 4 {
+                return Err(());
+            }
+            if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(());
+            }
+            // The optional fourth "tweak" component is accepted but not tracked.
+            if count < 3 {
+                nums[count] = part.parse().map_err(|_| ())?;
+            }
+            count += 1;
+        }
+
+        if count < 2 {
+            return Err(());
+        }
+
+        Ok(Self::new(nums[0], nums[1], nums[2]))
+    }
+}
+
+impl Display for CMakeVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.patch == 0 {
+            write!(f, "{}.{}", self.major, self.minor)
+        } else {
+            write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+        }
+    }
+}
+
+/// Stable CMake release series, oldest first.
+pub const CMAKE_RELEASES: &[CMakeVersion] = &[
+    CMakeVersion::new(3, 16, 0),
+    CMakeVersion::new(3, 17, 0),
+    CMakeVersion::new(3, 18, 0),
+    CMakeVersion::new(3, 19, 0),
+    CMakeVersion::new(3, 20, 0),
+    CMakeVersion::new(3, 21, 0),
+    CMakeVersion::new(3, 22, 0),
+    CMakeVersion::new(3, 23, 0),
+    CMakeVersion::new(3, 24, 0),
+    CMakeVersion::new(3, 25, 0),
+    CMakeVersion::new(3, 26, 0),
+    CMakeVersion::new(3, 27, 0),
+    CMakeVersion::new(3, 28, 0),
+    CMakeVersion::new(3, 29, 0),
+    CMakeVersion::new(3, 30, 0),
+    CMakeVersion::new(3, 31, 0),
+    CMakeVersion::new(4, 0, 0),
+    CMakeVersion::new(4, 1, 0),
+];
+
+/// The `<min>[...<max>]` form accepted by `cmake_minimum_required(VERSION)`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CMakeVersionRange {
+    pub min: CMakeVersion,
+    pub max: Option<CMakeVersion>,
+}
+
+impl FromStr for CMakeVersionRange {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (min, max) = match s.split_once("...") {
+            Some((min, max)) => (min.parse()?, Some(max.parse()?)),
+            None => (s.parse()?, None),
+        };
+
+        if let Some(m) = max
+            && m < min
+        {
+            return Err(());
+        }
+
+        Ok(Self { min, max })
+    }
+}
+
+/// Generator feature which is only available from a certain CMake version.
+pub struct CMakeFeature {
+    pub name: &'static str,
+    pub since: CMakeVersion,
+    pub description: &'static str,
+}
+
+impl CMakeFeature {
+    pub fn supported_by(&self, version: CMakeVersion) -> bool {
+        version >= self.since
+    }
+}
+
+macro_rules! feature {
+    ($name: literal, $major: literal, $minor: literal, $desc: literal) => {
+        CMakeFeature {
+            name: $name,
+            since: CMakeVersion::new($major, $minor, 0),
+            description: $desc,
+        }
+    };
+}
+
+pub const FEATURES: &[CMakeFeature] = &[
+    feature!("project-version", 3, 0, "VERSION clause of project()"),
+    feature!(
+        "project-description",
+        3,
+        9,
+        "DESCRIPTION clause of project()"
+    ),
+    feature!(
+        "project-homepage",
+        3,
+        12,
+        "HOMEPAGE_URL clause of project()"
+    ),
+    feature!("target-sources", 3, 1, "target_sources() command"),
+    feature!(
+        "sourceless-targets",
+        3,
+        11,
+        "add_executable()/add_library() without sources"
+    ),
+    feature!("fetch-content", 3, 11, "FetchContent module"),
+    feature!(
+        "fetch-content-make-available",
+        3,
+        14,
+        "FetchContent_MakeAvailable()"
+    ),
+    feature!(
+        "fetch-content-find-package",
+        3,
+        24,
+        "FIND_PACKAGE_ARGS of FetchContent_Declare()"
+    ),
+    feature!("presets", 3, 19, "CMakePresets.json (schema version 1)"),
+    feature!(
+        "presets-build-test",
+        3,
+        20,
+        "Build and test presets (schema version 2)"
+    ),
+    feature!(
+        "configure-depends",
+        3,
+        12,
+        "CONFIGURE_DEPENDS flag of file(GLOB)"
+    ),
+    feature!(
+        "file-set",
+        3,
+        23,
+        "FILE_SET header sets in target_sources()"
+    ),
+    feature!("c-standard-90", 3, 1, "CMAKE_C_STANDARD 90"),
+    feature!("c-standard-99", 3, 1, "CMAKE_C_STANDARD 99"),
+    feature!("c-standard-11", 3, 1, "CMAKE_C_STANDARD 11"),
+    feature!("c-standard-17", 3, 21, "CMAKE_C_STANDARD 17"),
+    feature!("c-standard-23", 3, 21, "CMAKE_C_STANDARD 23"),
+    feature!("cxx-standard-98", 3, 1, "CMAKE_CXX_STANDARD 98"),
+    feature!("cxx-standard-11", 3, 1, "CMAKE_CXX_STANDARD 11"),
+    feature!("cxx-standard-14", 3, 1, "CMAKE_CXX_STANDARD 14"),
+    feature!("cxx-standard-17", 3, 8, "CMAKE_CXX_STANDARD 17"),
+    feature!("cxx-standard-20", 3, 12, "CMAKE_CXX_STANDARD 20"),
+    feature!("cxx-standard-23", 3, 20, "CMAKE_CXX_STANDARD 23"),
+    feature!("cxx-standard-26", 3, 25, "CMAKE_CXX_STANDARD 26"),
+];
+
+/// Look up a feature by name, panics on names missing from [`FEATURES`].
+pub fn feature(name: &str) -> &'static CMakeFeature {
+    FEATURES.iter().find(|f| f.name == name).unwrap()
+}
+
+/// Look up the feature gating a language standard, `lang` is "c" or "cxx".
+pub fn standard_feature(lang: &str, standard: i64) -> Option<&'static CMakeFeature> {
+    let name = format!("{}-standard-{}", lang, standard);
+    FEATURES.iter().find(|f| f.name == name)
+}
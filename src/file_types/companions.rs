@@ -0,0 +1,146 @@
+//! Files commonly kept next to a generated one, written along with it when
+//! given with `--with`. They follow the language and standard of its arguments.
+
+use std::fmt::Write;
+
+use crate::{
+    file_types::FileType,
+    program_args::{CommandArg, normalize, suggest},
+};
+
+pub const COMPANIONS: &[&str] = &["gitignore", "clang-format", "editorconfig"];
+
+/// Indentation of C and C++ sources, shared by .clang-format and .editorconfig.
+const INDENT_WIDTH: usize = 4;
+
+/// What the companions take from the arguments of the primary file type.
+struct Shared {
+    cmake: bool,
+    /// Without a `main-lang` argument both C and C++ are assumed.
+    c: bool,
+    cxx: bool,
+    cxx_standard: Option<i64>,
+}
+
+impl Shared {
+    fn from_cmd(cmd: &CommandArg) -> Self {
+        let lang = cmd.get_arg("main-lang");
+        Self {
+            cmake: cmd.get_file_type() == FileType::CMake,
+            c: lang.is_none_or(|l| l == "c"),
+            cxx: lang.is_none_or(|l| l == "cxx"),
+            cxx_standard: cmd
+                .get_arg("cxxstd")
+                .and_then(|s| normalize::language_standard(s).parse().ok()),
+        }
+    }
+}
+
+/// Validator of `--with`, a comma separated list of companions.
+pub fn validate_list(value: &str) -> Result<(), String> {
+    for name in value.split(',').map(str::trim) {
+        if COMPANIONS.contains(&name) {
+            continue;
+        }
+        return Err(match suggest::closest(name, COMPANIONS.iter().copied()) {
+            Some(s) => format!("unknown companion \"{}\", did you mean \"{}\"?", name, s),
+            None => format!(
+                "unknown companion \"{}\", possible values: {}",
+                name,
+                COMPANIONS.join(", ")
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Companions given with `--with`, each once.
+pub fn requested(cmd: &CommandArg) -> Vec<&str> {
+    let mut names = Vec::new();
+    for name in cmd.get_arg("with").unwrap_or_default().split(',') {
+        let name = name.trim();
+        if !name.is_empty() && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// File name and content of the companion `name`.
+pub fn generate(name: &str, cmd: &CommandArg) -> (&'static str, String) {
+    let shared = Shared::from_cmd(cmd);
+    match name {
+        "gitignore" => (".gitignore", gitignore(&shared)),
+        "clang-format" => (".clang-format", clang_format(&shared)),
+        _ => (".editorconfig", editorconfig(&shared)),
+    }
+}
+
+fn gitignore(shared: &Shared) -> String {
+    let mut out = String::from("# Build output\nbuild/\nout/\n");
+    if shared.cmake {
+        out.push_str(
+            "\n# CMake\nCMakeFiles/\nCMakeCache.txt\ncmake_install.cmake\ncmake-build-*/\n\
+             CMakeUserPresets.json\ncompile_commands.json\n",
+        );
+    }
+    out.push_str("\n# Objects and binaries\n*.o\n*.obj\n*.a\n*.lib\n*.so\n*.dylib\n*.dll\n*.exe\n");
+    out.push_str("\n# Editors and tools\n.vscode/\n.idea/\n.cache/\n");
+    out
+}
+
+fn clang_format(shared: &Shared) -> String {
+    let mut out = String::from("BasedOnStyle: LLVM\n");
+    writeln!(&mut out, "IndentWidth: {}", INDENT_WIDTH).unwrap();
+    out.push_str("ColumnLimit: 100\n");
+    // Standard only applies to C++.
+    if shared.cxx {
+        let standard = match shared.cxx_standard {
+            None => "Auto",
+            Some(98) | Some(3) => "c++03",
+            Some(s) if s <= 11 => "c++11",
+            Some(s) if s <= 14 => "c++14",
+            Some(s) if s <= 17 => "c++17",
+            Some(s) if s <= 20 => "c++20",
+            Some(_) => "Latest",
+        };
+        writeln!(&mut out, "Standard: {}", standard).unwrap();
+    }
+    out
+}
+
+fn editorconfig(shared: &Shared) -> String {
+    let mut out = String::from(
+        "root = true\n\n[*]\ncharset = utf-8\nend_of_line = lf\n\
+         insert_final_newline = true\ntrim_trailing_whitespace = true\n",
+    );
+
+    let mut sources = Vec::new();
+    if shared.c {
+        sources.extend(["c", "h"]);
+    }
+    if shared.cxx {
+        sources.extend(["cc", "cpp", "cxx", "hh", "hpp", "hxx"]);
+        if !shared.c {
+            sources.push("h");
+        }
+    }
+    writeln!(
+        &mut out,
+        "\n[*.{{{}}}]\nindent_style = space\nindent_size = {}",
+        sources.join(","),
+        INDENT_WIDTH
+    )
+    .unwrap();
+    if shared.cmake {
+        writeln!(
+            &mut out,
+            "\n[{{CMakeLists.txt,*.cmake}}]\nindent_style = space\nindent_size = {}",
+            INDENT_WIDTH
+        )
+        .unwrap();
+    }
+
+    out.push_str("\n[*.{json,yml,yaml}]\nindent_size = 2\n\n[Makefile]\nindent_style = tab\n");
+    out
+}
@@ -0,0 +1,322 @@
+//! File types declared by TOML manifests, generated by rendering a template.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use super::{FileType, FileTypeGenerator};
+use crate::{
+    error::GenerateError,
+    hooks,
+    program_args::{Arg, ArgFileTypeView, CommandArg, providers},
+    templates::{is_builtin_var, render, with_builtin_vars},
+};
+
+/// Directory of type manifests, in the data directory.
+pub const TYPE_DIR_NAME: &str = "types";
+
+/// An argument declared in the `[args]` table of a manifest.
+struct CustomArg {
+    name: String,
+    required: bool,
+    default: Option<String>,
+    /// Name of a value provider computing the default.
+    provider: Option<&'static str>,
+    possible_values: Vec<String>,
+    multiple: bool,
+    flag: bool,
+    short: Option<char>,
+    help: String,
+    value_name: Option<String>,
+}
+
+pub struct CustomType {
+    pub name: String,
+    /// Name of the generated file.
+    pub output: String,
+    pub template_path: PathBuf,
+    pub template: String,
+    args: Vec<CustomArg>,
+    /// Commands run in the output directory after the file is written.
+    pub hooks: Vec<String>,
+}
+
+impl CustomType {
+    pub fn arg_names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.args.iter().map(|a| a.name.as_str())
+    }
+}
+
+static TYPES: OnceLock<Vec<CustomType>> = OnceLock::new();
+/// Why manifests were skipped, reported by `filetemp list` rather than on every run.
+static SKIPPED: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Load every `<name>.toml` in `dir`, once. Manifests that fail to load or
+/// would shadow a built-in type or a command are skipped.
+pub fn load_types(dir: Option<&Path>, reserved: &[&str]) {
+    let mut manifests: Vec<PathBuf> = dir
+        .and_then(|d| std::fs::read_dir(d).ok())
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "toml"))
+        .collect();
+    manifests.sort();
+
+    let mut types: Vec<CustomType> = Vec::new();
+    let mut skipped = Vec::new();
+    for path in manifests {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        if reserved.iter().any(|r| r.eq_ignore_ascii_case(&name))
+            || FileType::match_type(&name) != FileType::Unknown
+        {
+            skipped.push(format!(
+                "Skipped file type \"{}\" of \"{}\", the name is taken",
+                name,
+                path.display()
+            ));
+            continue;
+        }
+        match read_manifest(&path) {
+            Ok(t) => types.push(t),
+            Err(e) => skipped.push(format!("Skipped \"{}\": {}", path.display(), e)),
+        }
+    }
+
+    let _ = TYPES.set(types);
+    let _ = SKIPPED.set(skipped);
+}
+
+/// Reasons manifests were not loaded.
+pub fn skipped() -> &'static [String] {
+    SKIPPED.get().map_or(&[], Vec::as_slice)
+}
+
+pub fn types() -> &'static [CustomType] {
+    TYPES.get().map_or(&[], Vec::as_slice)
+}
+
+/// Index of the type named `name`, matched regardless of case like built-in types.
+pub fn find(name: &str) -> Option<usize> {
+    types()
+        .iter()
+        .position(|t| t.name.eq_ignore_ascii_case(name))
+}
+
+/// Values of declared types are only checked by their definitions, and they
+/// have no example project.
+impl FileTypeGenerator for CustomType {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn output_name(&self) -> &str {
+        &self.output
+    }
+
+    /// Define the arguments the manifest declares.
+    fn arg_defs(&self, view: &mut ArgFileTypeView) {
+        for def in self.args.iter() {
+            let mut arg = Arg::new(def.name.clone())
+                .required(def.required)
+                .multiple(def.multiple)
+                .flag(def.flag)
+                .help(def.help.clone());
+            if let Some(d) = &def.default {
+                arg = arg.default_val(d.clone());
+            }
+            if let Some(p) = def.provider {
+                arg = arg.default_provider(p);
+            }
+            if !def.possible_values.is_empty() {
+                arg = arg.possible_values(def.possible_values.clone());
+            }
+            if let Some(c) = def.short {
+                arg = arg.short(c);
+            }
+            if let Some(v) = &def.value_name {
+                arg = arg.value_name(v.clone());
+            }
+            view.add_arg_def(arg);
+        }
+    }
+
+    /// Render the template with the value of every declared argument, repeatable
+    /// ones joined by commas for `{{#each}}` and unset ones as their default
+    /// value or empty, and the built-in variables.
+    fn render(&self, cmd: &CommandArg) -> Result<String, GenerateError> {
+        let mut values = HashMap::new();
+        for def in self.args.iter() {
+            let value = if def.flag {
+                cmd.get_flag(&def.name).to_string()
+            } else if def.multiple {
+                cmd.get_args(&def.name).join(",")
+            } else {
+                match cmd.get_arg(&def.name) {
+                    Some(v) => v.to_string(),
+                    // Provided values are already set unless the argument refused them.
+                    None => def.default.clone().unwrap_or_default(),
+                }
+            };
+            values.insert(def.name.clone(), value);
+        }
+        with_builtin_vars(&mut values);
+        render::render(&self.template, &values).map_err(|message| GenerateError::Template {
+            path: self.template_path.clone(),
+            message,
+        })
+    }
+}
+
+/// Read a manifest, its template may only use declared arguments and built-in variables.
+fn read_manifest(path: &Path) -> Result<CustomType, String> {
+    let ty = read_unchecked(path)?;
+    let found = match render::placeholders(&ty.template) {
+        Ok(p) => p,
+        Err(e) => return Err(format!("{} in \"{}\"", e, ty.template_path.display())),
+    };
+    if let Some(p) = found
+        .iter()
+        .find(|p| !ty.arg_names().any(|a| a == p.name) && !is_builtin_var(&p.name))
+    {
+        return Err(format!(
+            "Undeclared variable \"{}\" at line {} of \"{}\"",
+            p.name,
+            p.line,
+            ty.template_path.display()
+        ));
+    }
+
+    Ok(ty)
+}
+
+/// Read a manifest with the `output` file name, the `template` path relative to
+/// the manifest and an `[args]` table of argument definitions, without parsing
+/// the template so `filetemp template check` can report all of its problems.
+pub fn read_unchecked(path: &Path) -> Result<CustomType, String> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(_) => return Err(String::from("Failed to read the manifest")),
+    };
+    let manifest = match content.parse::<toml::Table>() {
+        Ok(t) => t,
+        Err(e) => return Err(format!("Manifest parse error: {}", e.message())),
+    };
+
+    let name = path.file_stem().unwrap_or_default().to_string_lossy();
+    let mut output = None;
+    let mut template_path = None;
+    let mut args = Vec::new();
+    let mut hooks = Vec::new();
+    for (key, value) in manifest.iter() {
+        match (key.as_str(), value) {
+            ("output", toml::Value::String(s)) => output = Some(s.clone()),
+            ("template", toml::Value::String(s)) => template_path = Some(PathBuf::from(s)),
+            ("args", toml::Value::Table(t)) => {
+                for (arg, def) in t.iter() {
+                    args.push(read_arg(arg, def)?);
+                }
+            }
+            ("hooks", value) => hooks = hooks::read_hooks(value, "hooks")?,
+            ("output" | "template" | "args", _) => {
+                return Err(format!("Invalid type for {}", key));
+            }
+            _ => return Err(format!("Unknown manifest key: {}", key)),
+        }
+    }
+
+    let output = match output {
+        Some(o) if !o.is_empty() && !o.contains(['/', '\\']) => o,
+        Some(o) => return Err(format!("Invalid output file name \"{}\"", o)),
+        None => return Err(String::from("Manifest is missing output")),
+    };
+    let template_path = match template_path {
+        Some(t) => path.parent().unwrap_or(Path::new(".")).join(t),
+        None => return Err(String::from("Manifest is missing template")),
+    };
+    let template = match std::fs::read_to_string(&template_path) {
+        Ok(t) => t,
+        Err(_) => return Err(format!("Missing template \"{}\"", template_path.display())),
+    };
+
+    Ok(CustomType {
+        name: name.into_owned(),
+        output,
+        template_path,
+        template,
+        args,
+        hooks,
+    })
+}
+
+fn read_arg(name: &str, def: &toml::Value) -> Result<CustomArg, String> {
+    let def = match def.as_table() {
+        Some(t) => t,
+        None => return Err(format!("[args.{}] must be a table", name)),
+    };
+    if name.is_empty() || name.starts_with('-') || name.contains(char::is_whitespace) {
+        return Err(format!("Invalid argument name \"{}\"", name));
+    }
+
+    let mut arg = CustomArg {
+        name: name.to_string(),
+        required: false,
+        default: None,
+        provider: None,
+        possible_values: Vec::new(),
+        multiple: false,
+        flag: false,
+        short: None,
+        help: String::new(),
+        value_name: None,
+    };
+    for (key, value) in def.iter() {
+        match (key.as_str(), value) {
+            ("required", toml::Value::Boolean(b)) => arg.required = *b,
+            ("multiple", toml::Value::Boolean(b)) => arg.multiple = *b,
+            ("flag", toml::Value::Boolean(b)) => arg.flag = *b,
+            ("default", toml::Value::String(s)) => arg.default = Some(s.clone()),
+            ("provider", toml::Value::String(s)) => match providers::find_provider(s) {
+                Some(p) => arg.provider = Some(p.name),
+                None => {
+                    return Err(format!(
+                        "Unknown provider \"{}\" for args.{}, see \"filetemp providers\"",
+                        s, name
+                    ));
+                }
+            },
+            ("help", toml::Value::String(s)) => arg.help = s.clone(),
+            ("value_name", toml::Value::String(s)) => arg.value_name = Some(s.clone()),
+            ("short", toml::Value::String(s)) if s.chars().count() == 1 => {
+                arg.short = s.chars().next();
+            }
+            ("possible_values", toml::Value::Array(values)) => {
+                let values: Option<Vec<String>> = values
+                    .iter()
+                    .map(|v| v.as_str().map(String::from))
+                    .collect();
+                match values {
+                    Some(v) => arg.possible_values = v,
+                    None => return Err(format!("args.{}.possible_values must be strings", name)),
+                }
+            }
+            (
+                "required" | "multiple" | "flag" | "default" | "provider" | "help" | "value_name"
+                | "short" | "possible_values",
+                _,
+            ) => return Err(format!("Invalid type for args.{}.{}", name, key)),
+            _ => return Err(format!("Unknown manifest key: args.{}.{}", name, key)),
+        }
+    }
+    if arg.required && arg.provider.is_some() {
+        return Err(format!(
+            "args.{} is required and cannot have a provider, it must be given",
+            name
+        ));
+    }
+
+    Ok(arg)
+}
@@ -0,0 +1,147 @@
+//! Copyright and license comment of `--license-header`, put first in every
+//! example source. A custom header is read from a file, where `{year}` and
+//! `{author}` are replaced.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    error::{ArgError, FiletempError, IoError},
+    file_types::stamp::comment_prefix,
+    output_sink::OutputSink,
+    program_args::{
+        CommandArg,
+        providers::{find_provider, format_date},
+    },
+};
+
+pub const LICENSES: &[&str] = &["mit", "apache-2.0"];
+
+const CUSTOM_PREFIX: &str = "custom:";
+
+const MIT: &str = "Copyright (c) {year} {author}\n\nSPDX-License-Identifier: MIT";
+
+const APACHE_2_0: &str = "Copyright {year} {author}
+
+Licensed under the Apache License, Version 2.0 (the \"License\");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an \"AS IS\" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.";
+
+/// Validator of `--license-header`, a known license or `custom:<FILE>`.
+pub fn validate(value: &str) -> Result<(), String> {
+    match value.strip_prefix(CUSTOM_PREFIX) {
+        Some("") => Err(format!("expected a file after \"{}\"", CUSTOM_PREFIX)),
+        Some(_) => Ok(()),
+        None if LICENSES.contains(&value.to_ascii_lowercase().as_str()) => Ok(()),
+        None => Err(format!(
+            "possible values: {}, {}<FILE>",
+            LICENSES.join(", "),
+            CUSTOM_PREFIX
+        )),
+    }
+}
+
+/// Text of the header `--license-header` asks for, without comment markers.
+/// The author is `--license-author`, otherwise user.name of git.
+pub fn header(cmd: &CommandArg) -> Result<Option<String>, FiletempError> {
+    let license = match cmd.get_arg("license-header") {
+        Some(l) => l,
+        None => return Ok(None),
+    };
+    let template = match license.strip_prefix(CUSTOM_PREFIX) {
+        Some(file) => {
+            let path = PathBuf::from(file);
+            let text = fs::read_to_string(&path).map_err(|e| IoError::new("read", path, e))?;
+            text.trim_end().to_string()
+        }
+        None if license.eq_ignore_ascii_case("apache-2.0") => APACHE_2_0.to_string(),
+        None => MIT.to_string(),
+    };
+
+    let author = match cmd.get_arg("license-author") {
+        Some(a) => a.to_string(),
+        None => find_provider("git-user-name")
+            .and_then(|p| p.provide())
+            .ok_or_else(|| {
+                ArgError::Invalid(String::from(
+                    "--license-header needs an author, give --license-author or set user.name in git",
+                ))
+            })?,
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    let year = &format_date(now)[..4];
+    Ok(Some(
+        template
+            .replace("{year}", year)
+            .replace("{author}", &author),
+    ))
+}
+
+/// `text` as line comments of `prefix`, with a blank line after it.
+fn commented(text: &str, prefix: &str) -> String {
+    let mut out = String::new();
+    for line in text.lines() {
+        match line.trim_end() {
+            "" => out.push_str(prefix),
+            l => out.push_str(&format!("{} {}", prefix, l)),
+        }
+        out.push('\n');
+    }
+    out.push('\n');
+    out
+}
+
+/// Writes into another sink, with a header first in each file that has line comments.
+pub struct HeaderSink<'a> {
+    inner: &'a mut dyn OutputSink,
+    header: String,
+}
+
+impl<'a> HeaderSink<'a> {
+    pub fn new(inner: &'a mut dyn OutputSink, header: String) -> Self {
+        Self { inner, header }
+    }
+}
+
+impl OutputSink for HeaderSink<'_> {
+    fn write_file(
+        &mut self,
+        relative: &Path,
+        content: &[u8],
+        mode: u32,
+    ) -> Result<PathBuf, String> {
+        let name = relative
+            .file_name()
+            .map_or(String::new(), |n| n.to_string_lossy().into_owned());
+        match comment_prefix(&name) {
+            Some(prefix) => {
+                let mut with_header = commented(&self.header, prefix).into_bytes();
+                with_header.extend_from_slice(content);
+                self.inner.write_file(relative, &with_header, mode)
+            }
+            None => self.inner.write_file(relative, content, mode),
+        }
+    }
+
+    /// The wrapped sink is finished by its owner.
+    fn finish(self: Box<Self>) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn allow_merge(&mut self, relative: &Path) {
+        self.inner.allow_merge(relative);
+    }
+}
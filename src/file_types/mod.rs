@@ -1,55 +1,215 @@
-use crate::program_args::CommandArg;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use crate::{
+    error::{ArgError, FiletempError, GenerateError, IoError},
+    logging,
+    output_sink::OutputSink,
+    program_args::{ArgFileTypeView, CommandArg},
+    templates::{self, render, with_builtin_vars},
+};
 
 #[derive(Clone, Copy, Eq, PartialEq, Hash)]
 pub enum FileType {
     CMake,
+    /// Declared by a manifest, the index in `custom::types()`.
+    Custom(usize),
     Unknown,
 }
 
 impl FileType {
     pub fn match_type(name: &str) -> Self {
-        if name.eq_ignore_ascii_case("cmake") {
-            Self::CMake
+        if let Some((ty, _)) = BUILTIN
+            .iter()
+            .find(|(_, g)| g.name().eq_ignore_ascii_case(name))
+        {
+            *ty
+        } else if let Some(idx) = custom::find(name) {
+            Self::Custom(idx)
         } else {
             Self::Unknown
         }
     }
 
-    pub fn to_str(&self) -> &'static str {
-        match self {
-            FileType::CMake => "cmake",
-            FileType::Unknown => "unknown",
-        }
+    pub fn to_str(self) -> &'static str {
+        generator(self).map_or("unknown", |g| g.name())
+    }
+}
+
+/// What a file type provides to be generated, built-in or declared by a manifest.
+pub trait FileTypeGenerator {
+    /// Name given on the command line, matched regardless of case.
+    fn name(&self) -> &str;
+
+    /// Name of the generated file.
+    fn output_name(&self) -> &str;
+
+    fn arg_defs(&self, view: &mut ArgFileTypeView);
+
+    /// Check what the argument definitions cannot, like values depending on each other.
+    fn verify(&self, _cmd: &CommandArg) -> Result<(), ArgError> {
+        Ok(())
+    }
+
+    fn render(&self, cmd: &CommandArg) -> Result<String, GenerateError>;
+
+    /// Write the example sources of `--gen-example`, returns the written file.
+    fn example_files(
+        &self,
+        _cmd: &CommandArg,
+        _sink: &mut dyn OutputSink,
+    ) -> Result<PathBuf, GenerateError> {
+        Err(GenerateError::NoExample(self.name().to_string()))
+    }
+}
+
+/// The built-in file types, a new one is added here and as a `FileType` variant.
+const BUILTIN: &[(FileType, &dyn FileTypeGenerator)] =
+    &[(FileType::CMake, &cmake_files::CMakeGenerator)];
+
+/// The generator of `ty`.
+pub fn generator(ty: FileType) -> Result<&'static dyn FileTypeGenerator, ArgError> {
+    let unknown = || ArgError::UnknownFileType(String::from("unknown"));
+    match ty {
+        FileType::Custom(idx) => Ok(&custom::types()[idx]),
+        FileType::Unknown => Err(unknown()),
+        builtin => match BUILTIN.iter().find(|(t, _)| *t == builtin) {
+            Some((_, g)) => Ok(*g),
+            None => Err(unknown()),
+        },
     }
 }
 
 pub mod cmake_files;
+pub mod cmake_merge;
+pub mod cmake_parser;
+pub mod cmake_version;
+pub mod companions;
+pub mod custom;
+pub mod license;
+pub mod stamp;
+
+/// User templates named like a built-in type, rendered instead of its generator.
+static OVERRIDES: OnceLock<Vec<(FileType, PathBuf)>> = OnceLock::new();
+
+/// Find the user templates overriding built-in types, once. A template is
+/// named like the type, e.g. `cmake` or `cmake.txt`.
+pub fn load_overrides(template_dir: Option<&Path>) {
+    let found = BUILTIN
+        .iter()
+        .map(|(ty, _)| *ty)
+        .filter_map(|ty| Some((ty, templates::find_override(template_dir, ty.to_str())?)))
+        .collect();
+    let _ = OVERRIDES.set(found);
+}
 
-pub fn process_args(cmd: &CommandArg) -> Result<String, String> {
-    match cmd.get_file_type() {
-        FileType::CMake => Ok(cmake_files::process_args(cmd)),
-        FileType::Unknown => Err(String::from("Unknown file type")),
+/// The user template generating `ty` in place of its built-in generator.
+pub fn override_template(ty: FileType) -> Option<&'static Path> {
+    OVERRIDES
+        .get()?
+        .iter()
+        .find(|(t, _)| *t == ty)
+        .map(|(_, p)| p.as_path())
+}
+
+/// Define the arguments of every file type, built-in and declared by a manifest.
+pub fn register_types(cmd: &mut CommandArg) {
+    for (ty, _) in BUILTIN.iter() {
+        cmd.register_file_type(*ty, define_type_args);
+    }
+    for idx in 0..custom::types().len() {
+        cmd.register_file_type(FileType::Custom(idx), define_type_args);
     }
 }
 
-pub fn verify_existed_args(cmd: &CommandArg) -> Result<(), String> {
-    match cmd.get_file_type() {
-        FileType::CMake => cmake_files::verify_existed_args(cmd),
-        FileType::Unknown => Err(String::from("Unknown file type")),
+fn define_type_args(view: &mut ArgFileTypeView) {
+    if let Ok(g) = generator(view.file_type()) {
+        g.arg_defs(view);
     }
 }
 
-pub fn generate_example(cmd: &CommandArg, path: &std::path::Path) -> Result<(), String> {
-    match cmd.get_file_type() {
-        FileType::CMake => cmake_files::generate_example(cmd, path),
-        FileType::Unknown => Err(String::from("Unknown file type")),
+pub fn process_args(cmd: &CommandArg) -> Result<String, FiletempError> {
+    if let Some(path) = override_template(cmd.get_file_type())
+        && !cmd.get_flag("builtin")
+    {
+        return Ok(render_override(cmd, path)?);
+    }
+
+    let generated = generator(cmd.get_file_type())?.render(cmd)?;
+    logging::trace(format_args!(
+        "Generated {} byte(s) of {}",
+        generated.len(),
+        cmd.get_file_type().to_str()
+    ));
+    Ok(generated)
+}
+
+/// Check the arguments once every source is applied, their relations first.
+pub fn verify_existed_args(cmd: &CommandArg) -> Result<(), ArgError> {
+    cmd.verify_relations().map_err(ArgError::Parse)?;
+    generator(cmd.get_file_type())?.verify(cmd)
+}
+
+/// Write the example sources next to the generated file, returns the written file.
+pub fn generate_example(
+    cmd: &CommandArg,
+    sink: &mut dyn OutputSink,
+) -> Result<PathBuf, FiletempError> {
+    let generator = generator(cmd.get_file_type())?;
+    match license::header(cmd)? {
+        Some(header) => {
+            let mut sink = license::HeaderSink::new(sink, header);
+            Ok(generator.example_files(cmd, &mut sink)?)
+        }
+        None => Ok(generator.example_files(cmd, sink)?),
     }
 }
 
 pub fn get_result_filename(ty: FileType) -> &'static str {
-    if let FileType::CMake = ty {
-        cmake_files::get_filename()
-    } else {
-        ""
+    generator(ty).map_or("", |g| g.output_name())
+}
+
+/// `content` with ANSI colors for the syntax its file name tells, only CMake is known.
+pub fn highlight(file_name: &str, content: &str) -> String {
+    let is_cmake = file_name == "CMakeLists.txt" || file_name.ends_with(".cmake");
+    match is_cmake {
+        true => cmake_parser::highlight(content).unwrap_or_else(|_| content.to_string()),
+        false => content.to_string(),
     }
 }
+
+/// Render the user template overriding a built-in type with the value of every
+/// argument of the type, repeated ones joined by commas and unset ones empty,
+/// and the built-in variables.
+fn render_override(cmd: &CommandArg, path: &Path) -> Result<String, GenerateError> {
+    logging::verbose(format_args!(
+        "Rendering \"{}\" in place of the built-in {} generator",
+        path.display(),
+        cmd.get_file_type().to_str()
+    ));
+    let template = match std::fs::read_to_string(path) {
+        Ok(t) => t,
+        Err(e) => return Err(IoError::new("read", path, e).into()),
+    };
+
+    let mut values: HashMap<String, String> = cmd
+        .type_arg_names()
+        .map(|name| (name.to_string(), String::new()))
+        .collect();
+    for pair in cmd.extract_args() {
+        let value = values.entry(pair.arg.to_string()).or_default();
+        if !value.is_empty() {
+            value.push(',');
+        }
+        value.push_str(pair.content);
+    }
+    with_builtin_vars(&mut values);
+
+    render::render(&template, &values).map_err(|message| GenerateError::Template {
+        path: path.to_path_buf(),
+        message,
+    })
+}
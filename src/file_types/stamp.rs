@@ -0,0 +1,138 @@
+//! Provenance comment of `--stamp`, naming the filetemp version, the caches
+//! used and the date, with a checksum of the rest of the file. The checksum
+//! tells whether a file was edited by hand since it was generated.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::program_args::providers::format_date;
+
+const MARKER: &str = "generated by filetemp ";
+const CHECKSUM_PREFIX: &str = ", crc32:";
+
+/// Comment prefix of a file by its name, None if it has no line comments.
+pub fn comment_prefix(file_name: &str) -> Option<&'static str> {
+    let lower = file_name.to_ascii_lowercase();
+    let extension = lower.rsplit_once('.').map_or("", |(_, e)| e);
+    match (lower.as_str(), extension) {
+        ("cmakelists.txt" | "makefile" | "gnumakefile" | "dockerfile", _) => Some("#"),
+        (
+            _,
+            "cmake" | "mk" | "py" | "sh" | "bash" | "toml" | "yml" | "yaml" | "gitignore"
+            | "editorconfig" | "clang-format",
+        ) => Some("#"),
+        (
+            _,
+            "c" | "h" | "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" | "rs" | "go" | "java" | "js"
+            | "ts" | "cs",
+        ) => Some("//"),
+        _ => None,
+    }
+}
+
+/// `content` with a stamp as its first line, after a `#!` line if it has one.
+/// A stamp already in `content` is replaced.
+pub fn add(content: &str, file_name: &str, caches: &[String]) -> Result<String, String> {
+    let prefix = match comment_prefix(file_name) {
+        Some(p) => p,
+        None => {
+            return Err(format!(
+                "--stamp does not know how to write a comment in \"{}\"",
+                file_name
+            ));
+        }
+    };
+    let content = remove(content);
+    let eol = match content.contains("\r\n") {
+        true => "\r\n",
+        false => "\n",
+    };
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    let from = match caches {
+        [] => String::new(),
+        [name] => format!(" from cache \"{}\"", name),
+        names => format!(" from caches \"{}\"", names.join("\", \"")),
+    };
+    let line = format!(
+        "{} {}{}{} on {}{}{:08x}",
+        prefix,
+        MARKER,
+        env!("CARGO_PKG_VERSION"),
+        from,
+        format_date(now),
+        CHECKSUM_PREFIX,
+        checksum(&content)
+    );
+
+    let at = match content.starts_with("#!") {
+        true => content.find('\n').map_or(content.len(), |i| i + 1),
+        false => 0,
+    };
+    let mut stamped = String::with_capacity(content.len() + line.len() + 2);
+    stamped.push_str(&content[..at]);
+    if at == content.len() && at > 0 && !content.ends_with('\n') {
+        stamped.push_str(eol);
+    }
+    stamped.push_str(&line);
+    stamped.push_str(eol);
+    stamped.push_str(&content[at..]);
+    Ok(stamped)
+}
+
+/// A stamp found in an existing file.
+pub struct Stamp {
+    /// Date of the stamp, `YYYY-MM-DD`.
+    pub date: String,
+    /// Whether the file no longer matches the checksum of its stamp.
+    pub edited: bool,
+}
+
+/// The stamp of `content`, None if it has none or it cannot be read.
+pub fn find(content: &str) -> Option<Stamp> {
+    let (start, end) = stamp_line(content)?;
+    let line = content[start..end].trim_end();
+    let (described, sum) = line.rsplit_once(CHECKSUM_PREFIX)?;
+    let sum = u32::from_str_radix(sum, 16).ok()?;
+    let (_, date) = described.rsplit_once(" on ")?;
+
+    let rest = format!("{}{}", &content[..start], &content[end..]);
+    Some(Stamp {
+        date: date.to_string(),
+        edited: checksum(&rest) != sum,
+    })
+}
+
+/// `content` without its stamp line.
+fn remove(content: &str) -> String {
+    match stamp_line(content) {
+        Some((start, end)) => format!("{}{}", &content[..start], &content[end..]),
+        None => content.to_string(),
+    }
+}
+
+/// Byte range of the stamp line with its line ending, it is one of the first two lines.
+fn stamp_line(content: &str) -> Option<(usize, usize)> {
+    let mut start = 0;
+    for _ in 0..2 {
+        let end = content[start..]
+            .find('\n')
+            .map_or(content.len(), |i| start + i + 1);
+        let line = &content[start..end];
+        if line.contains(MARKER) && line.contains(CHECKSUM_PREFIX) {
+            return Some((start, end));
+        }
+        if end == content.len() {
+            break;
+        }
+        start = end;
+    }
+    None
+}
+
+fn checksum(content: &str) -> u32 {
+    let mut crc = flate2::Crc::new();
+    crc.update(content.as_bytes());
+    crc.sum()
+}
@@ -0,0 +1,133 @@
+//! Generating a file without the command line, for tools embedding filetemp.
+//!
+//! ```no_run
+//! use filetemp::{Generator, ResolvedArgs};
+//!
+//! let args = ResolvedArgs::parse("cmake", &["--proj", "demo", "--version", "3.20"])?;
+//! let rendered = Generator::new("cmake")?.render(&args)?;
+//! std::fs::write(rendered.file_name, rendered.content)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::{
+    config_file::{ArgCache, CachedArg, cache_layer},
+    error::{ArgError, CacheError, FiletempError},
+    file_types::{self, FileType, get_result_filename, process_args, verify_existed_args},
+    program_args::{ArgProcessErr, CommandArg},
+};
+
+/// The arguments of one file type, checked and completed with their defaults.
+pub struct ResolvedArgs {
+    cmd: CommandArg,
+}
+
+impl ResolvedArgs {
+    /// Arguments given like those after the file type of `filetemp gen`,
+    /// e.g. `["--proj", "demo", "--version", "3.20"]`.
+    pub fn parse(file_type: &str, args: &[&str]) -> Result<Self, FiletempError> {
+        Self::parse_with_caches(file_type, args, &[])
+    }
+
+    /// Like `parse`, arguments not in `args` are taken from `caches`, later
+    /// ones taking precedence, as given to `--use`.
+    pub fn parse_with_caches(
+        file_type: &str,
+        args: &[&str],
+        caches: &[ArgCache],
+    ) -> Result<Self, FiletempError> {
+        let mut cmd = CommandArg::new();
+        file_types::register_types(&mut cmd);
+        let raw: Vec<&str> = std::iter::once(file_type)
+            .chain(args.iter().copied())
+            .collect();
+        cmd.process_program_args(&raw).map_err(ArgError::Parse)?;
+
+        for cache in caches {
+            if cache.file_type != cmd.get_file_type() {
+                return Err(CacheError::OtherFileType {
+                    name: cache.cache_name.clone(),
+                    saved_for: vec![cache.file_type.to_str().to_string()],
+                    file_type: file_type.to_string(),
+                }
+                .into());
+            }
+            cmd.add_layer(cache_layer(cache));
+        }
+        if let Err((e, layer)) = cmd.resolve() {
+            return Err(FiletempError::refused(e, layer));
+        }
+        match cmd.assert_required_args_exist() {
+            Ok(()) => {}
+            Err(ArgProcessErr::MissingArgs { missing, required }) => {
+                return Err(ArgError::missing(&cmd, &missing, required).into());
+            }
+            Err(e) => return Err(ArgError::Parse(e).into()),
+        }
+        verify_existed_args(&cmd)?;
+        Ok(Self { cmd })
+    }
+
+    pub fn file_type(&self) -> &'static str {
+        self.cmd.get_file_type().to_str()
+    }
+
+    /// Value of the argument `name`, the first one of a repeatable argument.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.cmd.get_arg(name)
+    }
+
+    /// Every value of a repeatable argument.
+    pub fn get_all(&self, name: &str) -> &[String] {
+        self.cmd.get_args(name)
+    }
+
+    /// The arguments as a cache named `name`, to save with `CacheStore::save`.
+    pub fn to_cache(&self, name: &str) -> ArgCache {
+        let mut cache = ArgCache::new(name, self.cmd.get_file_type());
+        cache.args.extend(
+            self.cmd
+                .extract_given_args()
+                .into_iter()
+                .map(CachedArg::from),
+        );
+        cache
+    }
+}
+
+/// A generated file.
+pub struct Rendered {
+    /// Name of the file, e.g. `CMakeLists.txt`.
+    pub file_name: &'static str,
+    pub content: String,
+}
+
+/// Generates the files of one file type.
+pub struct Generator {
+    file_type: FileType,
+}
+
+impl Generator {
+    /// The generator of a built-in file type, or one declared by a manifest
+    /// once `file_types::custom::load_types` loaded it.
+    pub fn new(file_type: &str) -> Result<Self, ArgError> {
+        match FileType::match_type(file_type) {
+            FileType::Unknown => Err(ArgError::UnknownFileType(file_type.to_string())),
+            file_type => Ok(Self { file_type }),
+        }
+    }
+
+    pub fn render(&self, args: &ResolvedArgs) -> Result<Rendered, FiletempError> {
+        if args.cmd.get_file_type() != self.file_type {
+            return Err(ArgError::Invalid(format!(
+                "The arguments are for {}, not for {}",
+                args.file_type(),
+                self.file_type.to_str()
+            ))
+            .into());
+        }
+        Ok(Rendered {
+            file_name: get_result_filename(self.file_type),
+            content: process_args(&args.cmd)?,
+        })
+    }
+}
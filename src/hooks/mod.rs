@@ -0,0 +1,155 @@
+use std::{io, path::Path, process::Command};
+
+use crate::{events::EventBus, logging};
+
+/// Read the `hooks` array of a manifest, commands run after the files are written.
+pub fn read_hooks(value: &toml::Value, key: &str) -> Result<Vec<String>, String> {
+    let hooks: Option<Vec<String>> = value
+        .as_array()
+        .and_then(|a| a.iter().map(|v| v.as_str().map(String::from)).collect());
+    match hooks {
+        Some(h) if h.iter().all(|c| !c.trim().is_empty()) => Ok(h),
+        _ => Err(format!("{} must be an array of commands", key)),
+    }
+}
+
+/// Run each hook through the shell in `dir`, in order. Their output is shown
+/// when verbose, a failing hook is a warning with its output and skips the rest.
+pub fn run_hooks(hooks: &[String], dir: &Path, events: &mut EventBus) {
+    for (idx, hook) in hooks.iter().enumerate() {
+        logging::verbose(format_args!("Running hook \"{}\"", hook));
+        let mut message = match run(shell(hook).current_dir(dir)) {
+            Ok(()) => continue,
+            Err(Failed::Spawn(e)) => format!("Failed to run hook \"{}\": {}", hook, e),
+            Err(Failed::Status(status)) => format!("Hook \"{}\" failed with {}", hook, status),
+        };
+        if idx + 1 < hooks.len() {
+            message.push_str(&format!(
+                "\nSkipped {} later hook(s)",
+                hooks.len() - idx - 1
+            ));
+        }
+        events.warn(&message);
+        return;
+    }
+}
+
+/// Commands of `--git-init`, committing with `commit_message` if given.
+fn git_commands(commit_message: Option<&str>) -> Vec<Vec<&str>> {
+    let mut commands = vec![vec!["init"]];
+    if let Some(m) = commit_message {
+        commands.push(vec!["add", "--all"]);
+        commands.push(vec!["commit", "--message", m]);
+    }
+    commands
+}
+
+/// Start a git repository in `dir` and commit all its files with `commit_message`
+/// if given. A directory inside a repository already is left as it is.
+pub fn git_init(dir: &Path, commit_message: Option<&str>, events: &mut EventBus) {
+    let inside = Command::new("git")
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .current_dir(dir)
+        .output()
+        .is_ok_and(|o| o.status.success());
+    if inside {
+        match commit_message {
+            Some(_) => events.warn(&format!(
+                "Skipped git init and the initial commit, \"{}\" is in a git repository already",
+                dir.display()
+            )),
+            None => logging::verbose(format_args!(
+                "Skipped git init, \"{}\" is in a git repository already",
+                dir.display()
+            )),
+        }
+        return;
+    }
+
+    for args in git_commands(commit_message) {
+        let command = format!("git {}", args.join(" "));
+        logging::verbose(format_args!("Running \"{}\"", command));
+        let message = match run(Command::new("git").args(&args).current_dir(dir)) {
+            Ok(()) => continue,
+            Err(Failed::Spawn(e)) => format!("Failed to run \"{}\": {}", command, e),
+            Err(Failed::Status(status)) => format!("\"{}\" failed with {}", command, status),
+        };
+        events.warn(&message);
+        return;
+    }
+}
+
+/// List the commands of `--git-init` instead of running them, for `--dry-run` and `--diff`.
+pub fn print_git_dry_run(commit_message: Option<&str>) {
+    let commands: Vec<String> = git_commands(commit_message)
+        .iter()
+        .map(|args| {
+            let quoted: Vec<String> = args
+                .iter()
+                .map(|a| match a.contains(char::is_whitespace) {
+                    true => format!("\"{}\"", a),
+                    false => a.to_string(),
+                })
+                .collect();
+            format!("git {}", quoted.join(" "))
+        })
+        .collect();
+    print_dry_run(&commands);
+}
+
+enum Failed {
+    Spawn(io::Error),
+    /// The exit status, followed by the output unless verbose runs showed it.
+    Status(String),
+}
+
+/// Run `command` to its end, its output is shown when verbose.
+fn run(command: &mut Command) -> Result<(), Failed> {
+    let output = command.output().map_err(Failed::Spawn)?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    for line in stdout.lines().chain(stderr.lines()) {
+        logging::verbose(format_args!("  {}", line));
+    }
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let mut status = match output.status.code() {
+        Some(c) => format!("exit status {}", c),
+        None => String::from("a signal"),
+    };
+    // Verbose runs showed the output already.
+    let shown = if stderr.trim().is_empty() {
+        &stdout
+    } else {
+        &stderr
+    };
+    if !logging::enabled(logging::Level::Verbose) && !shown.trim().is_empty() {
+        status.push_str(&format!(":\n{}", shown.trim_end()));
+    }
+    Err(Failed::Status(status))
+}
+
+/// List the hooks instead of running them, for `--dry-run` and `--diff`.
+pub fn print_dry_run(hooks: &[String]) {
+    println!("Would run afterwards:");
+    for hook in hooks.iter() {
+        println!("  {}", hook);
+    }
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.args(["/C", command]);
+    cmd
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", command]);
+    cmd
+}
@@ -0,0 +1,98 @@
+//! Translations of user-facing messages. Messages are written in English where
+//! they are used and looked up by that text in the catalog of the locale, so a
+//! message without a translation is shown in English.
+//!
+//! The locale is `--lang` if given, otherwise the language of `LC_ALL`,
+//! `LC_MESSAGES` or `LANG`, the first one set.
+
+use std::{collections::HashMap, fmt::Display, fmt::Write, sync::OnceLock};
+
+mod zh;
+
+/// Languages with a catalog, English is the language of the messages themselves.
+pub const LANGUAGES: [&str; 2] = ["en", "zh"];
+
+static CATALOG: OnceLock<Option<HashMap<&'static str, &'static str>>> = OnceLock::new();
+
+/// Choose the locale, `lang` as given to `--lang` or the environment without it.
+/// Only the first call has an effect.
+pub fn set_language(lang: Option<&str>) -> Result<(), String> {
+    let language = match lang {
+        Some(l) => match language_of(l) {
+            Some(l) => l,
+            None => {
+                return Err(format!(
+                    "Invalid language \"{}\", possible values: {}",
+                    l,
+                    LANGUAGES.join(", ")
+                ));
+            }
+        },
+        None => ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|v| std::env::var(v).ok())
+            .find(|v| !v.is_empty())
+            .and_then(|v| language_of(&v))
+            .unwrap_or("en"),
+    };
+
+    let messages: Option<&[(&str, &str)]> = match language {
+        "zh" => Some(zh::MESSAGES),
+        _ => None,
+    };
+    let _ = CATALOG.set(messages.map(|m| m.iter().copied().collect()));
+    Ok(())
+}
+
+/// The language of a locale name like `zh_CN.UTF-8`, None if there is no catalog for it.
+fn language_of(locale: &str) -> Option<&'static str> {
+    let language = locale
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match language.as_str() {
+        // The POSIX locale is English.
+        "c" | "posix" => Some("en"),
+        l => LANGUAGES.iter().find(|&&known| known == l).copied(),
+    }
+}
+
+/// `message` in the chosen language.
+pub fn tr(message: &str) -> &str {
+    match CATALOG.get() {
+        Some(Some(catalog)) => catalog.get(message).copied().unwrap_or(message),
+        _ => message,
+    }
+}
+
+/// `message` in the chosen language with each `{}` replaced by the next of
+/// `args`. Translations may use `{0}`, `{1}` and so on where their word order differs.
+pub fn trf(message: &str, args: &[&dyn Display]) -> String {
+    let template = tr(message);
+    let mut out = String::with_capacity(template.len());
+    let mut next = 0;
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let index = match after.find('}').map(|end| (end, &after[..end])) {
+            Some((end, "")) => Some((end, next)),
+            Some((end, n)) => n.parse().ok().map(|i| (end, i)),
+            None => None,
+        };
+        match index.and_then(|(end, i)| Some((end, args.get(i)?))) {
+            Some((end, arg)) => {
+                write!(out, "{}", arg).unwrap();
+                next += 1;
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
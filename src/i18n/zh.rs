@@ -0,0 +1,464 @@
+//! Simplified Chinese.
+
+pub const MESSAGES: &[(&str, &str)] = &[
+    // Help
+    ("USAGE:", "用法："),
+    ("SUBCOMMANDS:", "子命令："),
+    ("FILE_TYPE:", "文件类型："),
+    ("{} OPTIONS:", "{} 选项："),
+    ("ARGUMENT SETS:", "参数组："),
+    ("ARGUMENTS:", "参数："),
+    ("GENERAL OPTIONS:", "通用选项："),
+    ("OPTIONS:", "选项："),
+    ("Generates {}", "生成 {}"),
+    (
+        "Run \"{} <FILE_TYPE> --help\" for the options of a file type.",
+        "运行 \"{} <FILE_TYPE> --help\" 查看某一文件类型的选项。",
+    ),
+    ("[aliases: {}]", "[别名：{}]"),
+    ("[possible values: {}]", "[可选值：{}]"),
+    ("[requires: {}]", "[需要：{}]"),
+    ("[conflicts with: {}]", "[不能与之同用：{}]"),
+    ("[required]", "[必需]"),
+    ("[default: {}]", "[默认：{}]"),
+    ("Usage: {}", "用法：{}"),
+    ("Run \"{}\" for details.", "运行 \"{}\" 查看详情。"),
+    (
+        "Missing required argument: {} ({} of {})",
+        "缺少必需参数：{}（共 {1}/{2} 个）",
+    ),
+    (
+        "Missing required arguments: {} ({} of {})",
+        "缺少必需参数：{}（共 {1}/{2} 个）",
+    ),
+    ("Try:", "可以尝试："),
+    // Prompts
+    ("[Y/n]", "[是/否，默认是]"),
+    ("[y/N]", "[是/否，默认否]"),
+    ("yes", "是"),
+    ("Possible values: {}", "可选值：{}"),
+    ("Invalid value", "无效的值"),
+    ("Edit again?", "重新编辑？"),
+    ("Replace \"{}\"?", "替换 \"{}\"？"),
+    ("File type", "文件类型"),
+    ("--{} is required", "--{} 是必需的"),
+    ("Directory to generate into", "生成到的目录"),
+    ("Write it into \"{}\"?", "写入 \"{}\"？"),
+    ("Write the fixes to \"{}\"?", "将修复写入 \"{}\"？"),
+    (
+        "Fixes were not written to \"{}\", give --force to write them without asking",
+        "修复未写入 \"{}\"，使用 --force 可不经询问直接写入",
+    ),
+    (
+        "Save the answers as a cache named, empty to skip",
+        "将回答保存为缓存，输入名称，留空跳过",
+    ),
+    (
+        "The same from the command line: filetemp {}",
+        "等效的命令行：filetemp {}",
+    ),
+    (
+        "Refusing to generate into \"{}\", {}. Pass --i-know-what-im-doing to proceed anyway.",
+        "拒绝生成到 \"{}\"，{}。如确需继续，请加上 --i-know-what-im-doing。",
+    ),
+    ("it is the filesystem root", "它是文件系统根目录"),
+    ("it is the home directory", "它是主目录"),
+    ("it contains more than {} entries", "其中已有超过 {} 个条目"),
+    (
+        "filetemp new asks its questions in a terminal, use filetemp gen in scripts",
+        "filetemp new 需要在终端中提问，脚本中请使用 filetemp gen",
+    ),
+    // Argument errors
+    ("Invalid argument: \"{}\"", "无效的参数：\"{}\""),
+    ("Invalid file type: \"{}\"", "无效的文件类型：\"{}\""),
+    (
+        "Invalid flag value, expected true or false: \"{}\"",
+        "无效的开关值，应为 true 或 false：\"{}\"",
+    ),
+    (
+        "Invalid value \"{}\" for --{}, possible values: {}",
+        "--{1} 的值 \"{0}\" 无效，可选值：{2}",
+    ),
+    (
+        "Value for --{} is {} bytes, the limit is {} bytes",
+        "--{} 的值有 {} 字节，上限为 {} 字节",
+    ),
+    (
+        "Invalid value \"{}\" for --{}: {}",
+        "--{1} 的值 \"{0}\" 无效：{2}",
+    ),
+    ("--{} was given more than once", "--{} 被指定了多次"),
+    ("Missing value for --{}", "--{} 缺少值"),
+    (
+        "Ignoring the value of the {} provider for --{}: {}",
+        "忽略 {} 提供器为 --{} 给出的值：{}",
+    ),
+    (
+        "--{} cannot be used together with --{}",
+        "--{} 不能与 --{} 同时使用",
+    ),
+    (
+        "Invalid combination of arguments ({}): {}",
+        "参数组合无效（{}）：{}",
+    ),
+    ("--{} requires --{} to be given", "--{} 需要同时指定 --{}"),
+    ("Missing required arguments: --{}", "缺少必需参数：--{}"),
+    (
+        "Unexpected value after \"--\": \"{}\"",
+        "\"--\" 之后出现意外的值：\"{}\"",
+    ),
+    ("Did you mean \"{}\"?", "你是不是想输入 \"{}\"？"),
+    ("Unknown {} command: \"{}\"", "未知的 {} 命令：\"{}\""),
+    (
+        "Missing {} command, expected one of: {}",
+        "缺少 {} 命令，应为以下之一：{}",
+    ),
+    (
+        "Invalid language \"{}\", possible values: {}",
+        "无效的语言 \"{}\"，可选值：{}",
+    ),
+    // Cache errors
+    (
+        "Cannot find the user data directory, set {} to choose where caches are kept",
+        "找不到用户数据目录，请设置 {} 以指定缓存的存放位置",
+    ),
+    (
+        "Cannot keep caches in \"{}\" given by {}: {}",
+        "无法在 {1} 指定的 \"{0}\" 中存放缓存：{2}",
+    ),
+    ("Argument cache parse error: {}", "参数缓存解析错误：{}"),
+    ("Cache import error: {}", "缓存导入错误：{}"),
+    (
+        "No saved {} cache named \"{}\"",
+        "没有名为 \"{1}\" 的已保存 {0} 缓存",
+    ),
+    (
+        "No saved cache named \"{}\"",
+        "没有名为 \"{}\" 的已保存缓存",
+    ),
+    (
+        "No saved caches match \"{}\"",
+        "没有与 \"{}\" 匹配的已保存缓存",
+    ),
+    (
+        "Cache \"{}\" was saved for {}, not for {}",
+        "缓存 \"{}\" 是为 {} 保存的，而不是 {}",
+    ),
+    (
+        "Caches named \"{}\" exist for {}, give the file type before the name",
+        "{1} 都有名为 \"{0}\" 的缓存，请在名称前指定文件类型",
+    ),
+    (
+        "A {} cache named \"{}\" already exists",
+        "名为 \"{1}\" 的 {0} 缓存已存在",
+    ),
+    ("Cache round trip failed, {}", "缓存往返校验失败，{}"),
+    (
+        "{}, cache \"{}\" was not changed",
+        "{}，缓存 \"{}\" 未被修改",
+    ),
+    ("Cache \"{}\" was not changed", "缓存 \"{}\" 未被修改"),
+    ("Create it with --save-as {}", "可使用 --save-as {} 创建"),
+    // Generation and IO errors
+    ("{} in \"{}\"", "{}（位于 \"{}\"）"),
+    (
+        "File type \"{}\" has no example project",
+        "文件类型 \"{}\" 没有示例项目",
+    ),
+    ("Cannot merge into \"{}\": {}", "无法合并到 \"{}\"：{}"),
+    ("Failed to {} \"{}\": {}", "无法{} \"{}\"：{}"),
+    ("read", "读取"),
+    ("move", "移动"),
+    ("The data directory is up to date", "数据目录已是最新"),
+    (
+        "Upgrading would make these changes:",
+        "升级将进行以下改动：",
+    ),
+    ("write", "写入"),
+    ("create", "创建"),
+    ("open", "打开"),
+    ("lock", "锁定"),
+    // Subcommands
+    (
+        "Generate a file, also the default when no subcommand is given",
+        "生成文件，未指定子命令时的默认行为",
+    ),
+    (
+        "Generate each file type of a filetemp-spec.toml, the OPTIONS are given to all",
+        "生成 filetemp-spec.toml 中的每个文件类型，OPTIONS 对所有类型生效",
+    ),
+    (
+        "Apply a filetemp-spec.toml again whenever it or the user templates it uses change",
+        "每当 filetemp-spec.toml 或其使用的用户模板改变时重新应用",
+    ),
+    (
+        "Files watched: {}, press Ctrl-C to stop",
+        "监视的文件数：{}，按 Ctrl-C 停止",
+    ),
+    ("Changed \"{}\"", "已改变 \"{}\""),
+    ("Rewrote \"{}\"", "已重写 \"{}\""),
+    ("{} rewritten, {} unchanged", "{} 个已重写，{} 个未改变"),
+    (
+        "Generating failed, waiting for the next change",
+        "生成失败，等待下一次改变",
+    ),
+    (
+        "Move the data directory and convert the cache file to the current format",
+        "将数据目录和缓存文件迁移到当前的位置和格式",
+    ),
+    (
+        "Print a default of the user config, or of the project config with --project",
+        "打印用户配置中的默认值，使用 --project 时打印项目配置中的",
+    ),
+    (
+        "Set a default in the user config, e.g. cmake.version 3.20",
+        "在用户配置中设置默认值，例如 cmake.version 3.20",
+    ),
+    (
+        "Remove a default from the user config",
+        "从用户配置中移除默认值",
+    ),
+    (
+        "Print the values of the user config",
+        "打印用户配置中的所有值",
+    ),
+    (
+        "Ask for a file type and its arguments one by one, preview and write the file",
+        "逐项询问文件类型及其参数，预览并写入文件",
+    ),
+    (
+        "Check an existing file against best-practice rules",
+        "按最佳实践规则检查已有文件",
+    ),
+    (
+        "List generator features by the version introducing them",
+        "按引入版本列出生成器特性",
+    ),
+    (
+        "Render a user template, or a bundle given with --template-archive",
+        "渲染用户模板，或 --template-archive 指定的模板包",
+    ),
+    (
+        "Download a template or bundle from git or https into the templates directory",
+        "从 git 或 https 下载模板或模板包到模板目录",
+    ),
+    (
+        "Report syntax errors and undeclared variables of a user template",
+        "报告用户模板中的语法错误和未声明的变量",
+    ),
+    (
+        "Lay out a project from a bundle of templates in the templates directory",
+        "用模板目录中的模板包搭建项目",
+    ),
+    (
+        "Remove the files and directories generating created in DIR, by its manifest",
+        "按清单删除生成时在 DIR 中创建的文件和目录",
+    ),
+    (
+        "List file types with their output files and arguments",
+        "列出文件类型及其输出文件和参数",
+    ),
+    (
+        "Create the cache file, in the data directory or DIR",
+        "在数据目录或 DIR 中创建缓存文件",
+    ),
+    (
+        "List saved argument caches, or those matching e.g. 'proj-*', --names prints only their names",
+        "列出已保存的参数缓存，或与 'proj-*' 等模式匹配的缓存，--names 只输出名称",
+    ),
+    (
+        "Print the arguments of a saved cache",
+        "打印已保存缓存的参数",
+    ),
+    (
+        "Edit the arguments of a saved cache in $VISUAL or $EDITOR",
+        "用 $VISUAL 或 $EDITOR 编辑已保存缓存的参数",
+    ),
+    (
+        "Delete a saved argument cache, or all matching e.g. 'tmp-*'",
+        "删除已保存的参数缓存，或所有与 'tmp-*' 等模式匹配的缓存",
+    ),
+    ("Rename a saved argument cache", "重命名已保存的参数缓存"),
+    (
+        "Write saved caches as JSON, to share them or check them in",
+        "将已保存的缓存写为 JSON，以便分享或提交",
+    ),
+    (
+        "Add the caches of an exported JSON file",
+        "添加导出的 JSON 文件中的缓存",
+    ),
+    (
+        "Delete caches not used for longer than AGE, e.g. 90d",
+        "删除超过 AGE（如 90d）未使用的缓存",
+    ),
+    (
+        "Check that saving and loading the arguments reproduces the output",
+        "检查保存并重新加载参数后输出是否一致",
+    ),
+    ("Print a shell completion script", "打印 shell 补全脚本"),
+    (
+        "List providers of computed default values",
+        "列出计算默认值的提供者",
+    ),
+    // General options
+    (
+        "Path where the file is generated to, created if missing",
+        "生成文件的目录，不存在时自动创建",
+    ),
+    (
+        "Write the generated files into a .tar.gz, .tgz or .zip archive instead",
+        "将生成的文件写入 .tar.gz、.tgz 或 .zip 归档",
+    ),
+    ("Show output content to stdout", "将输出内容打印到标准输出"),
+    ("Save current argument set to cache", "将当前参数保存为缓存"),
+    (
+        "Use existed cache, later ones of a comma separated list override earlier ones",
+        "使用已有缓存，逗号分隔的列表中靠后的覆盖靠前的",
+    ),
+    (
+        "Save the cache as an extension of another one, storing only what differs",
+        "将缓存保存为另一个缓存的扩展，只存储不同之处",
+    ),
+    (
+        "Use the built-in generator even if a user template overrides it",
+        "即使有用户模板覆盖，也使用内置生成器",
+    ),
+    (
+        "Do not run the commands a file type declares for after generating",
+        "不运行文件类型声明的生成后命令",
+    ),
+    (
+        "List the files that would be written, with their sizes, instead of writing them",
+        "只列出将要写入的文件及其大小，不实际写入",
+    ),
+    (
+        "Print a unified diff against the files in --path instead of writing them",
+        "打印与 --path 中文件的统一差异，不实际写入",
+    ),
+    (
+        "Replace existing files without asking",
+        "不询问直接替换已有文件",
+    ),
+    (
+        "Move existing files to <name>.bak before replacing them",
+        "替换前将已有文件移动为 <name>.bak",
+    ),
+    (
+        "Neither read nor write the argument cache, --use and --save-as are ignored",
+        "不读写参数缓存，忽略 --use 和 --save-as",
+    ),
+    (
+        "Print which source gives the value of an argument instead of generating",
+        "打印参数值的来源，不生成文件",
+    ),
+    ("Generate example project", "生成示例项目"),
+    (
+        "Write the generated file under this name, e.g. CMakeLists.txt.new or sub/CMakeLists.txt",
+        "以此名称写入生成的文件，如 CMakeLists.txt.new 或 sub/CMakeLists.txt",
+    ),
+    (
+        "Add only the commands an existing CMakeLists.txt at --path lacks, keeping the rest",
+        "只添加 --path 中已有 CMakeLists.txt 缺少的命令，其余保持不变",
+    ),
+    (
+        "Start the file with a comment naming the version, caches and date of its generation",
+        "在文件开头添加注释，注明生成时的版本、缓存和日期",
+    ),
+    (
+        "Also write companion files, a comma separated list of gitignore, clang-format and editorconfig",
+        "同时写入配套文件，逗号分隔的 gitignore、clang-format 和 editorconfig 列表",
+    ),
+    (
+        "Start the example sources with a copyright comment, mit, apache-2.0 or custom:<FILE> with {year} and {author}",
+        "在示例源文件开头添加版权注释，mit、apache-2.0 或含 {year} 和 {author} 的 custom:<FILE>",
+    ),
+    (
+        "Author named in the license header, user.name of git by default",
+        "许可证头中的作者，默认为 git 的 user.name",
+    ),
+    (
+        "Read file type arguments from a JSON object of names to values, - for stdin",
+        "从参数名到值的 JSON 对象读取文件类型参数，- 表示标准输入",
+    ),
+    (
+        "Read name:value lines of file type arguments from stdin, as in a cache, below those given",
+        "从标准输入读取 name:value 形式的文件类型参数行（同缓存格式），优先级低于命令行参数",
+    ),
+    (
+        "Start a git repository in --path after generating, with a .gitignore unless it has one",
+        "生成后在 --path 中创建 git 仓库，没有 .gitignore 时一并写入",
+    ),
+    (
+        "Commit the files of the new repository with this message",
+        "以此提交信息提交新仓库中的文件",
+    ),
+    (
+        "Report each generation step to stderr",
+        "将每个生成步骤报告到标准错误",
+    ),
+    ("Only report errors", "只报告错误"),
+    (
+        "Never ask for missing required arguments, even in a terminal",
+        "即使在终端中也不询问缺少的必需参数",
+    ),
+    (
+        "Allow generating into the filesystem root, home or crowded directories",
+        "允许生成到文件系统根目录、主目录或文件过多的目录",
+    ),
+    (
+        "Directory generated into, by default the current one",
+        "生成到的目录，默认为当前目录",
+    ),
+    (
+        "Also remove files changed since they were generated",
+        "同时删除生成后被修改过的文件",
+    ),
+    (
+        "Report each file and directory removed",
+        "报告每个被删除的文件和目录",
+    ),
+    // CMake options
+    (
+        "Used in \"cmake_minimum_required\", a version or a <min>...<max> range",
+        "用于 \"cmake_minimum_required\"，一个版本或 <min>...<max> 范围",
+    ),
+    ("Project name", "项目名称"),
+    (
+        "Project version, emitted as VERSION in \"project\"",
+        "项目版本，作为 \"project\" 的 VERSION 输出",
+    ),
+    (
+        "Project description, emitted as DESCRIPTION in \"project\"",
+        "项目描述，作为 \"project\" 的 DESCRIPTION 输出",
+    ),
+    (
+        "Project homepage, emitted as HOMEPAGE_URL in \"project\"",
+        "项目主页，作为 \"project\" 的 HOMEPAGE_URL 输出",
+    ),
+    (
+        "Main language, decides whether \"main.c\" or \"main.cpp\" is generated",
+        "主要语言，决定生成 \"main.c\" 还是 \"main.cpp\"",
+    ),
+    ("C standard, e.g. 11 or c11", "C 标准，如 11 或 c11"),
+    ("C++ standard, e.g. 17 or c++17", "C++ 标准，如 17 或 c++17"),
+    (
+        "Target type, executable if not specified",
+        "目标类型，未指定时为可执行文件",
+    ),
+    (
+        "Target name, use project name if not specified",
+        "目标名称，未指定时使用项目名称",
+    ),
+    (
+        "Link a library already known to the toolchain",
+        "链接工具链已知的库",
+    ),
+    (
+        "Find an installed package and link its PKG::PKG target",
+        "查找已安装的包并链接其 PKG::PKG 目标",
+    ),
+    (
+        "Download a git repository with FetchContent and link its NAME target",
+        "用 FetchContent 下载 git 仓库并链接其 NAME 目标",
+    ),
+];
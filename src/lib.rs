@@ -0,0 +1,31 @@
+//! Generate build files from a set of arguments, as the `filetemp` command
+//! line does. `Generator` renders a file type from `ResolvedArgs`, and
+//! `CacheStore` reads and saves the argument caches of `--use` and `--save-as`.
+//! Errors are a `FiletempError` telling their kind, and `cli::run` runs a
+//! command line the way the binary does.
+
+pub mod cli;
+pub mod config_file;
+pub mod crash;
+pub mod error;
+pub mod events;
+pub mod exit_status;
+pub mod file_types;
+pub mod generator;
+pub mod hooks;
+pub mod i18n;
+pub mod lint;
+pub mod logging;
+pub mod matrix;
+pub mod output_sink;
+pub mod plugins;
+pub mod program_args;
+pub mod project_config;
+pub mod scratch;
+pub mod templates;
+pub mod text_edit;
+pub mod watch;
+
+pub use config_file::CacheStore;
+pub use error::FiletempError;
+pub use generator::{Generator, Rendered, ResolvedArgs};
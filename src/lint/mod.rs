@@ -0,0 +1,651 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    error::{ArgError, FiletempError, IoError},
+    file_types::{FileType, cmake_parser, cmake_version::CMakeVersion, get_result_filename},
+    i18n::trf,
+    logging,
+    program_args::{Arg, ArgFileTypeView, ArgProcessErr, CommandArg, ValueType, normalize, prompt},
+    project_config::load_project_config,
+    text_edit::{TextEdit, apply_edits, unified_diff},
+};
+
+mod report;
+mod rules;
+
+/// Marker starting a suppression comment, e.g. `# filetemp-lint: disable=rule-name`.
+const SUPPRESSION_MARKER: &str = "filetemp-lint:";
+
+/// Rounds of fixing, some fixes only show up once others are applied.
+const MAX_FIX_PASSES: usize = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("info") {
+            Ok(Self::Info)
+        } else if s.eq_ignore_ascii_case("warning") {
+            Ok(Self::Warning)
+        } else if s.eq_ignore_ascii_case("error") {
+            Ok(Self::Error)
+        } else {
+            Err(())
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Sarif,
+}
+
+impl FromStr for OutputFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("text") {
+            Ok(Self::Text)
+        } else if s.eq_ignore_ascii_case("json") {
+            Ok(Self::Json)
+        } else if s.eq_ignore_ascii_case("sarif") {
+            Ok(Self::Sarif)
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// A single rule violation produced by a rule check.
+pub struct RuleHit {
+    pub line: usize,
+    pub message: String,
+    /// Edits resolving the violation, empty if it can't be fixed automatically.
+    pub fix: Vec<TextEdit>,
+}
+
+impl RuleHit {
+    pub fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+            fix: Vec::new(),
+        }
+    }
+
+    pub fn with_fix(mut self, fix: Vec<TextEdit>) -> Self {
+        self.fix = fix;
+        self
+    }
+}
+
+pub struct LintRule {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub severity: Severity,
+    check: fn(&cmake_parser::CMakeDocument, &LintConfig) -> Vec<RuleHit>,
+}
+
+pub struct Finding {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub line: usize,
+    pub message: String,
+    pub fix: Vec<TextEdit>,
+}
+
+pub struct LintConfig {
+    pub min_version: CMakeVersion,
+    pub disabled: Vec<String>,
+    pub severities: Vec<(String, Severity)>,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LintConfig {
+    pub fn new() -> Self {
+        Self {
+            min_version: CMakeVersion::new(3, 16, 0),
+            disabled: Vec::new(),
+            severities: Vec::new(),
+        }
+    }
+
+    fn is_enabled(&self, rule: &str) -> bool {
+        !self.disabled.iter().any(|d| d == rule)
+    }
+
+    fn set_enabled(&mut self, rule: &str, enabled: bool) {
+        self.disabled.retain(|d| d != rule);
+        if !enabled {
+            self.disabled.push(rule.to_string());
+        }
+    }
+
+    fn severity_of(&self, rule: &LintRule) -> Severity {
+        self.severities
+            .iter()
+            .rev()
+            .find(|(name, _)| name == rule.name)
+            .map(|(_, s)| *s)
+            .unwrap_or(rule.severity)
+    }
+
+    /// Apply the `[lint]` section of a project config:
+    ///
+    /// ```toml
+    /// [lint]
+    /// min-version = "3.20"
+    /// disable = ["directory-scoped-command"]
+    ///
+    /// [lint.rules]
+    /// glob-without-configure-depends = "error"   # or "off", true, false
+    /// ```
+    pub fn apply_table(&mut self, table: &toml::Table) -> Result<(), String> {
+        for (key, value) in table.iter() {
+            match key.as_str() {
+                "min-version" => {
+                    self.min_version = match value.as_str().map(|v| v.parse()) {
+                        Some(Ok(v)) => v,
+                        _ => {
+                            return Err(String::from("Invalid lint.min-version in project config"));
+                        }
+                    };
+                }
+                "disable" => {
+                    let list = match value.as_array() {
+                        Some(l) => l,
+                        None => return Err(String::from("lint.disable must be a list of rules")),
+                    };
+                    for rule in list.iter() {
+                        let rule = rule.as_str().unwrap_or("");
+                        self.set_enabled(verify_rule_name(rule)?, false);
+                    }
+                }
+                "rules" => {
+                    let rules = match value.as_table() {
+                        Some(r) => r,
+                        None => return Err(String::from("lint.rules must be a table")),
+                    };
+                    for (rule, setting) in rules.iter() {
+                        self.apply_rule_setting(verify_rule_name(rule)?, setting)?;
+                    }
+                }
+                _ => return Err(format!("Unknown lint config key: {}", key)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply_rule_setting(&mut self, rule: &str, setting: &toml::Value) -> Result<(), String> {
+        match setting {
+            toml::Value::Boolean(b) => self.set_enabled(rule, *b),
+            toml::Value::String(s) if s == "off" => self.set_enabled(rule, false),
+            toml::Value::String(s) => match s.parse::<Severity>() {
+                Ok(sev) => {
+                    self.set_enabled(rule, true);
+                    self.severities.push((rule.to_string(), sev));
+                }
+                Err(_) => {
+                    return Err(format!("Invalid setting \"{}\" for lint rule {}", s, rule));
+                }
+            },
+            _ => return Err(format!("Invalid setting for lint rule {}", rule)),
+        }
+
+        Ok(())
+    }
+}
+
+fn verify_rule_name(rule: &str) -> Result<&str, String> {
+    if rules::CMAKE_RULES.iter().any(|r| r.name == rule) {
+        Ok(rule)
+    } else {
+        Err(format!("Unknown lint rule: {}", rule))
+    }
+}
+
+/// A rule disabled on one line by a comment, `None` disables every rule.
+struct Suppression {
+    line: usize,
+    rule: Option<String>,
+}
+
+/// Collect suppression comments. `disable=<rules>` applies to the line the comment
+/// is on, `disable-next-line=<rules>` to the line after it.
+fn collect_suppressions(doc: &cmake_parser::CMakeDocument) -> Vec<Suppression> {
+    let mut suppressions = Vec::new();
+    for comment in doc.comments.iter() {
+        let directive = match comment.text.trim().strip_prefix(SUPPRESSION_MARKER) {
+            Some(d) => d.trim(),
+            None => continue,
+        };
+
+        let (kind, rules) = directive.split_once('=').unwrap_or((directive, ""));
+        let line = match kind.trim() {
+            "disable" => comment.line,
+            "disable-next-line" => comment.line + 1,
+            _ => continue,
+        };
+
+        let start = suppressions.len();
+        for rule in rules.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+            suppressions.push(Suppression {
+                line,
+                rule: Some(rule.to_string()),
+            });
+        }
+        if suppressions.len() == start {
+            suppressions.push(Suppression { line, rule: None });
+        }
+    }
+
+    suppressions
+}
+
+pub fn define_args(cmd: &mut CommandArg) {
+    cmd.usage_name("filetemp lint")
+        .register_file_type(FileType::CMake, define_cmake_args)
+        .after_help(
+            "Rules can also be configured in the [lint] section of .filetemp.toml, and \
+             silenced per line with \"# filetemp-lint: disable=<rules>\" or \
+             \"# filetemp-lint: disable-next-line=<rules>\".",
+        );
+}
+
+fn define_cmake_args(view: &mut ArgFileTypeView) {
+    view.add_arg_def(
+        Arg::new("path")
+            .value_name("PATH")
+            .normalize(normalize::path)
+            .value_type(ValueType::Path { must_exist: true })
+            .default_val(".")
+            .help("File to check, or the directory containing it"),
+    )
+    .add_arg_def(
+        Arg::new("disable")
+            .value_name("RULES")
+            .multiple(true)
+            .help("Comma-separated list of rules to skip, can be repeated"),
+    )
+    .add_arg_def(
+        Arg::new("min-version")
+            .value_name("VER")
+            .help("Oldest acceptable cmake_minimum_required version, 3.16 if not specified"),
+    )
+    .add_arg_def(
+        Arg::new("format")
+            .value_name("FORMAT")
+            .normalize(normalize::lowercase)
+            .default_val("text")
+            .value_type(ValueType::Enum(&["text", "json", "sarif"]))
+            .help("Report format"),
+    )
+    .add_arg_def(
+        Arg::new("fix")
+            .flag(true)
+            .help("Show automatic fixes as a diff and write them once confirmed"),
+    )
+    .add_arg_def(
+        Arg::new("dry-run")
+            .flag(true)
+            .requires("fix")
+            .help("Only show the diff of --fix, don't write it"),
+    )
+    .add_arg_def(
+        Arg::new("force")
+            .flag(true)
+            .requires("fix")
+            .conflicts_with("dry-run")
+            .help("Write the fixes of --fix without asking"),
+    );
+}
+
+/// Run the rule set over a source and collect findings ordered by line.
+pub fn lint_source(source: &str, config: &LintConfig) -> Result<Vec<Finding>, String> {
+    let doc = cmake_parser::parse(source)?;
+    let suppressions = collect_suppressions(&doc);
+
+    let mut findings = Vec::new();
+    for rule in rules::CMAKE_RULES.iter() {
+        if !config.is_enabled(rule.name) {
+            continue;
+        }
+
+        for hit in (rule.check)(&doc, config) {
+            let suppressed = suppressions
+                .iter()
+                .any(|s| s.line == hit.line && s.rule.as_ref().is_none_or(|r| r == rule.name));
+            if suppressed {
+                continue;
+            }
+
+            findings.push(Finding {
+                rule: rule.name,
+                severity: config.severity_of(rule),
+                line: hit.line,
+                message: hit.message,
+                fix: hit.fix,
+            });
+        }
+    }
+
+    findings.sort_by_key(|f| f.line);
+    Ok(findings)
+}
+
+/// Lint the file given to `cmd` and print the report. The findings left after
+/// fixing are returned, so the caller can tell whether errors were found.
+pub fn run(cmd: &mut CommandArg) -> Result<Vec<Finding>, FiletempError> {
+    if let FileType::Unknown = cmd.get_file_type() {
+        return Err(ArgError::Invalid(String::from("Unknown file type")).into());
+    }
+
+    match cmd.assert_required_args_exist() {
+        Ok(()) => {}
+        Err(ArgProcessErr::MissingArgs { missing, required }) => {
+            return Err(ArgError::missing(cmd, &missing, required).into());
+        }
+        Err(e) => return Err(ArgError::Parse(e).into()),
+    }
+    cmd.verify_relations().map_err(ArgError::Parse)?;
+
+    let format = match cmd.get_choice("format") {
+        Some("json") => OutputFormat::Json,
+        Some("sarif") => OutputFormat::Sarif,
+        _ => OutputFormat::Text,
+    };
+
+    let file_path = resolve_lint_path(cmd.get_file_type(), cmd.get_path("path").unwrap());
+
+    // Project config is applied first so command line arguments take precedence.
+    let mut config = LintConfig::new();
+    let config_dir = file_path.parent().unwrap_or(Path::new("."));
+    if let Some((_, table)) = load_project_config(config_dir).map_err(ArgError::Config)?
+        && let Some(lint) = table.get("lint")
+    {
+        match lint.as_table() {
+            Some(t) => config.apply_table(t).map_err(ArgError::Config)?,
+            None => {
+                let message = String::from("[lint] in project config must be a table");
+                return Err(ArgError::Config(message).into());
+            }
+        }
+    }
+
+    if let Some(v) = cmd.get_arg("min-version") {
+        config.min_version = match v.parse() {
+            Ok(ver) => ver,
+            Err(_) => {
+                let message = format!("Invalid minimum version: {}", v);
+                return Err(ArgError::Invalid(message).into());
+            }
+        };
+    }
+    for d in cmd.get_args("disable") {
+        for rule in d.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+            config.set_enabled(verify_rule_name(rule).map_err(ArgError::Invalid)?, false);
+        }
+    }
+
+    let source =
+        std::fs::read_to_string(&file_path).map_err(|e| IoError::new("read", &file_path, e))?;
+
+    let mut findings = parse_and_lint(&file_path, &source, &config)?;
+    if cmd.get_flag("fix") {
+        let write = match (cmd.get_flag("dry-run"), cmd.get_flag("force")) {
+            (true, _) => FixWrite::Never,
+            (false, true) => FixWrite::Always,
+            (false, false) => FixWrite::Ask,
+        };
+        findings = fix_file(&file_path, &source, findings, &config, format, write)?;
+    }
+
+    let out = match format {
+        OutputFormat::Text => report::text_report(&file_path, &findings),
+        OutputFormat::Json => report::json_report(&file_path, &findings),
+        OutputFormat::Sarif => report::sarif_report(&file_path, &findings),
+    };
+    print!("{}", out);
+
+    Ok(findings)
+}
+
+/// `lint_source` for the file at `path`, a file that doesn't parse is refused as input.
+fn parse_and_lint(
+    path: &Path,
+    source: &str,
+    config: &LintConfig,
+) -> Result<Vec<Finding>, ArgError> {
+    lint_source(source, config)
+        .map_err(|e| ArgError::Invalid(format!("Cannot parse \"{}\": {}", path.display(), e)))
+}
+
+/// Whether `--fix` writes the fixed file.
+#[derive(Clone, Copy, PartialEq)]
+enum FixWrite {
+    /// `--dry-run`, only the diff is shown.
+    Never,
+    /// Ask if someone can answer, otherwise leave the file as it is.
+    Ask,
+    /// `--force`.
+    Always,
+}
+
+/// Apply the fixes of all findings, show them as a diff and write them as
+/// `write` says. Returns the findings of the file as it is left.
+fn fix_file(
+    path: &Path,
+    source: &str,
+    findings: Vec<Finding>,
+    config: &LintConfig,
+    format: OutputFormat,
+    write: FixWrite,
+) -> Result<Vec<Finding>, FiletempError> {
+    // Overlapping fixes are skipped, and a fix may depend on another one, e.g.
+    // CONFIGURE_DEPENDS on the raised minimum version, so lint again until no
+    // fix is left.
+    let mut fixed = source.to_string();
+    let mut remaining = Vec::new();
+    let mut edits: Vec<TextEdit> = findings.iter().flat_map(|f| f.fix.clone()).collect();
+    for _ in 0..MAX_FIX_PASSES {
+        if edits.is_empty() {
+            break;
+        }
+        let (next, _) = apply_edits(&fixed, &edits);
+        if next == fixed {
+            break;
+        }
+        fixed = next;
+        remaining = parse_and_lint(path, &fixed, config)?;
+        edits = remaining.iter().flat_map(|f| f.fix.clone()).collect();
+    }
+    if fixed == source {
+        return Ok(findings);
+    }
+
+    let name = path.display().to_string();
+    let diff = unified_diff(source, &fixed, &name, &name);
+
+    // Keep stdout parseable for machine-readable formats.
+    if let OutputFormat::Text = format {
+        print!("{}", diff);
+    } else {
+        eprint!("{}", diff);
+    }
+
+    let confirmed = match write {
+        FixWrite::Never => false,
+        FixWrite::Always => true,
+        FixWrite::Ask if prompt::can_prompt() => {
+            prompt::confirm(&trf("Write the fixes to \"{}\"?", &[&name]), true)
+        }
+        FixWrite::Ask => {
+            logging::warn(trf(
+                "Fixes were not written to \"{}\", give --force to write them without asking",
+                &[&name],
+            ));
+            false
+        }
+    };
+    if !confirmed {
+        return Ok(findings);
+    }
+
+    write_fixed(path, &fixed).map_err(|e| IoError::new("write", path, e))?;
+    Ok(remaining)
+}
+
+/// Replace the file at `path` through a temporary file next to it, so an
+/// interrupted write leaves the original as it was. A symlink is written
+/// through and the permissions of the file are kept.
+fn write_fixed(path: &Path, content: &str) -> std::io::Result<()> {
+    let target = fs::canonicalize(path)?;
+    let mut tmp_path = target.clone().into_os_string();
+    tmp_path.push(format!(".tmp-{}", std::process::id()));
+    let tmp_path = PathBuf::from(tmp_path);
+
+    let written = File::create(&tmp_path).and_then(|mut f| {
+        f.write_all(content.as_bytes())?;
+        f.set_permissions(fs::metadata(&target)?.permissions())?;
+        f.sync_all()
+    });
+    if let Err(e) = written.and_then(|_| fs::rename(&tmp_path, &target)) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Accept either the file itself or the directory containing it.
+fn resolve_lint_path(ty: FileType, p: &Path) -> PathBuf {
+    if p.is_dir() {
+        p.join(get_result_filename(ty))
+    } else {
+        p.to_path_buf()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "cmake_minimum_required(VERSION 3.5)\n\
+        project(demo)\n\
+        file(GLOB SRC src/*.cpp)\n";
+
+    const FIXED: &str = "cmake_minimum_required(VERSION 3.16)\n\
+        project(demo)\n\
+        file(GLOB SRC CONFIGURE_DEPENDS src/*.cpp)\n";
+
+    /// A file holding `SOURCE` in a directory of its own, removed by the caller.
+    fn source_file(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("filetemp-lint-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("CMakeLists.txt");
+        fs::write(&path, SOURCE).unwrap();
+        path
+    }
+
+    fn rules(findings: &[Finding]) -> Vec<&str> {
+        findings.iter().map(|f| f.rule).collect()
+    }
+
+    #[test]
+    fn fix_depending_on_another_needs_a_second_pass() {
+        let config = LintConfig::new();
+        let findings = lint_source(SOURCE, &config).unwrap();
+        let glob = findings
+            .iter()
+            .find(|f| f.rule == "glob-without-configure-depends")
+            .unwrap();
+        assert!(glob.fix.is_empty());
+
+        let path = source_file("passes");
+        let left = fix_file(
+            &path,
+            SOURCE,
+            findings,
+            &config,
+            OutputFormat::Json,
+            FixWrite::Always,
+        )
+        .unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), FIXED);
+        assert!(left.is_empty(), "{:?}", rules(&left));
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn dry_run_leaves_the_file_and_its_findings() {
+        let config = LintConfig::new();
+        let findings = lint_source(SOURCE, &config).unwrap();
+        let before = rules(&findings).join(",");
+
+        let path = source_file("dry-run");
+        let left = fix_file(
+            &path,
+            SOURCE,
+            findings,
+            &config,
+            OutputFormat::Json,
+            FixWrite::Never,
+        )
+        .unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), SOURCE);
+        assert_eq!(rules(&left).join(","), before);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn clean_file_is_not_written() {
+        let config = LintConfig::new();
+        let path = source_file("clean");
+        fs::write(&path, FIXED).unwrap();
+        let findings = lint_source(FIXED, &config).unwrap();
+        assert!(findings.is_empty(), "{:?}", rules(&findings));
+
+        let left = fix_file(
+            &path,
+            FIXED,
+            findings,
+            &config,
+            OutputFormat::Json,
+            FixWrite::Always,
+        )
+        .unwrap();
+        assert!(left.is_empty());
+        assert_eq!(fs::read_to_string(&path).unwrap(), FIXED);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,118 @@
+use std::{fmt::Write, path::Path};
+
+use serde_json::json;
+
+use super::{Finding, Severity, rules::CMAKE_RULES};
+
+pub(super) fn text_report(path: &Path, findings: &[Finding]) -> String {
+    let mut out = String::new();
+    for f in findings.iter() {
+        writeln!(
+            &mut out,
+            "{}:{}: {}[{}]: {}{}",
+            path.display(),
+            f.line,
+            f.severity.to_str(),
+            f.rule,
+            f.message,
+            if f.fix.is_empty() { "" } else { " (fixable)" }
+        )
+        .unwrap();
+    }
+
+    let count = |s: Severity| findings.iter().filter(|f| f.severity == s).count();
+    writeln!(
+        &mut out,
+        "{} finding(s), {} error(s), {} warning(s)",
+        findings.len(),
+        count(Severity::Error),
+        count(Severity::Warning)
+    )
+    .unwrap();
+
+    out
+}
+
+pub(super) fn json_report(path: &Path, findings: &[Finding]) -> String {
+    let items: Vec<_> = findings
+        .iter()
+        .map(|f| {
+            json!({
+                "rule": f.rule,
+                "severity": f.severity.to_str(),
+                "line": f.line,
+                "message": f.message,
+                "fixable": !f.fix.is_empty(),
+            })
+        })
+        .collect();
+
+    let doc = json!({
+        "file": path.display().to_string(),
+        "findings": items,
+    });
+
+    format!("{}\n", serde_json::to_string_pretty(&doc).unwrap())
+}
+
+/// SARIF 2.1.0 log with a single run, as consumed by code scanning services.
+pub(super) fn sarif_report(path: &Path, findings: &[Finding]) -> String {
+    let uri = path
+        .to_string_lossy()
+        .replace('\\', "/")
+        .trim_start_matches("./")
+        .to_string();
+
+    let rules: Vec<_> = CMAKE_RULES
+        .iter()
+        .map(|r| {
+            json!({
+                "id": r.name,
+                "shortDescription": { "text": r.description },
+                "defaultConfiguration": { "level": sarif_level(r.severity) },
+            })
+        })
+        .collect();
+
+    let results: Vec<_> = findings
+        .iter()
+        .map(|f| {
+            json!({
+                "ruleId": f.rule,
+                "level": sarif_level(f.severity),
+                "message": { "text": f.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": uri },
+                        "region": { "startLine": f.line },
+                    }
+                }],
+            })
+        })
+        .collect();
+
+    let doc = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "filetemp",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }],
+    });
+
+    format!("{}\n", serde_json::to_string_pretty(&doc).unwrap())
+}
+
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Info => "note",
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    }
+}
@@ -0,0 +1,219 @@
+use crate::{
+    file_types::{
+        cmake_parser::CMakeDocument,
+        cmake_version::{CMakeVersion, CMakeVersionRange, feature},
+    },
+    text_edit::TextEdit,
+};
+
+use super::{LintConfig, LintRule, RuleHit, Severity};
+
+/// Oldest version CMake 4.x still accepts in `cmake_minimum_required`.
+const REMOVED_COMPAT_VERSION: CMakeVersion = CMakeVersion::new(3, 5, 0);
+
+/// Directory-scoped commands and the per-target command replacing each of them.
+const DIRECTORY_COMMANDS: &[(&str, &str)] = &[
+    ("include_directories", "target_include_directories"),
+    ("add_definitions", "target_compile_definitions"),
+    ("add_compile_definitions", "target_compile_definitions"),
+    ("add_compile_options", "target_compile_options"),
+    ("link_libraries", "target_link_libraries"),
+    ("link_directories", "target_link_directories"),
+    ("add_link_options", "target_link_options"),
+];
+
+pub(super) const CMAKE_RULES: &[LintRule] = &[
+    LintRule {
+        name: "missing-minimum-version",
+        description: "cmake_minimum_required must be the first command of the project",
+        severity: Severity::Error,
+        check: check_missing_minimum_version,
+    },
+    LintRule {
+        name: "ancient-minimum-version",
+        description: "cmake_minimum_required should not be older than the configured minimum",
+        severity: Severity::Warning,
+        check: check_ancient_minimum_version,
+    },
+    LintRule {
+        name: "glob-without-configure-depends",
+        description: "file(GLOB) should use CONFIGURE_DEPENDS so new files trigger a re-configure",
+        severity: Severity::Warning,
+        check: check_glob_configure_depends,
+    },
+    LintRule {
+        name: "directory-scoped-command",
+        description: "Prefer per-target commands over directory-scoped ones",
+        severity: Severity::Warning,
+        check: check_directory_scoped_commands,
+    },
+    LintRule {
+        name: "standard-not-required",
+        description: "CMAKE_<LANG>_STANDARD should come with CMAKE_<LANG>_STANDARD_REQUIRED ON",
+        severity: Severity::Warning,
+        check: check_standard_required,
+    },
+];
+
+fn check_missing_minimum_version(doc: &CMakeDocument, _: &LintConfig) -> Vec<RuleHit> {
+    match doc.commands.first() {
+        Some(c) if c.is("cmake_minimum_required") => Vec::new(),
+        Some(c) => match doc.find_command("cmake_minimum_required") {
+            Some(m) => vec![RuleHit::new(
+                m.line,
+                format!("cmake_minimum_required should come before \"{}\"", c.name),
+            )],
+            None => vec![RuleHit::new(1, "cmake_minimum_required is missing")],
+        },
+        None => vec![RuleHit::new(1, "cmake_minimum_required is missing")],
+    }
+}
+
+fn check_ancient_minimum_version(doc: &CMakeDocument, config: &LintConfig) -> Vec<RuleHit> {
+    let mut hits = Vec::new();
+    for cmd in doc.commands_named("cmake_minimum_required") {
+        let ver_arg = match cmd.arg_after("VERSION") {
+            Some(a) => a,
+            None => {
+                hits.push(RuleHit::new(
+                    cmd.line,
+                    "cmake_minimum_required has no VERSION",
+                ));
+                continue;
+            }
+        };
+
+        // Only the lower bound of a "min...max" range matters here.
+        let range = match ver_arg.value.parse::<CMakeVersionRange>() {
+            Ok(range) => range,
+            Err(_) => {
+                hits.push(RuleHit::new(
+                    cmd.line,
+                    format!("Invalid minimum version \"{}\"", ver_arg.value),
+                ));
+                continue;
+            }
+        };
+
+        let ver = range.min;
+        if ver >= config.min_version {
+            continue;
+        }
+
+        let message = if ver < REMOVED_COMPAT_VERSION {
+            format!(
+                "Minimum version {} is rejected by CMake 4.0 and newer, use at least {}",
+                ver, config.min_version
+            )
+        } else {
+            format!(
+                "Minimum version {} is older than {}, modern policies are disabled",
+                ver, config.min_version
+            )
+        };
+
+        // Bump the lower bound, keeping the policy maximum if it is still above it.
+        let bumped = match range.max {
+            Some(max) if max > config.min_version => format!("{}...{}", config.min_version, max),
+            _ => config.min_version.to_string(),
+        };
+        hits.push(
+            RuleHit::new(cmd.line, message)
+                .with_fix(vec![TextEdit::replace(ver_arg.span.clone(), bumped)]),
+        );
+    }
+
+    hits
+}
+
+fn check_glob_configure_depends(doc: &CMakeDocument, _: &LintConfig) -> Vec<RuleHit> {
+    // The flag is only fixable if the file requires a version knowing it. Once
+    // the minimum version fix is applied, `--fix` lints again and finds it is.
+    let fixable = doc
+        .minimum_version()
+        .map(|v| feature("configure-depends").supported_by(v))
+        .unwrap_or(false);
+
+    doc.commands_named("file")
+        .filter(|c| {
+            c.args
+                .first()
+                .map(|a| a.value == "GLOB" || a.value == "GLOB_RECURSE")
+                .unwrap_or(false)
+                && !c.has_arg("CONFIGURE_DEPENDS")
+        })
+        .map(|c| {
+            let hit = RuleHit::new(
+                c.line,
+                format!("file({}) without CONFIGURE_DEPENDS", c.args[0].value),
+            );
+            // The flag goes right after the result variable.
+            match c.args.get(1) {
+                Some(var) if fixable => {
+                    hit.with_fix(vec![TextEdit::insert(var.span.end, " CONFIGURE_DEPENDS")])
+                }
+                _ => hit,
+            }
+        })
+        .collect()
+}
+
+fn check_directory_scoped_commands(doc: &CMakeDocument, _: &LintConfig) -> Vec<RuleHit> {
+    let mut hits = Vec::new();
+    for cmd in doc.commands.iter() {
+        if let Some((name, replacement)) = DIRECTORY_COMMANDS.iter().find(|(n, _)| cmd.is(n)) {
+            hits.push(RuleHit::new(
+                cmd.line,
+                format!("{}() affects every target, use {}()", name, replacement),
+            ));
+        }
+    }
+
+    hits
+}
+
+fn check_standard_required(doc: &CMakeDocument, _: &LintConfig) -> Vec<RuleHit> {
+    let mut hits = Vec::new();
+    for lang in ["C", "CXX"] {
+        let std_var = format!("CMAKE_{}_STANDARD", lang);
+        let req_var = format!("CMAKE_{}_STANDARD_REQUIRED", lang);
+
+        let std_set = doc
+            .commands_named("set")
+            .find(|c| c.args.first().map(|a| a.value == std_var).unwrap_or(false));
+        let req_set = doc
+            .commands_named("set")
+            .find(|c| c.args.first().map(|a| a.value == req_var).unwrap_or(false));
+        let required = req_set
+            .and_then(|c| c.args.get(1))
+            .map(|a| is_cmake_true(&a.value))
+            .unwrap_or(false);
+
+        if let Some(c) = std_set
+            && !required
+        {
+            let hit = RuleHit::new(c.line, format!("{} is set without {} ON", std_var, req_var));
+
+            // An explicit OFF is left alone, it may well be intended.
+            hits.push(if req_set.is_none() {
+                let line = format!(
+                    "{}{}set({} ON)",
+                    doc.line_ending(),
+                    doc.indent_of(c),
+                    req_var
+                );
+                hit.with_fix(vec![TextEdit::insert(c.span.end, line)])
+            } else {
+                hit
+            });
+        }
+    }
+
+    hits
+}
+
+fn is_cmake_true(v: &str) -> bool {
+    ["1", "ON", "YES", "TRUE", "Y"]
+        .iter()
+        .any(|t| v.eq_ignore_ascii_case(t))
+}
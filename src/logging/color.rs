@@ -0,0 +1,87 @@
+//! ANSI colors for terminal output. Off when the stream is not a terminal or
+//! `NO_COLOR` is set, unless `--color always` is given.
+
+use std::{
+    io::IsTerminal,
+    str::FromStr,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+pub const ERROR: &str = "1;31";
+pub const WARNING: &str = "33";
+pub const SUCCESS: &str = "32";
+pub const COMMENT: &str = "2";
+pub const COMMAND: &str = "1;36";
+pub const KEYWORD: &str = "35";
+pub const STRING: &str = "32";
+pub const VARIABLE: &str = "33";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!(
+                "Invalid value \"{}\" for --color, possible values: auto, always, never",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+static CHOICE: AtomicU8 = AtomicU8::new(ColorChoice::Auto as u8);
+
+pub fn set_choice(choice: ColorChoice) {
+    CHOICE.store(choice as u8, Ordering::Relaxed);
+}
+
+pub fn enabled(stream: Stream) -> bool {
+    match CHOICE.load(Ordering::Relaxed) {
+        c if c == ColorChoice::Always as u8 => true,
+        c if c == ColorChoice::Never as u8 => false,
+        _ => {
+            let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+            let dumb = std::env::var_os("TERM").is_some_and(|t| t == "dumb");
+            let terminal = match stream {
+                Stream::Stdout => std::io::stdout().is_terminal(),
+                Stream::Stderr => std::io::stderr().is_terminal(),
+            };
+            terminal && !no_color && !dumb
+        }
+    }
+}
+
+/// `text` in `style` if `stream` is colored.
+pub fn paint(stream: Stream, style: &str, text: &str) -> String {
+    match enabled(stream) {
+        true => ansi(style, text),
+        false => text.to_string(),
+    }
+}
+
+/// `text` in `style`, each line on its own so a pager does not carry the color over.
+pub fn ansi(style: &str, text: &str) -> String {
+    text.split('\n')
+        .map(|line| match line.is_empty() {
+            true => String::new(),
+            false => format!("\x1b[{}m{}\x1b[0m", style, line),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,149 @@
+use std::{
+    fmt::Display,
+    fs::{File, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::{
+        Mutex, OnceLock,
+        atomic::{AtomicU8, Ordering},
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use color::Stream;
+
+use crate::program_args::providers::format_date;
+
+pub mod color;
+
+/// How much a run reports on stderr, errors are always reported.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Only errors.
+    Quiet,
+    /// Errors and warnings.
+    Normal,
+    /// Also each step: caches loaded, defaults applied, files written.
+    Verbose,
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Normal as u8);
+
+/// The `--log-file`, it gets every message of every level with a timestamp.
+static LOG_FILE: OnceLock<Mutex<File>> = OnceLock::new();
+
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Level from the `--quiet` and `--verbose` flags, quiet wins if both are given.
+pub fn level_from_flags(quiet: bool, verbose: bool) -> Level {
+    if quiet {
+        Level::Quiet
+    } else if verbose {
+        Level::Verbose
+    } else {
+        Level::Normal
+    }
+}
+
+/// Run `f` without verbose output, for steps that repeat work already reported.
+pub fn without_verbose<T>(f: impl FnOnce() -> T) -> T {
+    let previous = LEVEL.load(Ordering::Relaxed);
+    LEVEL.store(previous.min(Level::Normal as u8), Ordering::Relaxed);
+    let result = f();
+    LEVEL.store(previous, Ordering::Relaxed);
+    result
+}
+
+pub fn enabled(level: Level) -> bool {
+    LEVEL.load(Ordering::Relaxed) >= level as u8
+}
+
+/// Append every later message to `path`, whatever the level on stderr.
+pub fn open_log_file(path: &Path) -> Result<(), String> {
+    let file = match OpenOptions::new().create(true).append(true).open(path) {
+        Ok(f) => f,
+        Err(e) => {
+            return Err(format!(
+                "Failed to open log file \"{}\": {}",
+                path.display(),
+                e
+            ));
+        }
+    };
+    let _ = LOG_FILE.set(Mutex::new(file));
+    Ok(())
+}
+
+/// Write `message` to the log file, each line with a timestamp and `level`.
+fn record(level: &str, message: &str) {
+    let file = match LOG_FILE.get() {
+        Some(f) => f,
+        None => return,
+    };
+    let stamp = timestamp();
+    let mut text = String::new();
+    for line in message.lines() {
+        text.push_str(&format!("{} {:<7} {}\n", stamp, level, line));
+    }
+    if let Ok(mut f) = file.lock() {
+        let _ = f.write_all(text.as_bytes());
+    }
+}
+
+/// `YYYY-MM-DDTHH:MM:SS.mmmZ` of now, in UTC.
+fn timestamp() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = now.as_secs() as i64;
+    let of_day = secs.rem_euclid(86400);
+    format!(
+        "{}T{:02}:{:02}:{:02}.{:03}Z",
+        format_date(secs),
+        of_day / 3600,
+        of_day / 60 % 60,
+        of_day % 60,
+        now.subsec_millis()
+    )
+}
+
+pub fn error(message: impl Display) {
+    let message = message.to_string();
+    record("ERROR", &message);
+    eprintln!("{}", color::paint(Stream::Stderr, color::ERROR, &message));
+}
+
+pub fn warn(message: impl Display) {
+    let message = message.to_string();
+    record("WARN", &message);
+    if enabled(Level::Normal) {
+        eprintln!("{}", color::paint(Stream::Stderr, color::WARNING, &message));
+    }
+}
+
+/// Report on stdout what a command did, like the caches it saved.
+pub fn success(message: impl Display) {
+    let message = message.to_string();
+    record("INFO", &message);
+    println!("{}", color::paint(Stream::Stdout, color::SUCCESS, &message));
+}
+
+pub fn verbose(message: impl Display) {
+    if LOG_FILE.get().is_none() && !enabled(Level::Verbose) {
+        return;
+    }
+    let message = message.to_string();
+    record("VERBOSE", &message);
+    if enabled(Level::Verbose) {
+        eprintln!("{}", message);
+    }
+}
+
+/// Details only the log file gets, like each resolved argument and cache file access.
+pub fn trace(message: impl Display) {
+    if LOG_FILE.get().is_some() {
+        record("TRACE", &message.to_string());
+    }
+}
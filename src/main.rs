@@ -1,271 +1,142 @@
-use cache_dir::get_data_dir;
-use std::{
-    fs::{self, OpenOptions},
-    io,
-    path::Path,
-};
-
-use crate::{
-    config_file::{ArgCache, ArgCacheCollection, ConfigReader, ConfigWriter},
-    file_types::{
-        FileType, generate_example, get_result_filename, process_args, verify_existed_args,
+use std::{path::PathBuf, process::ExitCode};
+
+use filetemp::{
+    cli,
+    config_file::{CACHE_FILE_NAME, filetemp_data_dir},
+    crash,
+    exit_status::{self, Failure},
+    file_types::{self, custom},
+    i18n,
+    logging::{
+        self,
+        color::{self, ColorChoice},
     },
-    program_args::{Arg, ArgProcessErr, CommandArg},
+    program_args::{self, collect_raw_args},
+    scratch,
 };
 
-mod config_file;
-mod file_types;
-mod program_args;
-
-#[derive(PartialEq, Eq, Clone, Copy)]
-enum OutputMode {
-    NoOutput,
-    OutputFile,
-    OutputShow,
-    OutputShowAndFile,
-    SetConfig,
-}
-
-impl OutputMode {
-    fn from_cmd(cmd: &CommandArg) -> Self {
-        let mut ret = Self::NoOutput;
-        if cmd.get_arg("save-as").is_some() || cmd.get_arg("use").is_some() {
-            ret = Self::SetConfig;
-        }
-        if cmd.get_arg("path").is_some() {
-            ret = Self::OutputFile;
-        }
-        if cmd.get_flag("show") {
-            ret = if ret == Self::OutputFile {
-                Self::OutputShowAndFile
-            } else {
-                Self::OutputShow
-            };
-        }
-
-        ret
-    }
-
-    fn show(self) -> bool {
-        self == Self::OutputShow || self == Self::OutputShowAndFile
-    }
-
-    fn file(self) -> bool {
-        self == Self::OutputFile || self == Self::OutputShowAndFile
-    }
-
-    fn has_output(self) -> bool {
-        self.show() || self.file()
-    }
-}
-
-fn main() {
-    // Define usable arguments.
-    let mut cmd = CommandArg::new();
-    define_args(&mut cmd);
-
-    // Process actual arguments, check their validity.
-    if let Err(e) = cmd.process_program_args() {
-        process_arg_parse_err(e);
-        return;
-    }
-
-    let file_type = cmd.get_file_type();
-
-    let output_mode = OutputMode::from_cmd(&cmd);
+fn main() -> ExitCode {
+    let mut raw_args = collect_raw_args();
 
-    // Do nothing if no output is required or no possibility for cache IO.
-    if output_mode == OutputMode::NoOutput {
-        return;
+    // Accepted by every command, so they are taken out before parsing.
+    scratch::keep(take_flag_arg(&mut raw_args, "--keep-temp"));
+    program_args::set_ignore_long_case(take_flag_arg(&mut raw_args, "--ignore-case"));
+    let debug_bundle = take_path_arg(&mut raw_args, "--debug-bundle");
+    if let Err(e) =
+        take_lang_arg(&mut raw_args).and_then(|lang| i18n::set_language(lang.as_deref()))
+    {
+        logging::error(e);
+        exit_status::exit(Failure::Args);
     }
-
-    let arg_cache = match read_arg_cache(&mut cmd) {
-        Ok(collection) => collection,
+    match take_color_arg(&mut raw_args) {
+        Ok(choice) => color::set_choice(choice),
         Err(e) => {
-            eprintln!("{}", e);
-            return;
+            logging::error(e);
+            exit_status::exit(Failure::Args);
         }
-    };
-
-    if output_mode.file()
-        && let Err(e) = cmd.assert_required_args_exist()
-    {
-        process_arg_parse_err(e);
-    };
-
-    if let Err(e) = verify_existed_args(&cmd) {
-        eprintln!("{}", e);
-        return;
-    }
-
-    let mut result_str = String::new();
-    if output_mode.has_output() {
-        let process_result: Result<String, String> = process_args(&cmd);
-
-        result_str = match process_result {
-            Ok(r) => r,
-            Err(e) => {
-                eprintln!("{}", e);
-                return;
-            }
-        };
-    }
-
-    if output_mode.show() {
-        print!("{}", result_str);
     }
-
-    if let Some(p) = cmd.get_arg("path") {
-        if let Err(_) = write_to_file(file_type, p, &result_str) {
-            eprintln!("Failed to write to file.");
-        }
-
-        if cmd.get_flag("gen-example") {
-            if let Err(e) = generate_example(&cmd, Path::new(p)) {
-                eprintln!("{}", e);
-            }
+    if let Some(path) = take_path_arg(&mut raw_args, "--log-file") {
+        if let Err(e) = logging::open_log_file(&path) {
+            logging::error(e);
+            exit_status::exit(Failure::Io);
         }
+        logging::trace(format_args!(
+            "filetemp {} started with: {}",
+            env!("CARGO_PKG_VERSION"),
+            raw_args.join(" ")
+        ));
     }
 
-    if let Err(e) = write_arg_cache(&mut cmd, arg_cache) {
-        eprintln!("{}", e);
-    }
-}
-
-fn write_to_file(ty: FileType, path: &str, content: &str) -> io::Result<()> {
-    let file_name = Path::new(path).join(get_result_filename(ty));
-    fs::write(&file_name, content)?;
-    Ok(())
+    // File types declared in the data directory, their names must not hide a command.
+    let commands: Vec<&str> = cli::SUBCOMMANDS
+        .iter()
+        .filter_map(|s| s.words().next())
+        .collect();
+    custom::load_types(
+        filetemp_data_dir()
+            .ok()
+            .map(|(dir, _)| dir.join(custom::TYPE_DIR_NAME))
+            .as_deref(),
+        &commands,
+    );
+    file_types::load_overrides(cli::user_template_dir().as_deref());
+
+    crash::install_panic_hook(
+        raw_args.clone(),
+        debug_bundle,
+        filetemp_data_dir()
+            .ok()
+            .map(|(dir, _)| dir.join(CACHE_FILE_NAME)),
+    );
+    let args: Vec<&str> = raw_args.iter().map(String::as_str).collect();
+    let result = std::panic::catch_unwind(|| cli::run(&args));
+    scratch::cleanup();
+    if result.is_err() {
+        std::process::exit(101);
+    }
+    exit_status::status()
 }
 
-fn define_args(cmd: &mut CommandArg) {
-    cmd.define_file_type(FileType::CMake)
-        .add_arg_def(Arg::new("version").required(true))
-        .add_arg_def(Arg::new("proj").required(true))
-        .add_arg_def(Arg::new("main-lang").default_val("cxx"))
-        .add_arg_def(Arg::new("cstd"))
-        .add_arg_def(Arg::new("cxxstd"))
-        .add_arg_def(Arg::new("target-type"))
-        .add_arg_def(Arg::new("target-name"))
-        .add_general_arg_def(Arg::new("path"))
-        .add_general_arg_def(Arg::new("show").flag(true))
-        .add_general_arg_def(Arg::new("save-as"))
-        .add_general_arg_def(Arg::new("use"))
-        .add_general_arg_def(Arg::new("gen-example").flag(true));
+/// Remove every `<name>` flag from the arguments, true if there was one.
+fn take_flag_arg(raw_args: &mut Vec<String>, name: &str) -> bool {
+    let arg_count = raw_args.len();
+    raw_args.retain(|a| a != name);
+    raw_args.len() != arg_count
 }
 
-fn read_arg_cache(cmd: &mut CommandArg) -> Result<ArgCacheCollection<'static>, String> {
-    let cache_name = if let Some(n) = cmd.get_arg("use") {
-        n.to_string()
-    } else {
-        return Ok(ArgCacheCollection::new_empty());
-    };
+/// Remove `<name> <FILE>` or `<name>=<FILE>` from the arguments, e.g. `--debug-bundle`.
+fn take_path_arg(raw_args: &mut Vec<String>, name: &str) -> Option<PathBuf> {
+    let idx = raw_args.iter().position(|a| {
+        *a == name
+            || a.strip_prefix(name)
+                .is_some_and(|rest| rest.starts_with('='))
+    })?;
 
-    let config_file_dir = if let Ok(path) = get_data_dir() {
-        path
-    } else {
-        Path::new(".").to_path_buf()
+    let arg = raw_args.remove(idx);
+    match arg.split_once('=') {
+        Some((_, path)) => Some(PathBuf::from(path)),
+        None if idx < raw_args.len() => Some(PathBuf::from(raw_args.remove(idx))),
+        None => None,
     }
-    .join(".filetemp");
-
-    if let Err(_) = std::fs::create_dir_all(&config_file_dir) {
-        return Err(format!(
-            "Failed to create cache dir: \"{:?}\"",
-            &config_file_dir
-        ));
-    }
-
-    let config_file_path = config_file_dir.join("cache.txt");
+}
 
-    let config_file: fs::File = if let Ok(f) = OpenOptions::new().read(true).open(config_file_path)
+/// Remove `--color <WHEN>` or `--color=<WHEN>` from the arguments, auto without it.
+fn take_color_arg(raw_args: &mut Vec<String>) -> Result<ColorChoice, String> {
+    let idx = match raw_args
+        .iter()
+        .position(|a| *a == "--color" || a.starts_with("--color="))
     {
-        f
-    } else {
-        return Err(String::from("Failed to open config cache file."));
+        Some(i) => i,
+        None => return Ok(ColorChoice::Auto),
     };
 
-    let mut reader: ConfigReader = ConfigReader::new(config_file);
-    let valid_args = cmd.query_valid_args().map(|arg_group| arg_group.name);
-    let caches = reader.read_from_config(valid_args)?;
-
-    let used_args = if let Some(cache_item) = caches.iter().find(|c| c.cache_name == &cache_name) {
-        cache_item.args.iter()
-    } else {
-        return Err(format!("Used invalid cache name \"{}\"", cache_name));
-    };
-
-    for arg in used_args {
-        cmd.insert_arg_if_absent(arg.arg, arg.content);
+    let arg = raw_args.remove(idx);
+    match arg.split_once('=') {
+        Some((_, when)) => when.parse(),
+        None if idx < raw_args.len() => raw_args.remove(idx).parse(),
+        None => Err(String::from(
+            "Missing value for --color, possible values: auto, always, never",
+        )),
     }
-
-    Ok(ArgCacheCollection::new(caches))
 }
 
-fn write_arg_cache<'a>(
-    cmd: &'a mut CommandArg,
-    mut cache: ArgCacheCollection<'a>,
-) -> Result<(), String> {
-    let cache_name: &'static str = if let Some(n) = cmd.get_arg("save-as") {
-        Box::leak(n.to_string().into_boxed_str())
-    } else {
-        return Ok(());
-    };
-
-    let config_file_dir = if let Ok(path) = get_data_dir() {
-        path
-    } else {
-        Path::new(".").to_path_buf()
-    }
-    .join(".filetemp");
-
-    if let Err(_) = std::fs::create_dir_all(&config_file_dir) {
-        return Err(format!(
-            "Failed to create cache dir: \"{:?}\"",
-            &config_file_dir
-        ));
-    }
-
-    let config_file_path = config_file_dir.join("cache.txt");
-
-    let config_file: fs::File = if let Ok(f) = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(&config_file_path)
+/// Remove `--lang <LANG>` or `--lang=<LANG>` from the arguments, None without it.
+fn take_lang_arg(raw_args: &mut Vec<String>) -> Result<Option<String>, String> {
+    let idx = match raw_args
+        .iter()
+        .position(|a| *a == "--lang" || a.starts_with("--lang="))
     {
-        f
-    } else {
-        return Err(String::from("Failed to open config cache file."));
+        Some(i) => i,
+        None => return Ok(None),
     };
 
-    let mut new_cache = ArgCache {
-        cache_name: cache_name,
-        file_type: cmd.get_file_type(),
-        args: Vec::new(),
-    };
-    for arg in cmd.extract_args() {
-        new_cache.args.push(arg);
-    }
-
-    if let Some(pos) = cache.iter().position(|c| c.cache_name == cache_name) {
-        cache[pos] = new_cache;
-    } else {
-        cache.push(new_cache);
-    }
-
-    let mut writer = ConfigWriter::new(config_file);
-    if let Err(_) = writer.write_to_config(cache) {
-        Err(String::from("Failed to write into cache file."))
-    } else {
-        Ok(())
+    let arg = raw_args.remove(idx);
+    match arg.split_once('=') {
+        Some((_, lang)) => Ok(Some(lang.to_string())),
+        None if idx < raw_args.len() => Ok(Some(raw_args.remove(idx))),
+        None => Err(format!(
+            "Missing value for --lang, possible values: {}",
+            i18n::LANGUAGES.join(", ")
+        )),
     }
 }
-
-fn process_arg_parse_err(e: ArgProcessErr) {
-    match e {
-        ArgProcessErr::InvalidArg(inv) => eprintln!("Invalid argument: \"{}\"", inv),
-        ArgProcessErr::InvalidFileType(invf) => eprintln!("Invalid file type: \"{}\"", invf),
-        ArgProcessErr::MissingArg(ma) => eprintln!("Missing argument: \"{}\"", ma),
-        _ => {}
-    };
-}
@@ -0,0 +1,91 @@
+use std::fmt::Write;
+
+use crate::{
+    file_types::{
+        FileType,
+        cmake_version::{CMakeFeature, CMakeVersionRange, FEATURES},
+    },
+    program_args::{Arg, ArgFileTypeView, CommandArg},
+};
+
+pub fn define_args(cmd: &mut CommandArg) {
+    cmd.usage_name("filetemp matrix")
+        .register_file_type(FileType::CMake, define_cmake_args);
+}
+
+fn define_cmake_args(view: &mut ArgFileTypeView) {
+    view.add_arg_def(
+        Arg::new("version")
+            .value_name("VER")
+            .help("Mark the features available with this minimum version"),
+    );
+}
+
+/// Print the features of the generator with the CMake version introducing each of them.
+/// This reads the same table the generator gates its output on.
+pub fn run(cmd: &mut CommandArg) -> Result<(), String> {
+    if let FileType::Unknown = cmd.get_file_type() {
+        return Err(String::from("Unknown file type"));
+    }
+
+    let version = match cmd.get_arg("version") {
+        Some(v) => match v.parse::<CMakeVersionRange>() {
+            Ok(range) => Some(range.min),
+            Err(_) => return Err(format!("Invalid CMake version: {}", v)),
+        },
+        None => None,
+    };
+
+    let mut features: Vec<&CMakeFeature> = FEATURES.iter().collect();
+    features.sort_by_key(|f| f.since);
+
+    let name_width = features.iter().map(|f| f.name.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for f in features.iter() {
+        let mark = match version {
+            Some(v) if f.supported_by(v) => "[x] ",
+            Some(_) => "[ ] ",
+            None => "",
+        };
+        writeln!(
+            &mut out,
+            "{}{:<width$}  {:<6}  {}",
+            mark,
+            f.name,
+            f.since.to_string(),
+            f.description,
+            width = name_width
+        )
+        .unwrap();
+    }
+
+    if let Some(v) = version {
+        let unlocked = features.iter().filter(|f| f.supported_by(v)).count();
+        writeln!(
+            &mut out,
+            "\nCMake {} unlocks {} of {} features.",
+            v,
+            unlocked,
+            features.len()
+        )
+        .unwrap();
+
+        if let Some(next) = features.iter().find(|f| !f.supported_by(v)) {
+            writeln!(
+                &mut out,
+                "Next: {} unlocks {}.",
+                next.since,
+                features
+                    .iter()
+                    .filter(|f| f.since == next.since)
+                    .map(|f| f.name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+            .unwrap();
+        }
+    }
+
+    print!("{}", out);
+    Ok(())
+}
@@ -0,0 +1,214 @@
+//! The `.filetemp-manifest` a run writes into `--path`, listing the files and
+//! directories it created, so `filetemp clean` can remove exactly those.
+//!
+//! Each line is `file <crc32> <path>` or `dir <path>`, paths are relative to
+//! the directory of the manifest and `dir .` is that directory itself.
+
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::logging;
+
+pub const MANIFEST_FILE_NAME: &str = ".filetemp-manifest";
+
+const HEADER: &str = "# Created by filetemp, \"filetemp clean --path <DIR>\" removes them\n";
+
+#[derive(Default)]
+pub struct Manifest {
+    /// Each file with the checksum of the content filetemp wrote.
+    files: Vec<(PathBuf, u32)>,
+    dirs: Vec<PathBuf>,
+}
+
+impl Manifest {
+    /// The manifest in `root`, empty if there is none.
+    pub fn load(root: &Path) -> Result<Self, String> {
+        let path = root.join(MANIFEST_FILE_NAME);
+        let text = match fs::read_to_string(&path) {
+            Ok(t) => t,
+            Err(_) if !path.exists() => return Ok(Self::default()),
+            Err(_) => return Err(format!("Failed to read \"{}\"", path.display())),
+        };
+
+        let mut manifest = Self::default();
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || format!("Invalid line {} in \"{}\"", idx + 1, path.display());
+            let entry = match line.split_once(' ') {
+                Some(("file", rest)) => {
+                    let (sum, file) = rest.split_once(' ').ok_or_else(invalid)?;
+                    let sum = u32::from_str_radix(sum, 16).map_err(|_| invalid())?;
+                    manifest.files.push((PathBuf::from(file), sum));
+                    &manifest.files.last().unwrap().0
+                }
+                Some(("dir", dir)) => {
+                    manifest.dirs.push(PathBuf::from(dir));
+                    manifest.dirs.last().unwrap()
+                }
+                _ => return Err(invalid()),
+            };
+            // Never reach outside the manifest's directory.
+            if !entry
+                .components()
+                .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+            {
+                return Err(invalid());
+            }
+        }
+        Ok(manifest)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.dirs.is_empty()
+    }
+
+    /// The recorded files, relative to the directory of the manifest.
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(|(f, _)| f.as_path())
+    }
+
+    pub fn has_file(&self, relative: &Path) -> bool {
+        self.files.iter().any(|(f, _)| f == relative)
+    }
+
+    /// Record the file at `relative`, or update its checksum if it is recorded.
+    pub fn add_file(&mut self, relative: &Path, content: &[u8]) {
+        let sum = checksum(content);
+        match self.files.iter_mut().find(|(f, _)| f == relative) {
+            Some(entry) => entry.1 = sum,
+            None => self.files.push((relative.to_path_buf(), sum)),
+        }
+    }
+
+    pub fn add_dir(&mut self, relative: &Path) {
+        if !self.dirs.iter().any(|d| d == relative) {
+            self.dirs.push(relative.to_path_buf());
+        }
+    }
+
+    pub fn save(&self, root: &Path) -> Result<(), String> {
+        let mut text = String::from(HEADER);
+        for dir in self.dirs.iter() {
+            text.push_str(&format!("dir {}\n", slashed(dir)));
+        }
+        for (file, sum) in self.files.iter() {
+            text.push_str(&format!("file {:08x} {}\n", sum, slashed(file)));
+        }
+
+        let path = root.join(MANIFEST_FILE_NAME);
+        match fs::write(&path, text) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(format!("Failed to write \"{}\"", path.display())),
+        }
+    }
+}
+
+/// What `clean` removed and kept.
+pub struct Cleaned {
+    pub files: usize,
+    pub dirs: usize,
+    /// Files changed since they were generated, unless `force` was given.
+    pub changed: Vec<PathBuf>,
+}
+
+/// Remove the files and directories recorded in the manifest of `root`, then
+/// the manifest. Files changed since they were written are kept unless `force`
+/// is given, directories holding other files are kept.
+pub fn clean(root: &Path, force: bool) -> Result<Cleaned, String> {
+    let manifest = Manifest::load(root)?;
+    if manifest.is_empty() {
+        return Err(format!(
+            "No {} in \"{}\", nothing to clean",
+            MANIFEST_FILE_NAME,
+            root.display()
+        ));
+    }
+
+    let mut cleaned = Cleaned {
+        files: 0,
+        dirs: 0,
+        changed: Vec::new(),
+    };
+    let mut kept = Manifest::default();
+    for (file, sum) in manifest.files.iter() {
+        let path = root.join(file);
+        let content = match fs::read(&path) {
+            Ok(c) => c,
+            Err(_) => {
+                logging::verbose(format_args!("Skipped \"{}\", it is gone", path.display()));
+                continue;
+            }
+        };
+        if checksum(&content) != *sum && !force {
+            cleaned.changed.push(path);
+            kept.files.push((file.clone(), *sum));
+            continue;
+        }
+        if fs::remove_file(&path).is_err() {
+            return Err(format!("Failed to remove \"{}\"", path.display()));
+        }
+        logging::verbose(format_args!("Removed \"{}\"", path.display()));
+        cleaned.files += 1;
+    }
+
+    let manifest_path = root.join(MANIFEST_FILE_NAME);
+    if kept.files.is_empty() && fs::remove_file(&manifest_path).is_err() {
+        return Err(format!("Failed to remove \"{}\"", manifest_path.display()));
+    }
+
+    // Deepest first, the directory of the manifest last.
+    let mut dirs = manifest.dirs;
+    dirs.sort_by_key(|d| std::cmp::Reverse(depth(d)));
+    for dir in dirs {
+        let path = match depth(&dir) {
+            0 => root.to_path_buf(),
+            _ => root.join(&dir),
+        };
+        match fs::remove_dir(&path) {
+            Ok(()) => {
+                logging::verbose(format_args!("Removed directory \"{}\"", path.display()));
+                cleaned.dirs += 1;
+            }
+            Err(_) if !path.exists() => {}
+            Err(_) => {
+                logging::verbose(format_args!(
+                    "Kept directory \"{}\", it is not empty",
+                    path.display()
+                ));
+                kept.dirs.push(dir);
+            }
+        }
+    }
+
+    if !kept.files.is_empty() {
+        kept.save(root)?;
+    }
+    Ok(cleaned)
+}
+
+fn depth(relative: &Path) -> usize {
+    relative
+        .components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .count()
+}
+
+/// `path` with `/` separators, the same on every platform.
+fn slashed(path: &Path) -> String {
+    let parts: Vec<_> = path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect();
+    parts.join("/")
+}
+
+pub fn checksum(content: &[u8]) -> u32 {
+    let mut crc = flate2::Crc::new();
+    crc.update(content);
+    crc.sum()
+}
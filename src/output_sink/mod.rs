@@ -0,0 +1,620 @@
+use std::{
+    fs::File,
+    io::Write,
+    path::{Component, Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use flate2::{Compression, write::GzEncoder};
+use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+use crate::{
+    i18n::trf,
+    logging,
+    program_args::{normalize, prompt},
+    text_edit::unified_diff,
+};
+use manifest::Manifest;
+
+pub mod manifest;
+
+/// Permission bits of generated files.
+pub const FILE_MODE: u32 = 0o644;
+
+/// Destination of generated files, paths are relative to the output root.
+pub trait OutputSink {
+    /// Write a file and return where it went, for reporting.
+    fn write_file(&mut self, relative: &Path, content: &[u8], mode: u32)
+    -> Result<PathBuf, String>;
+
+    /// Flush everything written so far, an archive is incomplete before this.
+    fn finish(self: Box<Self>) -> Result<(), String>;
+
+    /// The file at `relative` is written merged with the existing one, which
+    /// it replaces without `--force`.
+    fn allow_merge(&mut self, _relative: &Path) {}
+}
+
+/// Validator of a file path below the output root, without `..` or a root.
+pub fn validate_relative_path(value: &str) -> Result<(), String> {
+    let path = Path::new(value);
+    let inside = path
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if !inside || path.file_name().is_none() || value.ends_with(['/', '\\']) {
+        return Err(String::from(
+            "expected a file path relative to --path, without \"..\"",
+        ));
+    }
+    match normalize::windows_name_problem(path) {
+        Some(problem) if cfg!(windows) => Err(problem),
+        _ => Ok(()),
+    }
+}
+
+/// Validator of `--path`, a directory that is created if it does not exist.
+pub fn validate_output_dir(value: &str) -> Result<(), String> {
+    if let Err(name) = normalize::expand_vars(value) {
+        return Err(format!("environment variable {} is not set", name));
+    }
+    if cfg!(windows)
+        && let Some(problem) = normalize::windows_name_problem(Path::new(value))
+    {
+        return Err(problem);
+    }
+    match Path::new(value).ancestors().find(|a| a.exists()) {
+        Some(existing) if !existing.is_dir() => Err(format!(
+            "\"{}\" is a file, expected a directory",
+            existing.display()
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// What happens to a file that already exists where output goes.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Overwrite {
+    Refuse,
+    Ask,
+    Replace,
+    /// Move the file aside to a `.bak` file, then replace it.
+    Backup,
+}
+
+impl Overwrite {
+    /// `--backup` or `--force` replace, otherwise ask if someone can answer.
+    pub fn from_flags(force: bool, backup: bool, interactive: bool) -> Self {
+        match (force, backup, interactive) {
+            (_, true, _) => Overwrite::Backup,
+            (true, false, _) => Overwrite::Replace,
+            (false, false, true) => Overwrite::Ask,
+            (false, false, false) => Overwrite::Refuse,
+        }
+    }
+
+    /// Whether an existing file is replaced without asking.
+    fn replaces(self) -> bool {
+        matches!(self, Overwrite::Replace | Overwrite::Backup)
+    }
+
+    /// Get `path` out of the way of a new file, or refuse to.
+    fn make_room(self, path: &Path) -> Result<(), String> {
+        let replace = match self {
+            _ if !path.exists() => true,
+            Overwrite::Replace => true,
+            Overwrite::Backup => {
+                let backup = backup_path(path);
+                if std::fs::rename(path, &backup).is_err() {
+                    return Err(format!(
+                        "Failed to back up \"{}\" to \"{}\"",
+                        path.display(),
+                        backup.display()
+                    ));
+                }
+                logging::verbose(format_args!(
+                    "Moved \"{}\" to \"{}\"",
+                    path.display(),
+                    backup.display()
+                ));
+                true
+            }
+            Overwrite::Refuse => false,
+            Overwrite::Ask => prompt::confirm(&trf("Replace \"{}\"?", &[&path.display()]), false),
+        };
+        match replace {
+            true => Ok(()),
+            false => Err(format!(
+                "\"{}\" already exists, give --force or --backup to replace it",
+                path.display()
+            )),
+        }
+    }
+}
+
+/// `<name>.bak`, or `<name>.<unix time>.bak` if an earlier backup has that name.
+fn backup_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let plain = path.with_file_name(format!("{}.bak", name));
+    if !plain.exists() {
+        return plain;
+    }
+    let stamp = unix_time();
+    (0..)
+        .map(|n| match n {
+            0 => format!("{}.{}.bak", name, stamp),
+            n => format!("{}.{}-{}.bak", name, stamp, n),
+        })
+        .map(|f| path.with_file_name(f))
+        .find(|p| !p.exists())
+        .unwrap()
+}
+
+/// Writes loose files below a directory, and records those it creates in
+/// its manifest.
+pub struct DirectorySink {
+    root: PathBuf,
+    overwrite: Overwrite,
+    merged: Vec<PathBuf>,
+    /// Each file written with its content, and whether it is new.
+    written: Vec<(PathBuf, Vec<u8>, bool)>,
+    /// Directories created, relative to the root, which is `.`.
+    created_dirs: Vec<PathBuf>,
+}
+
+impl DirectorySink {
+    pub fn new(root: &Path, overwrite: Overwrite) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            overwrite,
+            merged: Vec::new(),
+            written: Vec::new(),
+            created_dirs: Vec::new(),
+        }
+    }
+
+    /// Remember the directories `create_dir_all(dir)` is about to create.
+    fn record_missing_dirs(&mut self, dir: &Path) {
+        for missing in dir.ancestors().take_while(|d| !d.exists()) {
+            if let Ok(relative) = missing.strip_prefix(&self.root) {
+                let relative = match relative.as_os_str().is_empty() {
+                    true => PathBuf::from("."),
+                    false => relative.to_path_buf(),
+                };
+                self.created_dirs.push(relative);
+            }
+        }
+    }
+}
+
+impl OutputSink for DirectorySink {
+    fn write_file(
+        &mut self,
+        relative: &Path,
+        content: &[u8],
+        mode: u32,
+    ) -> Result<PathBuf, String> {
+        let path = self.root.join(relative);
+        let overwrite = match self.overwrite {
+            Overwrite::Backup => Overwrite::Backup,
+            _ if self.merged.iter().any(|m| m == relative) => Overwrite::Replace,
+            o => o,
+        };
+        let created = !path.exists();
+        overwrite.make_room(&path)?;
+        if let Some(parent) = path.parent() {
+            self.record_missing_dirs(parent);
+            if let Err(e) = std::fs::create_dir_all(parent) {
+                return Err(format!(
+                    "Failed to create directory \"{}\": {}",
+                    parent.display(),
+                    e
+                ));
+            }
+        }
+
+        if let Err(e) = std::fs::write(&path, content) {
+            return Err(format!("Failed to write \"{}\": {}", path.display(), e));
+        }
+        set_mode(&path, mode);
+        logging::trace(format_args!(
+            "Wrote \"{}\", {} byte(s), mode {:o}",
+            path.display(),
+            content.len(),
+            mode
+        ));
+        self.written
+            .push((relative.to_path_buf(), content.to_vec(), created));
+
+        Ok(path)
+    }
+
+    /// Add what was created to the manifest. A replaced file is only recorded
+    /// if an earlier run created it.
+    fn finish(self: Box<Self>) -> Result<(), String> {
+        if self.written.is_empty() {
+            return Ok(());
+        }
+        let mut manifest = Manifest::load(&self.root)?;
+        for dir in self.created_dirs.iter() {
+            manifest.add_dir(dir);
+        }
+        for (relative, content, created) in self.written.iter() {
+            if *created || manifest.has_file(relative) {
+                manifest.add_file(relative, content);
+            }
+        }
+        if manifest.is_empty() {
+            return Ok(());
+        }
+        manifest.save(&self.root)
+    }
+
+    fn allow_merge(&mut self, relative: &Path) {
+        self.merged.push(relative.to_path_buf());
+    }
+}
+
+/// Writes nothing, `finish` prints the files and directories that would be created.
+pub struct DryRunSink {
+    /// The output directory, or the archive.
+    target: PathBuf,
+    archive: bool,
+    /// Existing files are reported unless they would be replaced.
+    overwrite: Overwrite,
+    files: Vec<(PathBuf, usize)>,
+    merged: Vec<PathBuf>,
+}
+
+impl DryRunSink {
+    pub fn new(target: &Path, archive: bool, overwrite: Overwrite) -> Self {
+        Self {
+            target: target.to_path_buf(),
+            archive,
+            overwrite,
+            files: Vec::new(),
+            merged: Vec::new(),
+        }
+    }
+}
+
+impl OutputSink for DryRunSink {
+    fn write_file(
+        &mut self,
+        relative: &Path,
+        content: &[u8],
+        _mode: u32,
+    ) -> Result<PathBuf, String> {
+        self.files.push((relative.to_path_buf(), content.len()));
+        Ok(self.target.join(relative))
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), String> {
+        let action = |path: &Path, exists: bool| match (exists, self.overwrite) {
+            (false, _) => "create",
+            (true, Overwrite::Backup) => "backup",
+            (true, _) if self.merged.iter().any(|m| m == path) => "merge",
+            (true, Overwrite::Replace) => "replace",
+            (true, _) => "exists",
+        };
+        let mut existing = 0;
+        let total: usize = self.files.iter().map(|(_, size)| size).sum();
+        if self.archive {
+            let exists = self.target.exists();
+            existing += exists as usize;
+            println!(
+                "Dry run, would {} archive \"{}\" with:",
+                match (exists, self.overwrite) {
+                    (false, _) => "create",
+                    (true, Overwrite::Backup) => "back up and replace the existing",
+                    (true, _) => "replace the existing",
+                },
+                self.target.display()
+            );
+            for (path, size) in self.files.iter() {
+                println!("  {:>10} B  {}", size, archive_name(path));
+            }
+        } else {
+            println!(
+                "Dry run, would write into \"{}\"{}:",
+                self.target.display(),
+                if self.target.exists() {
+                    ""
+                } else {
+                    ", a new directory"
+                }
+            );
+            let mut new_dirs: Vec<&Path> = Vec::new();
+            for (path, size) in self.files.iter() {
+                let mut dirs: Vec<&Path> = path
+                    .ancestors()
+                    .skip(1)
+                    .filter(|d| !d.as_os_str().is_empty())
+                    .filter(|d| !self.target.join(d).exists() && !new_dirs.contains(d))
+                    .collect();
+                dirs.reverse();
+                for dir in dirs {
+                    println!("  {:<7}  {:>10}    {}/", "create", "", dir.display());
+                    new_dirs.push(dir);
+                }
+                let exists = self.target.join(path).exists();
+                let action = action(path, exists);
+                existing += (action == "exists") as usize;
+                println!("  {:<7}  {:>10} B  {}", action, size, path.display());
+            }
+        }
+        println!(
+            "{} file(s), {} byte(s), nothing was written",
+            self.files.len(),
+            total
+        );
+        if existing > 0 && !self.overwrite.replaces() {
+            println!(
+                "{} file(s) already exist, give --force or --backup to replace them",
+                existing
+            );
+        }
+        Ok(())
+    }
+
+    fn allow_merge(&mut self, relative: &Path) {
+        self.merged.push(relative.to_path_buf());
+    }
+}
+
+/// Writes nothing, prints a unified diff of each file against the one at its destination.
+pub struct DiffSink {
+    root: PathBuf,
+    files: usize,
+    changed: usize,
+}
+
+impl DiffSink {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            files: 0,
+            changed: 0,
+        }
+    }
+}
+
+impl OutputSink for DiffSink {
+    fn write_file(
+        &mut self,
+        relative: &Path,
+        content: &[u8],
+        _mode: u32,
+    ) -> Result<PathBuf, String> {
+        let path = self.root.join(relative);
+        let name = archive_name(relative);
+        let old = std::fs::read(&path).ok();
+        self.files += 1;
+
+        let old_name = match old {
+            Some(_) => format!("a/{}", name),
+            None => String::from("/dev/null"),
+        };
+        let new_name = format!("b/{}", name);
+        let old_text = old.as_deref().map(std::str::from_utf8).unwrap_or(Ok(""));
+        match (old_text, std::str::from_utf8(content)) {
+            (Ok(old_text), Ok(new_text)) => {
+                let diff = unified_diff(old_text, new_text, &old_name, &new_name);
+                if !diff.is_empty() || old.is_none() {
+                    self.changed += 1;
+                }
+                print!("{}", diff);
+            }
+            _ if old.as_deref() == Some(content) => {}
+            _ => {
+                self.changed += 1;
+                println!("Binary files {} and {} differ", old_name, new_name);
+            }
+        }
+        Ok(path)
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), String> {
+        // Stdout is the patch, the summary goes to stderr.
+        eprintln!(
+            "{} of {} file(s) in \"{}\" would change, nothing was written",
+            self.changed,
+            self.files,
+            self.root.display()
+        );
+        Ok(())
+    }
+}
+
+/// Output that is only previewed, given with `--dry-run` or `--diff`.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Preview {
+    Off,
+    DryRun,
+    Diff,
+}
+
+impl Preview {
+    pub fn from_flags(dry_run: bool, diff: bool) -> Self {
+        match (dry_run, diff) {
+            (true, _) => Preview::DryRun,
+            (false, true) => Preview::Diff,
+            (false, false) => Preview::Off,
+        }
+    }
+}
+
+/// Where `--path` or `--archive` output goes, only described for a preview.
+pub fn open_sink(
+    path: Option<&Path>,
+    archive: Option<&Path>,
+    overwrite: Overwrite,
+    preview: Preview,
+) -> Result<Box<dyn OutputSink>, String> {
+    match (archive, path) {
+        (Some(_), _) if preview == Preview::Diff => Err(String::from(
+            "--diff compares against the files in --path, not an archive",
+        )),
+        (Some(a), _) if preview == Preview::DryRun => {
+            Ok(Box::new(DryRunSink::new(a, true, overwrite)))
+        }
+        (Some(a), _) => {
+            overwrite.make_room(a)?;
+            archive_sink(a)
+        }
+        (None, Some(p)) if preview == Preview::Diff => Ok(Box::new(DiffSink::new(p))),
+        (None, Some(p)) if preview == Preview::DryRun => {
+            Ok(Box::new(DryRunSink::new(p, false, overwrite)))
+        }
+        (None, Some(p)) => Ok(Box::new(DirectorySink::new(p, overwrite))),
+        (None, None) => Err(String::from("Either --path or --archive is required")),
+    }
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) {
+    use std::os::unix::fs::PermissionsExt;
+    let _ = std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode));
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) {}
+
+pub struct TarGzSink {
+    archive_path: PathBuf,
+    builder: tar::Builder<GzEncoder<File>>,
+}
+
+impl OutputSink for TarGzSink {
+    fn write_file(
+        &mut self,
+        relative: &Path,
+        content: &[u8],
+        mode: u32,
+    ) -> Result<PathBuf, String> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(mode);
+        header.set_mtime(unix_time());
+        header.set_entry_type(tar::EntryType::Regular);
+
+        if self
+            .builder
+            .append_data(&mut header, archive_name(relative), content)
+            .is_err()
+        {
+            return Err(format!(
+                "Failed to add \"{}\" to \"{}\"",
+                relative.display(),
+                self.archive_path.display()
+            ));
+        }
+
+        Ok(self.archive_path.join(relative))
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), String> {
+        let result = self
+            .builder
+            .into_inner()
+            .and_then(|gz| gz.finish())
+            .and_then(|mut f| f.flush());
+        match result {
+            Ok(_) => Ok(()),
+            Err(_) => Err(format!(
+                "Failed to finish \"{}\"",
+                self.archive_path.display()
+            )),
+        }
+    }
+}
+
+pub struct ZipSink {
+    archive_path: PathBuf,
+    writer: ZipWriter<File>,
+}
+
+impl OutputSink for ZipSink {
+    fn write_file(
+        &mut self,
+        relative: &Path,
+        content: &[u8],
+        mode: u32,
+    ) -> Result<PathBuf, String> {
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .unix_permissions(mode);
+
+        let result = self
+            .writer
+            .start_file(archive_name(relative), options)
+            .map_err(|_| ())
+            .and_then(|_| self.writer.write_all(content).map_err(|_| ()));
+        if result.is_err() {
+            return Err(format!(
+                "Failed to add \"{}\" to \"{}\"",
+                relative.display(),
+                self.archive_path.display()
+            ));
+        }
+
+        Ok(self.archive_path.join(relative))
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), String> {
+        match self.writer.finish() {
+            Ok(_) => Ok(()),
+            Err(_) => Err(format!(
+                "Failed to finish \"{}\"",
+                self.archive_path.display()
+            )),
+        }
+    }
+}
+
+/// Open an archive sink, the format is chosen by the file extension.
+pub fn archive_sink(path: &Path) -> Result<Box<dyn OutputSink>, String> {
+    let name = path.to_string_lossy().to_lowercase();
+    let is_tar_gz = name.ends_with(".tar.gz") || name.ends_with(".tgz");
+    let is_zip = name.ends_with(".zip");
+    if !is_tar_gz && !is_zip {
+        return Err(format!(
+            "Unsupported archive \"{}\", expected .tar.gz, .tgz or .zip",
+            path.display()
+        ));
+    }
+
+    let file = match File::create(path) {
+        Ok(f) => f,
+        Err(_) => return Err(format!("Failed to create \"{}\"", path.display())),
+    };
+
+    let archive_path = path.to_path_buf();
+    if is_tar_gz {
+        let encoder = GzEncoder::new(file, Compression::default());
+        Ok(Box::new(TarGzSink {
+            archive_path,
+            builder: tar::Builder::new(encoder),
+        }))
+    } else {
+        Ok(Box::new(ZipSink {
+            archive_path,
+            writer: ZipWriter::new(file),
+        }))
+    }
+}
+
+/// Archive entries always use forward slashes.
+fn archive_name(relative: &Path) -> String {
+    let parts: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    parts.join("/")
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
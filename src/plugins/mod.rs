@@ -0,0 +1,225 @@
+//! File types generated by `filetemp-<type>` executables on PATH.
+//!
+//! Protocol version 1: the plugin gets a JSON object on stdin with `protocol`,
+//! `file_type`, `args` and `positional`, and writes the generated content to
+//! stdout. It fails with a non-zero exit status, stderr is passed through.
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use serde_json::{Map, Value, json};
+
+use crate::{
+    output_sink,
+    program_args::{Arg, CommandArg, ValueType, normalize},
+};
+
+pub const PLUGIN_PREFIX: &str = "filetemp-";
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// The `filetemp-<name>` executable on PATH, for a name that is no file type.
+pub fn find_plugin(name: &str) -> Option<PathBuf> {
+    let valid = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if name.is_empty() || name.starts_with('-') || !valid {
+        return None;
+    }
+    let file = format!("{}{}{}", PLUGIN_PREFIX, name, std::env::consts::EXE_SUFFIX);
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(&file))
+        .find(|p| p.is_file())
+}
+
+/// Arguments filetemp handles itself when generating with a plugin.
+pub fn define_args(cmd: &mut CommandArg, usage_name: String) {
+    cmd.usage_name(usage_name)
+        .no_file_type()
+        .add_general_arg_def(
+            Arg::new("path")
+                .value_name("PATH")
+                .normalize(normalize::path)
+                .value_type(ValueType::Path { must_exist: false })
+                .validator(output_sink::validate_output_dir)
+                .conflicts_with("archive")
+                .help("Directory the generated file is written into"),
+        )
+        .add_general_arg_def(
+            Arg::new("archive")
+                .value_name("FILE")
+                .value_type(ValueType::Path { must_exist: false })
+                .help("Write the generated file into a .tar.gz, .tgz or .zip archive instead"),
+        )
+        .add_general_arg_def(
+            Arg::new("output-name")
+                .value_name("NAME")
+                .validator(validate_output_name)
+                .help("Name of the generated file, by default the file type"),
+        )
+        .add_general_arg_def(
+            Arg::new("show")
+                .short('s')
+                .flag(true)
+                .help("Print the generated file"),
+        )
+        .add_general_arg_def(
+            Arg::new("dry-run")
+                .flag(true)
+                .help("List the file that would be written, with its size, instead of writing it"),
+        )
+        .add_general_arg_def(
+            Arg::new("diff")
+                .flag(true)
+                .requires("path")
+                .conflicts_with("dry-run")
+                .help("Print a unified diff against the file in --path instead of writing it"),
+        )
+        .add_general_arg_def(
+            Arg::new("force")
+                .flag(true)
+                .help("Replace an existing file without asking"),
+        )
+        .add_general_arg_def(
+            Arg::new("backup")
+                .flag(true)
+                .help("Move an existing file to <name>.bak before replacing it"),
+        )
+        .add_general_arg_def(
+            Arg::new("verbose")
+                .short('v')
+                .flag(true)
+                .last_wins(true)
+                .help("Report each generation step to stderr"),
+        )
+        .add_general_arg_def(
+            Arg::new("quiet")
+                .short('q')
+                .flag(true)
+                .last_wins(true)
+                .conflicts_with("verbose")
+                .help("Only report errors"),
+        )
+        .add_general_arg_def(
+            Arg::new("i-know-what-im-doing")
+                .flag(true)
+                .help("Allow --path to be the filesystem root, home or a crowded directory"),
+        );
+}
+
+fn validate_output_name(v: &str) -> Result<(), String> {
+    if v.is_empty() || v == "." || v == ".." || v.contains(['/', '\\']) {
+        return Err(String::from("expected a file name without directories"));
+    }
+    Ok(())
+}
+
+/// Split the arguments after the file type into those `cmd` defines and the plugin's.
+pub fn split_args<'a>(cmd: &CommandArg, args: &[&'a str]) -> (Vec<&'a str>, Vec<&'a str>) {
+    let mut own = Vec::new();
+    let mut plugin = Vec::new();
+    let mut iter = args.iter().copied();
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            plugin.push(arg);
+            plugin.extend(iter.by_ref());
+            break;
+        }
+        match cmd.general_option(arg) {
+            Some(takes_value) => {
+                own.push(arg);
+                if takes_value && let Some(value) = iter.next() {
+                    own.push(value);
+                }
+            }
+            None => plugin.push(arg),
+        }
+    }
+    (own, plugin)
+}
+
+/// The JSON sent to the plugin. `--name value` and `--name=value` give a string,
+/// `--name` alone gives true, repeated names give an array of their values.
+/// Other words, and everything after `--`, are positional.
+pub fn request(file_type: &str, args: &[&str]) -> Value {
+    let mut named = Map::new();
+    let mut positional = Vec::new();
+    let mut idx = 0;
+    while idx < args.len() {
+        let arg = args[idx];
+        idx += 1;
+        if arg == "--" {
+            positional.extend(args[idx..].iter().map(|a| Value::from(*a)));
+            break;
+        }
+        let name = match arg.strip_prefix("--").or_else(|| arg.strip_prefix('-')) {
+            Some(n) if !n.is_empty() => n,
+            _ => {
+                positional.push(Value::from(arg));
+                continue;
+            }
+        };
+
+        let (name, value) = match name.split_once('=') {
+            Some((n, v)) => (n, Value::from(v)),
+            None if args.get(idx).is_some_and(|next| !next.starts_with('-')) => {
+                idx += 1;
+                (name, Value::from(args[idx - 1]))
+            }
+            None => (name, Value::from(true)),
+        };
+        match named.get_mut(name) {
+            Some(Value::Array(values)) => values.push(value),
+            Some(previous) => *previous = Value::Array(vec![previous.take(), value]),
+            None => {
+                named.insert(name.to_string(), value);
+            }
+        }
+    }
+
+    json!({
+        "protocol": PROTOCOL_VERSION,
+        "file_type": file_type,
+        "args": named,
+        "positional": positional,
+    })
+}
+
+/// Run the plugin with the request on stdin, its stdout is the generated content.
+pub fn run(plugin: &Path, request: &Value) -> Result<String, String> {
+    let name = plugin.file_name().unwrap_or_default().to_string_lossy();
+    let mut child = match Command::new(plugin)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+    {
+        Ok(c) => c,
+        Err(e) => return Err(format!("Failed to run plugin \"{}\": {}", name, e)),
+    };
+
+    // A plugin may exit without reading its input, its status tells what went wrong.
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(request.to_string().as_bytes());
+    }
+    let output = match child.wait_with_output() {
+        Ok(o) => o,
+        Err(e) => return Err(format!("Failed to run plugin \"{}\": {}", name, e)),
+    };
+
+    if !output.status.success() {
+        return Err(match output.status.code() {
+            Some(c) => format!("Plugin \"{}\" failed with exit status {}", name, c),
+            None => format!("Plugin \"{}\" was stopped by a signal", name),
+        });
+    }
+    match String::from_utf8(output.stdout) {
+        Ok(s) => Ok(s),
+        Err(_) => Err(format!(
+            "Plugin \"{}\" wrote output that is not UTF-8",
+            name
+        )),
+    }
+}
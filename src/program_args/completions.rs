@@ -0,0 +1,350 @@
+use std::{borrow::Cow, fmt::Write, str::FromStr};
+
+use super::{Arg, CommandArg};
+use crate::file_types::{FileType, get_result_filename};
+
+/// Command printing saved cache names, one per line, used to complete `--use`.
+const CACHE_LIST_COMMAND: &str = "filetemp cache list --names";
+
+#[derive(Clone, Copy)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+impl FromStr for Shell {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("bash") {
+            Ok(Self::Bash)
+        } else if s.eq_ignore_ascii_case("zsh") {
+            Ok(Self::Zsh)
+        } else if s.eq_ignore_ascii_case("fish") {
+            Ok(Self::Fish)
+        } else if s.eq_ignore_ascii_case("powershell") {
+            Ok(Self::Powershell)
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// What the value of an argument is completed with.
+enum ValueHint<'a> {
+    None,
+    Path,
+    CacheName,
+    Choices(&'a [Cow<'static, str>]),
+}
+
+fn value_hint(arg: &Arg) -> ValueHint<'_> {
+    if arg.name == "use" {
+        ValueHint::CacheName
+    } else if !arg.possible_values.is_empty() {
+        ValueHint::Choices(&arg.possible_values)
+    } else if matches!(arg.value_name.as_deref(), Some("PATH" | "FILE" | "SOURCE")) {
+        ValueHint::Path
+    } else {
+        ValueHint::None
+    }
+}
+
+/// Every spelling of an argument, long names first.
+fn spellings(arg: &Arg) -> Vec<String> {
+    let mut names = vec![format!("--{}", arg.name)];
+    names.extend(arg.aliases.iter().map(|a| format!("--{}", a)));
+    names.extend(arg.short.map(|c| format!("-{}", c)));
+    names
+}
+
+impl CommandArg {
+    /// Render a completion script for file types, their options and subcommand names.
+    pub fn completion_script(&mut self, shell: Shell) -> String {
+        self.define_all_types();
+        match shell {
+            Shell::Bash => self.bash_completion(),
+            Shell::Zsh => self.zsh_completion(),
+            Shell::Fish => self.fish_completion(),
+            Shell::Powershell => self.powershell_completion(),
+        }
+    }
+
+    fn completion_types(&self) -> Vec<FileType> {
+        let mut types: Vec<FileType> = self.file_types().collect();
+        types.sort_by_key(|t| t.to_str());
+        types
+    }
+
+    fn type_args(&self, ty: FileType) -> impl Iterator<Item = &Arg> {
+        self.defined_args
+            .get(&ty)
+            .into_iter()
+            .flatten()
+            .chain(self.general_args.iter())
+            .map(|g| &g.definition)
+    }
+
+    /// First words of the file types and subcommands, with a description each.
+    fn completion_commands(&self) -> Vec<(&'static str, String)> {
+        let mut commands: Vec<(&'static str, String)> = self
+            .completion_types()
+            .into_iter()
+            .map(|ty| {
+                (
+                    ty.to_str(),
+                    format!("Generates {}", get_result_filename(ty)),
+                )
+            })
+            .collect();
+        for s in self.subcommands.iter() {
+            let name = match s.words().next() {
+                Some(n) => n,
+                None => continue,
+            };
+            if !commands.iter().any(|c| c.0 == name) {
+                commands.push((name, s.about.to_string()));
+            }
+        }
+        commands
+    }
+
+    fn bash_completion(&self) -> String {
+        let mut out = String::new();
+        let commands: Vec<&str> = self.completion_commands().iter().map(|c| c.0).collect();
+
+        out.push_str("_filetemp() {\n");
+        out.push_str("    local cur=\"${COMP_WORDS[COMP_CWORD]}\"\n");
+        out.push_str("    local prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n\n");
+        out.push_str("    if [[ $COMP_CWORD -eq 1 ]]; then\n");
+        writeln!(
+            out,
+            "        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+            commands.join(" ")
+        )
+        .unwrap();
+        out.push_str("        return\n    fi\n\n");
+        out.push_str("    case \"${COMP_WORDS[1]}\" in\n");
+
+        for ty in self.completion_types() {
+            writeln!(out, "        {})", ty.to_str()).unwrap();
+            out.push_str("            case \"$prev\" in\n");
+            let mut options = Vec::new();
+            for arg in self.type_args(ty) {
+                let names = spellings(arg);
+                let words = match value_hint(arg) {
+                    _ if arg.is_flag => None,
+                    ValueHint::CacheName => Some(format!(
+                        "COMPREPLY=($(compgen -W \"$({} 2>/dev/null)\" -- \"$cur\"))",
+                        CACHE_LIST_COMMAND
+                    )),
+                    ValueHint::Choices(c) => Some(format!(
+                        "COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+                        c.join(" ")
+                    )),
+                    ValueHint::Path => Some(String::from("COMPREPLY=($(compgen -f -- \"$cur\"))")),
+                    ValueHint::None => Some(String::from("COMPREPLY=()")),
+                };
+                if let Some(w) = words {
+                    writeln!(out, "                {}) {}; return ;;", names.join("|"), w).unwrap();
+                }
+                options.extend(names);
+            }
+            out.push_str("            esac\n");
+            writeln!(
+                out,
+                "            COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n            ;;",
+                options.join(" ")
+            )
+            .unwrap();
+        }
+
+        out.push_str("    esac\n}\n\ncomplete -F _filetemp filetemp\n");
+        out
+    }
+
+    fn zsh_completion(&self) -> String {
+        let mut out = String::from("#compdef filetemp\n\n");
+        writeln!(
+            out,
+            "_filetemp_caches() {{\n    local -a caches\n    caches=(${{(f)\"$({} 2>/dev/null)\"}})\n    _describe 'cache' caches\n}}\n",
+            CACHE_LIST_COMMAND
+        )
+        .unwrap();
+
+        out.push_str("_filetemp() {\n    if (( CURRENT == 2 )); then\n");
+        out.push_str("        local -a commands\n        commands=(\n");
+        for (name, about) in self.completion_commands() {
+            writeln!(out, "            '{}:{}'", name, zsh_escape(&about)).unwrap();
+        }
+        out.push_str("        )\n        _describe 'command' commands\n        return\n    fi\n\n");
+        out.push_str("    case $words[2] in\n");
+
+        for ty in self.completion_types() {
+            writeln!(out, "        {})\n            _arguments \\", ty.to_str()).unwrap();
+            for arg in self.type_args(ty) {
+                let names = spellings(arg);
+                let exclusion = if names.len() > 1 {
+                    format!("'({})'", names.join(" "))
+                } else {
+                    String::new()
+                };
+                let action = match value_hint(arg) {
+                    _ if arg.is_flag => String::new(),
+                    ValueHint::CacheName => String::from(":CACHE_NAME:_filetemp_caches"),
+                    ValueHint::Choices(c) => format!(":VALUE:({})", c.join(" ")),
+                    ValueHint::Path => String::from(":PATH:_files"),
+                    ValueHint::None => String::from(":VALUE:"),
+                };
+                let spec = format!("[{}]{}", zsh_escape(&arg.help), action);
+                if names.len() > 1 {
+                    writeln!(
+                        out,
+                        "                {}{{{}}}'{}' \\",
+                        exclusion,
+                        names.join(","),
+                        spec
+                    )
+                    .unwrap();
+                } else {
+                    writeln!(out, "                '{}{}' \\", names[0], spec).unwrap();
+                }
+            }
+            out.push_str("                && return\n            ;;\n");
+        }
+
+        out.push_str("    esac\n}\n\n_filetemp \"$@\"\n");
+        out
+    }
+
+    fn fish_completion(&self) -> String {
+        let mut out = String::from("complete -c filetemp -f\n");
+        for (name, about) in self.completion_commands() {
+            writeln!(
+                out,
+                "complete -c filetemp -n '__fish_use_subcommand' -a {} -d '{}'",
+                name,
+                fish_escape(&about)
+            )
+            .unwrap();
+        }
+
+        for ty in self.completion_types() {
+            out.push('\n');
+            for arg in self.type_args(ty) {
+                write!(
+                    out,
+                    "complete -c filetemp -n '__fish_seen_subcommand_from {}' -l {}",
+                    ty.to_str(),
+                    arg.name
+                )
+                .unwrap();
+                for alias in arg.aliases.iter() {
+                    write!(out, " -l {}", alias).unwrap();
+                }
+                if let Some(c) = arg.short {
+                    write!(out, " -s {}", c).unwrap();
+                }
+                match value_hint(arg) {
+                    _ if arg.is_flag => {}
+                    ValueHint::CacheName => {
+                        write!(out, " -x -a '({} 2>/dev/null)'", CACHE_LIST_COMMAND).unwrap()
+                    }
+                    ValueHint::Choices(c) => write!(out, " -x -a '{}'", c.join(" ")).unwrap(),
+                    ValueHint::Path => out.push_str(" -r -F"),
+                    ValueHint::None => out.push_str(" -x"),
+                }
+                if !arg.help.is_empty() {
+                    write!(out, " -d '{}'", fish_escape(&arg.help)).unwrap();
+                }
+                out.push('\n');
+            }
+        }
+
+        out
+    }
+
+    fn powershell_completion(&self) -> String {
+        let mut out = String::from(
+            "Register-ArgumentCompleter -Native -CommandName filetemp -ScriptBlock {\n    \
+             param($wordToComplete, $commandAst, $cursorPosition)\n    \
+             $words = @($commandAst.CommandElements | ForEach-Object { $_.ToString() })\n    \
+             $count = if ($wordToComplete) { $words.Count - 1 } else { $words.Count }\n    \
+             $candidates = @()\n\n    \
+             if ($count -le 1) {\n",
+        );
+        let commands: Vec<String> = self
+            .completion_commands()
+            .iter()
+            .map(|c| format!("'{}'", c.0))
+            .collect();
+        writeln!(out, "        $candidates = @({})", commands.join(", ")).unwrap();
+        out.push_str(
+            "    } else {\n        $prev = $words[$count - 1]\n        switch ($words[1]) {\n",
+        );
+
+        for ty in self.completion_types() {
+            writeln!(
+                out,
+                "            '{}' {{\n                switch ($prev) {{",
+                ty.to_str()
+            )
+            .unwrap();
+            let mut options = Vec::new();
+            for arg in self.type_args(ty) {
+                let names: Vec<String> =
+                    spellings(arg).iter().map(|n| format!("'{}'", n)).collect();
+                let values = match value_hint(arg) {
+                    _ if arg.is_flag => None,
+                    ValueHint::CacheName => Some(format!("@({} 2>$null)", CACHE_LIST_COMMAND)),
+                    ValueHint::Choices(c) => {
+                        let quoted: Vec<String> = c.iter().map(|v| format!("'{}'", v)).collect();
+                        Some(format!("@({})", quoted.join(", ")))
+                    }
+                    ValueHint::Path => Some(String::from(
+                        "@(Get-ChildItem -Name -Path \"$wordToComplete*\")",
+                    )),
+                    ValueHint::None => Some(String::from("@()")),
+                };
+                if let Some(v) = values {
+                    writeln!(
+                        out,
+                        "                    {{ $_ -in @({}) }} {{ $candidates = {}; break }}",
+                        names.join(", "),
+                        v
+                    )
+                    .unwrap();
+                }
+                options.extend(names);
+            }
+            writeln!(
+                out,
+                "                    default {{ $candidates = @({}) }}\n                }}\n            }}",
+                options.join(", ")
+            )
+            .unwrap();
+        }
+
+        out.push_str(
+            "        }\n    }\n\n    \
+             $candidates | Where-Object { $_ -like \"$wordToComplete*\" } | ForEach-Object {\n        \
+             [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)\n    \
+             }\n}\n",
+        );
+        out
+    }
+}
+
+/// Escape text for a single-quoted zsh `_arguments` or `_describe` item.
+fn zsh_escape(text: &str) -> String {
+    text.replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+}
+
+fn fish_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\'', "\\'")
+}
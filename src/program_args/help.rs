@@ -0,0 +1,295 @@
+use std::fmt::Write;
+
+use super::{ArgGroup, CommandArg, providers};
+use crate::{
+    file_types::{FileType, get_result_filename},
+    i18n::{tr, trf},
+};
+
+/// Column where option descriptions start.
+const HELP_COLUMN: usize = 29;
+
+impl CommandArg {
+    /// Render help from the registered definitions, for the selected file type if
+    /// one is known, otherwise an overview of all file types.
+    pub fn help_message(&self) -> String {
+        let mut out = String::new();
+        writeln!(&mut out, "filetemp {}\n", env!("CARGO_PKG_VERSION")).unwrap();
+
+        let mut types: Vec<FileType> = self.file_types().collect();
+        types.sort_by_key(|t| t.to_str());
+
+        writeln!(&mut out, "{}", tr("USAGE:")).unwrap();
+        writeln!(&mut out, "    {}", self.usage_synopsis()).unwrap();
+        for s in self.subcommands.iter() {
+            writeln!(&mut out, "    filetemp {}", s.usage).unwrap();
+        }
+
+        if !self.subcommands.is_empty() {
+            writeln!(&mut out, "\n{}", tr("SUBCOMMANDS:")).unwrap();
+            for s in self.subcommands.iter() {
+                write_help_line(&mut out, &s.name(), s.about);
+            }
+        }
+
+        match self.file_type {
+            _ if !self.takes_file_type => {}
+            FileType::Unknown => {
+                writeln!(&mut out, "\n{}", tr("FILE_TYPE:")).unwrap();
+                for ty in types.iter() {
+                    write_help_line(
+                        &mut out,
+                        ty.to_str(),
+                        &trf("Generates {}", &[&get_result_filename(*ty)]),
+                    );
+                }
+                let run_help = trf(
+                    "Run \"{} <FILE_TYPE> --help\" for the options of a file type.",
+                    &[&self.usage_name],
+                );
+                writeln!(&mut out, "\n    {}", run_help).unwrap();
+            }
+            ty => {
+                let heading = trf("{} OPTIONS:", &[&ty.to_str().to_uppercase()]);
+                writeln!(&mut out, "\n{}", heading).unwrap();
+                if let Some(args) = self.defined_args.get(&ty) {
+                    write_arg_groups(&mut out, args);
+                }
+                if let Some(help) = self.raw_value_help.get(&ty) {
+                    write_help_line(&mut out, "-- <VALUE>...", help);
+                }
+                if let Some(sets) = self.arg_sets.get(&ty) {
+                    writeln!(&mut out, "\n{}", tr("ARGUMENT SETS:")).unwrap();
+                    for set in sets.iter() {
+                        write_help_line(&mut out, set.name, &set.describe());
+                    }
+                }
+            }
+        }
+
+        if let Some((name, help)) = self.positional_help {
+            writeln!(&mut out, "\n{}", tr("ARGUMENTS:")).unwrap();
+            write_help_line(&mut out, &format!("[{}]", name), help);
+        }
+
+        if !self.general_args.is_empty() {
+            let heading = if self.takes_file_type {
+                "GENERAL OPTIONS:"
+            } else {
+                "OPTIONS:"
+            };
+            writeln!(&mut out, "\n{}", tr(heading)).unwrap();
+            write_arg_groups(&mut out, &self.general_args);
+        }
+
+        if !self.after_help.is_empty() {
+            writeln!(&mut out, "\n{}", tr(&self.after_help)).unwrap();
+        }
+
+        out
+    }
+
+    /// One line synopsis with the required arguments of the selected file type.
+    pub fn usage_synopsis(&self) -> String {
+        let mut words = vec![self.usage_name.to_string()];
+        match self.file_type {
+            _ if !self.takes_file_type => {}
+            FileType::Unknown => {
+                words.push(String::from("<FILE_TYPE>"));
+                words.push(String::from("[OPTIONS]"));
+                return words.join(" ");
+            }
+            ty => words.push(ty.to_str().to_string()),
+        }
+        if let Some((name, _)) = self.positional_help {
+            words.push(format!("[{}]", name));
+        }
+
+        let args = self
+            .defined_args
+            .get(&self.file_type)
+            .into_iter()
+            .flatten()
+            .chain(self.general_args.iter());
+        for arg in args.filter(|a| a.is_required) {
+            words.push(format!("--{} <{}>", arg.name, value_placeholder(arg)));
+        }
+        words.push(String::from("[OPTIONS]"));
+        if self.raw_value_help.contains_key(&self.file_type) {
+            words.push(String::from("[-- <VALUE>...]"));
+        }
+        words.join(" ")
+    }
+
+    /// Every registered file type with its output file and arguments.
+    pub fn file_type_list(&mut self) -> String {
+        self.define_all_types();
+
+        let mut types: Vec<FileType> = self.file_types().collect();
+        types.sort_by_key(|t| t.to_str());
+
+        let mut out = String::new();
+        for ty in types {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            writeln!(&mut out, "{} -> {}", ty.to_str(), get_result_filename(ty)).unwrap();
+            for arg in self.defined_args.get(&ty).into_iter().flatten() {
+                let note = if arg.is_required {
+                    tr("[required]").to_string()
+                } else if let Some(d) = default_description(arg) {
+                    trf("[default: {}]", &[&d])
+                } else {
+                    String::new()
+                };
+                write_help_line(&mut out, &arg_usage(arg), &note);
+            }
+        }
+        out
+    }
+
+    /// Shown after an argument error, points to the full help.
+    pub fn usage_hint(&self) -> String {
+        let help = match self.file_type {
+            FileType::Unknown => format!("{} --help", self.usage_name),
+            _ if !self.takes_file_type => format!("{} --help", self.usage_name),
+            ty => format!("{} {} --help", self.usage_name, ty.to_str()),
+        };
+        format!(
+            "\n{}\n{}",
+            trf("Usage: {}", &[&self.usage_synopsis()]),
+            trf("Run \"{}\" for details.", &[&help])
+        )
+    }
+
+    /// Explain which required arguments are missing, what they are for, and
+    /// the given command line completed with placeholders for them.
+    pub fn missing_args_message(&self, missing: &[String], required: usize) -> String {
+        let mut out = String::new();
+        let names: Vec<String> = missing.iter().map(|m| format!("--{}", m)).collect();
+        let message = if missing.len() == 1 {
+            "Missing required argument: {} ({} of {})"
+        } else {
+            "Missing required arguments: {} ({} of {})"
+        };
+        let counts = [
+            &names.join(", ") as &dyn std::fmt::Display,
+            &missing.len(),
+            &required,
+        ];
+        writeln!(&mut out, "{}", trf(message, &counts)).unwrap();
+
+        let args: Vec<&ArgGroup> = self
+            .defined_args
+            .get(&self.file_type)
+            .into_iter()
+            .flatten()
+            .chain(self.general_args.iter())
+            .filter(|a| missing.iter().any(|m| *m == a.name))
+            .collect();
+        for arg in args.iter() {
+            let left = format!("--{} <{}>", arg.name, value_placeholder(arg));
+            write_help_line(&mut out, &left, &arg.help);
+        }
+
+        let mut command = vec![self.usage_name.to_string()];
+        if self.takes_file_type {
+            command.push(self.file_type.to_str().to_string());
+        }
+        for arg in self.given_args.iter() {
+            if arg.is_empty() || arg.contains(char::is_whitespace) {
+                command.push(format!("\"{}\"", arg));
+            } else {
+                command.push(arg.to_string());
+            }
+        }
+        for arg in args.iter() {
+            command.push(format!("--{} <{}>", arg.name, value_placeholder(arg)));
+        }
+        writeln!(&mut out, "\n{}\n    {}", tr("Try:"), command.join(" ")).unwrap();
+
+        out
+    }
+}
+
+fn value_placeholder(arg: &ArgGroup) -> String {
+    match &arg.value_name {
+        Some(v) => v.to_string(),
+        None => arg.name.to_uppercase(),
+    }
+}
+
+/// Option names with the value placeholder, e.g. `-p, --proj <NAME>`.
+fn arg_usage(arg: &ArgGroup) -> String {
+    let mut left = match arg.short {
+        Some(c) => format!("-{}, --{}", c, arg.name),
+        None => format!("--{}", arg.name),
+    };
+    if !arg.is_flag {
+        write!(&mut left, " <{}>", value_placeholder(arg)).unwrap();
+        if arg.is_multiple {
+            left.push_str("...");
+        }
+    }
+    left
+}
+
+/// The fixed default or what its provider computes.
+fn default_description(arg: &ArgGroup) -> Option<String> {
+    if let Some(p) = arg.default_provider.and_then(providers::find_provider) {
+        Some(p.description.to_lowercase())
+    } else if arg.has_default_value {
+        Some(arg.default_value.to_string())
+    } else {
+        None
+    }
+}
+
+fn write_arg_groups(out: &mut String, args: &[ArgGroup]) {
+    for arg in args.iter() {
+        write_help_line(out, &arg_usage(arg), &arg.help);
+
+        let indent = " ".repeat(HELP_COLUMN);
+        if !arg.aliases.is_empty() {
+            let aliases: Vec<String> = arg.aliases.iter().map(|a| format!("--{}", a)).collect();
+            let note = trf("[aliases: {}]", &[&aliases.join(", ")]);
+            writeln!(out, "{}{}", indent, note).unwrap();
+        }
+        if !arg.possible_values.is_empty() {
+            let note = trf("[possible values: {}]", &[&arg.possible_values.join(", ")]);
+            writeln!(out, "{}{}", indent, note).unwrap();
+        }
+        if !arg.requirements.is_empty() {
+            let names: Vec<String> = arg
+                .requirements
+                .iter()
+                .map(|r| format!("--{}", r.replace('|', " or --")))
+                .collect();
+            let note = trf("[requires: {}]", &[&names.join(", ")]);
+            writeln!(out, "{}{}", indent, note).unwrap();
+        }
+        if !arg.conflicts.is_empty() {
+            let names: Vec<String> = arg.conflicts.iter().map(|c| format!("--{}", c)).collect();
+            let note = trf("[conflicts with: {}]", &[&names.join(", ")]);
+            writeln!(out, "{}{}", indent, note).unwrap();
+        }
+        if arg.is_required {
+            writeln!(out, "{}{}", indent, tr("[required]")).unwrap();
+        }
+        if let Some(d) = default_description(arg) {
+            writeln!(out, "{}{}", indent, trf("[default: {}]", &[&d])).unwrap();
+        }
+    }
+}
+
+fn write_help_line(out: &mut String, left: &str, help: &str) {
+    let help = tr(help);
+    if help.is_empty() {
+        writeln!(out, "    {}", left).unwrap();
+    } else if left.len() + 4 >= HELP_COLUMN {
+        writeln!(out, "    {}", left).unwrap();
+        writeln!(out, "{}{}", " ".repeat(HELP_COLUMN), help).unwrap();
+    } else {
+        writeln!(out, "    {:<width$}{}", left, help, width = HELP_COLUMN - 4).unwrap();
+    }
+}
@@ -1,89 +1,205 @@
 use std::{
+    borrow::Cow,
     collections::HashMap,
-    fmt::Debug,
+    fmt,
     ops::{Deref, DerefMut},
+    path::Path,
     str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
 };
 
-use crate::file_types::FileType;
+use crate::{file_types::FileType, i18n::trf, logging};
 
-const HELP_MESSAGE: &'static str = "\
-filetemp 0.1.0
+/// Largest accepted argument value in bytes, values are kept in memory and in the cache.
+pub const MAX_VALUE_LEN: usize = 64 * 1024;
 
-USAGE:
-    filetemp <FILE_TYPE> <CMAKE_OPTIONS> [GENERAL_OPTIONS]
+/// Whether commands created from now on match long options regardless of case.
+static IGNORE_LONG_CASE: AtomicBool = AtomicBool::new(false);
 
-FILE_TYPE:
-    CMake            Generates CMakeLists.txt
-
-CMAKE_OPTIONS:
-    SYNTAX: <--version <VER>> <--proj <NAME>> [...]
-
-    --version <VER>          Used in \"cmake_minimum_required\"
-
-    --proj <NAME>            Project name
-
-    --main-lang <LANG>       Main language of the project, decides whether \"main.c\" or \"main.cpp\" is generated.
-                            [possible values: C, CXX]
-                            [default: CXX]
-
-    --cstd <STD>             C standard
-
-    --cxxstd <STD>           C++ standard
-
-    --target-type <TYPE>     Target type
-                            [possible values: executable, staticlib, sharedlib]
-                            [default: executable]
-
-    --target-name <NAME>     Target name, use project name if not specified.
-
-GENERAL_OPTIONS:
-    SYNTAX: [--show] [--path <PATH>]
-
-    --show                   Show output content to stdout
-
-    --path <PATH>            Path where the file is generated to
-
-    --save-as <CACHE_NAME>   Save current argument set to cache
-
-    --use <CACHE_NAME>       Use existed cache
+/// Set by `--ignore-case`: match long options of every command created after
+/// this regardless of case. Exact matching is the default.
+pub fn set_ignore_long_case(ignore: bool) {
+    IGNORE_LONG_CASE.store(ignore, Ordering::Relaxed);
+}
 
-    --gen-example       Generate example project
-";
+pub mod completions;
+mod help;
+pub mod normalize;
+pub mod prompt;
+pub mod providers;
+pub mod resolve;
+pub mod subcommand;
+pub mod suggest;
+pub mod typed;
+mod wizard;
+
+use resolve::Layer;
+use subcommand::Subcommand;
+pub use typed::ValueType;
+use typed::{TypedArgMap, ValueParser};
+
+/// Refuses a value with an explanation of what was expected.
+pub type Validator = fn(&str) -> Result<(), String>;
 
 pub struct ArgPair<'a> {
-    pub arg: &'static str,
+    pub arg: &'a str,
     pub content: &'a str,
 }
 
+#[derive(Debug)]
 pub enum ArgProcessErr {
     PrintedHelp,
-    InvalidArg(&'static str),
-    InvalidFileType(&'static str),
-    MissingArg(String),
+    PrintedVersion,
+    /// Unknown argument, with the closest defined one if it looks like a typo.
+    InvalidArg(String, Option<String>),
+    InvalidFileType(String, Option<&'static str>),
+    InvalidFlagValue(String),
+    InvalidValue {
+        arg: String,
+        value: String,
+        possible: Vec<String>,
+    },
+    ValueTooLarge {
+        arg: String,
+        len: usize,
+    },
+    /// The value was refused by the argument's validator.
+    Rejected {
+        arg: String,
+        value: String,
+        reason: String,
+    },
+    /// A single-valued argument was given more than once.
+    DuplicateArg(String),
+    /// The argument takes a value but none follows it.
+    MissingValue(String),
+    /// The argument cannot be combined with the given `name` or `name=value`.
+    Conflict(String, String),
+    /// The rule of a named argument set was broken, with a description of the rule.
+    SetViolation(&'static str, String),
+    /// The argument needs the other one to be given too.
+    MissingRequirement(String, String),
+    /// Required arguments without a value, out of how many are required in total.
+    MissingArgs {
+        missing: Vec<String>,
+        required: usize,
+    },
+    /// Values after "--" were given to a file type that takes none, with the first one.
+    UnexpectedRawValue(String),
 }
 
+/// The error message, without the suggestion of a typo.
+impl fmt::Display for ArgProcessErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgProcessErr::PrintedHelp => write!(f, "Printed the help"),
+            ArgProcessErr::PrintedVersion => write!(f, "Printed the version"),
+            ArgProcessErr::InvalidArg(inv, _) => {
+                f.write_str(&trf("Invalid argument: \"{}\"", &[inv]))
+            }
+            ArgProcessErr::InvalidFileType(invf, _) => {
+                f.write_str(&trf("Invalid file type: \"{}\"", &[invf]))
+            }
+            ArgProcessErr::InvalidFlagValue(invv) => f.write_str(&trf(
+                "Invalid flag value, expected true or false: \"{}\"",
+                &[invv],
+            )),
+            ArgProcessErr::InvalidValue {
+                arg,
+                value,
+                possible,
+            } => f.write_str(&trf(
+                "Invalid value \"{}\" for --{}, possible values: {}",
+                &[value, arg, &possible.join(", ")],
+            )),
+            ArgProcessErr::ValueTooLarge { arg, len } => f.write_str(&trf(
+                "Value for --{} is {} bytes, the limit is {} bytes",
+                &[arg, len, &MAX_VALUE_LEN],
+            )),
+            ArgProcessErr::Rejected { arg, value, reason } => f.write_str(&trf(
+                "Invalid value \"{}\" for --{}: {}",
+                &[value, arg, reason],
+            )),
+            ArgProcessErr::DuplicateArg(arg) => {
+                f.write_str(&trf("--{} was given more than once", &[arg]))
+            }
+            ArgProcessErr::MissingValue(arg) => f.write_str(&trf("Missing value for --{}", &[arg])),
+            ArgProcessErr::Conflict(arg, other) => f.write_str(&trf(
+                "--{} cannot be used together with --{}",
+                &[arg, other],
+            )),
+            ArgProcessErr::SetViolation(set, rule) => f.write_str(&trf(
+                "Invalid combination of arguments ({}): {}",
+                &[set, rule],
+            )),
+            ArgProcessErr::MissingRequirement(arg, other) => f.write_str(&trf(
+                "--{} requires --{} to be given",
+                &[arg, &other.replace('|', " or --")],
+            )),
+            ArgProcessErr::MissingArgs { missing, .. } => f.write_str(&trf(
+                "Missing required arguments: --{}",
+                &[&missing.join(", --")],
+            )),
+            ArgProcessErr::UnexpectedRawValue(value) => {
+                f.write_str(&trf("Unexpected value after \"--\": \"{}\"", &[value]))
+            }
+        }
+    }
+}
+
+/// Definitions are mostly literals, those read from manifests and bundles are owned.
 pub struct Arg {
-    pub name: &'static str,
+    pub name: Cow<'static, str>,
     is_flag: bool,
     is_required: bool,
+    is_multiple: bool,
+    last_wins: bool,
     has_default_value: bool,
-    default_value: &'static str,
+    default_value: Cow<'static, str>,
+    default_provider: Option<&'static str>,
+    short: Option<char>,
+    aliases: Vec<&'static str>,
+    help: Cow<'static, str>,
+    value_name: Option<Cow<'static, str>>,
+    possible_values: Vec<Cow<'static, str>>,
+    normalizer: Option<fn(&str) -> String>,
+    value_type: ValueType,
+    value_parser: Option<ValueParser>,
+    validator: Option<Validator>,
+    conflicts: Vec<&'static str>,
+    requirements: Vec<&'static str>,
 }
 
 impl Arg {
-    pub fn new(arg_name: &'static str) -> Self {
+    pub fn new(arg_name: impl Into<Cow<'static, str>>) -> Self {
         Self {
-            name: arg_name,
+            name: arg_name.into(),
             is_flag: false,
             is_required: false,
+            is_multiple: false,
+            last_wins: false,
             has_default_value: false,
-            default_value: "",
+            default_value: Cow::Borrowed(""),
+            default_provider: None,
+            short: None,
+            aliases: Vec::new(),
+            help: Cow::Borrowed(""),
+            value_name: None,
+            possible_values: Vec::new(),
+            normalizer: None,
+            value_type: ValueType::Str,
+            value_parser: None,
+            validator: None,
+            conflicts: Vec::new(),
+            requirements: Vec::new(),
         }
     }
 
     pub fn flag(mut self, f: bool) -> Self {
         self.is_flag = f;
+        if f {
+            self.value_type = ValueType::Bool;
+        }
         self
     }
 
@@ -92,11 +208,205 @@ impl Arg {
         self
     }
 
-    pub fn default_val(mut self, v: &'static str) -> Self {
-        self.default_value = v;
+    /// Accept the argument several times, all values are available from `get_args`.
+    pub fn multiple(mut self, m: bool) -> Self {
+        self.is_multiple = m;
+        self
+    }
+
+    /// Let a repeated argument replace the earlier value instead of being an error.
+    pub fn last_wins(mut self, l: bool) -> Self {
+        self.last_wins = l;
+        self
+    }
+
+    pub fn default_val(mut self, v: impl Into<Cow<'static, str>>) -> Self {
+        self.default_value = v.into();
         self.has_default_value = true;
         self
     }
+
+    /// Description shown in the generated help.
+    pub fn help(mut self, text: impl Into<Cow<'static, str>>) -> Self {
+        self.help = text.into();
+        self
+    }
+
+    /// Placeholder shown for the value in help, defaults to the upper-cased name.
+    pub fn value_name(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.value_name = Some(name.into());
+        self
+    }
+
+    /// Check and convert values to a type, `Enum` values are also listed in
+    /// help and offered by completions.
+    pub fn value_type(mut self, ty: ValueType) -> Self {
+        if let ValueType::Enum(values) = ty {
+            self.possible_values = values.iter().map(|&v| Cow::Borrowed(v)).collect();
+        }
+        self.value_type = ty;
+        self
+    }
+
+    /// Only accept `values`, listed in help and offered by completions like
+    /// those of an `Enum`, while `value_type` still converts them.
+    pub fn possible_values<V: Into<Cow<'static, str>>>(
+        mut self,
+        values: impl IntoIterator<Item = V>,
+    ) -> Self {
+        self.possible_values = values.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Canonicalize values before they are validated, used or cached.
+    pub fn normalize(mut self, f: fn(&str) -> String) -> Self {
+        self.normalizer = Some(f);
+        self
+    }
+
+    /// Convert values with the `FromStr` of `T` once while parsing, read them
+    /// back with `CommandArg::get_parsed`.
+    pub fn value_parser<T: FromStr + 'static>(mut self) -> Self {
+        self.value_parser = Some(typed::parse_as::<T>);
+        self
+    }
+
+    /// Check normalized values with a domain-specific rule, the error explains the refusal.
+    pub fn validator(mut self, f: Validator) -> Self {
+        self.validator = Some(f);
+        self
+    }
+
+    fn normalize_value(&self, value: &str) -> String {
+        match self.normalizer {
+            Some(f) => f(value),
+            None => value.to_string(),
+        }
+    }
+
+    fn verify_value(&self, value: &str) -> Result<(), ArgProcessErr> {
+        let rejected = |reason| ArgProcessErr::Rejected {
+            arg: self.name.to_string(),
+            value: value.to_string(),
+            reason,
+        };
+        if value.len() > MAX_VALUE_LEN {
+            Err(ArgProcessErr::ValueTooLarge {
+                arg: self.name.to_string(),
+                len: value.len(),
+            })
+        } else if !self.possible_values.is_empty()
+            && !self
+                .possible_values
+                .iter()
+                .any(|p| p.eq_ignore_ascii_case(value))
+        {
+            Err(ArgProcessErr::InvalidValue {
+                arg: self.name.to_string(),
+                value: value.to_string(),
+                possible: self.possible_values.iter().map(|p| p.to_string()).collect(),
+            })
+        } else if let Err(reason) = self.value_type.parse(value) {
+            Err(rejected(reason))
+        } else if let Some(Err(reason)) = self.value_parser.map(|f| f(value).map(|_| ())) {
+            Err(rejected(reason))
+        } else if let Some(Err(reason)) = self.validator.map(|f| f(value)) {
+            Err(rejected(reason))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Refuse to be combined with another argument, `name=value` only conflicts
+    /// when the other argument is given with that value, not by its default.
+    pub fn conflicts_with(mut self, other: &'static str) -> Self {
+        self.conflicts.push(other);
+        self
+    }
+
+    /// Only accept the argument if another one is given too, `a|b` accepts either.
+    pub fn requires(mut self, other: &'static str) -> Self {
+        self.requirements.push(other);
+        self
+    }
+
+    /// Also match `-c`.
+    pub fn short(mut self, c: char) -> Self {
+        self.short = Some(c);
+        self
+    }
+
+    /// Also match `--<name>` as an alternate long name.
+    pub fn alias(mut self, name: &'static str) -> Self {
+        self.aliases.push(name);
+        self
+    }
+
+    /// Compute the default from a named provider in `providers::PROVIDERS`.
+    /// Takes precedence over `default_val`, which is used if the provider yields
+    /// nothing or a value the argument refuses. Required arguments ignore it,
+    /// they must be given.
+    pub fn default_provider(mut self, name: &'static str) -> Self {
+        self.default_provider = Some(name);
+        self
+    }
+}
+
+enum SetRule {
+    AtMostOne,
+    AtLeastOne,
+}
+
+/// A named constraint over several arguments, checked after parsing like
+/// `conflicts_with` and `requires` but for a whole set at once.
+pub struct ArgSet {
+    pub name: &'static str,
+    members: &'static [&'static str],
+    rule: SetRule,
+    condition: Option<&'static str>,
+}
+
+impl ArgSet {
+    /// Members may be given freely until a rule is chosen.
+    pub fn new(name: &'static str, members: &'static [&'static str]) -> Self {
+        Self {
+            name,
+            members,
+            rule: SetRule::AtMostOne,
+            condition: None,
+        }
+    }
+
+    /// Members exclude each other.
+    pub fn at_most_one(mut self) -> Self {
+        self.rule = SetRule::AtMostOne;
+        self
+    }
+
+    /// One of the members has to be given.
+    pub fn at_least_one(mut self) -> Self {
+        self.rule = SetRule::AtLeastOne;
+        self
+    }
+
+    /// Only apply the rule when another argument is given.
+    pub fn when(mut self, arg: &'static str) -> Self {
+        self.condition = Some(arg);
+        self
+    }
+
+    /// One line summary for the help.
+    fn describe(&self) -> String {
+        let names: Vec<String> = self.members.iter().map(|m| format!("--{}", m)).collect();
+        let rule = match self.rule {
+            SetRule::AtMostOne => "At most one of",
+            SetRule::AtLeastOne => "At least one of",
+        };
+        match self.condition {
+            Some(c) => format!("{} {} when --{} is given", rule, names.join(", "), c),
+            None => format!("{} {}", rule, names.join(", ")),
+        }
+    }
 }
 
 pub struct ArgGroup {
@@ -127,11 +437,45 @@ impl ArgGroup {
     }
 }
 
+/// Value of an argument as a config file holds it.
+pub enum ConfigValue {
+    Flag(bool),
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Adds the arguments of one file type, run only when that type is used.
+pub type TypeDefiner = fn(&mut ArgFileTypeView);
+
 pub struct CommandArg {
     file_type: FileType,
+    /// Registered file types in registration order, defined on first use.
+    type_definers: Vec<(FileType, TypeDefiner)>,
     defined_args: HashMap<FileType, Vec<ArgGroup>>,
     general_args: Vec<ArgGroup>,
-    arg_map: HashMap<&'static str, &'static str>,
+    arg_sets: HashMap<FileType, Vec<ArgSet>>,
+    arg_map: HashMap<String, String>,
+    multi_map: HashMap<String, Vec<String>>,
+    typed_map: TypedArgMap,
+    usage_name: Cow<'static, str>,
+    subcommands: &'static [Subcommand],
+    after_help: Cow<'static, str>,
+    takes_file_type: bool,
+    /// Arguments given after the file type, kept to suggest a completed command.
+    given_args: Vec<String>,
+    /// Flags turned off with "--no-<name>", caches may not turn them on again.
+    negated_flags: Vec<String>,
+    /// Help of the values a file type takes after "--".
+    raw_value_help: HashMap<FileType, &'static str>,
+    raw_values: Vec<String>,
+    /// Placeholder and help of a leading word taken by a command without file type.
+    positional_help: Option<(&'static str, &'static str)>,
+    positional: Option<String>,
+    ignore_long_case: bool,
+    /// Values of every source, kept to explain which one an argument took.
+    layers: Vec<Layer>,
+    /// Index in `layers` of the source each argument took its value from.
+    winners: HashMap<String, usize>,
 }
 
 pub struct ArgFileTypeView<'a> {
@@ -153,18 +497,108 @@ impl<'a> DerefMut for ArgFileTypeView<'a> {
     }
 }
 
+impl Default for CommandArg {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CommandArg {
     pub fn new() -> Self {
         Self {
             file_type: FileType::Unknown,
+            type_definers: Vec::new(),
             defined_args: HashMap::new(),
             general_args: Vec::new(),
+            arg_sets: HashMap::new(),
             arg_map: HashMap::new(),
+            multi_map: HashMap::new(),
+            typed_map: TypedArgMap::default(),
+            usage_name: Cow::Borrowed("filetemp"),
+            subcommands: &[],
+            after_help: Cow::Borrowed(""),
+            takes_file_type: true,
+            given_args: Vec::new(),
+            negated_flags: Vec::new(),
+            raw_value_help: HashMap::new(),
+            raw_values: Vec::new(),
+            positional_help: None,
+            positional: None,
+            ignore_long_case: IGNORE_LONG_CASE.load(Ordering::Relaxed),
+            layers: Vec::new(),
+            winners: HashMap::new(),
         }
     }
 
-    pub fn define_file_type(&mut self, ty: FileType) -> ArgFileTypeView<'_> {
-        ArgFileTypeView { arg_ref: self, ty }
+    /// Command shown in the help usage line, e.g. "filetemp lint".
+    pub fn usage_name(&mut self, name: impl Into<Cow<'static, str>>) -> &mut Self {
+        self.usage_name = name.into();
+        self
+    }
+
+    /// List the subcommands in the help and completions.
+    pub fn subcommands(&mut self, table: &'static [Subcommand]) -> &mut Self {
+        self.subcommands = table;
+        self
+    }
+
+    /// For commands that are not about a file type, only general args are accepted
+    /// and the first argument is not a file type.
+    pub fn no_file_type(&mut self) -> &mut Self {
+        self.takes_file_type = false;
+        self
+    }
+
+    /// For commands without a file type, take a leading word that is not an
+    /// option as a value, read back with `get_positional`.
+    pub fn positional(&mut self, value_name: &'static str, help: &'static str) -> &mut Self {
+        self.positional_help = Some((value_name, help));
+        self
+    }
+
+    /// Match long options of this command regardless of case, e.g. "--Proj" as
+    /// "--proj", the same way file types are matched. See `set_ignore_long_case`.
+    pub fn ignore_long_case(&mut self) -> &mut Self {
+        self.ignore_long_case = true;
+        self
+    }
+
+    /// Free text appended to the help.
+    pub fn after_help(&mut self, text: impl Into<Cow<'static, str>>) -> &mut Self {
+        self.after_help = text.into();
+        self
+    }
+
+    /// Accept a file type, its arguments are only built once it is selected
+    /// or all types are needed, e.g. for completions.
+    pub fn register_file_type(&mut self, ty: FileType, define: TypeDefiner) -> &mut Self {
+        self.type_definers.push((ty, define));
+        self
+    }
+
+    /// Registered file types, without defining their arguments.
+    pub fn file_types(&self) -> impl Iterator<Item = FileType> + '_ {
+        self.type_definers.iter().map(|(ty, _)| *ty)
+    }
+
+    fn ensure_type_defined(&mut self, ty: FileType) {
+        if self.defined_args.contains_key(&ty) {
+            return;
+        }
+        self.defined_args.insert(ty, Vec::new());
+
+        let definer = self.type_definers.iter().find(|(t, _)| *t == ty);
+        if let Some(&(_, define)) = definer {
+            define(&mut ArgFileTypeView { arg_ref: self, ty });
+        }
+    }
+
+    /// Build the arguments of every registered file type.
+    pub fn define_all_types(&mut self) {
+        let types: Vec<FileType> = self.file_types().collect();
+        for ty in types {
+            self.ensure_type_defined(ty);
+        }
     }
 
     pub fn add_general_arg_def(&mut self, arg: Arg) -> &mut Self {
@@ -192,108 +626,512 @@ impl CommandArg {
         }
     }
 
-    pub fn get_arg_parsed_unsafe<T: FromStr>(&self, key: &str) -> T
-    where
-        T: FromStr<Err: Debug>,
-    {
-        self.get_arg(key).unwrap().parse::<T>().unwrap()
+    /// All values of an argument defined with `multiple(true)`, in the given order.
+    pub fn get_args(&self, key: &str) -> &[String] {
+        match self.multi_map.get(key) {
+            Some(values) => values,
+            None => &[],
+        }
+    }
+
+    /// Value of an `Int` argument.
+    pub fn get_int(&self, key: &str) -> Option<i64> {
+        self.typed_map.get_int(key)
+    }
+
+    /// Value of a `Path` argument.
+    pub fn get_path(&self, key: &str) -> Option<&Path> {
+        self.typed_map.get_path(key)
+    }
+
+    /// Value of an `Enum` argument, spelled as in its definition.
+    pub fn get_choice(&self, key: &str) -> Option<&'static str> {
+        self.typed_map.get_choice(key)
+    }
+
+    /// Value of an argument with a `value_parser::<T>`.
+    pub fn get_parsed<T: 'static>(&self, key: &str) -> Option<&T> {
+        self.typed_map.get_parsed::<T>(key)
+    }
+
+    /// The leading word of a command with a `positional` value.
+    pub fn get_positional(&self) -> Option<&str> {
+        self.positional.as_deref()
+    }
+
+    /// Values given after "--", passed on as they are.
+    pub fn get_raw_values(&self) -> &[String] {
+        &self.raw_values
     }
 
     pub fn get_flag(&self, key: &str) -> bool {
-        self.arg_map.get(key).is_some()
+        self.typed_map.get_bool(key).unwrap_or(false)
+    }
+
+    /// Names of the arguments the file type defines.
+    pub fn type_arg_names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.defined_args
+            .get(&self.file_type)
+            .into_iter()
+            .flatten()
+            .map(|a| a.name.as_ref())
+    }
+
+    /// Whether `arg`, e.g. "--path", "--path=x" or "-s", names a general
+    /// argument, and if so whether its value is the next argument.
+    pub fn general_option(&self, arg: &str) -> Option<bool> {
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((n, _)) if arg.starts_with("--") => (n, true),
+            _ => (arg, false),
+        };
+        let found = self
+            .general_args
+            .iter()
+            .find(|a| verify_arg(name, a, self.ignore_long_case))?;
+        Some(!found.is_flag && !inline_value)
+    }
+
+    /// Whether `arg` names an argument of `ty` or a general one, as
+    /// `general_option` tells, also for `--no-<flag>`.
+    pub fn known_option(&mut self, ty: FileType, arg: &str) -> Option<bool> {
+        self.ensure_type_defined(ty);
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((n, _)) if arg.starts_with("--") => (n, true),
+            _ => (arg, false),
+        };
+        let ignore_case = self.ignore_long_case;
+        let mut args = self
+            .defined_args
+            .get(&ty)
+            .into_iter()
+            .flatten()
+            .chain(self.general_args.iter());
+        let found = args.find(|a| {
+            verify_arg(name, a, ignore_case)
+                || a.is_flag
+                    && name
+                        .strip_prefix("--no-")
+                        .is_some_and(|n| matches_long_name(n, a, ignore_case))
+        })?;
+        Some(!found.is_flag && !inline_value)
+    }
+
+    /// Whether `ty` takes raw values after `--`.
+    pub fn takes_raw_values(&mut self, ty: FileType) -> bool {
+        self.ensure_type_defined(ty);
+        self.raw_value_help.contains_key(&ty)
+    }
+
+    /// The argument of `ty` or the general one a config file names `arg`,
+    /// without dashes or by an alias.
+    fn config_arg(&mut self, ty: FileType, arg: &str) -> Result<&Arg, ArgProcessErr> {
+        self.ensure_type_defined(ty);
+        let ignore_case = self.ignore_long_case;
+        let args = || {
+            self.defined_args
+                .get(&ty)
+                .into_iter()
+                .flatten()
+                .chain(self.general_args.iter())
+        };
+        match args().find(|a| matches_long_name(arg, a, ignore_case)) {
+            Some(a) => Ok(a),
+            None => {
+                let suggestion =
+                    suggest::closest(arg, args().map(|a| a.name.as_ref())).map(String::from);
+                Err(ArgProcessErr::InvalidArg(arg.to_string(), suggestion))
+            }
+        }
+    }
+
+    /// The canonical name of the argument a config file names `arg`.
+    pub fn config_arg_name(&mut self, ty: FileType, arg: &str) -> Result<String, ArgProcessErr> {
+        self.config_arg(ty, arg).map(|a| a.name.to_string())
+    }
+
+    /// The canonical name of the argument a config file names `arg`, with
+    /// `values` checked and normalized as the config holds them.
+    pub fn config_value(
+        &mut self,
+        ty: FileType,
+        arg: &str,
+        values: &[&str],
+    ) -> Result<(String, ConfigValue), ArgProcessErr> {
+        let found = self.config_arg(ty, arg)?;
+
+        let value = match values {
+            [v] if found.is_flag => match *v {
+                "true" => ConfigValue::Flag(true),
+                "false" => ConfigValue::Flag(false),
+                _ => return Err(ArgProcessErr::InvalidFlagValue(v.to_string())),
+            },
+            [] if !found.is_multiple => {
+                return Err(ArgProcessErr::MissingValue(found.name.to_string()));
+            }
+            [_, _, ..] if !found.is_multiple => {
+                return Err(ArgProcessErr::DuplicateArg(found.name.to_string()));
+            }
+            _ => {
+                let mut checked = Vec::with_capacity(values.len());
+                for &v in values.iter() {
+                    let v = found.normalize_value(v);
+                    found.verify_value(&v)?;
+                    checked.push(v);
+                }
+                match checked.pop() {
+                    Some(v) if !found.is_multiple => ConfigValue::One(v),
+                    last => ConfigValue::Many(checked.into_iter().chain(last).collect()),
+                }
+            }
+        };
+        Ok((found.name.to_string(), value))
     }
 
     pub fn get_file_type(&self) -> FileType {
         self.file_type
     }
 
-    pub fn process_program_args(&mut self) -> Result<(), ArgProcessErr> {
-        let a: Vec<&'static str> = collect_raw_args();
-        if a.is_empty() {
-            println!("{}", HELP_MESSAGE);
+    pub fn process_program_args<S: AsRef<str>>(&mut self, a: &[S]) -> Result<(), ArgProcessErr> {
+        let a: Vec<&str> = a.iter().map(AsRef::as_ref).collect();
+        let a = a.as_slice();
+        if !self.takes_file_type {
+            self.defined_args.entry(self.file_type).or_default();
+            if a.is_empty() || self.asks_for_help(a) {
+                print!("{}", self.help_message());
+                return Err(ArgProcessErr::PrintedHelp);
+            }
+            let a = match a {
+                [first, rest @ ..] if self.positional_help.is_some() && !first.starts_with('-') => {
+                    self.positional = Some(first.to_string());
+                    rest
+                }
+                _ => a,
+            };
+            self.given_args = a.iter().map(|s| s.to_string()).collect();
+            self.process_arg_impl(a)?;
+            return self.check_relations(false);
+        }
+
+        if a.is_empty() || is_help_arg(a[0]) {
+            print!("{}", self.help_message());
             return Err(ArgProcessErr::PrintedHelp);
         }
 
+        // Only in the first position, file types may define their own --version.
+        if is_version_arg(a[0]) {
+            println!("{}", version_message());
+            return Err(ArgProcessErr::PrintedVersion);
+        }
+
         let file_type_name = a[0];
-        match FileType::match_type(&file_type_name) {
-            FileType::Unknown => return Err(ArgProcessErr::InvalidFileType(file_type_name)),
-            ty @ _ => self.file_type = ty,
+        match FileType::match_type(file_type_name) {
+            FileType::Unknown => {
+                let commands = self
+                    .file_types()
+                    .map(|t| t.to_str())
+                    .chain(self.subcommands.iter().filter_map(|s| s.words().next()));
+                let suggestion = suggest::closest(file_type_name, commands);
+                return Err(ArgProcessErr::InvalidFileType(
+                    file_type_name.to_string(),
+                    suggestion,
+                ));
+            }
+            ty => {
+                self.file_type = ty;
+                self.ensure_type_defined(ty);
+            }
         };
 
-        self.process_arg_impl(&a[1..])
-    }
+        if self.asks_for_help(&a[1..]) {
+            print!("{}", self.help_message());
+            return Err(ArgProcessErr::PrintedHelp);
+        }
 
-    pub fn query_valid_args(&mut self) -> impl Iterator<Item = &ArgGroup> + Clone {
-        let ty_args = self.defined_args.entry(self.file_type).or_default().iter();
-        let gn_args = self.general_args.iter();
+        self.given_args = a[1..].iter().map(|s| s.to_string()).collect();
+        self.process_arg_impl(&a[1..])?;
+        self.check_relations(false)
+    }
 
-        ty_args.chain(gn_args)
+    /// Whether `--help` or `-h` is given in place of an option, rather than as
+    /// the value of one or after "--".
+    fn asks_for_help(&self, args: &[&str]) -> bool {
+        let defined: Vec<&ArgGroup> = self
+            .defined_args
+            .get(&self.file_type)
+            .into_iter()
+            .flatten()
+            .chain(self.general_args.iter())
+            .collect();
+
+        let mut value_next = false;
+        for &arg in args {
+            if std::mem::take(&mut value_next) {
+                continue;
+            }
+            if arg == "--" {
+                break;
+            }
+            if is_help_arg(arg) {
+                return true;
+            }
+            value_next = !arg.contains('=')
+                && defined
+                    .iter()
+                    .any(|d| !d.is_flag && verify_arg(arg, d, self.ignore_long_case));
+        }
+        false
     }
 
-    /// Insert an argument item if absent.
-    /// Assumes that arg and content is correct.
-    pub fn insert_arg_if_absent(&mut self, arg: &'static str, content: &'static str) {
-        self.arg_map.entry(arg).or_insert(content);
+    /// Check `conflicts_with`, `requires` and the argument sets once every
+    /// source is applied. Defaults don't count as given, but a `name=value`
+    /// conflict also holds for the default value of `name`.
+    pub fn verify_relations(&self) -> Result<(), ArgProcessErr> {
+        self.check_relations(true)
+    }
 
-        for valid_args in self
+    /// Before the other sources are applied, only conflicts between given
+    /// arguments are final, another source may still add a required one.
+    fn check_relations(&self, resolved: bool) -> Result<(), ArgProcessErr> {
+        let all_args = self
             .defined_args
-            .get_mut(&self.file_type)
-            .unwrap()
-            .iter_mut()
-            .chain(self.general_args.iter_mut())
-        {
-            if valid_args.name == arg {
-                valid_args.found = true;
+            .get(&self.file_type)
+            .into_iter()
+            .flatten()
+            .chain(self.general_args.iter());
+
+        for arg in all_args {
+            if !self.is_given(&arg.name) {
+                continue;
+            }
+
+            for &conflict in arg.conflicts.iter() {
+                let is_given = match conflict.split_once('=') {
+                    Some((name, value)) => {
+                        self.is_given(name)
+                            && self
+                                .get_arg(name)
+                                .is_some_and(|v| v.eq_ignore_ascii_case(value))
+                    }
+                    None => self.is_given(conflict),
+                };
+                if is_given {
+                    return Err(ArgProcessErr::Conflict(
+                        arg.name.to_string(),
+                        conflict.to_string(),
+                    ));
+                }
+            }
+
+            for &requirement in arg.requirements.iter() {
+                if resolved && !requirement.split('|').any(|r| self.is_given(r)) {
+                    return Err(ArgProcessErr::MissingRequirement(
+                        arg.name.to_string(),
+                        requirement.to_string(),
+                    ));
+                }
+            }
+        }
+
+        for set in self.arg_sets.get(&self.file_type).into_iter().flatten() {
+            if set.condition.is_some_and(|c| !self.is_given(c)) {
+                continue;
+            }
+
+            let given: Vec<&'static str> = set
+                .members
+                .iter()
+                .copied()
+                .filter(|m| self.is_given(m))
+                .collect();
+            match set.rule {
+                SetRule::AtMostOne if given.len() > 1 => {
+                    return Err(ArgProcessErr::SetViolation(set.name, set.describe()));
+                }
+                SetRule::AtLeastOne if resolved && given.is_empty() => {
+                    return Err(ArgProcessErr::SetViolation(set.name, set.describe()));
+                }
+                _ => {}
             }
         }
+
+        Ok(())
     }
 
+    pub fn query_valid_args(&mut self) -> impl Iterator<Item = &ArgGroup> + Clone {
+        let ty_args = self.defined_args.entry(self.file_type).or_default().iter();
+        let gn_args = self.general_args.iter();
+
+        ty_args.chain(gn_args)
+    }
+
+    /// Arguments of the file type, general ones only describe a single run.
     pub fn extract_args(&self) -> Vec<ArgPair<'_>> {
         let mut args: Vec<ArgPair> = Vec::new();
-        for (&arg, content) in self.arg_map.iter() {
-            args.push(ArgPair { arg, content });
+        for (arg, content) in self.arg_map.iter() {
+            if self.general_args.iter().any(|g| g.name == *arg) {
+                continue;
+            }
+
+            match self.multi_map.get(arg) {
+                Some(values) => args.extend(values.iter().map(|v| ArgPair { arg, content: v })),
+                None => args.push(ArgPair { arg, content }),
+            }
         }
 
         args
     }
 
-    fn process_arg_impl(&mut self, args: &[&'static str]) -> Result<(), ArgProcessErr> {
+    /// Like `extract_args` without the defaults, for saving to a cache so it
+    /// keeps following them.
+    pub fn extract_given_args(&self) -> Vec<ArgPair<'_>> {
+        let mut args = self.extract_args();
+        args.retain(|pair| self.is_given(pair.arg));
+        args
+    }
+
+    fn process_arg_impl(&mut self, args: &[&str]) -> Result<(), ArgProcessErr> {
         let valid_args = self.defined_args.get_mut(&self.file_type).unwrap();
         let general_args: &mut Vec<ArgGroup> = &mut self.general_args;
+        let ignore_case = self.ignore_long_case;
+
+        // Index of the argument waiting for its value, in definition order.
+        let mut found_arg: Option<usize> = None;
+
+        for (pos, &arg) in args.iter().enumerate() {
+            if found_arg.is_none() && arg == "--" {
+                let rest = &args[pos + 1..];
+                if let Some(&first) = rest.first()
+                    && !self.raw_value_help.contains_key(&self.file_type)
+                {
+                    return Err(ArgProcessErr::UnexpectedRawValue(first.to_string()));
+                }
+                self.raw_values = rest.iter().map(|s| s.to_string()).collect();
+                break;
+            }
 
-        let mut found_arg = false;
-        let mut arg_ref: &'static str = "";
-
-        for arg in args.into_iter() {
-            if found_arg {
-                self.arg_map.entry(arg_ref).or_insert(arg);
-                found_arg = false;
+            if let Some(idx) = found_arg.take() {
+                let arg_ref = valid_args
+                    .iter()
+                    .chain(general_args.iter())
+                    .nth(idx)
+                    .unwrap();
+                let arg = arg_ref.normalize_value(arg);
+                arg_ref.verify_value(&arg)?;
+                store_value(
+                    &mut self.arg_map,
+                    &mut self.multi_map,
+                    &mut self.typed_map,
+                    arg_ref,
+                    arg,
+                );
             } else {
                 let mut verified = false;
 
-                for valid_arg in valid_args.iter_mut().chain(general_args.iter_mut()) {
-                    if !verify_arg(&arg, valid_arg.name) {
+                // "--name=value" carries its value in the same token.
+                let (name, inline_value) = match arg.split_once('=') {
+                    Some((n, v)) if arg.starts_with("--") => (n, Some(v)),
+                    _ => (arg, None),
+                };
+
+                // "--no-<flag>", unless an argument is really named like that.
+                let negated = name
+                    .get(..5)
+                    .filter(|p| long_name_eq(p, "--no-", ignore_case))
+                    .map(|_| &name[5..])
+                    .filter(|_| {
+                        !valid_args
+                            .iter()
+                            .chain(general_args.iter())
+                            .any(|a| verify_arg(name, a, ignore_case))
+                    });
+
+                for (idx, valid_arg) in valid_args
+                    .iter_mut()
+                    .chain(general_args.iter_mut())
+                    .enumerate()
+                {
+                    let negate = negated.is_some_and(|n| {
+                        valid_arg.is_flag && matches_long_name(n, valid_arg, ignore_case)
+                    });
+                    if !negate && !verify_arg(name, valid_arg, ignore_case) {
                         continue;
                     }
 
+                    if valid_arg.found && !valid_arg.is_multiple {
+                        if !valid_arg.last_wins {
+                            return Err(ArgProcessErr::DuplicateArg(valid_arg.name.to_string()));
+                        }
+                        self.arg_map.remove(valid_arg.name.as_ref());
+                        self.typed_map.remove(&valid_arg.name);
+                    }
+                    valid_arg.found = true;
+
                     if !valid_arg.is_flag {
-                        arg_ref = &valid_arg.name;
-                        found_arg = true;
+                        match inline_value {
+                            Some(v) => {
+                                let v = valid_arg.normalize_value(v);
+                                valid_arg.verify_value(&v)?;
+                                store_value(
+                                    &mut self.arg_map,
+                                    &mut self.multi_map,
+                                    &mut self.typed_map,
+                                    valid_arg,
+                                    v,
+                                );
+                            }
+                            None => found_arg = Some(idx),
+                        }
                     } else {
-                        self.arg_map.entry(valid_arg.name).or_insert("true");
+                        let value = match (negate, inline_value) {
+                            (true, None) => Some("false"),
+                            (true, Some(_)) => {
+                                return Err(ArgProcessErr::InvalidFlagValue(arg.to_string()));
+                            }
+                            (false, v) => v,
+                        };
+                        match value {
+                            None | Some("true") => {
+                                self.negated_flags.retain(|n| *n != valid_arg.name);
+                                store_value(
+                                    &mut self.arg_map,
+                                    &mut self.multi_map,
+                                    &mut self.typed_map,
+                                    valid_arg,
+                                    String::from("true"),
+                                );
+                            }
+                            Some("false") => self.negated_flags.push(valid_arg.name.to_string()),
+                            Some(_) => {
+                                return Err(ArgProcessErr::InvalidFlagValue(arg.to_string()));
+                            }
+                        }
                     }
 
-                    valid_arg.found = true;
                     verified = true;
                     break;
                 }
 
                 if !verified {
-                    return Err(ArgProcessErr::InvalidArg(arg));
+                    let suggestion = name.strip_prefix("--").and_then(|long| {
+                        let names = valid_args.iter().chain(general_args.iter()).flat_map(|a| {
+                            std::iter::once(a.name.as_ref()).chain(a.aliases.iter().copied())
+                        });
+                        suggest::closest(long, names).map(|n| format!("--{}", n))
+                    });
+                    return Err(ArgProcessErr::InvalidArg(arg.to_string(), suggestion));
                 }
             }
         }
 
+        // The last option still waits for its value.
+        if let Some(idx) = found_arg {
+            let arg = valid_args
+                .iter()
+                .chain(general_args.iter())
+                .nth(idx)
+                .unwrap();
+            return Err(ArgProcessErr::MissingValue(arg.name.to_string()));
+        }
         Ok(())
     }
 
@@ -302,54 +1140,176 @@ impl CommandArg {
         let general_args: &mut Vec<ArgGroup> = &mut self.general_args;
         let all_valid_args = valid_args.iter_mut().chain(general_args.iter_mut());
 
-        let mut missing_args = false;
-        let mut missing_msg = String::new();
+        let mut missing = Vec::new();
+        let mut required = 0;
+        // Argument, value and provider of the defaults used, empty for `default_val`.
+        let mut defaults: Vec<(String, String, &'static str)> = Vec::new();
         for valid_arg in all_valid_args {
-            if valid_arg.found {
+            if valid_arg.is_required {
+                required += 1;
+            }
+            if valid_arg.found
+                || self.arg_map.contains_key(valid_arg.name.as_ref())
+                || self.multi_map.contains_key(valid_arg.name.as_ref())
+            {
                 continue;
             }
 
             if valid_arg.is_required {
-                if missing_args {
-                    missing_msg.push_str(", ");
-                }
-
-                missing_msg.push_str(valid_arg.name);
-                missing_args = true;
-
+                missing.push(valid_arg.name.to_string());
                 continue;
             }
 
+            if let Some((provider, v)) = valid_arg
+                .default_provider
+                .and_then(providers::find_provider)
+                .and_then(|p| Some((p.name, p.provide()?)))
+            {
+                // Provided values are checked like given ones.
+                let v = valid_arg.normalize_value(&v);
+                match valid_arg.verify_value(&v) {
+                    Ok(()) => {
+                        logging::verbose(format_args!(
+                            "Using \"{}\" from the {} provider for --{}",
+                            v, provider, valid_arg.name
+                        ));
+                        store_value(
+                            &mut self.arg_map,
+                            &mut self.multi_map,
+                            &mut self.typed_map,
+                            valid_arg,
+                            v.clone(),
+                        );
+                        defaults.push((valid_arg.name.to_string(), v, provider));
+                        continue;
+                    }
+                    Err(e) => logging::warn(trf(
+                        "Ignoring the value of the {} provider for --{}: {}",
+                        &[&provider, &valid_arg.name, &e],
+                    )),
+                }
+            }
+
             if valid_arg.has_default_value {
-                self.arg_map.insert(valid_arg.name, valid_arg.default_value);
+                let v = valid_arg.default_value.to_string();
+                logging::verbose(format_args!(
+                    "Using the default \"{}\" for --{}",
+                    v, valid_arg.name
+                ));
+                store_value(
+                    &mut self.arg_map,
+                    &mut self.multi_map,
+                    &mut self.typed_map,
+                    valid_arg,
+                    v.clone(),
+                );
+                defaults.push((valid_arg.name.to_string(), v, ""));
             }
         }
+        for (arg, value, provider) in defaults {
+            self.record_default(&arg, &value, provider);
+        }
 
-        if missing_args {
-            Err(ArgProcessErr::MissingArg(missing_msg))
-        } else {
+        if missing.is_empty() {
             Ok(())
+        } else {
+            Err(ArgProcessErr::MissingArgs { missing, required })
         }
     }
 }
 
 impl<'a> ArgFileTypeView<'a> {
+    /// The file type being defined, types sharing a definer tell themselves apart by it.
+    pub fn file_type(&self) -> FileType {
+        self.ty
+    }
+
     pub fn add_arg_def(&mut self, arg: Arg) -> &mut Self {
         self.arg_ref.add_arg_def(self.ty, arg);
         self
     }
+
+    pub fn add_arg_set(&mut self, set: ArgSet) -> &mut Self {
+        self.arg_ref.arg_sets.entry(self.ty).or_default().push(set);
+        self
+    }
+
+    /// Take values after "--" even if they look like options, described by `help`.
+    pub fn raw_values(&mut self, help: &'static str) -> &mut Self {
+        self.arg_ref.raw_value_help.insert(self.ty, help);
+        self
+    }
 }
 
-fn verify_arg(arg: &str, valid_arg: &str) -> bool {
-    if arg.starts_with("--") && arg.len() > 2 {
-        valid_arg.eq(&arg[2..])
+/// Record a value and its typed form, repeats of single-valued arguments keep the first one.
+fn store_value(
+    arg_map: &mut HashMap<String, String>,
+    multi_map: &mut HashMap<String, Vec<String>>,
+    typed_map: &mut TypedArgMap,
+    arg: &Arg,
+    value: String,
+) {
+    if let Ok(Some(t)) = arg.value_type.parse(&value) {
+        typed_map.insert_if_absent(&arg.name, t);
+    }
+    if let Some(Ok(v)) = arg.value_parser.map(|f| f(&value)) {
+        typed_map.insert_parsed_if_absent(&arg.name, v);
+    }
+    if arg.is_multiple {
+        multi_map
+            .entry(arg.name.to_string())
+            .or_default()
+            .push(value.clone());
+    }
+    arg_map.entry(arg.name.to_string()).or_insert(value);
+}
+
+fn is_help_arg(arg: &str) -> bool {
+    arg == "--help" || arg == "-h"
+}
+
+fn is_version_arg(arg: &str) -> bool {
+    arg == "--version" || arg == "-V"
+}
+
+/// Crate version with the commit and date it was built from.
+pub fn version_message() -> String {
+    format!(
+        "filetemp {} ({} {})",
+        env!("CARGO_PKG_VERSION"),
+        env!("FILETEMP_GIT_COMMIT"),
+        env!("FILETEMP_BUILD_DATE")
+    )
+}
+
+fn long_name_eq(given: &str, defined: &str, ignore_case: bool) -> bool {
+    if ignore_case {
+        given.eq_ignore_ascii_case(defined)
+    } else {
+        given == defined
+    }
+}
+
+fn matches_long_name(long: &str, valid_arg: &Arg, ignore_case: bool) -> bool {
+    std::iter::once(valid_arg.name.as_ref())
+        .chain(valid_arg.aliases.iter().copied())
+        .any(|n| long_name_eq(long, n, ignore_case))
+}
+
+fn verify_arg(arg: &str, valid_arg: &Arg, ignore_case: bool) -> bool {
+    if let Some(long) = arg.strip_prefix("--") {
+        !long.is_empty() && matches_long_name(long, valid_arg, ignore_case)
+    } else if let Some(short) = arg.strip_prefix('-') {
+        let mut chars = short.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => valid_arg.short == Some(c),
+            _ => false,
+        }
     } else {
         false
     }
 }
 
-fn collect_raw_args() -> Vec<&'static str> {
-    let mut a = std::env::args();
-    a.next();
-    a.map(|arg| &*Box::leak(arg.into_boxed_str())).collect()
+pub fn collect_raw_args() -> Vec<String> {
+    std::env::args().skip(1).collect()
 }
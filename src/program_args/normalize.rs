@@ -0,0 +1,137 @@
+//! Normalizers for `Arg::normalize`.
+
+use std::path::{Component, Path, PathBuf};
+
+pub fn trim(value: &str) -> String {
+    value.trim().to_string()
+}
+
+pub fn lowercase(value: &str) -> String {
+    value.trim().to_lowercase()
+}
+
+/// "build/", "./build" and "src/../build" name the same directory. A leading
+/// `~` is the home directory and `$NAME` or `${NAME}` an environment variable,
+/// one that is not set is kept for the validator to report.
+pub fn path(value: &str) -> String {
+    let value = value.trim();
+    let home = match value.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+            std::env::home_dir().map(|h| format!("{}{}", h.display(), rest))
+        }
+        _ => None,
+    };
+    let value = home.as_deref().unwrap_or(value);
+    let value = expand_vars(value).unwrap_or_else(|_| value.to_string());
+
+    // "C:build" is relative to the current directory of drive C, only known to
+    // the system, and a ".." in it cannot be resolved from the text alone.
+    let drive_relative = matches!(
+        Path::new(&value).components().next(),
+        Some(Component::Prefix(_))
+    ) && !Path::new(&value).has_root();
+    let value = match drive_relative {
+        true => std::path::absolute(&value).map_or(value, |p| p.to_string_lossy().into_owned()),
+        false => value,
+    };
+
+    // Resolve `.` and `..` without following links, `..` above the start is kept.
+    let mut resolved = PathBuf::new();
+    for component in Path::new(&value).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match resolved.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    resolved.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => resolved.push(".."),
+            },
+            c => resolved.push(c),
+        }
+    }
+    match resolved.as_os_str().is_empty() {
+        true if !value.is_empty() => String::from("."),
+        _ => resolved.to_string_lossy().into_owned(),
+    }
+}
+
+/// CMake reads a backslash as an escape, so paths written into CMake files use
+/// forward slashes, e.g. "C:\libs\z.lib" becomes "C:/libs/z.lib".
+pub fn forward_slashes(value: &str) -> String {
+    value.trim().replace('\\', "/")
+}
+
+/// Names Windows keeps for devices, reserved in any case and with any extension.
+const WINDOWS_DEVICE_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Why Windows could not create a file or directory named like a component of
+/// `path`, if it could not. UNC and drive prefixes are not names and pass.
+pub fn windows_name_problem(path: &Path) -> Option<String> {
+    for component in path.components() {
+        let name = match component {
+            Component::Normal(n) => n.to_string_lossy(),
+            _ => continue,
+        };
+        let stem = name.split('.').next().unwrap_or_default().trim_end();
+        if WINDOWS_DEVICE_NAMES
+            .iter()
+            .any(|d| d.eq_ignore_ascii_case(stem))
+        {
+            return Some(format!("\"{}\" is a device name on Windows", name));
+        }
+        if name.ends_with(['.', ' ']) {
+            return Some(format!(
+                "\"{}\" ends with a dot or space, which Windows drops",
+                name
+            ));
+        }
+    }
+    None
+}
+
+/// `value` with each `$NAME` and `${NAME}` replaced by the environment variable,
+/// or the name of the first one that is not set.
+pub fn expand_vars(value: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(idx) = rest.find('$') {
+        out.push_str(&rest[..idx]);
+        let after = &rest[idx + 1..];
+        let (name, len) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            },
+            None => {
+                let end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                (&after[..end], end)
+            }
+        };
+        if name.is_empty() {
+            out.push('$');
+            rest = after;
+            continue;
+        }
+        match std::env::var(name) {
+            Ok(v) => out.push_str(&v),
+            Err(_) => return Err(name.to_string()),
+        }
+        rest = &after[len..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Language standards given as "C++17", "c11" or "gnu++20" become the bare number.
+pub fn language_standard(value: &str) -> String {
+    value
+        .trim()
+        .trim_start_matches(|c: char| c.is_ascii_alphabetic() || c == '+')
+        .to_string()
+}
@@ -0,0 +1,153 @@
+use std::io::{BufRead, IsTerminal, Write};
+
+use super::{Arg, ArgProcessErr, CommandArg, store_value};
+use crate::i18n::{tr, trf};
+
+/// Prompting only makes sense when someone can answer.
+pub fn can_prompt() -> bool {
+    std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+}
+
+/// Ask a yes or no question on stderr, an empty answer is `default`.
+pub fn confirm(question: &str, default: bool) -> bool {
+    eprint!(
+        "{} {} ",
+        tr(question),
+        tr(if default { "[Y/n]" } else { "[y/N]" })
+    );
+    std::io::stderr().flush().ok();
+
+    let mut line = String::new();
+    if std::io::stdin().lock().read_line(&mut line).unwrap_or(0) == 0 {
+        eprintln!();
+        return false;
+    }
+    match line.trim() {
+        "" => default,
+        answer => matches!(answer, "y" | "Y" | "yes") || answer == tr("yes"),
+    }
+}
+
+/// Ask for a line of text on stderr, an empty answer is `default` if given.
+/// None at the end of input.
+pub fn ask(question: &str, default: Option<&str>) -> Option<String> {
+    match default {
+        Some(d) => eprint!("{} [{}]: ", tr(question), d),
+        None => eprint!("{}: ", tr(question)),
+    }
+    std::io::stderr().flush().ok();
+
+    let mut line = String::new();
+    if std::io::stdin().lock().read_line(&mut line).unwrap_or(0) == 0 {
+        eprintln!();
+        return None;
+    }
+    match line.trim() {
+        "" => Some(default.unwrap_or_default().to_string()),
+        answer => Some(answer.to_string()),
+    }
+}
+
+/// An answer to the question for an argument value.
+pub(super) enum Answer {
+    Value(String),
+    Empty,
+    Ended,
+}
+
+/// Ask for a value of `arg`, showing `default` as what an empty answer gives.
+/// Invalid answers are asked again.
+pub(super) fn ask_value(arg: &Arg, default: Option<&str>) -> Answer {
+    loop {
+        let value_name = match &arg.value_name {
+            Some(v) => v.to_string(),
+            None => arg.name.to_uppercase(),
+        };
+        eprint!("--{} <{}>", arg.name, value_name);
+        if !arg.help.is_empty() {
+            eprint!(" ({})", tr(&arg.help));
+        }
+        if !arg.possible_values.is_empty() {
+            eprint!(" {{{}}}", arg.possible_values.join(", "));
+        }
+        if let Some(d) = default {
+            eprint!(" [{}]", d);
+        }
+        eprint!(": ");
+        std::io::stderr().flush().ok();
+
+        let mut line = String::new();
+        if std::io::stdin().lock().read_line(&mut line).unwrap_or(0) == 0 {
+            eprintln!();
+            return Answer::Ended;
+        }
+
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            return Answer::Empty;
+        }
+
+        let value = arg.normalize_value(line);
+        match arg.verify_value(&value) {
+            Ok(()) => return Answer::Value(value),
+            Err(ArgProcessErr::Rejected { reason, .. }) => eprintln!("{}", reason),
+            Err(ArgProcessErr::InvalidValue { possible, .. }) => {
+                eprintln!("{}", trf("Possible values: {}", &[&possible.join(", ")]))
+            }
+            Err(_) => eprintln!("{}", tr("Invalid value")),
+        }
+    }
+}
+
+impl CommandArg {
+    /// Ask on stderr for each missing argument and read the answers from stdin.
+    /// An empty answer takes the default if there is one, invalid answers are asked again.
+    pub fn prompt_for_args(&mut self, missing: &[String]) -> Result<(), ArgProcessErr> {
+        let mut still_missing = Vec::new();
+
+        for name in missing.iter() {
+            let arg = match self
+                .defined_args
+                .get_mut(&self.file_type)
+                .into_iter()
+                .flatten()
+                .chain(self.general_args.iter_mut())
+                .find(|a| a.name == *name)
+            {
+                Some(a) => a,
+                None => continue,
+            };
+
+            let default = arg.has_default_value.then(|| arg.default_value.to_string());
+            let value = match ask_value(arg, default.as_deref()) {
+                Answer::Value(v) => Some(v),
+                Answer::Empty => default,
+                Answer::Ended => None,
+            };
+
+            match value {
+                Some(v) => {
+                    store_value(
+                        &mut self.arg_map,
+                        &mut self.multi_map,
+                        &mut self.typed_map,
+                        arg,
+                        v,
+                    );
+                    arg.found = true;
+                }
+                None => still_missing.push(name.clone()),
+            }
+        }
+
+        if still_missing.is_empty() {
+            Ok(())
+        } else {
+            let required = self.query_valid_args().filter(|a| a.is_required).count();
+            Err(ArgProcessErr::MissingArgs {
+                missing: still_missing,
+                required,
+            })
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    fmt::Write,
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::file_types::cmake_version::CMAKE_RELEASES;
+
+/// Source of a default value computed at run time, attached with `Arg::default_provider`.
+pub struct ValueProvider {
+    pub name: &'static str,
+    pub description: &'static str,
+    provide: fn() -> Option<String>,
+}
+
+impl ValueProvider {
+    pub fn provide(&self) -> Option<String> {
+        (self.provide)()
+    }
+}
+
+pub const PROVIDERS: &[ValueProvider] = &[
+    ValueProvider {
+        name: "cmake-latest",
+        description: "Latest stable CMake release known to filetemp",
+        provide: provide_cmake_latest,
+    },
+    ValueProvider {
+        name: "git-branch",
+        description: "Branch checked out in the current directory",
+        provide: provide_git_branch,
+    },
+    ValueProvider {
+        name: "dirname",
+        description: "Name of the current directory",
+        provide: provide_dirname,
+    },
+    ValueProvider {
+        name: "os",
+        description: "Name of the operating system",
+        provide: provide_os,
+    },
+    ValueProvider {
+        name: "date",
+        description: "Today's date as YYYY-MM-DD, in UTC",
+        provide: provide_date,
+    },
+    ValueProvider {
+        name: "year",
+        description: "The current year, in UTC",
+        provide: provide_year,
+    },
+    ValueProvider {
+        name: "user",
+        description: "Login name of the current user",
+        provide: provide_user,
+    },
+    ValueProvider {
+        name: "git-user-name",
+        description: "user.name of the git configuration",
+        provide: provide_git_user_name,
+    },
+    ValueProvider {
+        name: "git-user-email",
+        description: "user.email of the git configuration",
+        provide: provide_git_user_email,
+    },
+];
+
+pub fn find_provider(name: &str) -> Option<&'static ValueProvider> {
+    PROVIDERS.iter().find(|p| p.name == name)
+}
+
+/// Table of all providers with the value each yields right now.
+pub fn list_providers() -> String {
+    let mut out = String::new();
+    let width = PROVIDERS.iter().map(|p| p.name.len()).max().unwrap_or(0) + 2;
+    for p in PROVIDERS.iter() {
+        writeln!(
+            &mut out,
+            "{:<width$}{:<48}{}",
+            p.name,
+            p.description,
+            p.provide().unwrap_or_else(|| String::from("(unavailable)"))
+        )
+        .unwrap();
+    }
+
+    out
+}
+
+fn provide_cmake_latest() -> Option<String> {
+    CMAKE_RELEASES.last().map(|v| v.to_string())
+}
+
+fn provide_git_branch() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let branch = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    if branch.is_empty() || branch == "HEAD" {
+        None
+    } else {
+        Some(branch)
+    }
+}
+
+fn provide_dirname() -> Option<String> {
+    let dir = std::env::current_dir().ok()?;
+    dir.file_name().map(|n| n.to_string_lossy().into_owned())
+}
+
+fn provide_os() -> Option<String> {
+    Some(String::from(std::env::consts::OS))
+}
+
+fn provide_date() -> Option<String> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+    Some(format_date(now.as_secs() as i64))
+}
+
+fn provide_year() -> Option<String> {
+    provide_date().map(|d| d[..4].to_string())
+}
+
+fn provide_user() -> Option<String> {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok()
+        .filter(|u| !u.is_empty())
+}
+
+fn provide_git_user_name() -> Option<String> {
+    git_config("user.name")
+}
+
+fn provide_git_user_email() -> Option<String> {
+    git_config("user.email")
+}
+
+fn git_config(key: &str) -> Option<String> {
+    let output = Command::new("git").args(["config", key]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let value = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    if value.is_empty() { None } else { Some(value) }
+}
+
+/// `YYYY-MM-DD` of a Unix time, in UTC.
+pub fn format_date(secs: i64) -> String {
+    // Civil date from days since the epoch, after Howard Hinnant's `civil_from_days`.
+    let z = secs.div_euclid(86400) + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
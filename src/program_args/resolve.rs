@@ -0,0 +1,279 @@
+//! Values of every source an argument can come from, merged by a fixed precedence.
+
+use std::fmt;
+
+use super::{ArgGroup, ArgProcessErr, CommandArg, store_value, suggest};
+use crate::logging;
+
+/// Prefix of the environment variables giving file type arguments,
+/// e.g. `FILETEMP_PROJ` for `--proj` or `FILETEMP_TARGET_NAME` for `--target-name`.
+pub const ENV_PREFIX: &str = "FILETEMP_";
+
+/// Where values come from, from the highest precedence to the lowest.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Source {
+    CommandLine,
+    /// The object of `--from-json`.
+    Json,
+    /// The lines of `--stdin-args`.
+    Stdin,
+    Environment,
+    ProjectConfig,
+    UserConfig,
+    Cache,
+    Default,
+}
+
+/// Values of one source, `origin` names the file, cache, variable or provider
+/// they were read from.
+pub struct Layer {
+    pub source: Source,
+    pub origin: String,
+    values: Vec<(String, Vec<String>)>,
+}
+
+impl Layer {
+    pub fn new(source: Source, origin: impl Into<String>) -> Self {
+        Self {
+            source,
+            origin: origin.into(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Add a value, the values of a repeated argument are kept in order.
+    pub fn push(&mut self, arg: &str, value: &str) {
+        match self.values.iter_mut().find(|(a, _)| a == arg) {
+            Some((_, values)) => values.push(value.to_string()),
+            None => self.values.push((arg.to_string(), vec![value.to_string()])),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn values_of(&self, arg: &str) -> Option<&[String]> {
+        self.values
+            .iter()
+            .find(|(a, _)| a == arg)
+            .map(|(_, v)| v.as_slice())
+    }
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.source {
+            Source::CommandLine => write!(f, "the command line"),
+            Source::Json if self.origin == "-" => write!(f, "JSON document on stdin"),
+            Source::Json => write!(f, "JSON document \"{}\"", self.origin),
+            Source::Stdin => write!(f, "--stdin-args"),
+            Source::Environment => write!(f, "environment variable {}", self.origin),
+            Source::ProjectConfig => write!(f, "project config \"{}\"", self.origin),
+            Source::UserConfig => write!(f, "user config \"{}\"", self.origin),
+            Source::Cache => write!(f, "cache \"{}\"", self.origin),
+            Source::Default if self.origin.is_empty() => write!(f, "the default"),
+            Source::Default => write!(f, "the {} provider", self.origin),
+        }
+    }
+}
+
+/// Environment variable giving the argument `name`.
+pub fn env_var_name(name: &str) -> String {
+    format!(
+        "{}{}",
+        ENV_PREFIX,
+        name.to_ascii_uppercase().replace('-', "_")
+    )
+}
+
+impl CommandArg {
+    /// Add values from a source other than the command line, applied by `resolve`.
+    pub fn add_layer(&mut self, layer: Layer) {
+        if !layer.is_empty() {
+            self.layers.push(layer);
+        }
+    }
+
+    /// Add a layer for each set `FILETEMP_<NAME>` variable naming an argument
+    /// of the file type, general arguments only describe a single run.
+    pub fn add_environment_layers(&mut self) {
+        let names: Vec<String> = self.type_arg_names().map(String::from).collect();
+        for name in names {
+            let var = env_var_name(&name);
+            if let Some(value) = std::env::var(&var).ok().filter(|v| !v.is_empty()) {
+                let mut layer = Layer::new(Source::Environment, var);
+                layer.push(&name, &value);
+                self.add_layer(layer);
+            }
+        }
+    }
+
+    /// Apply the added layers: an argument takes every value of the highest
+    /// source giving it, the command line first. Of several layers of one
+    /// source, e.g. caches given to `--use`, the one added last wins.
+    /// The error comes with the layer of the refused value.
+    pub fn resolve(&mut self) -> Result<(), (ArgProcessErr, &Layer)> {
+        let mut given = Layer::new(Source::CommandLine, "");
+        for (arg, value) in self.arg_map.iter() {
+            match self.multi_map.get(arg) {
+                Some(values) => values.iter().for_each(|v| given.push(arg, v)),
+                None => given.push(arg, value),
+            }
+        }
+        for flag in self.negated_flags.iter() {
+            given.push(flag, "false");
+        }
+        for (arg, values) in given.values.iter() {
+            logging::trace(format_args!(
+                "--{} = {} from {}",
+                arg,
+                values.join(", "),
+                given
+            ));
+            if let Some(name) = self.find_defined(arg).map(|d| d.name.to_string()) {
+                self.winners.insert(name, self.layers.len());
+            }
+        }
+        self.layers.push(given);
+
+        for idx in self.layer_order() {
+            let layer = &self.layers[idx];
+            for (arg, values) in layer.values.iter() {
+                let valid_arg = match self
+                    .defined_args
+                    .get_mut(&self.file_type)
+                    .into_iter()
+                    .flatten()
+                    .chain(self.general_args.iter_mut())
+                    .find(|a| a.name == *arg)
+                {
+                    Some(a) if !self.winners.contains_key(a.name.as_ref()) => a,
+                    _ => continue,
+                };
+
+                for value in values.iter() {
+                    let value = valid_arg.normalize_value(value);
+                    if let Err(e) = valid_arg.verify_value(&value) {
+                        return Err((e, layer));
+                    }
+                    store_value(
+                        &mut self.arg_map,
+                        &mut self.multi_map,
+                        &mut self.typed_map,
+                        valid_arg,
+                        value,
+                    );
+                }
+                if !valid_arg.is_multiple {
+                    valid_arg.found = true;
+                }
+                logging::trace(format_args!(
+                    "--{} = {} from {}",
+                    valid_arg.name,
+                    values.join(", "),
+                    layer
+                ));
+                self.winners.insert(valid_arg.name.to_string(), idx);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Which source gave the value of `arg`, and the values it overrides.
+    pub fn explain(&mut self, arg: &str) -> Result<String, String> {
+        let arg = arg.strip_prefix("--").unwrap_or(arg);
+        let (name, default) = match self.find_defined(arg) {
+            Some(a) => (
+                a.name.to_string(),
+                a.has_default_value.then(|| a.default_value.to_string()),
+            ),
+            None => {
+                let names = self.query_valid_args().map(|a| a.name.as_ref());
+                let mut err = format!("Unknown argument \"--{}\"", arg);
+                if let Some(s) = suggest::closest(arg, names) {
+                    err.push_str(&format!(", did you mean \"--{}\"?", s));
+                }
+                return Err(err);
+            }
+        };
+
+        let winner = match self.winners.get(&name) {
+            Some(&idx) => idx,
+            None => return Ok(format!("--{} is not given by any source\n", name)),
+        };
+        let layer = &self.layers[winner];
+        let mut result = format!(
+            "--{} = {} from {}\n",
+            name,
+            quote_values(layer.values_of(&name).unwrap_or_default()),
+            layer
+        );
+
+        let mut overridden: Vec<String> = self
+            .layer_order()
+            .into_iter()
+            .filter(|&idx| idx != winner)
+            .filter_map(|idx| {
+                let layer = &self.layers[idx];
+                let values = layer.values_of(&name)?;
+                Some(format!("{} from {}", quote_values(values), layer))
+            })
+            .collect();
+        if let Some(d) = default
+            && layer.source != Source::Default
+        {
+            overridden.push(format!("{} from the default", quote_values(&[d])));
+        }
+
+        if !overridden.is_empty() {
+            result.push_str("Overrides:\n");
+            for line in overridden.iter() {
+                result.push_str(&format!("  {}\n", line));
+            }
+        }
+        Ok(result)
+    }
+
+    /// Record a default or provided value, after every layer was applied.
+    pub(super) fn record_default(&mut self, arg: &str, value: &str, provider: &str) {
+        let mut layer = Layer::new(Source::Default, provider);
+        layer.push(arg, value);
+        self.winners.insert(arg.to_string(), self.layers.len());
+        self.layers.push(layer);
+    }
+
+    /// Whether a source other than the defaults gave `arg`.
+    pub(super) fn is_given(&self, arg: &str) -> bool {
+        self.arg_map.contains_key(arg)
+            && self
+                .winners
+                .get(arg)
+                .is_none_or(|&idx| self.layers[idx].source != Source::Default)
+    }
+
+    /// Indices of the layers from the highest precedence to the lowest.
+    fn layer_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.layers.len()).rev().collect();
+        order.sort_by_key(|&idx| self.layers[idx].source);
+        order
+    }
+
+    fn find_defined(&self, arg: &str) -> Option<&ArgGroup> {
+        self.defined_args
+            .get(&self.file_type)
+            .into_iter()
+            .flatten()
+            .chain(self.general_args.iter())
+            .find(|a| a.name == arg)
+    }
+}
+
+fn quote_values<S: AsRef<str>>(values: &[S]) -> String {
+    let quoted: Vec<String> = values
+        .iter()
+        .map(|v| format!("\"{}\"", v.as_ref().escape_debug()))
+        .collect();
+    quoted.join(", ")
+}
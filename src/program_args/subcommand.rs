@@ -0,0 +1,69 @@
+/// A command reached by its leading words, e.g. `cache list`.
+pub struct Subcommand {
+    /// The words naming the command followed by a synopsis of its arguments.
+    pub usage: &'static str,
+    pub about: &'static str,
+    /// Receives the arguments after the command words.
+    pub run: fn(&[&str]),
+}
+
+impl Subcommand {
+    /// Words naming the command, the part of `usage` before any placeholder or option.
+    pub fn words(&self) -> impl Iterator<Item = &'static str> {
+        self.usage
+            .split_whitespace()
+            .take_while(|w| !w.starts_with(['<', '[', '-']))
+    }
+
+    pub fn name(&self) -> String {
+        self.words().collect::<Vec<&str>>().join(" ")
+    }
+
+    fn matches(&self, args: &[&str]) -> bool {
+        let words: Vec<&str> = self.words().collect();
+        args.len() >= words.len() && words.iter().zip(args.iter()).all(|(w, a)| w == a)
+    }
+}
+
+pub enum Dispatch<'a, 'b> {
+    Run(&'a Subcommand, &'b [&'b str]),
+    /// The first word names a group like `cache`, but not one of its commands.
+    UnknownInGroup {
+        group: &'static str,
+        given: Option<&'b str>,
+        commands: Vec<&'static str>,
+    },
+    NotFound,
+}
+
+/// Find the command with the most words matching the start of `args`.
+pub fn dispatch<'a, 'b>(table: &'a [Subcommand], args: &'b [&'b str]) -> Dispatch<'a, 'b> {
+    let found = table
+        .iter()
+        .filter(|s| s.matches(args))
+        .max_by_key(|s| s.words().count());
+    if let Some(s) = found {
+        return Dispatch::Run(s, &args[s.words().count()..]);
+    }
+
+    let group = match args.first() {
+        Some(g) => *g,
+        None => return Dispatch::NotFound,
+    };
+    let commands: Vec<&'static str> = table
+        .iter()
+        .filter(|s| s.words().next() == Some(group))
+        .filter_map(|s| s.words().nth(1))
+        .collect();
+    match table
+        .iter()
+        .find_map(|s| s.words().next().filter(|w| *w == group))
+    {
+        Some(group) if !commands.is_empty() => Dispatch::UnknownInGroup {
+            group,
+            given: args.get(1).copied(),
+            commands,
+        },
+        _ => Dispatch::NotFound,
+    }
+}
@@ -0,0 +1,35 @@
+/// Edit distance counting insertions, deletions, substitutions and swaps of
+/// adjacent characters, so "porj" is one edit away from "proj".
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    d[0] = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[a.len()][b.len()]
+}
+
+/// The candidate closest to `input`, if it is close enough to be a likely typo.
+pub fn closest<'a>(input: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let max_distance = (input.chars().count() / 3).max(1);
+    candidates
+        .map(|c| (edit_distance(input, c), c))
+        .filter(|&(d, _)| d <= max_distance)
+        .min_by_key(|&(d, _)| d)
+        .map(|(_, c)| c)
+}
@@ -0,0 +1,148 @@
+use std::{
+    any::Any,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// What kind of value an argument takes, decides how it is checked and stored.
+#[derive(Clone, Copy)]
+pub enum ValueType {
+    /// Kept as given, the default.
+    Str,
+    Int {
+        min: Option<i64>,
+        max: Option<i64>,
+    },
+    /// `true`/`false`, `yes`/`no`, `on`/`off` or `1`/`0`. Flags are always bool.
+    Bool,
+    Path {
+        must_exist: bool,
+    },
+    /// One of the listed values, compared case-insensitively.
+    Enum(&'static [&'static str]),
+}
+
+pub enum TypedValue {
+    Int(i64),
+    Bool(bool),
+    Path(PathBuf),
+    Choice(&'static str),
+}
+
+impl ValueType {
+    /// Convert a value, `Str` has no typed form and yields `None`.
+    pub fn parse(&self, value: &str) -> Result<Option<TypedValue>, String> {
+        match *self {
+            ValueType::Str => Ok(None),
+            ValueType::Int { min, max } => {
+                let n = match value.parse::<i64>() {
+                    Ok(n) => n,
+                    Err(_) => return Err(String::from("expected a whole number")),
+                };
+                if let Some(m) = min
+                    && n < m
+                {
+                    return Err(format!("must be at least {}", m));
+                }
+                if let Some(m) = max
+                    && n > m
+                {
+                    return Err(format!("must be at most {}", m));
+                }
+                Ok(Some(TypedValue::Int(n)))
+            }
+            ValueType::Bool => match value.to_ascii_lowercase().as_str() {
+                "true" | "yes" | "on" | "1" => Ok(Some(TypedValue::Bool(true))),
+                "false" | "no" | "off" | "0" => Ok(Some(TypedValue::Bool(false))),
+                _ => Err(String::from("expected true or false")),
+            },
+            ValueType::Path { must_exist } => {
+                let p = PathBuf::from(value);
+                if must_exist && !p.exists() {
+                    Err(String::from("no such file or directory"))
+                } else {
+                    Ok(Some(TypedValue::Path(p)))
+                }
+            }
+            ValueType::Enum(choices) => {
+                match choices.iter().find(|c| c.eq_ignore_ascii_case(value)) {
+                    Some(c) => Ok(Some(TypedValue::Choice(c))),
+                    None => Err(format!("expected one of {}", choices.join(", "))),
+                }
+            }
+        }
+    }
+}
+
+/// Converts a value with the `FromStr` of the type given to `Arg::value_parser`.
+pub type ValueParser = fn(&str) -> Result<Box<dyn Any>, String>;
+
+pub fn parse_as<T: FromStr + 'static>(value: &str) -> Result<Box<dyn Any>, String> {
+    match value.parse::<T>() {
+        Ok(v) => Ok(Box::new(v)),
+        Err(_) => {
+            let name = std::any::type_name::<T>();
+            Err(format!(
+                "cannot be read as {}",
+                name.rsplit("::").next().unwrap_or(name)
+            ))
+        }
+    }
+}
+
+/// Converted values by argument name, the first one of repeatable arguments.
+#[derive(Default)]
+pub struct TypedArgMap {
+    values: HashMap<String, TypedValue>,
+    parsed: HashMap<String, Box<dyn Any>>,
+}
+
+impl TypedArgMap {
+    pub fn insert_if_absent(&mut self, name: &str, value: TypedValue) {
+        self.values.entry(name.to_string()).or_insert(value);
+    }
+
+    pub fn insert_parsed_if_absent(&mut self, name: &str, value: Box<dyn Any>) {
+        self.parsed.entry(name.to_string()).or_insert(value);
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.values.remove(name);
+        self.parsed.remove(name);
+    }
+
+    /// Value converted by a `value_parser`, `None` if absent or of another type.
+    pub fn get_parsed<T: 'static>(&self, name: &str) -> Option<&T> {
+        self.parsed.get(name)?.downcast_ref::<T>()
+    }
+
+    pub fn get_int(&self, name: &str) -> Option<i64> {
+        match self.values.get(name) {
+            Some(TypedValue::Int(n)) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        match self.values.get(name) {
+            Some(TypedValue::Bool(b)) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn get_path(&self, name: &str) -> Option<&Path> {
+        match self.values.get(name) {
+            Some(TypedValue::Path(p)) => Some(p),
+            _ => None,
+        }
+    }
+
+    /// The canonical spelling of an `Enum` value.
+    pub fn get_choice(&self, name: &str) -> Option<&'static str> {
+        match self.values.get(name) {
+            Some(TypedValue::Choice(c)) => Some(c),
+            _ => None,
+        }
+    }
+}
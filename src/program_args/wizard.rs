@@ -0,0 +1,87 @@
+//! Questions of `filetemp new`, answered one argument at a time and turned
+//! into the command line `gen` would be given.
+
+use super::{
+    CommandArg,
+    prompt::{self, Answer, ask_value},
+    providers,
+};
+use crate::{
+    file_types::{FileType, get_result_filename},
+    i18n::{tr, trf},
+};
+
+impl CommandArg {
+    /// Ask for a file type by its number in a list or its name. None at the end of input.
+    pub fn ask_file_type(&self) -> Option<FileType> {
+        let mut types: Vec<FileType> = self.file_types().collect();
+        types.sort_by_key(|t| t.to_str());
+
+        eprintln!("{}", tr("FILE_TYPE:"));
+        for (idx, ty) in types.iter().enumerate() {
+            let generates = trf("Generates {}", &[&get_result_filename(*ty)]);
+            eprintln!("    {:>2}) {:<20}{}", idx + 1, ty.to_str(), generates);
+        }
+
+        let default = (types.len() == 1).then_some("1");
+        loop {
+            let answer = prompt::ask("File type", default)?;
+            let chosen = match answer.parse::<usize>() {
+                Ok(n) => n.checked_sub(1).and_then(|i| types.get(i)).copied(),
+                Err(_) => Some(FileType::match_type(&answer)).filter(|t| types.contains(t)),
+            };
+            match chosen {
+                Some(ty) => return Some(ty),
+                None => eprintln!("{}", trf("Invalid file type: \"{}\"", &[&answer])),
+            }
+        }
+    }
+
+    /// Ask for each argument of `ty` and give the answers as command line words,
+    /// e.g. `["--proj", "demo"]`. Empty answers are left out, so defaults apply as
+    /// they would on the command line, except for required arguments without one.
+    /// None at the end of input.
+    pub fn ask_type_args(&mut self, ty: FileType) -> Option<Vec<String>> {
+        self.ensure_type_defined(ty);
+        let mut words = Vec::new();
+
+        for arg in self.defined_args.get(&ty).into_iter().flatten() {
+            let flag = format!("--{}", arg.name);
+
+            if arg.is_flag {
+                let question = match arg.help.as_ref() {
+                    "" => flag.clone(),
+                    help => format!("{} ({})", flag, tr(help)),
+                };
+                if prompt::confirm(&question, false) {
+                    words.push(flag);
+                }
+                continue;
+            }
+
+            let default = match arg.default_provider.and_then(providers::find_provider) {
+                Some(p) => p.provide(),
+                None => arg.has_default_value.then(|| arg.default_value.to_string()),
+            };
+            loop {
+                match ask_value(arg, default.as_deref()) {
+                    Answer::Value(v) => {
+                        words.extend([flag.clone(), v]);
+                        // More values are asked until an empty answer.
+                        if !arg.is_multiple {
+                            break;
+                        }
+                    }
+                    Answer::Empty
+                        if arg.is_required && default.is_none() && !words.contains(&flag) =>
+                    {
+                        eprintln!("{}", trf("--{} is required", &[&arg.name]));
+                    }
+                    Answer::Empty => break,
+                    Answer::Ended => return None,
+                }
+            }
+        }
+        Some(words)
+    }
+}
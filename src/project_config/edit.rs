@@ -0,0 +1,115 @@
+//! Setting and removing defaults of a config file for `filetemp config`,
+//! keeping the comments and layout of everything else in it.
+
+use std::{
+    fs::{self, File},
+    io::{ErrorKind, Write},
+    path::Path,
+};
+
+use toml_edit::{Array, DocumentMut, Item, Table, Value};
+
+use crate::{error::IoError, logging, program_args::ConfigValue};
+
+/// The config file at `path` ready to be changed, empty if it does not exist.
+pub fn load(path: &Path) -> Result<DocumentMut, String> {
+    let content = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+        Err(_) => return Err(format!("Failed to read \"{}\"", path.display())),
+    };
+    content.parse::<DocumentMut>().map_err(|e| {
+        format!(
+            "Config parse error in \"{}\": {}",
+            path.display(),
+            e.to_string().trim_end()
+        )
+    })
+}
+
+/// Write `doc` to `path` through a temporary file, creating its directory.
+pub fn save(path: &Path, doc: &DocumentMut) -> Result<(), IoError> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| IoError::new("create", dir, e))?;
+    }
+    let content = doc.to_string();
+    let tmp_path = path.with_extension(format!("toml.tmp-{}", std::process::id()));
+    let written = File::create(&tmp_path).and_then(|mut f| {
+        f.write_all(content.as_bytes())?;
+        f.sync_all()
+    });
+
+    if let Err(e) = written.and_then(|_| fs::rename(&tmp_path, path)) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(IoError::new("write", path, e));
+    }
+    logging::trace(format_args!(
+        "Wrote config file \"{}\", {} byte(s)",
+        path.display(),
+        content.len()
+    ));
+    Ok(())
+}
+
+/// Set `arg` in the table `[file_type]`, added at the end if it is missing.
+pub fn set(
+    doc: &mut DocumentMut,
+    path: &Path,
+    file_type: &str,
+    arg: &str,
+    value: &ConfigValue,
+) -> Result<(), String> {
+    let section = doc
+        .entry(file_type)
+        .or_insert_with(|| Item::Table(Table::new()));
+    let table = match section.as_table_like_mut() {
+        Some(t) => t,
+        None => {
+            return Err(format!(
+                "[{}] in \"{}\" must be a table",
+                file_type,
+                path.display()
+            ));
+        }
+    };
+
+    let value = match value {
+        ConfigValue::Flag(b) => Value::from(*b),
+        ConfigValue::One(v) => Value::from(v.as_str()),
+        ConfigValue::Many(values) => {
+            Value::Array(values.iter().map(String::as_str).collect::<Array>())
+        }
+    };
+    match table.get_mut(arg).and_then(Item::as_value_mut) {
+        // Keep the comment written after the old value.
+        Some(old) => {
+            let decor = old.decor().clone();
+            *old = value;
+            *old.decor_mut() = decor;
+        }
+        None => {
+            table.insert(arg, Item::Value(value));
+        }
+    }
+    Ok(())
+}
+
+/// Remove `arg` from the table `[file_type]`, and the table once it is empty
+/// unless a comment is written above it. Whether there was a value to remove.
+pub fn unset(doc: &mut DocumentMut, file_type: &str, arg: &str) -> bool {
+    let table = match doc.get_mut(file_type).and_then(Item::as_table_like_mut) {
+        Some(t) => t,
+        None => return false,
+    };
+    let removed = table.remove(arg).is_some();
+    let empty = table.is_empty();
+    let commented = doc
+        .get(file_type)
+        .and_then(Item::as_table)
+        .and_then(|t| t.decor().prefix()?.as_str())
+        .is_some_and(|p| p.contains('#'));
+    if removed && empty && !commented {
+        doc.remove(file_type);
+    }
+    removed
+}
@@ -0,0 +1,172 @@
+use std::path::{Path, PathBuf};
+
+use crate::config_file::toml_error;
+
+pub mod edit;
+
+/// Names of a project config file, looked for in this order in each directory.
+pub const PROJECT_CONFIG_NAMES: [&str; 3] = [".filetemp.toml", ".filetemp", "filetemp.toml"];
+
+/// Name of the user-wide config, kept in the same directory as the argument cache.
+pub const USER_CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Name of the project spec `filetemp apply` reads.
+pub const SPEC_FILE_NAME: &str = "filetemp-spec.toml";
+
+/// A file type to generate by the project spec, with its arguments as command line words.
+pub struct SpecEntry {
+    pub file_type: String,
+    pub args: Vec<String>,
+}
+
+/// Walk up from `start` looking for a project config file.
+pub fn find_project_config(start: &Path) -> Option<PathBuf> {
+    let start = std::path::absolute(start).ok()?;
+    start.ancestors().find_map(|dir| {
+        PROJECT_CONFIG_NAMES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|p| p.is_file())
+    })
+}
+
+/// Load the nearest project config above `start` with its path, if there is one.
+pub fn load_project_config(start: &Path) -> Result<Option<(PathBuf, toml::Table)>, String> {
+    let path = match find_project_config(start) {
+        Some(p) => p,
+        None => return Ok(None),
+    };
+
+    let table = read_config(&path)?;
+    Ok(Some((path, table)))
+}
+
+/// Read a config file of the project config format, a project config or the user config.
+pub fn read_config(path: &Path) -> Result<toml::Table, String> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(_) => return Err(format!("Failed to read \"{}\"", path.display())),
+    };
+
+    match content.parse::<toml::Table>() {
+        Ok(t) => Ok(t),
+        Err(e) => Err(toml_error(
+            &format!("Config parse error in \"{}\"", path.display()),
+            &content,
+            &e,
+        )),
+    }
+}
+
+/// Default values of a file type, from the table named after it, e.g. `[cmake]`.
+/// Arrays give a value per item, numbers and booleans are taken as written.
+pub fn file_type_defaults(
+    path: &Path,
+    table: &toml::Table,
+    file_type: &str,
+) -> Result<Vec<(String, Vec<String>)>, String> {
+    match table.get(file_type) {
+        Some(toml::Value::Table(t)) => section_values(path, t, file_type),
+        Some(_) => Err(format!(
+            "[{}] in \"{}\" must be a table",
+            file_type,
+            path.display()
+        )),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Values of each argument in the table `[file_type]`.
+fn section_values(
+    path: &Path,
+    section: &toml::Table,
+    file_type: &str,
+) -> Result<Vec<(String, Vec<String>)>, String> {
+    let mut defaults = Vec::new();
+    for (arg, value) in section.iter() {
+        let items: Vec<&toml::Value> = match value {
+            toml::Value::Array(a) => a.iter().collect(),
+            v => vec![v],
+        };
+        let mut values = Vec::with_capacity(items.len());
+        for item in items {
+            match item {
+                toml::Value::String(s) => values.push(s.clone()),
+                toml::Value::Integer(n) => values.push(n.to_string()),
+                toml::Value::Boolean(b) => values.push(b.to_string()),
+                _ => {
+                    return Err(format!(
+                        "Unsupported value for \"{}\" in [{}] of \"{}\"",
+                        arg,
+                        file_type,
+                        path.display()
+                    ));
+                }
+            }
+        }
+        defaults.push((arg.clone(), values));
+    }
+    Ok(defaults)
+}
+
+/// Walk up from `start` looking for the project spec.
+pub fn find_spec(start: &Path) -> Option<PathBuf> {
+    let start = std::path::absolute(start).ok()?;
+    start
+        .ancestors()
+        .map(|dir| dir.join(SPEC_FILE_NAME))
+        .find(|p| p.is_file())
+}
+
+/// The file types of the spec at `path` in the order written. Each table, e.g.
+/// `[cmake]`, holds the arguments of one file, an array of tables `[[cmake]]`
+/// generates the type once for each. Files go into the directory of the spec,
+/// a relative `path` argument is taken from there too.
+pub fn load_spec(path: &Path) -> Result<Vec<SpecEntry>, String> {
+    let table = read_config(path)?;
+    let dir = path
+        .parent()
+        .filter(|d| !d.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+    let mut entries = Vec::new();
+    for (file_type, value) in table.iter() {
+        let sections: Vec<&toml::Table> = match value {
+            toml::Value::Table(t) => vec![t],
+            toml::Value::Array(a) => a.iter().filter_map(toml::Value::as_table).collect(),
+            _ => Vec::new(),
+        };
+        if sections.is_empty() {
+            return Err(format!(
+                "[{}] in \"{}\" must be a table or an array of tables",
+                file_type,
+                path.display()
+            ));
+        }
+
+        for section in sections {
+            let mut args = Vec::new();
+            let mut has_output = false;
+            for (arg, values) in section_values(path, section, file_type)? {
+                has_output |= arg == "path" || arg == "archive";
+                for value in values {
+                    let value = match arg.as_str() {
+                        "path" | "archive" if !value.starts_with(['~', '$']) => {
+                            dir.join(&value).display().to_string()
+                        }
+                        _ => value,
+                    };
+                    args.push(format!("--{}={}", arg, value));
+                }
+            }
+            if !has_output {
+                args.push(format!("--path={}", dir.display()));
+            }
+            entries.push(SpecEntry {
+                file_type: file_type.clone(),
+                args,
+            });
+        }
+    }
+    Ok(entries)
+}
@@ -0,0 +1,135 @@
+use std::{
+    collections::hash_map::RandomState,
+    fs::DirBuilder,
+    hash::{BuildHasher, Hasher},
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Names tried for the scratch root before giving up.
+const ROOT_ATTEMPTS: usize = 16;
+
+/// Per-run scratch root, created on first use. Only set to a directory this
+/// process created, so `cleanup` never removes one it found.
+static ROOT: Mutex<Option<PathBuf>> = Mutex::new(None);
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+static KEEP: AtomicBool = AtomicBool::new(false);
+
+/// Keep the scratch directory after the run, for debugging.
+pub fn keep(k: bool) {
+    KEEP.store(k, Ordering::Relaxed);
+}
+
+/// Create a fresh directory for one task, e.g. extracting an archive.
+/// Everything below the scratch root is removed by `cleanup`.
+pub fn new_dir(purpose: &str) -> Result<PathBuf, String> {
+    let mut root = ROOT.lock().unwrap();
+    let root = match root.as_ref() {
+        Some(r) => r,
+        None => root.insert(create_root()?),
+    };
+
+    let dir = root.join(format!(
+        "{}-{}",
+        purpose,
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+    match create_private_dir(&dir) {
+        Ok(_) => Ok(dir),
+        Err(_) => Err(format!(
+            "Failed to create temporary directory \"{}\"",
+            dir.display()
+        )),
+    }
+}
+
+/// A new directory in the system temporary directory with a name that cannot
+/// be guessed ahead of the run. An existing path, which another user may have
+/// planted in a shared temporary directory, is never reused.
+fn create_root() -> Result<PathBuf, String> {
+    let temp = std::env::temp_dir();
+    for _ in 0..ROOT_ATTEMPTS {
+        let root = temp.join(format!(
+            "filetemp-{}-{:016x}",
+            std::process::id(),
+            random_suffix()
+        ));
+        match create_private_dir(&root) {
+            Ok(_) => return Ok(root),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(_) => {
+                return Err(format!(
+                    "Failed to create temporary directory \"{}\"",
+                    root.display()
+                ));
+            }
+        }
+    }
+    Err(format!(
+        "Failed to create a temporary directory in \"{}\"",
+        temp.display()
+    ))
+}
+
+/// Randomly seeded per call by the standard library, mixed with the time.
+fn random_suffix() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH);
+    hasher.write_u128(now.map_or(0, |d| d.as_nanos()));
+    hasher.finish()
+}
+
+/// Create `dir`, failing if anything exists at its path, even a symlink.
+/// Only the current user may enter it on Unix.
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    let mut builder = DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(dir)
+}
+
+/// Remove the scratch root unless `keep` was requested.
+pub fn cleanup() {
+    let root = match ROOT.lock().unwrap().take() {
+        Some(r) => r,
+        None => return,
+    };
+
+    if KEEP.load(Ordering::Relaxed) {
+        eprintln!("Kept temporary files in \"{}\"", root.display());
+    } else {
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn root_is_new_and_private() {
+        let root = create_root().unwrap();
+        let other = create_root().unwrap();
+        assert_ne!(other, root);
+        std::fs::remove_dir(&other).unwrap();
+        assert_eq!(
+            create_private_dir(&root).unwrap_err().kind(),
+            ErrorKind::AlreadyExists
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&root).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+        std::fs::remove_dir(&root).unwrap();
+    }
+}
@@ -0,0 +1,336 @@
+use std::{
+    fs::File,
+    path::{Component, Path, PathBuf},
+};
+
+use flate2::read::GzDecoder;
+use zip::ZipArchive;
+
+use super::render::placeholders;
+use crate::{hooks, scratch};
+
+/// Manifest describing a template bundle, at the root of the bundle.
+pub const MANIFEST_NAME: &str = "manifest.toml";
+
+pub struct TemplateVar {
+    pub name: String,
+    pub required: bool,
+    pub default: Option<String>,
+    pub help: String,
+}
+
+pub struct TemplateFile {
+    /// Template path relative to the bundle root.
+    pub template: PathBuf,
+    /// Output path relative to the output root, may hold `{{placeholders}}`.
+    pub output: PathBuf,
+}
+
+/// A validated template bundle, archives are extracted to a scratch directory.
+pub struct TemplateBundle {
+    pub root: PathBuf,
+    pub name: String,
+    pub vars: Vec<TemplateVar>,
+    pub files: Vec<TemplateFile>,
+    /// Commands run in the output directory after rendering.
+    pub hooks: Vec<String>,
+}
+
+impl TemplateBundle {
+    pub fn var(&self, name: &str) -> Option<&TemplateVar> {
+        self.vars.iter().find(|v| v.name == name)
+    }
+}
+
+/// Load a bundle from a directory, or a .zip, .tar.gz or .tgz archive of one.
+pub fn load_bundle(source: &Path) -> Result<TemplateBundle, String> {
+    let bundle = read_bundle(source)?;
+    validate(&bundle)?;
+    Ok(bundle)
+}
+
+/// Load a bundle without reading its templates, `filetemp template check`
+/// reports their problems all at once.
+pub fn read_bundle(source: &Path) -> Result<TemplateBundle, String> {
+    if source.is_dir() {
+        return read_manifest(source.to_path_buf());
+    }
+
+    let dir = extract_archive(source)?;
+    // Archives often wrap the bundle in a single top-level directory.
+    let root = if dir.join(MANIFEST_NAME).is_file() {
+        dir.clone()
+    } else {
+        match single_subdir(&dir) {
+            Some(sub) if sub.join(MANIFEST_NAME).is_file() => sub,
+            _ => dir.clone(),
+        }
+    };
+
+    read_manifest(root)
+}
+
+fn single_subdir(dir: &Path) -> Option<PathBuf> {
+    let mut entries = std::fs::read_dir(dir).ok()?.filter_map(|e| e.ok());
+    let first = entries.next()?;
+    if entries.next().is_some() || !first.path().is_dir() {
+        return None;
+    }
+    Some(first.path())
+}
+
+fn extract_archive(source: &Path) -> Result<PathBuf, String> {
+    let name = source.to_string_lossy().to_lowercase();
+    let is_tar_gz = name.ends_with(".tar.gz") || name.ends_with(".tgz");
+    if !is_tar_gz && !name.ends_with(".zip") {
+        return Err(format!(
+            "Unsupported template source \"{}\", expected a directory, .zip, .tar.gz or .tgz",
+            source.display()
+        ));
+    }
+
+    let file = match File::open(source) {
+        Ok(f) => f,
+        Err(_) => return Err(format!("Failed to open \"{}\"", source.display())),
+    };
+
+    let dir = scratch::new_dir("template")?;
+
+    let result = if is_tar_gz {
+        tar::Archive::new(GzDecoder::new(file))
+            .unpack(&dir)
+            .map_err(|e| e.to_string())
+    } else {
+        ZipArchive::new(file)
+            .and_then(|mut z| z.extract(&dir))
+            .map_err(|e| e.to_string())
+    };
+
+    match result {
+        Ok(_) => Ok(dir),
+        Err(e) => Err(format!("Failed to extract \"{}\": {}", source.display(), e)),
+    }
+}
+
+/// Read the manifest, it must name the bundle and list its files.
+fn read_manifest(root: PathBuf) -> Result<TemplateBundle, String> {
+    let mut bundle = TemplateBundle {
+        root,
+        name: String::new(),
+        vars: Vec::new(),
+        files: Vec::new(),
+        hooks: Vec::new(),
+    };
+
+    let manifest_path = bundle.root.join(MANIFEST_NAME);
+    let content = match std::fs::read_to_string(&manifest_path) {
+        Ok(c) => c,
+        Err(_) => return Err(format!("Template bundle has no {}", MANIFEST_NAME)),
+    };
+    let manifest = match content.parse::<toml::Table>() {
+        Ok(t) => t,
+        Err(e) => return Err(format!("{} parse error: {}", MANIFEST_NAME, e.message())),
+    };
+
+    for (key, value) in manifest.iter() {
+        match key.as_str() {
+            "bundle" => read_bundle_table(&mut bundle, value)?,
+            "vars" => read_vars(&mut bundle, value)?,
+            "files" => read_files(&mut bundle, value)?,
+            _ => return Err(format!("Unknown manifest key: {}", key)),
+        }
+    }
+
+    if bundle.name.is_empty() {
+        return Err(String::from("Manifest is missing bundle.name"));
+    }
+    if bundle.files.is_empty() {
+        return Err(String::from("Manifest lists no [[files]]"));
+    }
+
+    Ok(bundle)
+}
+
+/// Every template must exist and only use declared variables.
+fn validate(bundle: &TemplateBundle) -> Result<(), String> {
+    for file in bundle.files.iter() {
+        let path = bundle.root.join(&file.template);
+        let template = match std::fs::read_to_string(&path) {
+            Ok(t) => t,
+            Err(_) => return Err(format!("Missing template \"{}\"", file.template.display())),
+        };
+
+        let found = match placeholders(&template) {
+            Ok(p) => p,
+            Err(e) => return Err(format!("{} in \"{}\"", e, file.template.display())),
+        };
+        let in_output = match placeholders(&file.output.to_string_lossy()) {
+            Ok(p) => p,
+            Err(e) => return Err(format!("{} in output \"{}\"", e, file.output.display())),
+        };
+        for p in found.into_iter().chain(in_output) {
+            if bundle.var(&p.name).is_none() && !super::is_builtin_var(&p.name) {
+                return Err(format!(
+                    "Undeclared variable \"{}\" at line {} of \"{}\"",
+                    p.name,
+                    p.line,
+                    file.template.display()
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn read_bundle_table(bundle: &mut TemplateBundle, value: &toml::Value) -> Result<(), String> {
+    let table = match value.as_table() {
+        Some(t) => t,
+        None => return Err(String::from("[bundle] must be a table")),
+    };
+
+    for (key, value) in table.iter() {
+        if key == "hooks" {
+            bundle.hooks = hooks::read_hooks(value, "bundle.hooks")?;
+            continue;
+        }
+        let text = match value.as_str() {
+            Some(s) => s.to_string(),
+            None => return Err(format!("bundle.{} must be a string", key)),
+        };
+        match key.as_str() {
+            "name" => bundle.name = text,
+            // Only shown to people browsing the manifest.
+            "description" => {}
+            _ => return Err(format!("Unknown manifest key: bundle.{}", key)),
+        }
+    }
+
+    Ok(())
+}
+
+fn read_vars(bundle: &mut TemplateBundle, value: &toml::Value) -> Result<(), String> {
+    let table = match value.as_table() {
+        Some(t) => t,
+        None => return Err(String::from("[vars] must be a table")),
+    };
+
+    for (name, def) in table.iter() {
+        let def = match def.as_table() {
+            Some(t) => t,
+            None => return Err(format!("[vars.{}] must be a table", name)),
+        };
+
+        let mut var = TemplateVar {
+            name: name.clone(),
+            required: false,
+            default: None,
+            help: String::new(),
+        };
+        for (key, value) in def.iter() {
+            match (key.as_str(), value) {
+                ("required", toml::Value::Boolean(b)) => var.required = *b,
+                ("default", toml::Value::String(s)) => var.default = Some(s.clone()),
+                ("help", toml::Value::String(s)) => var.help = s.clone(),
+                ("required" | "default" | "help", _) => {
+                    return Err(format!("Invalid type for vars.{}.{}", name, key));
+                }
+                _ => return Err(format!("Unknown manifest key: vars.{}.{}", name, key)),
+            }
+        }
+        bundle.vars.push(var);
+    }
+
+    Ok(())
+}
+
+fn read_files(bundle: &mut TemplateBundle, value: &toml::Value) -> Result<(), String> {
+    let entries = match value.as_array() {
+        Some(a) => a,
+        None => return Err(String::from("files must be an array of tables")),
+    };
+
+    for entry in entries.iter() {
+        let table = match entry.as_table() {
+            Some(t) => t,
+            None => return Err(String::from("files must be an array of tables")),
+        };
+
+        let mut template = None;
+        let mut output = None;
+        for (key, value) in table.iter() {
+            let path = match value.as_str() {
+                Some(s) => relative_path(s)?,
+                None => return Err(format!("files.{} must be a string", key)),
+            };
+            match key.as_str() {
+                "template" => template = Some(path),
+                "output" => output = Some(path),
+                _ => return Err(format!("Unknown manifest key: files.{}", key)),
+            }
+        }
+
+        match (template, output) {
+            (Some(template), Some(output)) if bundle.root.join(&template).is_dir() => {
+                add_tree(bundle, &template, &output)?;
+            }
+            (Some(template), Some(output)) => bundle.files.push(TemplateFile { template, output }),
+            _ => return Err(String::from("Each [[files]] needs template and output")),
+        }
+    }
+
+    Ok(())
+}
+
+/// Add every file below the template directory, nested directories and
+/// hidden files included, to the same place below the output directory.
+fn add_tree(bundle: &mut TemplateBundle, template: &Path, output: &Path) -> Result<(), String> {
+    let dir = bundle.root.join(template);
+    let mut entries: Vec<PathBuf> = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+        Err(_) => return Err(format!("Failed to read \"{}\"", template.display())),
+    };
+    entries.sort();
+
+    for path in entries {
+        let name = match path.file_name() {
+            Some(n) => n,
+            None => continue,
+        };
+        // A bundle may be its own template tree.
+        if path == bundle.root.join(MANIFEST_NAME) {
+            continue;
+        }
+        if path.is_dir() {
+            add_tree(bundle, &template.join(name), &output.join(name))?;
+        } else {
+            bundle.files.push(TemplateFile {
+                template: template.join(name),
+                output: output.join(name),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Paths in a manifest must stay inside the bundle and the output root,
+/// `.` components are dropped so `output = "."` names the output root.
+pub fn relative_path(s: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(s);
+    if path
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        && !s.is_empty()
+    {
+        Ok(path
+            .components()
+            .filter(|c| *c != Component::CurDir)
+            .collect())
+    } else {
+        Err(format!(
+            "Path \"{}\" must be relative and stay inside the bundle",
+            s
+        ))
+    }
+}
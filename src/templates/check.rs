@@ -0,0 +1,268 @@
+//! Problems of a user template or file type manifest, found before rendering it.
+
+use std::{fmt::Write, path::Path};
+
+use super::{TEMPLATE_DIR_NAME, bundle, find_user_template, is_archive, is_builtin_var, render};
+use crate::{
+    file_types::custom,
+    lint::Severity,
+    program_args::{Arg, CommandArg, suggest},
+};
+
+struct Problem {
+    file: String,
+    line: Option<usize>,
+    severity: Severity,
+    message: String,
+}
+
+impl Problem {
+    fn new(file: impl Into<String>, severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            file: file.into(),
+            line: None,
+            severity,
+            message: message.into(),
+        }
+    }
+}
+
+/// Variables a template may use, and where they are declared.
+struct Declared<'a> {
+    names: Vec<&'a str>,
+    table: &'static str,
+}
+
+pub fn define_args(cmd: &mut CommandArg) {
+    cmd.usage_name("filetemp template check")
+        .no_file_type()
+        .positional("NAME", "Template or bundle in the templates directory")
+        .add_general_arg_def(
+            Arg::new("type")
+                .flag(true)
+                .help("Check the file type manifest NAME.toml in the types directory instead"),
+        )
+        .after_help(
+            "Reports syntax errors like unclosed {{placeholders}} or {{#if}} blocks, \
+             placeholders that are not valid variable names, variables a bundle or \
+             file type does not declare, and declared variables no template uses.",
+        );
+}
+
+/// Check the template named by NAME and print what is wrong with it.
+pub fn run(cmd: &mut CommandArg, data_dir: &Path) -> Result<(), String> {
+    let name = match cmd.get_positional() {
+        Some(n) => n,
+        None => return Err(String::from("Give the NAME of a template to check")),
+    };
+
+    let problems = if cmd.get_flag("type") {
+        let path = data_dir
+            .join(custom::TYPE_DIR_NAME)
+            .join(format!("{}.toml", name));
+        if !path.is_file() {
+            return Err(format!("No file type manifest \"{}\"", path.display()));
+        }
+        check_type(&path)
+    } else {
+        let source = find_user_template(Some(&data_dir.join(TEMPLATE_DIR_NAME)), name)?;
+        if source.is_file() && !is_archive(&source) {
+            check_file(&source)
+        } else {
+            check_bundle(&source)
+        }
+    };
+
+    print!("{}", text_report(&problems));
+    Ok(())
+}
+
+/// A single file declares nothing, its variables are given with `--set`.
+fn check_file(path: &Path) -> Vec<Problem> {
+    let file = path.display().to_string();
+    let template = match std::fs::read_to_string(path) {
+        Ok(t) => t,
+        Err(_) => {
+            return vec![Problem::new(
+                file,
+                Severity::Error,
+                "Failed to read the template",
+            )];
+        }
+    };
+
+    let mut problems = Vec::new();
+    let mut used = Vec::new();
+    check_template(&file, &template, None, &mut used, &mut problems);
+
+    let mut needed: Vec<String> = Vec::new();
+    for name in used {
+        if !is_builtin_var(&name) && !needed.contains(&name) {
+            needed.push(name);
+        }
+    }
+    if !needed.is_empty() {
+        problems.push(Problem::new(
+            file,
+            Severity::Info,
+            format!("Variables given with --set: {}", needed.join(", ")),
+        ));
+    }
+    problems
+}
+
+fn check_bundle(source: &Path) -> Vec<Problem> {
+    let bundle = match bundle::read_bundle(source) {
+        Ok(b) => b,
+        Err(e) => {
+            return vec![Problem::new(
+                source.display().to_string(),
+                Severity::Error,
+                e,
+            )];
+        }
+    };
+    let declared = Declared {
+        names: bundle.vars.iter().map(|v| v.name.as_str()).collect(),
+        table: "[vars]",
+    };
+
+    let mut problems = Vec::new();
+    let mut used = Vec::new();
+    for file in bundle.files.iter() {
+        let label = file.template.display().to_string();
+        match std::fs::read_to_string(bundle.root.join(&file.template)) {
+            Ok(t) => check_template(&label, &t, Some(&declared), &mut used, &mut problems),
+            Err(_) => problems.push(Problem::new(label, Severity::Error, "Missing template")),
+        }
+
+        // Lines of an output path are meaningless, the problem is in the manifest.
+        let label = format!(
+            "{} output \"{}\"",
+            bundle::MANIFEST_NAME,
+            file.output.display()
+        );
+        let start = problems.len();
+        let output = file.output.to_string_lossy();
+        check_template(&label, &output, Some(&declared), &mut used, &mut problems);
+        problems[start..].iter_mut().for_each(|p| p.line = None);
+    }
+
+    report_unused(bundle::MANIFEST_NAME, &declared, &used, &mut problems);
+    problems
+}
+
+fn check_type(path: &Path) -> Vec<Problem> {
+    let file = path.display().to_string();
+    let ty = match custom::read_unchecked(path) {
+        Ok(t) => t,
+        Err(e) => return vec![Problem::new(file, Severity::Error, e)],
+    };
+    let declared = Declared {
+        names: ty.arg_names().collect(),
+        table: "[args]",
+    };
+
+    let mut problems = Vec::new();
+    let mut used = Vec::new();
+    let label = ty.template_path.display().to_string();
+    check_template(
+        &label,
+        &ty.template,
+        Some(&declared),
+        &mut used,
+        &mut problems,
+    );
+    report_unused(&file, &declared, &used, &mut problems);
+    problems
+}
+
+/// Check the syntax and variables of one template, the variables it uses are
+/// added to `used`. Without `declared` any valid name is accepted.
+fn check_template(
+    file: &str,
+    template: &str,
+    declared: Option<&Declared>,
+    used: &mut Vec<String>,
+    problems: &mut Vec<Problem>,
+) {
+    let found = match render::placeholders(template) {
+        Ok(p) => p,
+        Err(e) => {
+            problems.push(Problem::new(file, Severity::Error, e));
+            return;
+        }
+    };
+
+    for p in found {
+        let mut problem = Problem::new(file, Severity::Error, "");
+        problem.line = Some(p.line);
+        if !p
+            .name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            problem.message = format!(
+                "Unknown placeholder \"{{{{{}}}}}\", variable names are letters, digits, _ and -",
+                p.name
+            );
+            problems.push(problem);
+            continue;
+        }
+
+        used.push(p.name.clone());
+        let declared = match declared {
+            Some(d) if !d.names.contains(&p.name.as_str()) && !is_builtin_var(&p.name) => d,
+            _ => continue,
+        };
+        problem.message = match suggest::closest(&p.name, declared.names.iter().copied()) {
+            Some(s) => format!(
+                "Undeclared variable \"{}\", did you mean \"{}\"?",
+                p.name, s
+            ),
+            None => format!(
+                "Undeclared variable \"{}\", declare it in {}",
+                p.name, declared.table
+            ),
+        };
+        problems.push(problem);
+    }
+}
+
+fn report_unused(file: &str, declared: &Declared, used: &[String], problems: &mut Vec<Problem>) {
+    for name in declared.names.iter() {
+        if !used.iter().any(|u| u == name) {
+            problems.push(Problem::new(
+                file,
+                Severity::Warning,
+                format!(
+                    "{} declares \"{}\", but no template uses it",
+                    declared.table, name
+                ),
+            ));
+        }
+    }
+}
+
+fn text_report(problems: &[Problem]) -> String {
+    let mut out = String::new();
+    for p in problems.iter() {
+        match p.line {
+            Some(line) => write!(&mut out, "{}:{}: ", p.file, line).unwrap(),
+            None => write!(&mut out, "{}: ", p.file).unwrap(),
+        }
+        writeln!(&mut out, "{}: {}", p.severity.to_str(), p.message).unwrap();
+    }
+
+    let count = |s: Severity| problems.iter().filter(|p| p.severity == s).count();
+    writeln!(
+        &mut out,
+        "{} finding(s), {} error(s), {} warning(s)",
+        problems.len(),
+        count(Severity::Error),
+        count(Severity::Warning)
+    )
+    .unwrap();
+
+    out
+}
@@ -0,0 +1,522 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use crate::{
+    events::{self, Event, EventBus},
+    hooks, logging,
+    output_sink::{FILE_MODE, Overwrite, Preview, open_sink, validate_output_dir},
+    program_args::{Arg, ArgProcessErr, CommandArg, ValueType, normalize, prompt, providers},
+};
+
+mod bundle;
+pub mod check;
+pub mod remote;
+pub mod render;
+
+pub use bundle::TemplateBundle;
+
+/// Directory of user templates, in the data directory.
+pub const TEMPLATE_DIR_NAME: &str = "templates";
+
+/// Options of the bundle command, variables of these names are only given with `--set`.
+const BUNDLE_OPTIONS: &[&str] = &[
+    "set", "path", "archive", "no-hooks", "dry-run", "diff", "force", "backup", "verbose", "quiet",
+    "help",
+];
+
+/// Every value provider is a template variable named with underscores, e.g.
+/// `{{git_user_name}}`. Resolved once so all rendered files agree, providers
+/// without a value are left out.
+pub fn builtin_vars() -> &'static HashMap<String, String> {
+    static VARS: OnceLock<HashMap<String, String>> = OnceLock::new();
+    VARS.get_or_init(|| {
+        providers::PROVIDERS
+            .iter()
+            .filter_map(|p| Some((p.name.replace('-', "_"), p.provide()?)))
+            .collect()
+    })
+}
+
+/// Whether `name` is a built-in variable, usable without being declared.
+pub fn is_builtin_var(name: &str) -> bool {
+    providers::PROVIDERS
+        .iter()
+        .any(|p| p.name.replace('-', "_") == name)
+}
+
+/// Add the built-in variables that `values` does not set itself.
+pub fn with_builtin_vars(values: &mut HashMap<String, String>) {
+    for (name, value) in builtin_vars().iter() {
+        values.entry(name.clone()).or_insert_with(|| value.clone());
+    }
+}
+
+pub fn define_args(cmd: &mut CommandArg) {
+    cmd.usage_name("filetemp template")
+        .no_file_type()
+        .positional(
+            "NAME",
+            "Template in the templates directory, a file or a bundle directory",
+        )
+        .add_general_arg_def(
+            Arg::new("template-archive")
+                .value_name("SOURCE")
+                .normalize(normalize::path)
+                .value_type(ValueType::Path { must_exist: true })
+                .help("Template bundle, a directory or a .zip, .tar.gz or .tgz archive of one"),
+        );
+    define_output_args(cmd);
+    cmd.after_help(
+        "User templates are kept in the templates directory next to the cache file, \
+         e.g. $XDG_DATA_HOME/filetemp/templates. A file there is rendered on its own, \
+         NAME may leave out its extension, and printed unless --path or --archive is given.\n\
+         Templates can hold {{#if NAME}}...{{else}}...{{/if}} with conditions like \
+         \"cxxstd >= 23\" or \"!NAME\", and {{#each NAME}}...{{/each}} over a comma separated \
+         value, where {{this}} is the item and {{@index}} its position.\n\
+         Every value provider is also a variable named with underscores, e.g. {{date}}, \
+         {{year}}, {{user}}, {{git_user_name}} and {{git_user_email}}, \
+         see \"filetemp providers\".\n\
+         \"filetemp template add <URL>\" downloads a template or bundle into the directory, \
+         and \"filetemp template check <NAME>\" reports its problems before rendering.\n\
+         A bundle holds a manifest.toml with a [bundle] name, the [vars] used as \
+         {{placeholders}} and the [[files]] to render, each with a template and an output path. \
+         A template directory renders every file below it into the output directory, \
+         and output paths may hold placeholders too, e.g. \"src/{{proj}}.cpp\". \
+         The commands in bundle.hooks, e.g. [\"git init\"], run in the --path directory \
+         after rendering, their output is shown with --verbose.",
+    );
+}
+
+/// Arguments of the bundle command, each variable of `bundle` is also an option.
+pub fn define_bundle_args(cmd: &mut CommandArg, bundle: Option<&TemplateBundle>) {
+    cmd.usage_name("filetemp bundle")
+        .no_file_type()
+        .positional("NAME", "Bundle in the templates directory");
+    for var in bundle.into_iter().flat_map(|b| b.vars.iter()) {
+        if BUNDLE_OPTIONS.contains(&var.name.as_str()) {
+            continue;
+        }
+        let help = match var.help.is_empty() {
+            true => format!("Value of the {} variable", var.name),
+            false => var.help.clone(),
+        };
+        cmd.add_general_arg_def(Arg::new(var.name.clone()).value_name("VALUE").help(help));
+    }
+    define_output_args(cmd);
+    cmd.after_help(
+        "A bundle is a directory or a .zip, .tar.gz or .tgz archive in the templates \
+         directory, e.g. $XDG_DATA_HOME/filetemp/templates/cpp-app, laying out a whole \
+         project in one pass. Each of its variables is an option, see \
+         \"filetemp bundle NAME --help\", and can also be given with --set.\n\
+         See \"filetemp template --help\" for the manifest and template syntax.",
+    );
+}
+
+/// Where and how rendered templates are written, shared by both commands.
+fn define_output_args(cmd: &mut CommandArg) {
+    cmd.add_general_arg_def(
+        Arg::new("set")
+            .value_name("KEY=VALUE")
+            .multiple(true)
+            .help("Value of a template variable, can be repeated"),
+    )
+    .add_general_arg_def(
+        Arg::new("path")
+            .value_name("PATH")
+            .normalize(normalize::path)
+            .value_type(ValueType::Path { must_exist: false })
+            .validator(validate_output_dir)
+            .conflicts_with("archive")
+            .help("Directory the template is rendered into"),
+    )
+    .add_general_arg_def(
+        Arg::new("archive")
+            .value_name("FILE")
+            .value_type(ValueType::Path { must_exist: false })
+            .help("Render into a .tar.gz, .tgz or .zip archive instead"),
+    )
+    .add_general_arg_def(
+        Arg::new("no-hooks")
+            .flag(true)
+            .help("Do not run the commands a bundle declares for after rendering"),
+    )
+    .add_general_arg_def(
+        Arg::new("dry-run").flag(true).help(
+            "List the files that would be written, with their sizes, instead of writing them",
+        ),
+    )
+    .add_general_arg_def(
+        Arg::new("diff")
+            .flag(true)
+            .requires("path")
+            .conflicts_with("dry-run")
+            .help("Print a unified diff against the files in --path instead of writing them"),
+    )
+    .add_general_arg_def(
+        Arg::new("force")
+            .flag(true)
+            .help("Replace existing files without asking"),
+    )
+    .add_general_arg_def(
+        Arg::new("backup")
+            .flag(true)
+            .help("Move existing files to <name>.bak before replacing them"),
+    )
+    .add_general_arg_def(
+        Arg::new("verbose")
+            .short('v')
+            .flag(true)
+            .last_wins(true)
+            .help("Report each generation step to stderr"),
+    )
+    .add_general_arg_def(
+        Arg::new("quiet")
+            .short('q')
+            .flag(true)
+            .last_wins(true)
+            .conflicts_with("verbose")
+            .help("Only report errors"),
+    );
+}
+
+/// Render a user template from `template_dir` or the bundle given with `--template-archive`.
+pub fn run(cmd: &mut CommandArg, template_dir: Option<&Path>) -> Result<(), String> {
+    logging::set_level(logging::level_from_flags(
+        cmd.get_flag("quiet"),
+        cmd.get_flag("verbose"),
+    ));
+
+    match cmd.assert_required_args_exist() {
+        Ok(()) => {}
+        Err(ArgProcessErr::MissingArgs { missing, required }) => {
+            return Err(cmd.missing_args_message(&missing, required));
+        }
+        Err(e) => return Err(e.to_string()),
+    }
+    cmd.verify_relations().map_err(|e| e.to_string())?;
+
+    let source = match (cmd.get_positional(), cmd.get_path("template-archive")) {
+        (Some(name), None) => find_user_template(template_dir, name)?,
+        (None, Some(s)) => s.to_path_buf(),
+        (Some(_), Some(_)) => {
+            return Err(String::from(
+                "Give either a template NAME or --template-archive, not both",
+            ));
+        }
+        (None, None) => {
+            return Err(String::from(
+                "Give a template NAME or --template-archive <SOURCE>",
+            ));
+        }
+    };
+
+    let single_file = cmd.get_positional().is_some() && source.is_file();
+    let (rendered, hooks) = if single_file {
+        (vec![render_file(&source, cmd.get_args("set"))?], Vec::new())
+    } else {
+        let bundle = bundle::load_bundle(&source)?;
+        let values = bundle_sets(&bundle, cmd.get_args("set"))?;
+        let rendered = render_bundle(&bundle, values, |name| format!("--set {}=...", name))?;
+        (rendered, bundle.hooks)
+    };
+
+    write_rendered(cmd, rendered, single_file, &hooks)
+}
+
+/// The bundle named `name` in the templates directory, a directory or an archive.
+pub fn find_bundle(template_dir: Option<&Path>, name: &str) -> Result<TemplateBundle, String> {
+    let source = find_user_template(template_dir, name)?;
+    if source.is_file() && !is_archive(&source) {
+        return Err(format!(
+            "\"{}\" is a single template, render it with \"filetemp template {}\"",
+            name, name
+        ));
+    }
+    bundle::load_bundle(&source)
+}
+
+/// The user template overriding the built-in type `name`, a single file
+/// named like it. An ambiguous name overrides nothing.
+pub fn find_override(template_dir: Option<&Path>, name: &str) -> Option<PathBuf> {
+    find_user_template(template_dir, name)
+        .ok()
+        .filter(|p| p.is_file() && !is_archive(p))
+}
+
+/// Whether a file in the templates directory is a bundle archive.
+fn is_archive(path: &Path) -> bool {
+    let name = path.to_string_lossy().to_lowercase();
+    [".zip", ".tar.gz", ".tgz"]
+        .iter()
+        .any(|ext| name.ends_with(ext))
+}
+
+/// Render every file of `bundle`, its variables are given as options or with `--set`.
+pub fn run_bundle(cmd: &mut CommandArg, bundle: Option<&TemplateBundle>) -> Result<(), String> {
+    logging::set_level(logging::level_from_flags(
+        cmd.get_flag("quiet"),
+        cmd.get_flag("verbose"),
+    ));
+
+    let bundle = match bundle {
+        Some(b) => b,
+        None => {
+            return Err(String::from(
+                "Give the NAME of a bundle in the templates directory",
+            ));
+        }
+    };
+    let mut values = bundle_sets(bundle, cmd.get_args("set"))?;
+    for var in bundle.vars.iter() {
+        if let Some(v) = cmd.get_arg(&var.name)
+            && !BUNDLE_OPTIONS.contains(&var.name.as_str())
+        {
+            values.insert(var.name.clone(), v.to_string());
+        }
+    }
+
+    let rendered = render_bundle(bundle, values, |name| {
+        match BUNDLE_OPTIONS.contains(&name) {
+            true => format!("--set {}=...", name),
+            false => format!("--{} <VALUE>", name),
+        }
+    })?;
+    write_rendered(cmd, rendered, false, &bundle.hooks)
+}
+
+/// Write rendered files to `--path` or `--archive`, a single file is printed without either.
+/// The hooks run in the `--path` directory afterwards.
+fn write_rendered(
+    cmd: &CommandArg,
+    rendered: Vec<(PathBuf, String)>,
+    single_file: bool,
+    hooks: &[String],
+) -> Result<(), String> {
+    let (path, archive) = (cmd.get_path("path"), cmd.get_path("archive"));
+    if single_file && path.is_none() && archive.is_none() {
+        print!("{}", rendered[0].1);
+        return Ok(());
+    }
+    let preview = Preview::from_flags(cmd.get_flag("dry-run"), cmd.get_flag("diff"));
+    let overwrite = Overwrite::from_flags(
+        cmd.get_flag("force"),
+        cmd.get_flag("backup"),
+        prompt::can_prompt(),
+    );
+    let mut sink = open_sink(path, archive, overwrite, preview)?;
+
+    let mut events = EventBus::new();
+    events.subscribe(events::print_warnings);
+    if preview == Preview::Off {
+        events.subscribe(events::print_progress);
+    }
+
+    for (output, content) in rendered {
+        match sink.write_file(&output, content.as_bytes(), FILE_MODE) {
+            Ok(path) => events.emit(Event::FileWritten { path: &path }),
+            Err(e) => events.warn(&e),
+        }
+    }
+    if let Err(e) = sink.finish() {
+        events.warn(&e);
+    }
+
+    match cmd.get_path("path") {
+        _ if hooks.is_empty() => {}
+        _ if cmd.get_flag("no-hooks") => logging::verbose("Skipped hooks, --no-hooks was given"),
+        Some(_) if preview != Preview::Off => hooks::print_dry_run(hooks),
+        Some(dir) => hooks::run_hooks(hooks, dir, &mut events),
+        None => logging::verbose("Skipped hooks, they only run for --path"),
+    }
+
+    events.finish();
+    Ok(())
+}
+
+/// The template named `name` in the templates directory, a file or a bundle
+/// directory. A file may be named without its extension, `readme` finds `readme.md`.
+fn find_user_template(template_dir: Option<&Path>, name: &str) -> Result<PathBuf, String> {
+    let dir = match template_dir {
+        Some(d) => d,
+        None => return Err(String::from("No data directory to keep user templates in")),
+    };
+    if name.contains(['/', '\\']) || name == "." || name == ".." {
+        return Err(format!(
+            "Invalid template name \"{}\", templates are named after their file",
+            name
+        ));
+    }
+
+    let exact = dir.join(name);
+    if exact.exists() {
+        return Ok(exact);
+    }
+
+    let mut available: Vec<String> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| e.file_name().into_string().ok())
+            .filter(|n| !n.starts_with('.'))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    available.sort();
+    let matching: Vec<&String> = available
+        .iter()
+        .filter(|n| Path::new(n).file_stem().is_some_and(|s| s == name))
+        .collect();
+
+    match matching.as_slice() {
+        [found] => Ok(dir.join(found)),
+        [] if available.is_empty() => Err(format!(
+            "No template named \"{}\", put templates in \"{}\"",
+            name,
+            dir.display()
+        )),
+        [] => Err(format!(
+            "No template named \"{}\", available: {}",
+            name,
+            available.join(", ")
+        )),
+        _ => {
+            let names: Vec<&str> = matching.iter().map(|n| n.as_str()).collect();
+            Err(format!(
+                "Template name \"{}\" is ambiguous, it could be {}",
+                name,
+                names.join(", ")
+            ))
+        }
+    }
+}
+
+/// Render a single template file, its placeholders are its variables and all need a
+/// value, except built-in ones and those only tested by `{{#if}}` or iterated by `{{#each}}`.
+fn render_file(path: &Path, sets: &[String]) -> Result<(PathBuf, String), String> {
+    let template = match std::fs::read_to_string(path) {
+        Ok(t) => t,
+        Err(_) => return Err(format!("Failed to read \"{}\"", path.display())),
+    };
+    let vars = match render::placeholders(&template) {
+        Ok(p) => p,
+        Err(e) => return Err(format!("{} in \"{}\"", e, path.display())),
+    };
+
+    let mut values = HashMap::new();
+    for set in sets.iter() {
+        let (key, value) = split_set(set)?;
+        if !vars.iter().any(|v| v.name == key) {
+            return Err(format!(
+                "Unknown variable \"{}\" for template \"{}\"",
+                key,
+                path.display()
+            ));
+        }
+        values.insert(key.to_string(), value.to_string());
+    }
+    with_builtin_vars(&mut values);
+    if let Some(missing) = vars
+        .iter()
+        .find(|v| !v.optional && !values.contains_key(&v.name))
+    {
+        return Err(format!("Missing variable: --set {}=...", missing.name));
+    }
+
+    // The output keeps the name of the template file.
+    let output = PathBuf::from(path.file_name().unwrap_or_default());
+    Ok((output, render::render(&template, &values)?))
+}
+
+/// Render every file of a bundle to its rendered output path, `hint` tells how
+/// a missing variable is given.
+fn render_bundle(
+    bundle: &TemplateBundle,
+    given: HashMap<String, String>,
+    hint: impl Fn(&str) -> String,
+) -> Result<Vec<(PathBuf, String)>, String> {
+    let values = resolve_values(bundle, given, hint)?;
+
+    let mut rendered = Vec::new();
+    for file in bundle.files.iter() {
+        let template = match std::fs::read_to_string(bundle.root.join(&file.template)) {
+            Ok(t) => t,
+            Err(_) => return Err(format!("Failed to read \"{}\"", file.template.display())),
+        };
+        // Values could lead the output path out of the output root.
+        let output = render::render(&file.output.to_string_lossy(), &values)?;
+        let output = match bundle::relative_path(&output) {
+            Ok(o) if !o.as_os_str().is_empty() => o,
+            _ => {
+                return Err(format!(
+                    "Output path \"{}\" leaves the output directory",
+                    output
+                ));
+            }
+        };
+        rendered.push((output, render::render(&template, &values)?));
+    }
+    Ok(rendered)
+}
+
+fn split_set(set: &str) -> Result<(&str, &str), String> {
+    match set.split_once('=') {
+        Some(kv) => Ok(kv),
+        None => Err(format!("Invalid --set \"{}\", expected KEY=VALUE", set)),
+    }
+}
+
+/// Values of `--set`, each must be a variable of the bundle.
+fn bundle_sets(
+    bundle: &TemplateBundle,
+    sets: &[String],
+) -> Result<HashMap<String, String>, String> {
+    let mut values = HashMap::new();
+    for set in sets.iter() {
+        let (key, value) = split_set(set)?;
+        if bundle.var(key).is_none() {
+            return Err(format!(
+                "Unknown variable \"{}\" for bundle \"{}\"",
+                key, bundle.name
+            ));
+        }
+        values.insert(key.to_string(), value.to_string());
+    }
+    Ok(values)
+}
+
+/// Combine the given values with the declared defaults, every required variable needs a value.
+fn resolve_values(
+    bundle: &TemplateBundle,
+    mut values: HashMap<String, String>,
+    hint: impl Fn(&str) -> String,
+) -> Result<HashMap<String, String>, String> {
+    for var in bundle.vars.iter() {
+        if values.contains_key(&var.name) {
+            continue;
+        }
+        match (&var.default, var.required) {
+            (Some(d), _) => {
+                values.insert(var.name.clone(), d.clone());
+            }
+            (None, true) if var.help.is_empty() => {
+                return Err(format!("Missing variable: {}", hint(&var.name)));
+            }
+            (None, true) => {
+                return Err(format!(
+                    "Missing variable: {} ({})",
+                    hint(&var.name),
+                    var.help
+                ));
+            }
+            // Optional variables without a default render as empty.
+            (None, false) => {
+                values.insert(var.name.clone(), String::new());
+            }
+        }
+    }
+
+    with_builtin_vars(&mut values);
+    Ok(values)
+}
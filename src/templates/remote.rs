@@ -0,0 +1,296 @@
+//! Templates and bundles added from a git repository or an https URL.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use super::{TEMPLATE_DIR_NAME, bundle, render};
+use crate::{
+    config_file::toml_error,
+    logging,
+    program_args::{Arg, CommandArg, providers},
+    scratch,
+};
+
+/// Where each added template came from, in the data directory next to the templates.
+pub const LOCK_FILE_NAME: &str = "templates.lock";
+
+const ARCHIVE_EXTENSIONS: &[&str] = &[".tar.gz", ".tgz", ".zip"];
+
+enum Remote<'a> {
+    /// A repository, cloned at the branch or tag after `#`.
+    Git {
+        url: &'a str,
+        branch: Option<&'a str>,
+    },
+    /// A single template file or a bundle archive.
+    Https(&'a str),
+}
+
+pub fn define_add_args(cmd: &mut CommandArg) {
+    cmd.usage_name("filetemp template add")
+        .no_file_type()
+        .positional(
+            "URL",
+            "Git repository, or https URL of a template file or bundle archive",
+        )
+        .add_general_arg_def(
+            Arg::new("name")
+                .value_name("NAME")
+                .help("Name in the templates directory, by default the last part of the URL"),
+        )
+        .add_general_arg_def(
+            Arg::new("force")
+                .flag(true)
+                .help("Replace a template of the same name"),
+        )
+        .after_help(
+            "A git URL ends with .git or starts with git@, git://, ssh:// or git+, e.g. \
+             git+https://example.com/templates/cpp-app, and may end with #BRANCH or #TAG. \
+             The repository must be a bundle, with a manifest.toml at its root.\n\
+             An https URL names a template file, or a bundle in a .zip, .tar.gz or .tgz archive.\n\
+             Each added template is recorded in templates.lock in the data directory, \
+             with its source and revision.",
+        );
+}
+
+/// Download the template given by URL into the templates directory of `data_dir`
+/// and record it in the lockfile.
+pub fn add(cmd: &mut CommandArg, data_dir: &Path) -> Result<(), String> {
+    let url = match cmd.get_positional() {
+        Some(u) => u,
+        None => return Err(String::from("Give the URL of a template to add")),
+    };
+    let remote = parse_url(url)?;
+    let name = match cmd.get_arg("name") {
+        Some(n) => n.to_string(),
+        None => default_name(&remote),
+    };
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(format!(
+            "Invalid template name \"{}\", give one with --name",
+            name
+        ));
+    }
+
+    let template_dir = data_dir.join(TEMPLATE_DIR_NAME);
+    let target = template_dir.join(&name);
+    if target.exists() && !cmd.get_flag("force") {
+        return Err(format!(
+            "Template \"{}\" already exists, give --force to replace it",
+            name
+        ));
+    }
+
+    let work = scratch::new_dir("template-add")?;
+    let (fetched, revision) = match remote {
+        Remote::Git { url, branch } => clone(url, branch, &work.join("repo"))?,
+        Remote::Https(url) => download(url, &work.join(&name))?,
+    };
+
+    // Validate before replacing anything, a bundle is copied from its root.
+    let is_archive = ARCHIVE_EXTENSIONS
+        .iter()
+        .any(|e| url_path(url).ends_with(e));
+    let bundle_root = if fetched.is_dir() || is_archive {
+        let bundle = bundle::load_bundle(&fetched)?;
+        if !bundle.hooks.is_empty() {
+            logging::warn(format_args!(
+                "\"{}\" runs these commands after rendering, unless --no-hooks is given:\n  {}",
+                name,
+                bundle.hooks.join("\n  ")
+            ));
+        }
+        Some(bundle.root)
+    } else {
+        let content = match fs::read_to_string(&fetched) {
+            Ok(c) => c,
+            Err(_) => return Err(format!("\"{}\" is not a text template", url)),
+        };
+        if let Err(e) = render::placeholders(&content) {
+            return Err(format!("{} in \"{}\"", e, url));
+        }
+        None
+    };
+
+    if target.is_dir() {
+        let _ = fs::remove_dir_all(&target);
+    } else if target.exists() {
+        let _ = fs::remove_file(&target);
+    }
+    let copied = match &bundle_root {
+        Some(root) => copy_tree(root, &target),
+        None => {
+            fs::create_dir_all(&template_dir).and_then(|_| fs::copy(&fetched, &target).map(|_| ()))
+        }
+    };
+    if copied.is_err() {
+        return Err(format!("Failed to write \"{}\"", target.display()));
+    }
+
+    record_lock(&data_dir.join(LOCK_FILE_NAME), &name, url, &revision)?;
+    logging::success(format_args!("Added template \"{}\" at {}", name, revision));
+    Ok(())
+}
+
+fn parse_url(url: &str) -> Result<Remote<'_>, String> {
+    let (base, fragment) = match url.split_once('#') {
+        Some((b, f)) => (b, Some(f).filter(|f| !f.is_empty())),
+        None => (url, None),
+    };
+
+    let is_git = base.starts_with("git@")
+        || base.starts_with("git://")
+        || base.starts_with("ssh://")
+        || base.starts_with("git+")
+        || base.trim_end_matches('/').ends_with(".git");
+    if is_git {
+        return Ok(Remote::Git {
+            url: base.strip_prefix("git+").unwrap_or(base),
+            branch: fragment,
+        });
+    }
+
+    match url {
+        u if u.starts_with("https://") => Ok(Remote::Https(u)),
+        u if u.starts_with("http://") => Err(format!(
+            "Refusing to download \"{}\" without TLS, use https://",
+            u
+        )),
+        u => Err(format!(
+            "Unsupported template URL \"{}\", expected a git repository or an https URL",
+            u
+        )),
+    }
+}
+
+/// Last part of the URL path, without `.git` or an archive extension.
+fn default_name(remote: &Remote) -> String {
+    let url = match remote {
+        Remote::Git { url, .. } | Remote::Https(url) => url,
+    };
+    let last = url_path(url)
+        .trim_end_matches('/')
+        .rsplit(['/', ':'])
+        .next()
+        .unwrap_or_default();
+
+    let stripped = std::iter::once(".git")
+        .chain(ARCHIVE_EXTENSIONS.iter().copied())
+        .find_map(|e| last.strip_suffix(e));
+    stripped.unwrap_or(last).to_string()
+}
+
+/// The URL without its query or fragment.
+fn url_path(url: &str) -> &str {
+    url.split(['?', '#']).next().unwrap_or_default()
+}
+
+/// Shallow clone of the repository, returns the checkout and its commit.
+fn clone(url: &str, branch: Option<&str>, dir: &Path) -> Result<(PathBuf, String), String> {
+    let mut git = Command::new("git");
+    git.args(["clone", "--quiet", "--depth", "1"]);
+    if let Some(b) = branch {
+        git.args(["--branch", b]);
+    }
+    git.arg(url).arg(dir);
+    run(&mut git, "git clone")?;
+
+    let revision = run(
+        Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["rev-parse", "HEAD"]),
+        "git rev-parse",
+    )?;
+    let _ = fs::remove_dir_all(dir.join(".git"));
+    Ok((dir.to_path_buf(), revision))
+}
+
+/// Download to `file`, the revision of a download is the CRC-32 of its content.
+fn download(url: &str, file: &Path) -> Result<(PathBuf, String), String> {
+    run(
+        Command::new("curl")
+            .args([
+                "--fail",
+                "--silent",
+                "--show-error",
+                "--location",
+                "--output",
+            ])
+            .arg(file)
+            .arg(url),
+        "curl",
+    )?;
+
+    let content = match fs::read(file) {
+        Ok(c) => c,
+        Err(_) => return Err(format!("Failed to read the download of \"{}\"", url)),
+    };
+    let mut crc = flate2::Crc::new();
+    crc.update(&content);
+    Ok((file.to_path_buf(), format!("crc32:{:08x}", crc.sum())))
+}
+
+/// Run a command, its trimmed stdout on success or its stderr as the error.
+fn run(command: &mut Command, what: &str) -> Result<String, String> {
+    let output = match command.output() {
+        Ok(o) => o,
+        Err(_) => {
+            return Err(format!(
+                "Failed to run {}, is it installed?",
+                what.split(' ').next().unwrap_or(what)
+            ));
+        }
+    };
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            what,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn copy_tree(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let path = entry?.path();
+        let target = to.join(path.file_name().unwrap_or_default());
+        if path.is_dir() {
+            copy_tree(&path, &target)?;
+        } else {
+            fs::copy(&path, &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Record the source and revision of `name`, replacing an earlier entry.
+fn record_lock(path: &Path, name: &str, url: &str, revision: &str) -> Result<(), String> {
+    let content = fs::read_to_string(path).unwrap_or_default();
+    let mut lock = match content.parse::<toml::Table>() {
+        Ok(t) => t,
+        Err(e) => return Err(toml_error(LOCK_FILE_NAME, &content, &e)),
+    };
+
+    let mut entry = toml::Table::new();
+    entry.insert(String::from("source"), toml::Value::from(url));
+    entry.insert(String::from("revision"), toml::Value::from(revision));
+    if let Some(date) = providers::find_provider("date").and_then(|p| p.provide()) {
+        entry.insert(String::from("added"), toml::Value::from(date));
+    }
+    lock.insert(name.to_string(), toml::Value::Table(entry));
+
+    let text = match toml::to_string(&lock) {
+        Ok(t) => t,
+        Err(e) => return Err(format!("Failed to write {}: {}", LOCK_FILE_NAME, e)),
+    };
+    match fs::write(path, text) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!("Failed to write \"{}\"", path.display())),
+    }
+}
@@ -0,0 +1,496 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+/// A variable used by a template, in a `{{name}}` placeholder or a block.
+pub struct Placeholder {
+    pub name: String,
+    /// 1-based line of the opening braces.
+    pub line: usize,
+    /// Only tested by `{{#if}}` or iterated by `{{#each}}`, an unset value counts as empty.
+    pub optional: bool,
+}
+
+/// Text, or the trimmed content of a `{{...}}` tag with its 1-based line.
+#[derive(Clone, Copy)]
+enum Token<'a> {
+    Text(&'a str),
+    Tag(&'a str, usize),
+}
+
+enum Node<'a> {
+    Text(&'a str),
+    Var(&'a str, usize),
+    /// `this` or `@index` of the enclosing `{{#each}}`.
+    Item(&'a str),
+    If {
+        cond: Condition<'a>,
+        then: Vec<Node<'a>>,
+        otherwise: Vec<Node<'a>>,
+    },
+    Each {
+        name: &'a str,
+        line: usize,
+        body: Vec<Node<'a>>,
+    },
+}
+
+struct Condition<'a> {
+    name: &'a str,
+    line: usize,
+    test: Test<'a>,
+}
+
+enum Test<'a> {
+    /// Set, not empty and neither "false" nor "0".
+    Truthy,
+    Falsy,
+    Compare(Ordering, bool, &'a str),
+}
+
+/// How a block ended, the closing tags are checked against the opening one.
+enum End {
+    Close,
+    Else,
+    Eof,
+}
+
+/// Collect the variables of a template, failing on unclosed, empty or misplaced tags.
+pub fn placeholders(template: &str) -> Result<Vec<Placeholder>, String> {
+    let nodes = parse(template)?;
+    let mut found = Vec::new();
+    collect_vars(&nodes, &mut found);
+    Ok(found)
+}
+
+/// Replace every placeholder with its value and expand the blocks, plain
+/// placeholders must have a value.
+pub fn render(template: &str, values: &HashMap<String, String>) -> Result<String, String> {
+    let nodes = parse(template)?;
+    let mut out = String::with_capacity(template.len());
+    render_nodes(&nodes, values, None, &mut out)?;
+    Ok(out)
+}
+
+fn parse(template: &str) -> Result<Vec<Node<'_>>, String> {
+    let tokens = tokenize(template)?;
+    let mut pos = 0;
+    let (nodes, _) = parse_nodes(&tokens, &mut pos, None, false)?;
+    Ok(nodes)
+}
+
+fn tokenize(template: &str) -> Result<Vec<Token<'_>>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = template;
+    let mut offset = 0;
+
+    while let Some(start) = rest.find("{{") {
+        let line = template[..offset + start].matches('\n').count() + 1;
+        let after = &rest[start + 2..];
+        let end = match after.find("}}") {
+            Some(e) => e,
+            None => return Err(format!("Unclosed placeholder at line {}", line)),
+        };
+
+        let content = after[..end].trim();
+        if content.is_empty() {
+            return Err(format!("Empty placeholder at line {}", line));
+        }
+        tokens.push(Token::Text(&rest[..start]));
+        tokens.push(Token::Tag(content, line));
+
+        let consumed = start + 2 + end + 2;
+        offset += consumed;
+        rest = &rest[consumed..];
+    }
+    tokens.push(Token::Text(rest));
+
+    strip_standalone(&mut tokens);
+    Ok(tokens)
+}
+
+/// A block tag alone on its line leaves no blank line behind. Tokens alternate
+/// between text and tags, starting and ending with text.
+fn strip_standalone(tokens: &mut [Token<'_>]) {
+    // Whether the text before the current tag starts at the beginning of a line.
+    let mut at_line_start = true;
+    for idx in (1..tokens.len()).step_by(2) {
+        let is_block =
+            matches!(tokens[idx], Token::Tag(t, _) if t.starts_with(['#', '/']) || t == "else");
+        let (before, after) = match (tokens[idx - 1], tokens[idx + 1]) {
+            (Token::Text(b), Token::Text(a)) => (b, a),
+            _ => unreachable!(),
+        };
+
+        let line_start = match before.rfind('\n') {
+            Some(p) => Some(p + 1),
+            None if at_line_start => Some(0),
+            None => None,
+        };
+        let line_end = match after.find('\n') {
+            Some(p) => Some(p + 1),
+            None if idx + 2 == tokens.len() => Some(after.len()),
+            None => None,
+        };
+
+        at_line_start = false;
+        if let (true, Some(s), Some(e)) = (is_block, line_start, line_end)
+            && before[s..].trim().is_empty()
+            && after[..e].trim().is_empty()
+        {
+            tokens[idx - 1] = Token::Text(&before[..s]);
+            tokens[idx + 1] = Token::Text(&after[e..]);
+            at_line_start = true;
+        }
+    }
+}
+
+/// Nodes up to the end of the block `open`, named with the line it was opened at.
+fn parse_nodes<'a>(
+    tokens: &[Token<'a>],
+    pos: &mut usize,
+    open: Option<(&str, usize)>,
+    in_each: bool,
+) -> Result<(Vec<Node<'a>>, End), String> {
+    let mut nodes = Vec::new();
+
+    while let Some(&token) = tokens.get(*pos) {
+        *pos += 1;
+        let (tag, line) = match token {
+            Token::Text("") => continue,
+            Token::Text(t) => {
+                nodes.push(Node::Text(t));
+                continue;
+            }
+            Token::Tag(tag, line) => (tag, line),
+        };
+
+        if let Some(expr) = tag.strip_prefix("#if ") {
+            let cond = parse_condition(expr.trim(), line)?;
+            let (then, end) = parse_nodes(tokens, pos, Some(("if", line)), in_each)?;
+            let otherwise = match end {
+                End::Else => parse_nodes(tokens, pos, Some(("else", line)), in_each)?.0,
+                _ => Vec::new(),
+            };
+            nodes.push(Node::If {
+                cond,
+                then,
+                otherwise,
+            });
+        } else if let Some(name) = tag.strip_prefix("#each ") {
+            let name = name.trim();
+            let (body, _) = parse_nodes(tokens, pos, Some(("each", line)), true)?;
+            nodes.push(Node::Each { name, line, body });
+        } else if tag.starts_with('#') {
+            return Err(format!(
+                "Unknown block \"{{{{{}}}}}\" at line {}",
+                tag, line
+            ));
+        } else if let Some(name) = tag.strip_prefix('/') {
+            let name = name.trim();
+            return match open {
+                Some((o, _)) if o == name || (o == "else" && name == "if") => {
+                    Ok((nodes, End::Close))
+                }
+                Some((o, o_line)) => Err(format!(
+                    "{{{{/{}}}}} at line {} does not close {{{{#{}}}}} from line {}",
+                    name,
+                    line,
+                    if o == "else" { "if" } else { o },
+                    o_line
+                )),
+                None => Err(format!(
+                    "{{{{/{}}}}} without {{{{#{}}}}} at line {}",
+                    name, name, line
+                )),
+            };
+        } else if tag == "else" {
+            return match open {
+                Some(("if", _)) => Ok((nodes, End::Else)),
+                _ => Err(format!(
+                    "{{{{else}}}} outside of {{{{#if}}}} at line {}",
+                    line
+                )),
+            };
+        } else if tag == "this" || tag == "@index" {
+            if !in_each {
+                return Err(format!(
+                    "{{{{{}}}}} outside of {{{{#each}}}} at line {}",
+                    tag, line
+                ));
+            }
+            nodes.push(Node::Item(tag));
+        } else {
+            nodes.push(Node::Var(tag, line));
+        }
+    }
+
+    match open {
+        Some((o, line)) => Err(format!(
+            "Unclosed {{{{#{}}}}} from line {}",
+            if o == "else" { "if" } else { o },
+            line
+        )),
+        None => Ok((nodes, End::Eof)),
+    }
+}
+
+/// `name`, `!name` or `name OP value` with `==`, `!=`, `<`, `<=`, `>` or `>=`,
+/// the value may be quoted.
+fn parse_condition(expr: &str, line: usize) -> Result<Condition<'_>, String> {
+    const OPS: [(&str, Ordering, bool); 6] = [
+        (">=", Ordering::Less, false),
+        ("<=", Ordering::Greater, false),
+        ("==", Ordering::Equal, true),
+        ("!=", Ordering::Equal, false),
+        (">", Ordering::Greater, true),
+        ("<", Ordering::Less, true),
+    ];
+
+    let found = OPS
+        .iter()
+        .filter_map(|&(op, ord, is)| expr.find(op).map(|p| (p, op, ord, is)))
+        .min_by_key(|&(p, op, _, _)| (p, std::cmp::Reverse(op.len())));
+    let (name, test) = match found {
+        Some((p, op, ord, is)) => {
+            let value = expr[p + op.len()..].trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value);
+            (expr[..p].trim(), Test::Compare(ord, is, value))
+        }
+        None => match expr.strip_prefix('!') {
+            Some(n) => (n.trim(), Test::Falsy),
+            None => (expr, Test::Truthy),
+        },
+    };
+
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(format!("Invalid condition \"{}\" at line {}", expr, line));
+    }
+    Ok(Condition { name, line, test })
+}
+
+fn collect_vars(nodes: &[Node<'_>], found: &mut Vec<Placeholder>) {
+    for node in nodes.iter() {
+        match node {
+            Node::Text(_) | Node::Item(_) => {}
+            Node::Var(name, line) => push_var(found, name, *line, false),
+            Node::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                push_var(found, cond.name, cond.line, true);
+                collect_vars(then, found);
+                collect_vars(otherwise, found);
+            }
+            Node::Each { name, line, body } => {
+                push_var(found, name, *line, true);
+                collect_vars(body, found);
+            }
+        }
+    }
+}
+
+/// Blocks may test or iterate the item of an enclosing `{{#each}}`, it is no variable.
+fn push_var(found: &mut Vec<Placeholder>, name: &str, line: usize, optional: bool) {
+    if name != "this" && name != "@index" {
+        found.push(Placeholder {
+            name: name.to_string(),
+            line,
+            optional,
+        });
+    }
+}
+
+/// `item` is the value and index of the innermost `{{#each}}`.
+fn render_nodes(
+    nodes: &[Node<'_>],
+    values: &HashMap<String, String>,
+    item: Option<(&str, usize)>,
+    out: &mut String,
+) -> Result<(), String> {
+    for node in nodes.iter() {
+        match node {
+            Node::Text(t) => out.push_str(t),
+            Node::Var(name, _) => match values.get(*name) {
+                Some(v) => out.push_str(v),
+                None => return Err(format!("No value for placeholder \"{}\"", name)),
+            },
+            Node::Item(name) => {
+                let (value, idx) = item.unwrap_or_default();
+                match *name {
+                    "this" => out.push_str(value),
+                    _ => out.push_str(&idx.to_string()),
+                }
+            }
+            Node::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                let index;
+                let value = match cond.name {
+                    "this" => item.map(|(v, _)| v),
+                    "@index" => {
+                        index = item.map(|(_, idx)| idx.to_string());
+                        index.as_deref()
+                    }
+                    name => values.get(name).map(String::as_str),
+                };
+                let branch = if test(&cond.test, value.unwrap_or_default()) {
+                    then
+                } else {
+                    otherwise
+                };
+                render_nodes(branch, values, item, out)?;
+            }
+            Node::Each { name, body, .. } => {
+                let list = match *name {
+                    "this" => item.map(|(v, _)| v),
+                    name => values.get(name).map(String::as_str),
+                };
+                for (idx, value) in list_items(list.unwrap_or_default()).enumerate() {
+                    render_nodes(body, values, Some((value, idx)), out)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn test(test: &Test<'_>, value: &str) -> bool {
+    let truthy = !value.is_empty() && value != "false" && value != "0";
+    match *test {
+        Test::Truthy => truthy,
+        Test::Falsy => !truthy,
+        Test::Compare(ord, is, other) => (compare(value, other) == ord) == is,
+    }
+}
+
+/// Dotted numbers such as versions compare by their parts, missing parts count
+/// as 0 so "3.20" equals "3.20.0". Anything else compares as text.
+fn compare(a: &str, b: &str) -> Ordering {
+    let parts = |s: &str| {
+        s.split('.')
+            .map(|p| p.parse::<u64>())
+            .collect::<Result<Vec<u64>, _>>()
+    };
+    match (parts(a), parts(b)) {
+        (Ok(x), Ok(y)) => (0..x.len().max(y.len()))
+            .map(|i| {
+                let part = |v: &[u64]| v.get(i).copied().unwrap_or(0);
+                part(&x).cmp(&part(&y))
+            })
+            .find(|o| o.is_ne())
+            .unwrap_or(Ordering::Equal),
+        _ => a.cmp(b),
+    }
+}
+
+/// Items of a list value, separated by commas.
+fn list_items(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_with(template: &str, values: &[(&str, &str)]) -> Result<String, String> {
+        let values: HashMap<String, String> = values
+            .iter()
+            .map(|&(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        render(template, &values)
+    }
+
+    #[test]
+    fn nested_blocks() {
+        let template = "{{#each libs}}{{#if @index > 0}}, {{/if}}{{this}}{{/each}}";
+        assert_eq!(
+            render_with(template, &[("libs", "m, pthread, dl")]).unwrap(),
+            "m, pthread, dl"
+        );
+
+        let template = "{{#if tests}}{{#each tests}}[{{#if this == unit}}u{{else}}{{this}}{{/if}}]{{/each}}{{else}}none{{/if}}";
+        assert_eq!(
+            render_with(template, &[("tests", "unit,e2e")]).unwrap(),
+            "[u][e2e]"
+        );
+        assert_eq!(render_with(template, &[("tests", "")]).unwrap(), "none");
+
+        let template = "{{#each dirs}}{{#each files}}{{this}}{{/each}}{{/each}}";
+        assert_eq!(
+            render_with(template, &[("dirs", "a,b"), ("files", "x,y")]).unwrap(),
+            "xyxy"
+        );
+    }
+
+    #[test]
+    fn standalone_block_tags_leave_no_blank_lines() {
+        let template = "start\n{{#each libs}}\n- {{this}}\n{{/each}}\nend\n";
+        assert_eq!(
+            render_with(template, &[("libs", "m,dl")]).unwrap(),
+            "start\n- m\n- dl\nend\n"
+        );
+    }
+
+    #[test]
+    fn empty_lists_render_nothing() {
+        let template = "[{{#each libs}}{{this}};{{/each}}]";
+        for empty in ["", " ", ",", " , ,"] {
+            assert_eq!(
+                render_with(template, &[("libs", empty)]).unwrap(),
+                "[]",
+                "{:?}",
+                empty
+            );
+        }
+        assert_eq!(
+            render_with(template, &[("libs", " m ,, dl ")]).unwrap(),
+            "[m;dl;]"
+        );
+    }
+
+    #[test]
+    fn missing_keys() {
+        // Blocks treat a missing value as empty, plain placeholders need one.
+        let template = "{{#if lto}}lto{{else}}no lto{{/if}}{{#each libs}}{{this}}{{/each}}";
+        assert_eq!(render_with(template, &[]).unwrap(), "no lto");
+        assert_eq!(render_with("{{#if !lto}}off{{/if}}", &[]).unwrap(), "off");
+        assert_eq!(
+            render_with("{{#if lto}}{{name}}{{/if}}", &[("lto", "1")]).unwrap_err(),
+            "No value for placeholder \"name\""
+        );
+
+        let vars = placeholders("{{#if lto}}{{name}}{{/if}}{{#each libs}}{{/each}}").unwrap();
+        let found: Vec<(&str, bool)> = vars.iter().map(|v| (v.name.as_str(), v.optional)).collect();
+        assert_eq!(found, [("lto", true), ("name", false), ("libs", true)]);
+    }
+
+    #[test]
+    fn unterminated_blocks() {
+        let cases = [
+            ("{{#if a}}x", "Unclosed {{#if}} from line 1"),
+            ("{{#if a}}x{{else}}y", "Unclosed {{#if}} from line 1"),
+            ("a\n{{#each b}}\n{{this}}", "Unclosed {{#each}} from line 2"),
+            (
+                "{{#each a}}{{#if this}}{{/each}}",
+                "{{/each}} at line 1 does not close {{#if}} from line 1",
+            ),
+            ("{{/if}}", "{{/if}} without {{#if}} at line 1"),
+            ("{{else}}", "{{else}} outside of {{#if}} at line 1"),
+            ("{{this}}", "{{this}} outside of {{#each}} at line 1"),
+            ("{{#if a}}\n{{name", "Unclosed placeholder at line 2"),
+        ];
+        for (template, error) in cases {
+            assert_eq!(
+                placeholders(template).err().as_deref(),
+                Some(error),
+                "{:?}",
+                template
+            );
+        }
+    }
+}
@@ -0,0 +1,309 @@
+use std::{fmt::Write, ops::Range};
+
+/// Lines of unchanged context shown around each diff hunk.
+const DIFF_CONTEXT: usize = 3;
+
+/// Replacement of a byte range in a source text, insertions use an empty range.
+#[derive(Clone)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+impl TextEdit {
+    pub fn replace(range: Range<usize>, replacement: impl Into<String>) -> Self {
+        Self {
+            range,
+            replacement: replacement.into(),
+        }
+    }
+
+    pub fn insert(at: usize, text: impl Into<String>) -> Self {
+        Self::replace(at..at, text)
+    }
+}
+
+/// Columns `text` takes in a terminal: East Asian wide and fullwidth characters
+/// take two, combining marks none.
+pub fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x0300..=0x036F | 0x200B..=0x200F | 0xFE00..=0xFE0F => 0,
+            0x1100..=0x115F
+            | 0x2E80..=0x303E
+            | 0x3041..=0x33FF
+            | 0x3400..=0x4DBF
+            | 0x4E00..=0x9FFF
+            | 0xA000..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x1F300..=0x1F64F
+            | 0x1F900..=0x1F9FF
+            | 0x20000..=0x3FFFD => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+/// `text` followed by spaces up to `width` columns.
+pub fn pad(text: &str, width: usize) -> String {
+    let fill = width.saturating_sub(display_width(text));
+    format!("{}{}", text, " ".repeat(fill))
+}
+
+/// Apply edits to a source. Edits overlapping an earlier one are skipped,
+/// their indices are returned so callers can report them.
+pub fn apply_edits(source: &str, edits: &[TextEdit]) -> (String, Vec<usize>) {
+    let mut order: Vec<usize> = (0..edits.len()).collect();
+    order.sort_by_key(|&i| (edits[i].range.start, edits[i].range.end));
+
+    let mut out = String::with_capacity(source.len());
+    let mut skipped = Vec::new();
+    let mut pos = 0;
+
+    for i in order {
+        let e = &edits[i];
+        if e.range.start < pos || e.range.end > source.len() {
+            skipped.push(i);
+            continue;
+        }
+
+        out.push_str(&source[pos..e.range.start]);
+        out.push_str(&e.replacement);
+        pos = e.range.end;
+    }
+    out.push_str(&source[pos..]);
+
+    (out, skipped)
+}
+
+enum DiffOp {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// Line based unified diff between two texts, empty if they are equal.
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    let a: Vec<&str> = old.split_inclusive('\n').collect();
+    let b: Vec<&str> = new.split_inclusive('\n').collect();
+    let ops = diff_ops(&a, &b);
+
+    if ops.iter().all(|op| matches!(op, DiffOp::Equal(..))) {
+        return String::new();
+    }
+
+    let mut out = String::new();
+    writeln!(&mut out, "--- {}", old_name).unwrap();
+    writeln!(&mut out, "+++ {}", new_name).unwrap();
+
+    let mut idx = 0;
+    while idx < ops.len() {
+        // Find the next change and the extent of its hunk.
+        let first_change = match ops[idx..]
+            .iter()
+            .position(|op| !matches!(op, DiffOp::Equal(..)))
+        {
+            Some(p) => idx + p,
+            None => break,
+        };
+
+        let start = first_change.saturating_sub(DIFF_CONTEXT).max(idx);
+        let mut last_change = first_change;
+        for (i, op) in ops.iter().enumerate().skip(first_change) {
+            if !matches!(op, DiffOp::Equal(..)) {
+                last_change = i;
+            } else if i - last_change > DIFF_CONTEXT * 2 {
+                break;
+            }
+        }
+        let end = (last_change + 1 + DIFF_CONTEXT).min(ops.len());
+
+        let (mut old_start, mut new_start) = (None, None);
+        let (mut old_len, mut new_len) = (0, 0);
+        let mut body = String::new();
+        for op in ops[start..end].iter() {
+            match *op {
+                DiffOp::Equal(i, j) => {
+                    old_start.get_or_insert(i);
+                    new_start.get_or_insert(j);
+                    old_len += 1;
+                    new_len += 1;
+                    push_diff_line(&mut body, ' ', a[i]);
+                }
+                DiffOp::Delete(i) => {
+                    old_start.get_or_insert(i);
+                    old_len += 1;
+                    push_diff_line(&mut body, '-', a[i]);
+                }
+                DiffOp::Insert(j) => {
+                    new_start.get_or_insert(j);
+                    new_len += 1;
+                    push_diff_line(&mut body, '+', b[j]);
+                }
+            }
+        }
+
+        // A hunk with no lines on one side points at the line before it.
+        let old_start = old_start
+            .map(|i| i + 1)
+            .unwrap_or_else(|| hunk_anchor(&ops[..start], true));
+        let new_start = new_start
+            .map(|j| j + 1)
+            .unwrap_or_else(|| hunk_anchor(&ops[..start], false));
+        writeln!(
+            &mut out,
+            "@@ -{},{} +{},{} @@",
+            old_start, old_len, new_start, new_len
+        )
+        .unwrap();
+        out.push_str(&body);
+
+        idx = end;
+    }
+
+    out
+}
+
+/// A last line without a line ending is marked as in `diff -u`, otherwise
+/// adding or removing only the final line break shows the same line twice.
+fn push_diff_line(out: &mut String, marker: char, line: &str) {
+    out.push(marker);
+    out.push_str(line.trim_end_matches(['\r', '\n']));
+    out.push('\n');
+    if !line.ends_with('\n') {
+        out.push_str("\\ No newline at end of file\n");
+    }
+}
+
+fn hunk_anchor(before: &[DiffOp], old_side: bool) -> usize {
+    before
+        .iter()
+        .filter(|op| match op {
+            DiffOp::Equal(..) => true,
+            DiffOp::Delete(_) => old_side,
+            DiffOp::Insert(_) => !old_side,
+        })
+        .count()
+}
+
+/// Longest-common-subsequence line diff.
+fn diff_ops(a: &[&str], b: &[&str]) -> Vec<DiffOp> {
+    let (n, m) = (a.len(), b.len());
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && a[i] == b[j] {
+            ops.push(DiffOp::Equal(i, j));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(DiffOp::Delete(i));
+            i += 1;
+        } else {
+            ops.push(DiffOp::Insert(j));
+            j += 1;
+        }
+    }
+
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_apply_in_source_order_and_overlaps_are_skipped() {
+        let edits = [
+            TextEdit::replace(4..5, "two"),
+            TextEdit::insert(0, ">"),
+            TextEdit::replace(4..6, "x"),
+            TextEdit::replace(8..20, "out of range"),
+        ];
+        assert_eq!(
+            apply_edits("one 2 three", &edits),
+            (String::from(">one two three"), vec![2, 3])
+        );
+    }
+
+    #[test]
+    fn equal_texts_have_no_diff() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "old", "new"), "");
+    }
+
+    #[test]
+    fn changed_line_with_context() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n";
+        let new = "1\n2\n3\n4\nfive\n6\n7\n8\n";
+        assert_eq!(
+            unified_diff(old, new, "a", "b"),
+            "--- a\n+++ b\n@@ -2,7 +2,7 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8\n"
+        );
+    }
+
+    #[test]
+    fn distant_changes_get_their_own_hunks() {
+        let old: String = (1..=20).map(|i| format!("{}\n", i)).collect();
+        let new: String = (1..=20)
+            .map(|i| match i {
+                2 => String::from("two\n"),
+                19 => String::from("nineteen\n"),
+                i => format!("{}\n", i),
+            })
+            .collect();
+        let diff = unified_diff(&old, &new, "a", "b");
+        let hunks: Vec<&str> = diff.lines().filter(|l| l.starts_with("@@")).collect();
+        assert_eq!(hunks, ["@@ -1,5 +1,5 @@", "@@ -16,5 +16,5 @@"]);
+    }
+
+    #[test]
+    fn insertion_into_an_empty_text_points_before_the_first_line() {
+        assert_eq!(
+            unified_diff("", "a\n", "a", "b"),
+            "--- a\n+++ b\n@@ -0,0 +1,1 @@\n+a\n"
+        );
+    }
+
+    #[test]
+    fn missing_final_line_break_is_marked() {
+        assert_eq!(
+            unified_diff("a\nb", "a\nb\n", "a", "b"),
+            "--- a\n+++ b\n@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+b\n"
+        );
+        assert_eq!(
+            unified_diff("a\n", "a\nb", "a", "b"),
+            "--- a\n+++ b\n@@ -1,1 +1,2 @@\n a\n+b\n\\ No newline at end of file\n"
+        );
+    }
+
+    #[test]
+    fn crlf_lines_show_without_their_carriage_return() {
+        assert_eq!(
+            unified_diff("a\r\nb\r\n", "a\r\nc\r\n", "a", "b"),
+            "--- a\n+++ b\n@@ -1,2 +1,2 @@\n a\n-b\n+c\n"
+        );
+    }
+
+    #[test]
+    fn wide_characters_pad_to_columns() {
+        assert_eq!(display_width("项目"), 4);
+        assert_eq!(display_width("e\u{301}"), 1);
+        assert_eq!(pad("项目", 6), "项目  ");
+        assert_eq!(pad("toolong", 3), "toolong");
+    }
+}
@@ -0,0 +1,226 @@
+//! `filetemp watch`, applying a spec again whenever it or a user template of
+//! its file types changes. Files are polled rather than subscribed to, which
+//! works the same on every platform and file system.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    file_types::custom::{self, TYPE_DIR_NAME},
+    i18n::{tr, trf},
+    logging,
+    output_sink::manifest::{Manifest, checksum},
+    project_config::load_spec,
+    templates::{self, TEMPLATE_DIR_NAME},
+};
+
+/// How often the watched files are looked at.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Milliseconds without changes before regenerating, so an editor saving a
+/// file in several writes regenerates once.
+pub const DEFAULT_DEBOUNCE_MS: u64 = 300;
+
+/// Modification time and size of each watched file, None if it is missing.
+type Stamps = Vec<(PathBuf, Option<(SystemTime, u64)>)>;
+
+/// The files `spec` is generated from: the spec itself and, for each file type
+/// it lists, the manifest and template of a declared type or the user template
+/// rendered in place of a built-in one.
+pub fn watched_files(spec: &Path, data_dir: Option<&Path>) -> Vec<PathBuf> {
+    let template_dir = data_dir.map(|d| d.join(TEMPLATE_DIR_NAME));
+    let type_dir = data_dir.map(|d| d.join(TYPE_DIR_NAME));
+
+    let mut files = vec![spec.to_path_buf()];
+    for entry in load_spec(spec).unwrap_or_default() {
+        let found: Vec<PathBuf> = match type_dir
+            .as_deref()
+            .and_then(|d| find_manifest(d, &entry.file_type))
+        {
+            Some(manifest) => {
+                let template = custom::read_unchecked(&manifest)
+                    .ok()
+                    .map(|t| t.template_path);
+                std::iter::once(manifest).chain(template).collect()
+            }
+            None => templates::find_override(template_dir.as_deref(), &entry.file_type)
+                .into_iter()
+                .collect(),
+        };
+        for file in found {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+    }
+    files
+}
+
+/// The manifest declaring the type `name`, matched regardless of case.
+fn find_manifest(type_dir: &Path, name: &str) -> Option<PathBuf> {
+    fs::read_dir(type_dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .find(|p| {
+            p.extension().is_some_and(|e| e == "toml")
+                && p.file_stem()
+                    .is_some_and(|s| s.to_string_lossy().eq_ignore_ascii_case(name))
+        })
+}
+
+/// The directories `spec` generates into, the `--path` of `options` if given.
+pub fn output_dirs(spec: &Path, options: &[&str]) -> Vec<PathBuf> {
+    let given = path_arg(options.iter().copied());
+    if let Some(dir) = given {
+        return vec![dir];
+    }
+
+    let mut dirs = Vec::new();
+    for entry in load_spec(spec).unwrap_or_default() {
+        if let Some(dir) = path_arg(entry.args.iter().map(String::as_str))
+            && !dirs.contains(&dir)
+        {
+            dirs.push(dir);
+        }
+    }
+    dirs
+}
+
+/// The value of the last `--path` among `words`.
+fn path_arg<'a>(words: impl Iterator<Item = &'a str>) -> Option<PathBuf> {
+    let mut path = None;
+    let mut words = words;
+    while let Some(word) = words.next() {
+        match word.strip_prefix("--path") {
+            Some(rest) if rest.starts_with('=') => path = Some(PathBuf::from(&rest[1..])),
+            Some("") => path = words.next().map(PathBuf::from),
+            _ => {}
+        }
+    }
+    path
+}
+
+/// Checksums of the files recorded in the manifests of `dirs`, as they are on disk.
+fn generated_files(dirs: &[PathBuf]) -> BTreeMap<PathBuf, u32> {
+    let mut files = BTreeMap::new();
+    for dir in dirs {
+        let manifest = match Manifest::load(dir) {
+            Ok(m) => m,
+            Err(e) => {
+                logging::warn(e);
+                continue;
+            }
+        };
+        for file in manifest.files() {
+            let path = dir.join(file);
+            if let Ok(content) = fs::read(&path) {
+                files.insert(path, checksum(&content));
+            }
+        }
+    }
+    files
+}
+
+fn stamps(files: &[PathBuf]) -> Stamps {
+    files
+        .iter()
+        .map(|f| {
+            let stamp = fs::metadata(f)
+                .ok()
+                .and_then(|m| Some((m.modified().ok()?, m.len())));
+            (f.clone(), stamp)
+        })
+        .collect()
+}
+
+/// Files whose stamp differs between `before` and `after`, or that are only in one.
+fn changed<'a>(before: &'a Stamps, after: &'a Stamps) -> Vec<&'a Path> {
+    let mut changed: Vec<&Path> = after
+        .iter()
+        .filter(|entry| !before.contains(entry))
+        .map(|(f, _)| f.as_path())
+        .collect();
+    for (file, _) in before.iter() {
+        if !after.iter().any(|(f, _)| f == file) {
+            changed.push(file);
+        }
+    }
+    changed
+}
+
+/// Generate with `apply`, then print the generated files it rewrote. `apply`
+/// tells whether generating succeeded.
+fn regenerate(spec: &Path, options: &[&str], apply: &mut impl FnMut() -> bool) {
+    let dirs = output_dirs(spec, options);
+    let before = generated_files(&dirs);
+    let succeeded = apply();
+    let after = generated_files(&dirs);
+
+    let rewritten: Vec<&PathBuf> = after
+        .iter()
+        .filter(|(f, sum)| before.get(*f) != Some(sum))
+        .map(|(f, _)| f)
+        .collect();
+    for file in rewritten.iter() {
+        logging::success(trf("Rewrote \"{}\"", &[&file.display()]));
+    }
+    eprintln!(
+        "{}",
+        trf(
+            "{} rewritten, {} unchanged",
+            &[&rewritten.len(), &(after.len() - rewritten.len())]
+        )
+    );
+    if !succeeded {
+        logging::warn(tr("Generating failed, waiting for the next change"));
+    }
+}
+
+/// Generate once with `apply`, then again each time a file `spec` is generated
+/// from changes and is left alone for `debounce`. Runs until interrupted.
+pub fn run(
+    spec: &Path,
+    options: &[&str],
+    data_dir: Option<&Path>,
+    debounce: Duration,
+    mut apply: impl FnMut() -> bool,
+) -> ! {
+    regenerate(spec, options, &mut apply);
+    let mut seen = stamps(&watched_files(spec, data_dir));
+    eprintln!(
+        "{}",
+        trf("Files watched: {}, press Ctrl-C to stop", &[&seen.len()])
+    );
+    for (file, _) in seen.iter() {
+        logging::verbose(format_args!("Watching \"{}\"", file.display()));
+    }
+
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let mut current = stamps(&watched_files(spec, data_dir));
+        if current == seen {
+            continue;
+        }
+        // Wait until the files stay the same for the whole debounce.
+        loop {
+            thread::sleep(debounce);
+            let settled = stamps(&watched_files(spec, data_dir));
+            if settled == current {
+                break;
+            }
+            current = settled;
+        }
+
+        for file in changed(&seen, &current) {
+            eprintln!("{}", trf("Changed \"{}\"", &[&file.display()]));
+        }
+        regenerate(spec, options, &mut apply);
+        seen = current;
+    }
+}
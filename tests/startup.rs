@@ -0,0 +1,51 @@
+//! Startup time, which stays low as file types are added because only the
+//! selected type defines its arguments. The budgets are loose enough for a
+//! debug build on a busy CI machine, they catch eager definition of every type.
+
+use std::{
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use filetemp::ResolvedArgs;
+
+const RUNS: usize = 25;
+
+/// Median time of `RUNS` calls of `f`, after one to warm up.
+fn median(mut f: impl FnMut()) -> Duration {
+    f();
+    let mut times: Vec<Duration> = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+    times[RUNS / 2]
+}
+
+#[test]
+fn parsing_arguments_is_fast() {
+    let time = median(|| {
+        ResolvedArgs::parse("cmake", &["--proj", "demo", "--version", "3.20"]).unwrap();
+    });
+    assert!(time < Duration::from_millis(5), "parsing took {:?}", time);
+}
+
+#[test]
+fn starting_the_command_is_fast() {
+    let time = median(|| {
+        let status = Command::new(env!("CARGO_BIN_EXE_filetemp"))
+            .args(["cmake", "--help"])
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success());
+    });
+    assert!(
+        time < Duration::from_millis(100),
+        "starting took {:?}",
+        time
+    );
+}